@@ -0,0 +1,89 @@
+use alloy::dyn_abi::{DynSolType, DynSolValue};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// Renders a dynamically-decoded ABI value as JSON.
+/// Integers are strings (they routinely exceed JSON's safe range) and byte types are 0x-hex.
+pub fn dyn_value_to_json(value: &DynSolValue) -> Value {
+    match value {
+        DynSolValue::Bool(b) => json!(b),
+        DynSolValue::Int(i, _) => json!(i.to_string()),
+        DynSolValue::Uint(u, _) => json!(u.to_string()),
+        DynSolValue::FixedBytes(word, size) => {
+            json!(format!("0x{}", hex::encode(&word[..*size])))
+        }
+        DynSolValue::Address(a) => json!(a.to_string()),
+        DynSolValue::Function(f) => json!(format!("0x{}", hex::encode(f.as_slice()))),
+        DynSolValue::Bytes(b) => json!(format!("0x{}", hex::encode(b))),
+        DynSolValue::String(s) => json!(s),
+        DynSolValue::Array(items) | DynSolValue::FixedArray(items) | DynSolValue::Tuple(items) => {
+            Value::Array(items.iter().map(dyn_value_to_json).collect())
+        }
+        DynSolValue::CustomStruct {
+            prop_names, tuple, ..
+        } => Value::Object(
+            prop_names
+                .iter()
+                .cloned()
+                .zip(tuple.iter().map(dyn_value_to_json))
+                .collect(),
+        ),
+    }
+}
+
+/// Parses a JSON argument as a value of `ty`. The inverse of [`dyn_value_to_json`]:
+/// scalars may be strings (or JSON numbers/bools), arrays and tuples are JSON arrays.
+pub fn json_to_dyn_value(ty: &DynSolType, value: &Value) -> Result<DynSolValue> {
+    let items = |expected: Option<usize>| -> Result<&Vec<Value>> {
+        let items = value
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("expected a JSON array for {}, got {}", ty, value))?;
+        match expected {
+            Some(n) if items.len() != n => Err(anyhow::anyhow!(
+                "expected {} elements for {}, got {}",
+                n,
+                ty,
+                items.len()
+            )),
+            _ => Ok(items),
+        }
+    };
+    let element = |i: usize, ty: &DynSolType, v: &Value| {
+        json_to_dyn_value(ty, v).map_err(|e| anyhow::anyhow!("[{}]: {}", i, e))
+    };
+
+    match ty {
+        DynSolType::Array(inner) => Ok(DynSolValue::Array(
+            items(None)?
+                .iter()
+                .enumerate()
+                .map(|(i, v)| element(i, inner, v))
+                .collect::<Result<_>>()?,
+        )),
+        DynSolType::FixedArray(inner, n) => Ok(DynSolValue::FixedArray(
+            items(Some(*n))?
+                .iter()
+                .enumerate()
+                .map(|(i, v)| element(i, inner, v))
+                .collect::<Result<_>>()?,
+        )),
+        DynSolType::Tuple(types) => Ok(DynSolValue::Tuple(
+            items(Some(types.len()))?
+                .iter()
+                .zip(types)
+                .enumerate()
+                .map(|(i, (v, ty))| element(i, ty, v))
+                .collect::<Result<_>>()?,
+        )),
+        _ => {
+            let text = match value {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                Value::Bool(b) => b.to_string(),
+                other => return Err(anyhow::anyhow!("expected a {} value, got {}", ty, other)),
+            };
+            ty.coerce_str(&text)
+                .map_err(|e| anyhow::anyhow!("'{}' is not a valid {}: {}", text, ty, e))
+        }
+    }
+}
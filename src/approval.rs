@@ -0,0 +1,119 @@
+use crate::ethereum::{decode_return, EthereumClient};
+use crate::tools::{watch::WatchTransactionTool, Tool};
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+sol! {
+    #[allow(missing_docs)]
+    function approve(address spender, uint256 amount) external returns (bool);
+    #[allow(missing_docs)]
+    function allowance(address owner, address spender) external view returns (uint256);
+}
+
+/// How long an automatic approval may take to confirm before the follow-up is abandoned.
+pub const APPROVAL_TIMEOUT_SECS: u64 = 180;
+
+/// How much an automatic approval grants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalPolicy {
+    /// Exactly the amount the pending operation spends.
+    Exact,
+    /// `type(uint256).max`, so later swaps need no approval (and the spender keeps it).
+    Unlimited,
+}
+
+impl ApprovalPolicy {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "exact" => Ok(Self::Exact),
+            "unlimited" | "max" => Ok(Self::Unlimited),
+            other => Err(anyhow::anyhow!(
+                "Invalid APPROVAL_POLICY value '{}': expected exact or unlimited",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Unlimited => "unlimited",
+        }
+    }
+
+    pub fn amount(self, needed: U256) -> U256 {
+        match self {
+            Self::Exact => needed,
+            Self::Unlimited => U256::MAX,
+        }
+    }
+}
+
+pub async fn allowance(
+    client: &EthereumClient,
+    token: Address,
+    owner: Address,
+    spender: Address,
+) -> Result<U256> {
+    let req = TransactionRequest::default()
+        .to(token)
+        .input(allowanceCall { owner, spender }.abi_encode().into());
+    let res = client.provider.call(&req).await?;
+    Ok(decode_return::<allowanceCall>(&res, token)?._0)
+}
+
+pub fn approve_request(token: Address, spender: Address, amount: U256) -> TransactionRequest {
+    TransactionRequest::default()
+        .to(token)
+        .input(approveCall { spender, amount }.abi_encode().into())
+}
+
+/// Sends `approve(spender, amount)` from the server wallet and waits for one confirmation.
+/// Fails if the approval reverts or does not confirm in time, since whatever follows would
+/// then revert too.
+pub async fn approve_and_wait(
+    client: &EthereumClient,
+    token: Address,
+    spender: Address,
+    amount: U256,
+    private: bool,
+) -> Result<Value> {
+    let submission = client
+        .submit_transaction(approve_request(token, spender, amount), private)
+        .await?;
+    let watched = WatchTransactionTool
+        .call(
+            client,
+            json!({
+                "tx_hash": submission.tx_hash.to_string(),
+                "confirmations": 1,
+                "timeout_seconds": APPROVAL_TIMEOUT_SECS
+            }),
+        )
+        .await?;
+    if watched["status"] != "confirmed" {
+        return Err(anyhow::anyhow!(
+            "Approval {} did not confirm within {}s; not sending the follow-up transaction",
+            submission.tx_hash,
+            APPROVAL_TIMEOUT_SECS
+        ));
+    }
+    if watched["receipt"]["success"] == false {
+        return Err(anyhow::anyhow!(
+            "Approval {} reverted; not sending the follow-up transaction",
+            submission.tx_hash
+        ));
+    }
+    let mut result = submission.to_json();
+    result["amount"] = json!(amount.to_string());
+    result["spender"] = json!(spender.to_string());
+    result["receipt"] = watched["receipt"].clone();
+    Ok(result)
+}
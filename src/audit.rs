@@ -0,0 +1,112 @@
+use crate::format;
+use alloy::primitives::TxHash;
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Argument keys whose values are never written to the audit log.
+const SENSITIVE_KEYS: [&str; 5] = ["private_key", "mnemonic", "password", "secret", "api_key"];
+
+/// Append-only JSON-lines record of every tool call, separate from tracing output.
+pub struct AuditLog {
+    file: Mutex<File>,
+    // Signer key in normalized form, scrubbed from any argument value that echoes it.
+    private_key: String,
+}
+
+impl AuditLog {
+    pub fn open(path: &str, private_key: &str) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open audit log at {}", path))?;
+        Ok(Self {
+            file: Mutex::new(file),
+            private_key: normalize_key(private_key),
+        })
+    }
+
+    /// Appends one entry. `broadcasts` are the hashes the call sent, in order, whether it
+    /// then succeeded or not.
+    pub fn record(
+        &self,
+        tool: &str,
+        args: &Value,
+        outcome: &Result<Value>,
+        broadcasts: &[TxHash],
+        duration: Duration,
+    ) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+
+        let mut entry = json!({
+            "timestamp_ms": timestamp,
+            "timestamp": format::timestamp((timestamp / 1000) as u64),
+            "tool": tool,
+            "arguments": self.redact(args),
+            "duration_ms": duration.as_millis(),
+            // The last broadcast is the one the call was for, e.g. a swap after its approval.
+            "tx_hash": broadcasts.last().map(|h| h.to_string()),
+            "tx_hashes": broadcasts.iter().map(|h| h.to_string()).collect::<Vec<_>>(),
+        });
+        match outcome {
+            Ok(_) => {
+                entry["status"] = json!("success");
+            }
+            Err(e) => {
+                entry["status"] = json!("error");
+                entry["error"] = json!(e.to_string());
+            }
+        }
+
+        // Audit failures must never take down a tool call; surface them in the logs instead.
+        let Ok(mut file) = self.file.lock() else {
+            tracing::error!("Audit log lock poisoned; dropping entry for {}", tool);
+            return;
+        };
+        let line = format!("{}\n", entry);
+        if let Err(e) = file
+            .write_all(line.as_bytes())
+            .and_then(|_| file.flush())
+            .and_then(|_| file.sync_data())
+        {
+            tracing::error!("Failed to write audit log entry: {}", e);
+        }
+    }
+
+    fn redact(&self, value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let redacted: Map<String, Value> = map
+                    .iter()
+                    .map(|(k, v)| {
+                        let key = k.to_lowercase();
+                        if SENSITIVE_KEYS.iter().any(|s| key.contains(s)) {
+                            (k.clone(), json!("[REDACTED]"))
+                        } else {
+                            (k.clone(), self.redact(v))
+                        }
+                    })
+                    .collect();
+                Value::Object(redacted)
+            }
+            Value::Array(items) => Value::Array(items.iter().map(|v| self.redact(v)).collect()),
+            Value::String(s)
+                if !self.private_key.is_empty() && normalize_key(s).contains(&self.private_key) =>
+            {
+                json!("[REDACTED]")
+            }
+            other => other.clone(),
+        }
+    }
+}
+
+fn normalize_key(s: &str) -> String {
+    s.trim().trim_start_matches("0x").to_lowercase()
+}
@@ -0,0 +1,82 @@
+use alloy::primitives::Bytes;
+use dashmap::DashMap;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Entries kept for one block before new reads stop being cached.
+const MAX_ENTRIES: usize = 10_000;
+
+/// `eth_call` results for the current head block, keyed by the serialized request.
+///
+/// Every cached read is pinned to the block it was cached at, so a hit returns exactly what
+/// the node would answer for that block. The head is re-read once it is older than
+/// `head_ttl`; when it has advanced, the previous block's entries are dropped.
+pub struct CallCache {
+    head_ttl: Duration,
+    head: Mutex<Option<(u64, Instant)>>,
+    entries: DashMap<(u64, String), Bytes>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CallCache {
+    pub fn new(head_ttl: Duration) -> Self {
+        Self {
+            head_ttl,
+            head: Mutex::new(None),
+            entries: DashMap::new(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The head block last seen, unless it is older than `head_ttl`.
+    pub fn fresh_head(&self) -> Option<u64> {
+        let head = self.head.lock().ok()?;
+        head.filter(|(_, seen)| seen.elapsed() < self.head_ttl)
+            .map(|(block, _)| block)
+    }
+
+    /// Records `block` as the head. Entries from earlier blocks are dropped once it moves on.
+    pub fn set_head(&self, block: u64) {
+        let Ok(mut head) = self.head.lock() else {
+            return;
+        };
+        let advanced = head.is_none_or(|(previous, _)| block > previous);
+        *head = Some((block, Instant::now()));
+        drop(head);
+        if advanced {
+            self.entries.retain(|(cached_at, _), _| *cached_at >= block);
+        }
+    }
+
+    pub fn get(&self, block: u64, key: &str) -> Option<Bytes> {
+        let hit = self
+            .entries
+            .get(&(block, key.to_string()))
+            .map(|entry| entry.clone());
+        match hit {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        hit
+    }
+
+    pub fn insert(&self, block: u64, key: String, data: Bytes) {
+        if self.entries.len() < MAX_ENTRIES {
+            self.entries.insert((block, key), data);
+        }
+    }
+
+    pub fn stats(&self) -> Value {
+        json!({
+            "head_block": self.head.lock().ok().and_then(|h| h.map(|(block, _)| block)),
+            "head_ttl_ms": self.head_ttl.as_millis() as u64,
+            "entries": self.entries.len(),
+            "hits": self.hits.load(Ordering::Relaxed),
+            "misses": self.misses.load(Ordering::Relaxed)
+        })
+    }
+}
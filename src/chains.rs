@@ -0,0 +1,269 @@
+use alloy::primitives::{address, Address};
+
+/// Which Uniswap V3 Quoter interface a deployment has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoterVersion {
+    /// Flat arguments, returns only the amount.
+    V1,
+    /// Struct arguments, returns the amount with the price after, ticks crossed and gas.
+    V2,
+}
+
+impl QuoterVersion {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+        }
+    }
+}
+
+/// Uniswap V3 periphery deployment on a chain.
+#[derive(Debug, Clone, Copy)]
+pub struct UniswapV3 {
+    pub factory: Address,
+    pub quoter: Address,
+    pub quoter_version: QuoterVersion,
+    pub swap_router: Address,
+    /// `swap_router` is SwapRouter02, whose `exactInputSingle` params carry no deadline.
+    pub router_02: bool,
+}
+
+/// A USD stablecoin deployment used for price routing.
+#[derive(Debug, Clone, Copy)]
+pub struct Stablecoin {
+    pub symbol: &'static str,
+    pub address: Address,
+    /// Bridged from mainnet (e.g. USDC.e, USDbC) rather than issued natively on the chain.
+    pub bridged: bool,
+}
+
+/// Per-chain contract addresses and capabilities.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainConfig {
+    pub chain_id: u64,
+    pub name: &'static str,
+    pub weth: Address,
+    pub uniswap_v3: Option<UniswapV3>,
+    /// Chainlink ETH/USD aggregator.
+    pub eth_usd_feed: Option<Address>,
+    /// Token -> Chainlink USD aggregator pairs.
+    pub token_usd_feeds: &'static [(Address, Address)],
+    /// Stablecoins in routing preference order. Tokens without a WETH pool are priced
+    /// through the first of these that has one.
+    pub stablecoins: &'static [Stablecoin],
+    /// ENS registry. ENS lives on mainnet only; L2s have no registry of their own.
+    pub ens_registry: Option<Address>,
+    /// Average block interval, for block-count <-> time estimates.
+    pub block_time_seconds: f64,
+}
+
+// Factory, QuoterV2 and SwapRouter share addresses on mainnet and the older L2 deployments.
+const UNISWAP_V3_CANONICAL: UniswapV3 = UniswapV3 {
+    factory: address!("1F98431c8aD98523631AE4a59f267346ea31F984"),
+    quoter: address!("61fFE0149A332c47d847296F720a48855e9cb754"),
+    quoter_version: QuoterVersion::V2,
+    swap_router: address!("E592427A0AEce92De3Edee1F18E0157C05861564"),
+    router_02: false,
+};
+
+pub const MAINNET: u64 = 1;
+
+// WBTC is priced off BTC/USD (assumes the peg holds). LINK, UNI, AAVE and COMP have feeds
+// of their own, which `get_token_price` prefers over a pool price.
+const MAINNET_USD_FEEDS: [(Address, Address); 9] = [
+    (
+        address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+        address!("5f4eC3Df9cbd43714FE2740f5E3616155c5b8419"),
+    ),
+    (
+        address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+        address!("8fFfFfd4AfB6115b954Bd326cbe7B4BA576818f6"),
+    ),
+    (
+        address!("dAC17F958D2ee523a2206206994597C13D831ec7"),
+        address!("3E7d1eAB13ad0104d2750B8863b489D65364e32D"),
+    ),
+    (
+        address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
+        address!("Aed0c38402a5d19df6E4c03F4E2DceD6e29c1ee9"),
+    ),
+    (
+        address!("2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"),
+        address!("F4030086522a5bEEa4988F8cA5B36dbC97BeE88c"),
+    ),
+    (
+        address!("514910771AF9Ca656af840dff83E8264EcF986CA"),
+        address!("2c1d072e956AFFC0D435Cb7AC38EF18d24d9127c"),
+    ),
+    (
+        address!("1f9840a85d5aF5bf1D1762F925BDADdC4201F984"),
+        address!("553303d460EE0afB37EdFf9bE42922D8FF63220e"),
+    ),
+    (
+        address!("7Fc66500c84A76Ad7e9c93437bFc5Ac33E2DDaE9"),
+        address!("547a514d5e3769680Ce22B2361c10Ea13619e8a9"),
+    ),
+    (
+        address!("c00e94Cb662C3520282E6f5717214004A7f26888"),
+        address!("dbd020CAeF83eFd542f4De03e3cF0C28A4428bd5"),
+    ),
+];
+
+const fn stable(symbol: &'static str, address: Address, bridged: bool) -> Stablecoin {
+    Stablecoin {
+        symbol,
+        address,
+        bridged,
+    }
+}
+
+// Native USDC first: since Circle's native launches the bridged variants have thinner pools.
+const MAINNET_STABLECOINS: [Stablecoin; 3] = [
+    stable(
+        "USDC",
+        address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+        false,
+    ),
+    stable(
+        "USDT",
+        address!("dAC17F958D2ee523a2206206994597C13D831ec7"),
+        false,
+    ),
+    stable(
+        "DAI",
+        address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
+        false,
+    ),
+];
+
+const OPTIMISM_STABLECOINS: [Stablecoin; 4] = [
+    stable(
+        "USDC",
+        address!("0b2C639c533813f4Aa9D7837CAf62653d097Ff85"),
+        false,
+    ),
+    stable(
+        "USDC.e",
+        address!("7F5c764cBc14f9669B88837ca1490cCa17c31607"),
+        true,
+    ),
+    stable(
+        "USDT",
+        address!("94b008aA00579c1307B0EF2c499aD98a8ce58e58"),
+        true,
+    ),
+    stable(
+        "DAI",
+        address!("DA10009cBd5D07dd0CeCc66161FC93D7c9000da1"),
+        true,
+    ),
+];
+
+const BASE_STABLECOINS: [Stablecoin; 3] = [
+    stable(
+        "USDC",
+        address!("833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+        false,
+    ),
+    stable(
+        "USDbC",
+        address!("d9aAEc86B65D86f6A7B5B1b0c42FFA531710b6CA"),
+        true,
+    ),
+    stable(
+        "DAI",
+        address!("50c5725949A6F0c72E6C4a641F24049A917DB0Cb"),
+        true,
+    ),
+];
+
+const ARBITRUM_STABLECOINS: [Stablecoin; 4] = [
+    stable(
+        "USDC",
+        address!("af88d065e77c8cC2239327C5EDb3A432268e5831"),
+        false,
+    ),
+    stable(
+        "USDC.e",
+        address!("FF970A61A04b1cA14834A43f5dE4533eBDDB5CC8"),
+        true,
+    ),
+    stable(
+        "USDT",
+        address!("Fd086bC7CD5C481DCC9C85ebE478A1C0b69FCbb9"),
+        true,
+    ),
+    stable(
+        "DAI",
+        address!("DA10009cBd5D07dd0CeCc66161FC93D7c9000da1"),
+        true,
+    ),
+];
+
+static CHAINS: [ChainConfig; 4] = [
+    ChainConfig {
+        chain_id: MAINNET,
+        name: "Ethereum Mainnet",
+        weth: address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+        uniswap_v3: Some(UNISWAP_V3_CANONICAL),
+        eth_usd_feed: Some(address!("5f4eC3Df9cbd43714FE2740f5E3616155c5b8419")),
+        token_usd_feeds: &MAINNET_USD_FEEDS,
+        stablecoins: &MAINNET_STABLECOINS,
+        ens_registry: Some(address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e")),
+        block_time_seconds: 12.0,
+    },
+    ChainConfig {
+        chain_id: 10,
+        name: "Optimism",
+        weth: address!("4200000000000000000000000000000000000006"),
+        uniswap_v3: Some(UNISWAP_V3_CANONICAL),
+        eth_usd_feed: Some(address!("13e3Ee699D1909E989722E753853AE30b17e08c5")),
+        token_usd_feeds: &[],
+        stablecoins: &OPTIMISM_STABLECOINS,
+        ens_registry: None,
+        block_time_seconds: 2.0,
+    },
+    ChainConfig {
+        chain_id: 8453,
+        name: "Base",
+        weth: address!("4200000000000000000000000000000000000006"),
+        // Base only has SwapRouter02, whose exactInputSingle takes no deadline.
+        uniswap_v3: Some(UniswapV3 {
+            factory: address!("33128a8fC17869897dcE68Ed026d694621f6FDfD"),
+            quoter: address!("3d4e44Eb1374240CE5F1B871ab261CD16335B76a"),
+            quoter_version: QuoterVersion::V2,
+            swap_router: address!("2626664c2603336E57B271c5C0b26F421741e481"),
+            router_02: true,
+        }),
+        eth_usd_feed: Some(address!("71041dddad3595F9CEd3DcCFBe3D1F4b0a16Bb70")),
+        token_usd_feeds: &[],
+        stablecoins: &BASE_STABLECOINS,
+        ens_registry: None,
+        block_time_seconds: 2.0,
+    },
+    ChainConfig {
+        chain_id: 42161,
+        name: "Arbitrum One",
+        weth: address!("82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
+        uniswap_v3: Some(UNISWAP_V3_CANONICAL),
+        eth_usd_feed: Some(address!("639Fe6ab55C921f74e7fac1ee960C0B6293ba612")),
+        token_usd_feeds: &[],
+        stablecoins: &ARBITRUM_STABLECOINS,
+        ens_registry: None,
+        block_time_seconds: 0.25,
+    },
+];
+
+pub fn supported() -> &'static [ChainConfig] {
+    &CHAINS
+}
+
+pub fn get(chain_id: u64) -> Option<&'static ChainConfig> {
+    CHAINS.iter().find(|c| c.chain_id == chain_id)
+}
+
+/// Block interval of `chain_id`, assuming Ethereum's 12s for unknown chains.
+pub fn block_time_seconds(chain_id: u64) -> f64 {
+    get(chain_id).map_or(12.0, |c| c.block_time_seconds)
+}
@@ -1,20 +1,460 @@
+use crate::approval::ApprovalPolicy;
+use crate::ethereum::endpoint_label;
+use crate::retry;
+use crate::tokens::{SymbolPolicy, TokenListValidation};
+use crate::tools::{DEFAULT_MAX_BATCH_SIZE, DEFAULT_TOOL_TIMEOUT_SECS};
 use anyhow::Context;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
 use std::env;
+use std::str::FromStr;
+use url::Url;
+
+const DEFAULT_CALL_CACHE_HEAD_MS: u64 = 1000;
+const DEFAULT_HTTP_BIND: &str = "127.0.0.1:8080";
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 64;
+const DEFAULT_MAX_CONCURRENT_REQUESTS_PER_CLIENT: usize = 8;
+const DEFAULT_SHUTDOWN_GRACE_SECS: u64 = 10;
+const DEFAULT_HTTP_MAX_SESSIONS: usize = 1000;
+const DEFAULT_HTTP_SESSION_IDLE_SECS: u64 = 1800;
+
+/// Where the signing key comes from: exactly one of `PRIVATE_KEY`, `MNEMONIC` or
+/// `KEYSTORE_PATH`.
+#[derive(Clone)]
+pub enum WalletSource {
+    /// Raw hex private key.
+    PrivateKey(String),
+    /// BIP-39 phrase, derived at `derivation_path` or, when that is unset, at
+    /// `m/44'/60'/0'/0/<index>`.
+    Mnemonic {
+        phrase: String,
+        derivation_path: Option<String>,
+        index: u32,
+    },
+    /// Encrypted JSON (V3) keystore file and its password.
+    Keystore { path: String, password: String },
+}
+
+impl From<&str> for WalletSource {
+    fn from(private_key: &str) -> Self {
+        Self::PrivateKey(private_key.to_string())
+    }
+}
+
+impl WalletSource {
+    /// The configured signing method; `None` when no key variable is set (read-only mode).
+    fn from_env() -> anyhow::Result<Option<Self>> {
+        let private_key = env_secret("PRIVATE_KEY");
+        let mnemonic = env_secret("MNEMONIC");
+        let keystore_path = env_secret("KEYSTORE_PATH");
+        let configured: Vec<&str> = [
+            ("PRIVATE_KEY", private_key.is_some()),
+            ("MNEMONIC", mnemonic.is_some()),
+            ("KEYSTORE_PATH", keystore_path.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect();
+        if configured.len() > 1 {
+            return Err(anyhow::anyhow!(
+                "{} are all set; configure exactly one signing method",
+                configured.join(" and ")
+            ));
+        }
+        let derivation_path = env_secret("DERIVATION_PATH");
+        let index = env::var("ACCOUNT_INDEX")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| {
+                v.trim()
+                    .parse::<u32>()
+                    .context("ACCOUNT_INDEX must be a non-negative integer")
+            })
+            .transpose()?;
+        if mnemonic.is_none() && (derivation_path.is_some() || index.is_some()) {
+            return Err(anyhow::anyhow!(
+                "DERIVATION_PATH and ACCOUNT_INDEX only apply to MNEMONIC"
+            ));
+        }
+        if derivation_path.is_some() && index.is_some() {
+            return Err(anyhow::anyhow!(
+                "Set DERIVATION_PATH (the full path) or ACCOUNT_INDEX, not both"
+            ));
+        }
+        let password = env::var("KEYSTORE_PASSWORD").ok();
+        if keystore_path.is_none() && password.is_some() {
+            return Err(anyhow::anyhow!(
+                "KEYSTORE_PASSWORD is set without KEYSTORE_PATH"
+            ));
+        }
+
+        if let Some(key) = private_key {
+            Ok(Some(Self::PrivateKey(key)))
+        } else if let Some(phrase) = mnemonic {
+            Ok(Some(Self::Mnemonic {
+                phrase,
+                derivation_path,
+                index: index.unwrap_or(0),
+            }))
+        } else if let Some(path) = keystore_path {
+            Ok(Some(Self::Keystore {
+                path,
+                password: password
+                    .context("KEYSTORE_PASSWORD must be set to decrypt KEYSTORE_PATH")?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The method in use and its non-secret settings.
+    pub fn redacted(&self) -> Value {
+        match self {
+            Self::PrivateKey(_) => json!({ "source": "private_key" }),
+            Self::Mnemonic {
+                derivation_path,
+                index,
+                ..
+            } => json!({
+                "source": "mnemonic",
+                "derivation_path": derivation_path,
+                "account_index": derivation_path.is_none().then_some(index)
+            }),
+            Self::Keystore { path, .. } => json!({ "source": "keystore", "path": path }),
+        }
+    }
+}
+
+/// How the server receives JSON-RPC requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// Newline-delimited JSON-RPC on stdin/stdout, for subprocess clients.
+    Stdio,
+    /// JSON-RPC POSTed to `/`, answered as Server-Sent Events.
+    Http,
+}
+
+impl Transport {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "stdio" => Ok(Self::Stdio),
+            "http" => Ok(Self::Http),
+            other => Err(anyhow::anyhow!(
+                "Invalid TRANSPORT value '{}': expected stdio or http",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Stdio => "stdio",
+            Self::Http => "http",
+        }
+    }
+}
+
+/// What to do when the startup self-call from the signer fails (`SIGNER_CHECK`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignerCheck {
+    Off,
+    /// Log a warning and start anyway.
+    Warn,
+    /// Refuse to start.
+    Strict,
+}
+
+impl SignerCheck {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "0" | "false" | "off" => Ok(Self::Off),
+            "" | "warn" => Ok(Self::Warn),
+            "strict" => Ok(Self::Strict),
+            other => Err(anyhow::anyhow!(
+                "Invalid SIGNER_CHECK value '{}': expected off, warn, or strict",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Warn => "warn",
+            Self::Strict => "strict",
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Config {
-    pub rpc_url: String,
-    pub private_key: String,
+    /// `ETHEREUM_RPC_URL`, comma-separated: the primary endpoint, then fallbacks tried in
+    /// order when it is unreachable or rate-limited.
+    pub rpc_urls: Vec<String>,
+    /// Secondary read endpoints (`ETHEREUM_RPC_URLS`, comma-separated) for cross-checks.
+    pub extra_rpc_urls: Vec<String>,
+    /// Chain ids the server agrees to run on (`SUPPORTED_CHAIN_IDS`); `None` means the
+    /// chains in `chains::supported()`.
+    pub supported_chain_ids: Option<Vec<u64>>,
+    /// `None` runs the server read-only: no key, so nothing can be signed or sent.
+    pub wallet: Option<WalletSource>,
+    /// Startup zero-value self-call from the signer, to catch a key the chain won't accept.
+    pub signer_check: SignerCheck,
+    /// When set, every tool call is appended to this file as a JSON line.
+    pub audit_log_path: Option<String>,
+    /// Optional JSON token list merged over the built-in symbol registry.
+    pub token_list_path: Option<String>,
+    /// Startup check of registry decimals against the chain (costs one call per token).
+    pub validate_token_list: TokenListValidation,
+    /// How registry symbols shared by several tokens are resolved.
+    pub symbol_resolution: SymbolPolicy,
+    /// Group digits of human-readable amounts (`1,234.5`). Raw fields are unaffected.
+    pub thousands_separators: bool,
+    /// Private-mempool RPC (e.g. `https://rpc.flashbots.net`) used for `private: true` submissions.
+    pub private_rpc_url: Option<String>,
+    /// Addresses (one per line, or a JSON array) that every tool refuses to touch.
+    pub denylist_path: Option<String>,
+    /// Swap guardrail: maximum percent the execution rate may trail the Chainlink rate.
+    pub max_oracle_deviation: Option<Decimal>,
+    /// Trusted stablecoins (addresses or registry symbols); `None` keeps the USDC/USDT/DAI default.
+    pub stablecoins: Option<Vec<String>>,
+    /// Value stablecoins at exactly $1 instead of reading their Chainlink feeds.
+    pub assume_stablecoin_peg: bool,
+    /// Requests in flight across all clients before new ones get "server busy".
+    pub max_concurrent_requests: usize,
+    /// Requests in flight per client (peer IP over HTTP) before "server busy".
+    pub max_concurrent_requests_per_client: usize,
+    /// How much `auto_approve` grants: the exact amount (default) or unlimited.
+    pub approval_policy: ApprovalPolicy,
+    /// Enables the `admin/*` JSON-RPC methods (e.g. `admin/reinitialize`).
+    pub admin_methods: bool,
+    /// stdio (default) or http.
+    pub transport: Transport,
+    /// Address the HTTP transport listens on (`HTTP_BIND`).
+    pub http_bind: String,
+    /// Bearer token every HTTP request must carry (`HTTP_AUTH_TOKEN`); `None` leaves the
+    /// listener open to anyone who can reach it.
+    pub http_auth_token: Option<String>,
+    /// Open HTTP sessions before `initialize` is refused (`HTTP_MAX_SESSIONS`).
+    pub http_max_sessions: usize,
+    /// Seconds an HTTP session may go unused before it expires (`HTTP_SESSION_IDLE_SECONDS`).
+    pub http_session_idle_seconds: u64,
+    /// How long a head block is reused for cached reads (`CALL_CACHE_HEAD_MS`); 0 disables
+    /// the block cache.
+    pub call_cache_head_ms: u64,
+    /// Retries of a transient RPC failure (`RPC_MAX_RETRIES`); 0 disables retrying.
+    pub rpc_max_retries: u32,
+    /// Backoff before the first retry (`RPC_RETRY_BASE_MS`), doubled for each one after.
+    pub rpc_retry_base_ms: u64,
+    /// Most items (calls, tokens) one batch tool call accepts (`MAX_BATCH_SIZE`).
+    pub max_batch_size: usize,
+    /// Seconds a tool call may run before it fails with a timeout (`TOOL_TIMEOUT_SECONDS`);
+    /// 0 disables the limit.
+    pub tool_timeout_seconds: u64,
+    /// Seconds requests in flight get to finish after SIGINT/SIGTERM before they are
+    /// dropped (`SHUTDOWN_GRACE_SECONDS`).
+    pub shutdown_grace_seconds: u64,
 }
 
 impl Config {
     pub fn from_env() -> anyhow::Result<Self> {
-        let rpc_url = env::var("ETHEREUM_RPC_URL").context("ETHEREUM_RPC_URL must be set")?;
-        let private_key = env::var("PRIVATE_KEY").context("PRIVATE_KEY must be set")?;
+        let rpc_urls = env_list("ETHEREUM_RPC_URL").context("ETHEREUM_RPC_URL must be set")?;
+        let wallet = WalletSource::from_env()?;
+        let extra_rpc_urls = env_list("ETHEREUM_RPC_URLS")
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|u| !rpc_urls.contains(u))
+            .collect();
+        let supported_chain_ids = env_list("SUPPORTED_CHAIN_IDS")
+            .map(|ids| {
+                ids.iter()
+                    .map(|id| {
+                        id.parse::<u64>().with_context(|| {
+                            format!("SUPPORTED_CHAIN_IDS: invalid chain id {}", id)
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()
+            })
+            .transpose()?;
+        let signer_check = SignerCheck::parse(&env::var("SIGNER_CHECK").unwrap_or_default())?;
+        let audit_log_path = env::var("AUDIT_LOG_PATH").ok().filter(|p| !p.is_empty());
+        let token_list_path = env::var("TOKEN_LIST_PATH").ok().filter(|p| !p.is_empty());
+        let validate_token_list =
+            TokenListValidation::parse(&env::var("VALIDATE_TOKEN_LIST").unwrap_or_default())?;
+        let symbol_resolution =
+            SymbolPolicy::parse(&env::var("SYMBOL_RESOLUTION").unwrap_or_default())?;
+        let thousands_separators = env_flag("THOUSANDS_SEPARATORS");
+        let private_rpc_url = env::var("PRIVATE_RPC_URL").ok().filter(|u| !u.is_empty());
+        let denylist_path = env::var("DENYLIST_PATH").ok().filter(|p| !p.is_empty());
+        let max_oracle_deviation = match env::var("MAX_ORACLE_DEVIATION_PERCENT") {
+            Ok(v) if !v.trim().is_empty() => Some(
+                Decimal::from_str(v.trim())
+                    .context("MAX_ORACLE_DEVIATION_PERCENT must be a number")?,
+            ),
+            _ => None,
+        };
+        let stablecoins = env_list("STABLECOINS");
+        let assume_stablecoin_peg = env_flag("ASSUME_STABLECOIN_PEG");
+        let max_concurrent_requests =
+            env_usize("MAX_CONCURRENT_REQUESTS", DEFAULT_MAX_CONCURRENT_REQUESTS)?;
+        let max_concurrent_requests_per_client = env_usize(
+            "MAX_CONCURRENT_REQUESTS_PER_CLIENT",
+            DEFAULT_MAX_CONCURRENT_REQUESTS_PER_CLIENT,
+        )?;
+        let approval_policy =
+            ApprovalPolicy::parse(&env::var("APPROVAL_POLICY").unwrap_or_default())?;
+        let admin_methods = env_flag("ENABLE_ADMIN_METHODS");
+        let transport = Transport::parse(&env::var("TRANSPORT").unwrap_or_default())?;
+        let http_bind = env::var("HTTP_BIND")
+            .ok()
+            .filter(|b| !b.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_HTTP_BIND.to_string());
+        let http_auth_token = env_secret("HTTP_AUTH_TOKEN");
+        let http_max_sessions = env_usize("HTTP_MAX_SESSIONS", DEFAULT_HTTP_MAX_SESSIONS)?;
+        if http_max_sessions == 0 {
+            return Err(anyhow::anyhow!("HTTP_MAX_SESSIONS must be at least 1"));
+        }
+        let http_session_idle_seconds = env_usize(
+            "HTTP_SESSION_IDLE_SECONDS",
+            DEFAULT_HTTP_SESSION_IDLE_SECS as usize,
+        )? as u64;
+        let call_cache_head_ms =
+            env_usize("CALL_CACHE_HEAD_MS", DEFAULT_CALL_CACHE_HEAD_MS as usize)? as u64;
+        let rpc_max_retries =
+            env_usize("RPC_MAX_RETRIES", retry::DEFAULT_MAX_RETRIES as usize)? as u32;
+        let rpc_retry_base_ms =
+            env_usize("RPC_RETRY_BASE_MS", retry::DEFAULT_BASE_DELAY_MS as usize)? as u64;
+        let max_batch_size = env_usize("MAX_BATCH_SIZE", DEFAULT_MAX_BATCH_SIZE)?;
+        if max_batch_size == 0 {
+            return Err(anyhow::anyhow!("MAX_BATCH_SIZE must be at least 1"));
+        }
+        let tool_timeout_seconds =
+            env_usize("TOOL_TIMEOUT_SECONDS", DEFAULT_TOOL_TIMEOUT_SECS as usize)? as u64;
+        let shutdown_grace_seconds = env_usize(
+            "SHUTDOWN_GRACE_SECONDS",
+            DEFAULT_SHUTDOWN_GRACE_SECS as usize,
+        )? as u64;
 
         Ok(Self {
-            rpc_url,
-            private_key,
+            rpc_urls,
+            extra_rpc_urls,
+            supported_chain_ids,
+            wallet,
+            signer_check,
+            audit_log_path,
+            token_list_path,
+            validate_token_list,
+            symbol_resolution,
+            thousands_separators,
+            private_rpc_url,
+            denylist_path,
+            max_oracle_deviation,
+            stablecoins,
+            assume_stablecoin_peg,
+            max_concurrent_requests,
+            max_concurrent_requests_per_client,
+            approval_policy,
+            admin_methods,
+            transport,
+            http_bind,
+            http_auth_token,
+            http_max_sessions,
+            http_session_idle_seconds,
+            call_cache_head_ms,
+            rpc_max_retries,
+            rpc_retry_base_ms,
+            max_batch_size,
+            tool_timeout_seconds,
+            shutdown_grace_seconds,
         })
     }
 }
+
+impl Config {
+    /// The effective settings with secrets removed: no key material, and RPC endpoints
+    /// reduced to scheme and host since providers put API keys in paths and query strings.
+    pub fn redacted(&self) -> Value {
+        let label = |u: &str| {
+            Url::parse(u)
+                .map(|u| endpoint_label(&u))
+                .unwrap_or_else(|_| "<invalid url>".into())
+        };
+        json!({
+            "rpc": {
+                "endpoint_count": self.rpc_urls.len() + self.extra_rpc_urls.len(),
+                "primary": self.rpc_urls.first().map(|u| label(u)),
+                "fallback": self.rpc_urls.iter().skip(1).map(|u| label(u)).collect::<Vec<_>>(),
+                "extra": self.extra_rpc_urls.iter().map(|u| label(u)).collect::<Vec<_>>(),
+                "private_relay": self.private_rpc_url.as_deref().map(label)
+            },
+            "supported_chain_ids": self.supported_chain_ids,
+            "private_key": "<redacted>",
+            "wallet": self.wallet.as_ref().map(WalletSource::redacted),
+            "read_only": self.wallet.is_none(),
+            "signer_check": self.signer_check.as_str(),
+            "audit_log_path": self.audit_log_path,
+            "token_list_path": self.token_list_path,
+            "validate_token_list": self.validate_token_list.as_str(),
+            "symbol_resolution": self.symbol_resolution.as_str(),
+            "thousands_separators": self.thousands_separators,
+            "denylist_path": self.denylist_path,
+            "max_oracle_deviation_percent": self.max_oracle_deviation,
+            "stablecoins": self.stablecoins,
+            "assume_stablecoin_peg": self.assume_stablecoin_peg,
+            "max_concurrent_requests": self.max_concurrent_requests,
+            "max_concurrent_requests_per_client": self.max_concurrent_requests_per_client,
+            "approval_policy": self.approval_policy.as_str(),
+            "admin_methods": self.admin_methods,
+            "transport": self.transport.as_str(),
+            "http_bind": self.http_bind,
+            "http_auth": self.http_auth_token.is_some(),
+            "http_max_sessions": self.http_max_sessions,
+            "http_session_idle_seconds": self.http_session_idle_seconds,
+            "call_cache_head_ms": self.call_cache_head_ms,
+            "rpc_max_retries": self.rpc_max_retries,
+            "rpc_retry_base_ms": self.rpc_retry_base_ms,
+            "max_batch_size": self.max_batch_size,
+            "tool_timeout_seconds": self.tool_timeout_seconds,
+            "shutdown_grace_seconds": self.shutdown_grace_seconds
+        })
+    }
+}
+
+/// A secret-bearing variable; `None` when unset or blank.
+fn env_secret(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+fn env_usize(name: &str, default: usize) -> anyhow::Result<usize> {
+    match env::var(name) {
+        Ok(v) if !v.trim().is_empty() => v
+            .trim()
+            .parse()
+            .with_context(|| format!("{} must be a non-negative integer", name)),
+        _ => Ok(default),
+    }
+}
+
+/// Comma-separated values; `None` when the variable is unset or blank.
+fn env_list(name: &str) -> Option<Vec<String>> {
+    env::var(name)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+}
+
+fn env_flag(name: &str) -> bool {
+    env::var(name)
+        .map(|v| {
+            matches!(
+                v.trim().to_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
@@ -0,0 +1,111 @@
+use crate::ethereum::EthereumClient;
+use crate::tokens::TokenRegistry;
+use crate::tools::ens;
+use alloy::primitives::Address;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Addresses no tool may touch, checked against every argument before a tool runs.
+#[derive(Debug, Clone, Default)]
+pub struct DenyList {
+    addresses: HashSet<Address>,
+}
+
+impl DenyList {
+    /// Reads either a JSON array of addresses or plain text with one address per line
+    /// (blank lines and `#` comments ignored).
+    pub fn load(path: &str) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read deny-list at {}", path))?;
+        Self::parse(&raw).with_context(|| format!("Invalid deny-list at {}", path))
+    }
+
+    pub fn parse(raw: &str) -> Result<Self> {
+        let entries: Vec<String> = if raw.trim_start().starts_with('[') {
+            serde_json::from_str(raw)?
+        } else {
+            raw.lines()
+                .map(|l| l.split('#').next().unwrap_or_default().trim().to_string())
+                .filter(|l| !l.is_empty())
+                .collect()
+        };
+        let addresses = entries
+            .iter()
+            .map(|e| Address::from_str(e).map_err(|_| anyhow::anyhow!("'{}' is not an address", e)))
+            .collect::<Result<_>>()?;
+        Ok(Self { addresses })
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    pub fn contains(&self, address: &Address) -> bool {
+        self.addresses.contains(address)
+    }
+
+    /// Fails if any string in `args` (at any depth) is a denied address, or a registry
+    /// symbol that any registry entry (verified or not) maps to one.
+    pub fn check_args(&self, args: &Value, tokens: &TokenRegistry) -> Result<()> {
+        match args {
+            Value::String(s) => {
+                let denied = match Address::from_str(s) {
+                    Ok(addr) => self.contains(&addr).then_some(addr),
+                    Err(_) => tokens
+                        .candidates(s)
+                        .map(|t| t.address)
+                        .find(|addr| self.contains(addr)),
+                };
+                match denied {
+                    Some(addr) => Err(anyhow::anyhow!(
+                        "address {} is on the configured deny-list",
+                        addr
+                    )),
+                    None => Ok(()),
+                }
+            }
+            Value::Array(items) => items.iter().try_for_each(|v| self.check_args(v, tokens)),
+            Value::Object(map) => map.values().try_for_each(|v| self.check_args(v, tokens)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if any ENS name in `args` resolves to a denied address. Names are only
+    /// resolved when the list is non-empty; one that fails to resolve is left to the tool,
+    /// which reports the resolution error itself.
+    pub async fn check_ens_names(&self, args: &Value, client: &EthereumClient) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let mut names = Vec::new();
+        ens_names(args, &mut names);
+        for name in names {
+            if let Ok(resolution) = ens::resolve(client, name).await {
+                if self.contains(&resolution.address) {
+                    return Err(anyhow::anyhow!(
+                        "address {} ({}) is on the configured deny-list",
+                        resolution.address,
+                        resolution.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every string in `args` (at any depth) that looks like an ENS name.
+fn ens_names<'a>(args: &'a Value, names: &mut Vec<&'a str>) {
+    match args {
+        Value::String(s) if ens::is_ens_name(s) => names.push(s),
+        Value::Array(items) => items.iter().for_each(|v| ens_names(v, names)),
+        Value::Object(map) => map.values().for_each(|v| ens_names(v, names)),
+        _ => {}
+    }
+}
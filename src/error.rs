@@ -0,0 +1,159 @@
+//! Error taxonomy for tool failures, so a client can tell a bad argument from a contract
+//! revert or an RPC outage. Tools raise a `ToolError` through `anyhow` where they know the
+//! kind; `ToolError::classify` recovers it, or infers one from the transport error.
+
+use crate::retry::is_transient;
+use crate::tools::explain_tx::decode_revert;
+use alloy::transports::{RpcError, TransportError};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// JSON-RPC code for a call the contract reverted.
+pub const REVERT_CODE: i32 = -32001;
+/// JSON-RPC code for an Ethereum node that is down, rate limiting or timing out.
+pub const RPC_UNAVAILABLE_CODE: i32 = -32002;
+/// JSON-RPC code for a request the server or the connected chain can't serve.
+pub const NOT_SUPPORTED_CODE: i32 = -32003;
+/// JSON-RPC code for a tool call that ran past `TOOL_TIMEOUT_SECONDS`.
+pub const TIMEOUT_CODE: i32 = -32004;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolError {
+    /// A missing or malformed argument; the same call will fail again.
+    InvalidArgument(String),
+    /// The contract reverted, with the raw revert data when the node returned it.
+    Revert {
+        message: String,
+        data: Option<String>,
+    },
+    /// The node could not be reached or refused the request for now; retrying may work.
+    RpcUnavailable(String),
+    /// Not available on this chain or in this server's mode.
+    NotSupported(String),
+    /// The call ran past the server's time limit and was abandoned, with the hashes of any
+    /// transactions it had already broadcast. Only a call that broadcast nothing is safe
+    /// to retry.
+    Timeout {
+        message: String,
+        tx_hashes: Vec<String>,
+    },
+    /// Anything else.
+    Internal(String),
+}
+
+impl ToolError {
+    /// An `InvalidArgument` as the `anyhow::Error` tools return.
+    pub fn invalid_argument(message: impl fmt::Display) -> anyhow::Error {
+        ToolError::InvalidArgument(message.to_string()).into()
+    }
+
+    /// The error for a required argument that is absent.
+    pub fn missing(field: &str) -> anyhow::Error {
+        Self::invalid_argument(format!("Missing {}", field))
+    }
+
+    /// A `NotSupported` as the `anyhow::Error` tools return.
+    pub fn not_supported(message: impl fmt::Display) -> anyhow::Error {
+        ToolError::NotSupported(message.to_string()).into()
+    }
+
+    /// The kind `error` was raised as, else one inferred from the first transport error in
+    /// its chain: reverts from the node's error response, outages from `is_transient`.
+    pub fn classify(error: &anyhow::Error) -> Self {
+        if let Some(tool_error) = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<ToolError>())
+        {
+            return tool_error.clone();
+        }
+        let message = format!("{:#}", error);
+        let transport = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<TransportError>());
+        match transport {
+            Some(RpcError::ErrorResp(payload))
+                if payload.code == 3 || payload.message.contains("revert") =>
+            {
+                ToolError::Revert {
+                    message,
+                    data: payload
+                        .data
+                        .as_ref()
+                        .and_then(|d| serde_json::from_str::<String>(d.get()).ok()),
+                }
+            }
+            Some(e) if is_transient(e) => ToolError::RpcUnavailable(message),
+            _ => ToolError::Internal(message),
+        }
+    }
+
+    pub fn code(&self) -> i32 {
+        match self {
+            ToolError::InvalidArgument(_) => -32602,
+            ToolError::Revert { .. } => REVERT_CODE,
+            ToolError::RpcUnavailable(_) => RPC_UNAVAILABLE_CODE,
+            ToolError::NotSupported(_) => NOT_SUPPORTED_CODE,
+            ToolError::Timeout { .. } => TIMEOUT_CODE,
+            ToolError::Internal(_) => -32603,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ToolError::InvalidArgument(_) => "invalid_argument",
+            ToolError::Revert { .. } => "revert",
+            ToolError::RpcUnavailable(_) => "rpc_unavailable",
+            ToolError::NotSupported(_) => "not_supported",
+            ToolError::Timeout { .. } => "timeout",
+            ToolError::Internal(_) => "internal",
+        }
+    }
+
+    /// The JSON-RPC error `data`: the kind, whether a retry may help and, for reverts,
+    /// the decoded revert data. A timeout after a broadcast lists the transactions instead
+    /// of being retryable, since a retry would send them again.
+    pub fn data(&self) -> Value {
+        let retryable = match self {
+            ToolError::RpcUnavailable(_) => true,
+            ToolError::Timeout { tx_hashes, .. } => tx_hashes.is_empty(),
+            _ => false,
+        };
+        let mut data = json!({
+            "kind": self.kind(),
+            "retryable": retryable
+        });
+        if let ToolError::Timeout { tx_hashes, .. } = self {
+            if !tx_hashes.is_empty() {
+                data["tx_hashes"] = json!(tx_hashes);
+            }
+        }
+        if let ToolError::Revert {
+            data: Some(revert_data),
+            ..
+        } = self
+        {
+            if let Some(bytes) = revert_data
+                .strip_prefix("0x")
+                .and_then(|h| hex::decode(h).ok())
+            {
+                data["revert"] = decode_revert(&bytes, &[]);
+            }
+        }
+        data
+    }
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolError::InvalidArgument(message)
+            | ToolError::Revert { message, .. }
+            | ToolError::RpcUnavailable(message)
+            | ToolError::NotSupported(message)
+            | ToolError::Timeout { message, .. }
+            | ToolError::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ToolError {}
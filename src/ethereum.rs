@@ -1,33 +1,944 @@
+use crate::approval::ApprovalPolicy;
+use crate::cache::CallCache;
+use crate::chains::{self, ChainConfig, UniswapV3};
+use crate::config::{Config, SignerCheck, WalletSource};
+use crate::error::ToolError;
+use crate::failover::{Endpoints, FailoverTransport};
+use crate::oracle;
+use crate::retry::{RetryLayer, RetryPolicy};
+use crate::tokens::TokenRegistry;
+use crate::tools::balance::{decimalsCall, decode_symbol, symbolCall};
+use crate::tools::{DEFAULT_MAX_BATCH_SIZE, DEFAULT_TOOL_TIMEOUT_SECS};
 use alloy::{
-    network::EthereumWallet, primitives::Address, providers::ProviderBuilder,
-    signers::local::PrivateKeySigner,
+    consensus::{SignableTransaction, TxEip1559, TypedTransaction},
+    eips::eip2718::Encodable2718,
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{address, Address, Bytes, Signature, TxHash, TxKind, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::client::ClientBuilder,
+    rpc::types::eth::TransactionRequest,
+    signers::{
+        local::{coins_bip39::English, MnemonicBuilder, PrivateKeySigner},
+        SignerSync,
+    },
+    sol,
+    sol_types::{Panic, Revert, SolCall, SolError},
+    transports::{RpcError, TransportError},
 };
 use anyhow::Result;
+use dashmap::DashMap;
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::future::Future;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
 use url::Url;
 
+// Multicall3 is deployed at the same address on virtually every EVM chain.
+pub const MULTICALL3_ADDRESS: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+sol! {
+    #[allow(missing_docs)]
+    struct Call3 {
+        address target;
+        bool allowFailure;
+        bytes callData;
+    }
+
+    #[allow(missing_docs)]
+    struct Call3Result {
+        bool success;
+        bytes returnData;
+    }
+
+    #[allow(missing_docs)]
+    struct Call3Value {
+        address target;
+        bool allowFailure;
+        uint256 value;
+        bytes callData;
+    }
+
+    #[allow(missing_docs)]
+    function aggregate3(Call3[] calldata calls) external payable returns (Call3Result[] memory returnData);
+    #[allow(missing_docs)]
+    function aggregate3Value(Call3Value[] calldata calls) external payable returns (Call3Result[] memory returnData);
+    #[allow(missing_docs)]
+    function getEthBalance(address addr) external view returns (uint256 balance);
+    #[allow(missing_docs)]
+    function getCurrentBlockTimestamp() external view returns (uint256 timestamp);
+}
+
+/// Decodes the return data of an `eth_call` to `target`. Empty data (no code at the
+/// address, e.g. a typo'd token address) gets a readable error instead of an ABI
+/// decoding failure.
+pub fn decode_return<C: SolCall>(data: &[u8], target: Address) -> Result<C::Return> {
+    if data.is_empty() {
+        return Err(anyhow::anyhow!(
+            "call to {} ({}) returned no data; is the address a contract implementing this function?",
+            target,
+            C::SIGNATURE
+        ));
+    }
+    C::abi_decode_returns(data, true).map_err(|e| {
+        anyhow::anyhow!(
+            "could not decode {} returned by {}: {}",
+            C::SIGNATURE,
+            target,
+            e
+        )
+    })
+}
+
+/// `decode_return` for one result of a `multicall` batch, with a failed call as an error.
+pub fn decode_result<C: SolCall>(result: &Call3Result, target: Address) -> Result<C::Return> {
+    if !result.success {
+        return Err(match revert_reason(&result.returnData) {
+            Some(reason) => {
+                anyhow::anyhow!("call to {} ({}) reverted: {}", target, C::SIGNATURE, reason)
+            }
+            None => anyhow::anyhow!("call to {} ({}) reverted", target, C::SIGNATURE),
+        });
+    }
+    decode_return::<C>(&result.returnData, target)
+}
+
+/// Hex revert data from a JSON-RPC error response, where nodes put it in `error.data`.
+pub fn revert_data(e: &TransportError) -> Option<Vec<u8>> {
+    let payload = e.as_error_resp()?;
+    let data: String = payload.try_data_as::<String>()?.ok()?;
+    hex::decode(data.trim_start_matches("0x")).ok()
+}
+
+/// A readable reason from revert data: the message of `Error(string)` (`STF`), or what a
+/// `Panic(uint256)` code means (`Panic(0x11): arithmetic underflow or overflow`). `None`
+/// for empty data and custom errors.
+pub fn revert_reason(data: &[u8]) -> Option<String> {
+    if let Ok(revert) = Revert::abi_decode(data, false) {
+        return Some(revert.reason().to_string());
+    }
+    let panic = Panic::abi_decode(data, false).ok()?;
+    Some(format!(
+        "Panic(0x{:02x}): {}",
+        panic.code,
+        panic.kind().map_or("unknown panic code", |k| k.as_str())
+    ))
+}
+
+/// The node's error for a failed call, with the decoded revert reason appended when the
+/// message doesn't already carry it (many nodes only return the raw data).
+pub fn describe_call_error(e: &TransportError) -> String {
+    let message = e.to_string();
+    match revert_data(e).as_deref().and_then(revert_reason) {
+        Some(reason) if !message.contains(&reason) => {
+            format!("{} (revert reason: {})", message, reason)
+        }
+        _ => message,
+    }
+}
+
+/// Scheme and host of an RPC URL, for logs and responses. Paths and query strings often
+/// carry API keys, so they are dropped.
+pub fn endpoint_label(url: &Url) -> String {
+    match url.port() {
+        Some(port) => format!(
+            "{}://{}:{}",
+            url.scheme(),
+            url.host_str().unwrap_or("?"),
+            port
+        ),
+        None => format!("{}://{}", url.scheme(), url.host_str().unwrap_or("?")),
+    }
+}
+
+pub type HttpProvider =
+    alloy::providers::RootProvider<crate::retry::RetryService<FailoverTransport>>;
+
+/// An HTTP provider for `url` whose requests are retried on transient failures.
+pub fn http_provider(url: Url, retry: RetryPolicy) -> HttpProvider {
+    failover_provider(vec![url], retry)
+}
+
+/// An HTTP provider sending each request to the first of `urls` that answers it (see
+/// `FailoverTransport`), retrying when none does.
+pub fn failover_provider(urls: Vec<Url>, retry: RetryPolicy) -> HttpProvider {
+    let transport = FailoverTransport::new(Arc::new(Endpoints::new(urls)));
+    let is_local = transport.is_local();
+    ProviderBuilder::new().on_client(
+        ClientBuilder::default()
+            .layer(RetryLayer::new(retry))
+            .transport(transport, is_local),
+    )
+}
+
+/// The URLs of a comma-separated `ETHEREUM_RPC_URL`, in failover order. A single URL is
+/// a list of one.
+pub fn parse_rpc_urls(value: &str) -> Result<Vec<Url>> {
+    let urls = value
+        .split(',')
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .map(|u| Url::parse(u).map_err(|e| anyhow::anyhow!("Invalid RPC URL: {}", e)))
+        .collect::<Result<Vec<_>>>()?;
+    if urls.is_empty() {
+        return Err(anyhow::anyhow!("No RPC URL given"));
+    }
+    Ok(urls)
+}
+
 #[derive(Clone)]
 pub struct EthereumClient {
-    pub provider: alloy::providers::RootProvider<
-        alloy::transports::http::Http<alloy::transports::http::Client>,
-    >,
-    pub wallet: EthereumWallet,
-    pub signer_address: Address,
+    pub provider: HttpProvider,
+    /// `None` in read-only mode, when no signing key is configured.
+    pub wallet: Option<EthereumWallet>,
+    /// `None` in read-only mode; see `require_signer`.
+    pub signer_address: Option<Address>,
+    /// The wallet's key, for signing digests (typed data) rather than transactions.
+    signer: Option<PrivateKeySigner>,
+    /// Chain the RPC endpoint reported at construction.
+    chain_id: u64,
+    pub tokens: Arc<TokenRegistry>,
+    /// Private-mempool endpoint (e.g. Flashbots Protect) for `private: true` submissions.
+    pub private_relay: Option<(Url, HttpProvider)>,
+    /// Secondary read endpoints, used to cross-check the primary one.
+    pub extra_endpoints: Vec<(Url, HttpProvider)>,
+    /// Whether Multicall3 has code on this chain; `multicall` falls back to single calls if not.
+    pub multicall_available: bool,
+    /// Refuse swaps whose execution rate is worse than the Chainlink rate by more than this (percent).
+    pub max_oracle_deviation: Option<Decimal>,
+    /// Tokens priced off their USD peg rather than a Uniswap pool.
+    pub stablecoins: Vec<Address>,
+    /// Value stablecoins at exactly $1 even when a Chainlink feed exists.
+    pub assume_stablecoin_peg: bool,
+    /// Size of approvals sent on the caller's behalf.
+    pub approval_policy: ApprovalPolicy,
+    /// ERC20 decimals/symbol already read, shared by clones of this client.
+    token_metadata: Arc<DashMap<Address, TokenMetadata>>,
+    /// Block-scoped `eth_call` results for `call_cached`; `None` reads straight through.
+    pub call_cache: Option<Arc<CallCache>>,
+    /// Retries of transient RPC failures, for this client's endpoints.
+    pub rpc_retry: RetryPolicy,
+    /// Most items a batch tool takes in one call; see `tools::check_batch_size`.
+    pub max_batch_size: usize,
+    /// How long a tool call may run before the server abandons it; `None` for no limit.
+    pub tool_timeout: Option<Duration>,
+}
+
+/// ERC20 metadata that never changes once a token is deployed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenMetadata {
+    pub decimals: u8,
+    /// `None` when the token has no readable `symbol()`.
+    pub symbol: Option<String>,
+}
+
+tokio::task_local! {
+    /// Hashes of the transactions the running tool call has handed to a node.
+    static BROADCASTS: Arc<Mutex<Vec<TxHash>>>;
+}
+
+/// Runs `call`, recording in `sink` the hash of each transaction it broadcasts. A hash is
+/// recorded before it is sent, so a call abandoned mid-broadcast still reports it.
+pub async fn recording_broadcasts<F: Future>(sink: Arc<Mutex<Vec<TxHash>>>, call: F) -> F::Output {
+    BROADCASTS.scope(sink, call).await
+}
+
+/// Outcome of broadcasting a signed transaction.
+#[derive(Debug)]
+pub struct Submission {
+    pub tx_hash: TxHash,
+    pub nonce: u64,
+    pub gas_limit: u128,
+    /// `maxFeePerGas`, or the legacy gas price.
+    pub max_fee_per_gas: u128,
+    /// Relay the transaction was sent to, or `None` for the public RPC.
+    pub private_relay: Option<Url>,
+}
+
+impl Submission {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "tx_hash": self.tx_hash.to_string(),
+            "nonce": self.nonce,
+            "gas_limit": self.gas_limit.to_string(),
+            "max_fee_per_gas": self.max_fee_per_gas.to_string(),
+            "private": self.private_relay.is_some(),
+            "submitted_to": match &self.private_relay {
+                Some(url) => endpoint_label(url),
+                None => "public mempool".to_string(),
+            },
+            "note": self.private_relay.as_ref().map(|_| {
+                "Sent to a private relay; it will not appear in the public mempool and may take several blocks to land or be dropped."
+            })
+        })
+    }
+}
+
+/// A complete EIP-1559 transaction for an external signer (hardware wallet, KMS).
+#[derive(Debug)]
+pub struct UnsignedTransaction {
+    pub from: Address,
+    pub tx: TxEip1559,
+}
+
+impl UnsignedTransaction {
+    /// Every field the signer needs, plus the EIP-2718 payload it signs and its hash.
+    pub fn to_json(&self) -> Value {
+        let tx = &self.tx;
+        json!({
+            "type": "0x2",
+            "chain_id": tx.chain_id,
+            "from": self.from.to_string(),
+            "to": match tx.to {
+                TxKind::Call(to) => Some(to.to_string()),
+                TxKind::Create => None,
+            },
+            "nonce": tx.nonce,
+            "gas_limit": tx.gas_limit.to_string(),
+            "max_fee_per_gas": tx.max_fee_per_gas.to_string(),
+            "max_priority_fee_per_gas": tx.max_priority_fee_per_gas.to_string(),
+            "value": tx.value.to_string(),
+            "data": tx.input.to_string(),
+            "access_list": [],
+            "signing_payload": format!("0x{}", hex::encode(tx.encoded_for_signing())),
+            "signing_hash": tx.signature_hash().to_string(),
+            "note": "Unsigned. Sign signing_payload (0x02 || rlp of the fields above) with from's key, or sign the fields with any EIP-1559 wallet, then broadcast the signed transaction with eth_sendRawTransaction. The nonce and fees are current as of now; a transaction sent from from in the meantime invalidates the nonce."
+        })
+    }
 }
 
 impl EthereumClient {
-    pub async fn new(rpc_url: &str, private_key: &str) -> Result<Self> {
-        let signer = PrivateKeySigner::from_str(private_key)?;
-        let signer_address = signer.address();
-        let wallet = EthereumWallet::from(signer);
+    /// Connects to `rpc_url`, refusing endpoints on chains outside `chains::supported()`.
+    /// `wallet` is a hex private key or any other `WalletSource`.
+    pub async fn new(rpc_url: &str, wallet: impl Into<WalletSource>) -> Result<Self> {
+        let supported: Vec<u64> = chains::supported().iter().map(|c| c.chain_id).collect();
+        Self::connect(
+            rpc_url,
+            Some(&wallet.into()),
+            &supported,
+            RetryPolicy::default(),
+        )
+        .await
+    }
 
-        let url = Url::parse(rpc_url)?;
-        let provider = ProviderBuilder::new().on_http(url);
+    /// Like `new`, without a signing key: reads work, signing and sending fail.
+    pub async fn read_only(rpc_url: &str) -> Result<Self> {
+        let supported: Vec<u64> = chains::supported().iter().map(|c| c.chain_id).collect();
+        Self::connect(rpc_url, None, &supported, RetryPolicy::default()).await
+    }
+
+    /// Connects to `rpc_url` and reads its chain id, failing unless it is one of
+    /// `supported_chains`: the tools' contract addresses are per chain, and calling them on
+    /// the wrong network returns garbage rather than errors. Transient RPC failures are
+    /// retried per `retry`. A comma-separated `rpc_url` lists fallback endpoints, which
+    /// must all be on the same chain.
+    pub async fn connect(
+        rpc_url: &str,
+        wallet: Option<&WalletSource>,
+        supported_chains: &[u64],
+        retry: RetryPolicy,
+    ) -> Result<Self> {
+        Self::connect_endpoints(parse_rpc_urls(rpc_url)?, wallet, supported_chains, retry).await
+    }
+
+    /// `connect` with the endpoints already parsed, in failover order.
+    pub async fn connect_endpoints(
+        urls: Vec<Url>,
+        wallet: Option<&WalletSource>,
+        supported_chains: &[u64],
+        retry: RetryPolicy,
+    ) -> Result<Self> {
+        let signer = wallet.map(load_signer).transpose()?;
+        let signer_address = signer.as_ref().map(|s| s.address());
+        let wallet = signer.clone().map(EthereumWallet::from);
+
+        let chain_id = endpoints_chain_id(&urls, retry).await?;
+        if !supported_chains.contains(&chain_id) {
+            return Err(anyhow::anyhow!(
+                "RPC endpoint is on chain {}, which is not supported (supported: {}). Set SUPPORTED_CHAIN_IDS to allow it.",
+                chain_id,
+                supported_chains
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
 
         Ok(Self {
-            provider,
+            provider: failover_provider(urls, retry),
             wallet,
             signer_address,
+            signer,
+            chain_id,
+            tokens: Arc::new(TokenRegistry::builtin()),
+            private_relay: None,
+            extra_endpoints: Vec::new(),
+            multicall_available: true,
+            max_oracle_deviation: None,
+            stablecoins: oracle::default_stablecoins(chain_id),
+            assume_stablecoin_peg: false,
+            approval_policy: ApprovalPolicy::Exact,
+            token_metadata: Arc::new(DashMap::new()),
+            call_cache: None,
+            rpc_retry: retry,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            tool_timeout: Some(Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS)),
+        })
+    }
+
+    /// Builds a fully configured client: Multicall3 detection, swap guardrails, private
+    /// relay and the (optionally validated) token registry. Used at startup and by
+    /// `admin/reinitialize`.
+    pub async fn from_config(config: &Config) -> Result<Self> {
+        let retry = RetryPolicy {
+            max_retries: config.rpc_max_retries,
+            base_delay: Duration::from_millis(config.rpc_retry_base_ms),
+        };
+        let supported = match &config.supported_chain_ids {
+            Some(supported) => supported.clone(),
+            None => chains::supported().iter().map(|c| c.chain_id).collect(),
+        };
+        let urls = config
+            .rpc_urls
+            .iter()
+            .map(|u| Url::parse(u))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut client = Self::connect_endpoints(urls, config.wallet.as_ref(), &supported, retry)
+            .await?
+            .detect_multicall()
+            .await;
+        client.check_signer(config.signer_check).await?;
+        info!(
+            "Connected to chain {} via {}",
+            client.chain_id,
+            client.active_endpoint()
+        );
+        if config.rpc_urls.len() > 1 {
+            info!(
+                "RPC failover order: {}",
+                client
+                    .rpc_endpoints()
+                    .urls()
+                    .iter()
+                    .map(endpoint_label)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        client.max_oracle_deviation = config.max_oracle_deviation;
+        if let Some(relay) = &config.private_rpc_url {
+            client = client.with_private_relay(relay)?;
+        }
+        for url in &config.extra_rpc_urls {
+            client = client.with_extra_endpoint(url)?;
+        }
+
+        let registry = TokenRegistry::load(config.token_list_path.as_deref())?
+            .with_policy(config.symbol_resolution)
+            .for_chain(client.chain_id)
+            .validate(&client, config.validate_token_list)
+            .await;
+        client.tokens = Arc::new(registry);
+
+        client.assume_stablecoin_peg = config.assume_stablecoin_peg;
+        client.approval_policy = config.approval_policy;
+        client.max_batch_size = config.max_batch_size;
+        client.tool_timeout = (config.tool_timeout_seconds > 0)
+            .then(|| Duration::from_secs(config.tool_timeout_seconds));
+        client = client.with_call_cache(Duration::from_millis(config.call_cache_head_ms));
+        if let Some(stablecoins) = &config.stablecoins {
+            client.stablecoins =
+                stablecoins
+                    .iter()
+                    .map(|s| match Address::from_str(s) {
+                        Ok(addr) => Ok(addr),
+                        Err(_) => client.tokens.resolve(s).map(|t| t.address).ok_or_else(|| {
+                            anyhow::anyhow!("STABLECOINS: unknown token symbol {}", s)
+                        }),
+                    })
+                    .collect::<Result<_>>()?;
+        }
+        Ok(client)
+    }
+
+    /// Checks once (at startup) whether Multicall3 is deployed at its canonical address.
+    /// If the check itself fails, Multicall3 is assumed present.
+    pub async fn detect_multicall(mut self) -> Self {
+        match self.provider.get_code_at(MULTICALL3_ADDRESS).await {
+            Ok(code) if code.is_empty() => {
+                warn!(
+                    "Multicall3 not deployed at {}; batched reads will use individual calls",
+                    MULTICALL3_ADDRESS
+                );
+                self.multicall_available = false;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Could not check for Multicall3: {}", e),
+        }
+        self
+    }
+
+    /// Startup sanity check that the chain accepts calls from the signer: a zero-value
+    /// self-call via `eth_call`, carrying the chain id. A rejection is logged under `Warn`
+    /// and fails startup under `Strict`; a node that can't be reached only logs.
+    pub async fn check_signer(&self, policy: SignerCheck) -> Result<()> {
+        let Some(signer) = self.signer_address else {
+            return Ok(());
+        };
+        if policy == SignerCheck::Off {
+            return Ok(());
+        }
+        let tx = TransactionRequest::default()
+            .from(signer)
+            .to(signer)
+            .value(U256::ZERO)
+            .with_chain_id(self.chain_id);
+        match self.provider.call(&tx).await {
+            Ok(_) => {
+                info!("Signer {} accepted on chain {}", signer, self.chain_id);
+                Ok(())
+            }
+            Err(e @ RpcError::ErrorResp(_)) => {
+                let message = format!(
+                    "Chain {} rejected a zero-value self-call from signer {}: {}. Transactions from this key are likely to fail; check the key and SUPPORTED_CHAIN_IDS.",
+                    self.chain_id,
+                    signer,
+                    describe_call_error(&e)
+                );
+                if policy == SignerCheck::Strict {
+                    return Err(anyhow::anyhow!("{} (SIGNER_CHECK=strict)", message));
+                }
+                warn!("{}", message);
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Could not check signer {}: {}", signer, e);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn with_extra_endpoint(mut self, rpc_url: &str) -> Result<Self> {
+        let url = Url::parse(rpc_url)?;
+        let provider = http_provider(url.clone(), self.rpc_retry);
+        info!("Secondary RPC endpoint: {}", endpoint_label(&url));
+        self.extra_endpoints.push((url, provider));
+        Ok(self)
+    }
+
+    /// Signs a 32-byte digest, such as an EIP-712 signing hash, with the server wallet's key.
+    pub fn sign_hash(&self, hash: &B256) -> Result<Signature> {
+        let signer = self.signer.as_ref().ok_or_else(read_only_error)?;
+        Ok(signer.sign_hash_sync(hash)?)
+    }
+
+    /// The signer's key as hex, so the audit log can redact it whichever way it was loaded.
+    /// `None` in read-only mode.
+    pub(crate) fn signing_key_hex(&self) -> Option<String> {
+        self.signer.as_ref().map(|s| hex::encode(s.to_bytes()))
+    }
+
+    /// The server wallet's address, for tools that sign, send or act as the wallet.
+    /// Fails with an explanation in read-only mode.
+    pub fn require_signer(&self) -> Result<Address> {
+        self.signer_address.ok_or_else(read_only_error)
+    }
+
+    /// The sender for simulations that only need some `from`: the signer, or a fixed
+    /// placeholder in read-only mode.
+    pub fn simulation_sender(&self) -> Address {
+        self.signer_address.unwrap_or(READ_ONLY_SENDER)
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// The endpoints `provider` fails over between, in order.
+    pub fn rpc_endpoints(&self) -> &Endpoints {
+        self.provider.client().transport().inner().endpoints()
+    }
+
+    /// Label of the endpoint reads and broadcasts currently go to, for logs and responses.
+    pub fn active_endpoint(&self) -> String {
+        endpoint_label(self.rpc_endpoints().active())
+    }
+
+    /// Contract addresses for the connected chain. Errors on chains allowed through
+    /// `SUPPORTED_CHAIN_IDS` that have no entry in `chains::supported()`.
+    pub fn chain(&self) -> Result<&'static ChainConfig> {
+        chains::get(self.chain_id).ok_or_else(|| {
+            ToolError::not_supported(format!(
+                "No contract addresses are configured for chain {}",
+                self.chain_id
+            ))
+        })
+    }
+
+    /// Uniswap V3 deployment on the connected chain.
+    pub fn uniswap_v3(&self) -> Result<UniswapV3> {
+        let chain = self.chain()?;
+        chain.uniswap_v3.ok_or_else(|| {
+            ToolError::not_supported(format!("Uniswap V3 is not configured for {}", chain.name))
         })
     }
+
+    /// Decimals and symbol of `token`, read once and then served from the cache. A failed
+    /// `decimals()` is an error and is not cached; a missing symbol is cached as `None`.
+    pub async fn get_token_metadata(&self, token: Address) -> Result<TokenMetadata> {
+        if let Some(cached) = self.token_metadata.get(&token) {
+            return Ok(cached.clone());
+        }
+        let call = |data: Vec<u8>| {
+            let req = TransactionRequest::default().to(token).input(data.into());
+            async move { self.call_cached(&req).await }
+        };
+        let (decimals, symbol) = futures::join!(
+            call(decimalsCall {}.abi_encode()),
+            call(symbolCall {}.abi_encode())
+        );
+        let metadata = TokenMetadata {
+            decimals: decode_return::<decimalsCall>(&decimals?, token)?._0,
+            symbol: symbol.ok().and_then(|s| decode_symbol(&s)),
+        };
+        self.token_metadata.insert(token, metadata.clone());
+        Ok(metadata)
+    }
+
+    /// This client reading through `provider`. The call cache is not carried over: its
+    /// entries are answers from the original endpoint.
+    pub fn on_provider(&self, provider: HttpProvider) -> Self {
+        Self {
+            provider,
+            call_cache: None,
+            ..self.clone()
+        }
+    }
+
+    /// Caches `call_cached` reads per block, re-reading the head block once it is older
+    /// than `head_ttl`. A zero `head_ttl` disables the cache.
+    pub fn with_call_cache(mut self, head_ttl: Duration) -> Self {
+        self.call_cache = (!head_ttl.is_zero()).then(|| Arc::new(CallCache::new(head_ttl)));
+        self
+    }
+
+    /// `eth_call` through the block cache. The call is pinned to the cached head block, so a
+    /// repeat within that block is answered without an RPC call and never mixes blocks.
+    /// Failed calls are not cached. Without a cache (or a readable head) this is a plain
+    /// `eth_call` at latest.
+    pub async fn call_cached(&self, tx: &TransactionRequest) -> Result<Bytes, TransportError> {
+        let Some(cache) = &self.call_cache else {
+            return self.provider.call(tx).await;
+        };
+        let block = match cache.fresh_head() {
+            Some(block) => block,
+            None => match self.provider.get_block_number().await {
+                Ok(block) => {
+                    cache.set_head(block);
+                    block
+                }
+                Err(_) => return self.provider.call(tx).await,
+            },
+        };
+        let key = serde_json::to_string(tx).unwrap_or_default();
+        if let Some(data) = cache.get(block, &key) {
+            return Ok(data);
+        }
+        let data = self.provider.call(tx).block(block.into()).await?;
+        cache.insert(block, key, data.clone());
+        Ok(data)
+    }
+
+    pub fn with_private_relay(mut self, relay_url: &str) -> Result<Self> {
+        let url = Url::parse(relay_url)?;
+        info!("Private transaction relay: {}", endpoint_label(&url));
+        let relay = http_provider(url.clone(), self.rpc_retry);
+        self.private_relay = Some((url, relay));
+        Ok(self)
+    }
+
+    /// Completes `tx` as sent from `from`: nonce, chain id, gas limit and EIP-1559 fees are
+    /// filled from the node where unset. Fails unless `from` can cover
+    /// `value + gas * maxFeePerGas`.
+    pub async fn fill_transaction(
+        &self,
+        tx: TransactionRequest,
+        from: Address,
+    ) -> Result<TransactionRequest> {
+        let mut tx = tx.from(from);
+        if tx.nonce.is_none() {
+            let nonce = self.provider.get_transaction_count(from).await?;
+            tx = tx.with_nonce(nonce);
+        }
+        if tx.chain_id.is_none() {
+            tx = tx.with_chain_id(self.chain_id);
+        }
+        if tx.gas.is_none() {
+            let gas = self.provider.estimate_gas(&tx).await?;
+            tx = tx.with_gas_limit(gas);
+        }
+        if tx.gas_price.is_none()
+            && (tx.max_fee_per_gas.is_none() || tx.max_priority_fee_per_gas.is_none())
+        {
+            let fees = self.provider.estimate_eip1559_fees(None).await?;
+            let max_fee = tx.max_fee_per_gas.unwrap_or(fees.max_fee_per_gas);
+            let priority = tx
+                .max_priority_fee_per_gas
+                .unwrap_or(fees.max_priority_fee_per_gas)
+                .min(max_fee);
+            tx = tx
+                .with_max_fee_per_gas(max_fee)
+                .with_max_priority_fee_per_gas(priority);
+        }
+
+        let gas_limit = tx.gas.unwrap_or_default();
+        let max_fee_per_gas = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default();
+        let value = tx.value.unwrap_or_default();
+        let required = value + U256::from(gas_limit) * U256::from(max_fee_per_gas);
+        let balance = self.provider.get_balance(from).await?;
+        if balance < required {
+            return Err(anyhow::anyhow!(
+                "Insufficient balance: {} holds {} wei but the transaction needs up to {} wei ({} value + {} gas at {} wei/gas)",
+                from,
+                balance,
+                required,
+                value,
+                gas_limit,
+                max_fee_per_gas
+            ));
+        }
+        Ok(tx)
+    }
+
+    /// `tx` filled for `from` (see `fill_transaction`) and returned for signing elsewhere
+    /// instead of being signed with the server wallet.
+    pub async fn unsigned_transaction(
+        &self,
+        tx: TransactionRequest,
+        from: Address,
+    ) -> Result<UnsignedTransaction> {
+        let filled = self.fill_transaction(tx, from).await?;
+        match filled.build_typed_tx() {
+            Ok(TypedTransaction::Eip1559(tx)) => Ok(UnsignedTransaction { from, tx }),
+            Ok(other) => Err(anyhow::anyhow!(
+                "Expected an EIP-1559 transaction, built type {}",
+                other.tx_type() as u8
+            )),
+            Err(tx) => Err(anyhow::anyhow!(
+                "Transaction is missing fields: {}",
+                tx.complete_1559().err().unwrap_or_default().join(", ")
+            )),
+        }
+    }
+
+    /// Fills nonce, gas and fees from the public node, checks the wallet can cover
+    /// `value + gas * maxFeePerGas`, signs with the server wallet and broadcasts. With `private`, the raw transaction goes only to the private relay,
+    /// keeping it out of the public mempool (and away from sandwich bots).
+    pub async fn submit_transaction(
+        &self,
+        tx: TransactionRequest,
+        private: bool,
+    ) -> Result<Submission> {
+        let relay = match (private, &self.private_relay) {
+            (false, _) => None,
+            (true, Some(relay)) => Some(relay),
+            (true, None) => {
+                return Err(anyhow::anyhow!(
+                    "private submission requested but no PRIVATE_RPC_URL is configured"
+                ))
+            }
+        };
+
+        let (Some(signer_address), Some(wallet)) = (self.signer_address, &self.wallet) else {
+            return Err(read_only_error());
+        };
+        let tx = self.fill_transaction(tx, signer_address).await?;
+        let gas_limit = tx.gas.unwrap_or_default();
+        let max_fee_per_gas = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default();
+        let nonce = tx.nonce.unwrap_or_default();
+
+        let envelope = tx
+            .build(wallet)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to sign transaction: {}", e))?;
+        let raw = envelope.encoded_2718();
+        let _ = BROADCASTS.try_with(|sink| {
+            sink.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(*envelope.tx_hash())
+        });
+
+        let tx_hash = match relay {
+            Some((_, relay)) => *relay.send_raw_transaction(&raw).await?.tx_hash(),
+            None => *self.provider.send_raw_transaction(&raw).await?.tx_hash(),
+        };
+        Ok(Submission {
+            tx_hash,
+            nonce,
+            gas_limit,
+            max_fee_per_gas,
+            private_relay: relay.map(|(url, _)| url.clone()),
+        })
+    }
+
+    /// Executes `calls` in a single `eth_call` through Multicall3's `aggregate3`.
+    /// Results are returned in call order; calls made with `allowFailure` report
+    /// `success = false` instead of failing the batch.
+    ///
+    /// Without Multicall3 the calls are issued concurrently one by one, with the same
+    /// result shape.
+    pub async fn multicall(&self, calls: Vec<Call3>) -> Result<Vec<Call3Result>> {
+        if calls.is_empty() {
+            return Ok(Vec::new());
+        }
+        if !self.multicall_available {
+            debug!(
+                "Multicall3 unavailable; issuing {} calls individually",
+                calls.len()
+            );
+            return self.call_individually(calls).await;
+        }
+        let data = aggregate3Call { calls }.abi_encode();
+        let req = TransactionRequest::default()
+            .to(MULTICALL3_ADDRESS)
+            .input(data.into());
+        let res = self.call_cached(&req).await?;
+        Ok(decode_return::<aggregate3Call>(&res, MULTICALL3_ADDRESS)?.returnData)
+    }
+
+    /// Only the node's answer to a call (a revert or other error response) is a failed
+    /// `allowFailure` call, with any revert data kept as its return data; transport
+    /// failures fail the batch rather than pass for a revert.
+    async fn call_individually(&self, calls: Vec<Call3>) -> Result<Vec<Call3Result>> {
+        let results = join_all(calls.iter().map(|c| {
+            let req = TransactionRequest::default()
+                .to(c.target)
+                .input(c.callData.clone().into());
+            async move { self.call_cached(&req).await }
+        }))
+        .await;
+        calls
+            .iter()
+            .zip(results)
+            .map(|(call, res)| match res {
+                Ok(data) => Ok(Call3Result {
+                    success: true,
+                    returnData: data,
+                }),
+                Err(RpcError::ErrorResp(payload)) if call.allowFailure => Ok(Call3Result {
+                    success: false,
+                    returnData: payload
+                        .data
+                        .as_ref()
+                        .and_then(|d| serde_json::from_str::<Bytes>(d.get()).ok())
+                        .unwrap_or_default(),
+                }),
+                Err(e) => {
+                    Err(anyhow::Error::new(e).context(format!("call to {} failed", call.target)))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Placeholder `from` for simulations in read-only mode. It holds nothing, so simulations
+/// that depend on the sender's balance or allowances fail unless state is overridden.
+pub const READ_ONLY_SENDER: Address = address!("00000000000000000000000000000000000000fe");
+
+/// The chain `urls` are on. Every endpoint that answers must report the same chain, so
+/// failing over never switches networks; one that is down at startup is kept as a
+/// fallback (with a warning), but at least one must answer.
+async fn endpoints_chain_id(urls: &[Url], retry: RetryPolicy) -> Result<u64> {
+    let answers = join_all(urls.iter().map(|url| async move {
+        let chain_id = http_provider(url.clone(), retry).get_chain_id().await;
+        (url, chain_id)
+    }))
+    .await;
+    let mut chain: Option<(&Url, u64)> = None;
+    let mut first_error = None;
+    for (url, answer) in answers {
+        match answer {
+            Ok(id) => match chain {
+                Some((other, other_id)) if other_id != id => {
+                    return Err(anyhow::anyhow!(
+                        "RPC endpoints are on different chains: {} is on {}, {} is on {}",
+                        endpoint_label(other),
+                        other_id,
+                        endpoint_label(url),
+                        id
+                    ))
+                }
+                Some(_) => {}
+                None => chain = Some((url, id)),
+            },
+            Err(e) if urls.len() > 1 => {
+                warn!(
+                    "Could not read the chain id from {} ({}); keeping it as a fallback",
+                    endpoint_label(url),
+                    e
+                );
+                first_error.get_or_insert((url, e));
+            }
+            Err(e) => {
+                first_error.get_or_insert((url, e));
+            }
+        }
+    }
+    match (chain, first_error) {
+        (Some((_, id)), _) => Ok(id),
+        (None, Some((url, e))) => Err(anyhow::anyhow!(
+            "Could not read the chain id from {}: {}",
+            endpoint_label(url),
+            e
+        )),
+        (None, None) => Err(anyhow::anyhow!("No RPC URL given")),
+    }
+}
+
+fn read_only_error() -> anyhow::Error {
+    ToolError::not_supported(
+        "The server is in read-only mode: no signing key is configured. Set PRIVATE_KEY, MNEMONIC or KEYSTORE_PATH to sign or send transactions."
+    )
+}
+
+/// The signing key `wallet` describes. Mnemonics are derived with alloy's BIP-39/BIP-32
+/// support; keystores are decrypted with their password.
+pub fn load_signer(wallet: &WalletSource) -> Result<PrivateKeySigner> {
+    match wallet {
+        WalletSource::PrivateKey(key) => PrivateKeySigner::from_str(key.trim())
+            .map_err(|e| anyhow::anyhow!("PRIVATE_KEY is not a valid private key: {}", e)),
+        WalletSource::Mnemonic {
+            phrase,
+            derivation_path,
+            index,
+        } => {
+            let builder = MnemonicBuilder::<English>::default().phrase(phrase.trim());
+            let builder = match derivation_path {
+                Some(path) => builder
+                    .derivation_path(path)
+                    .map_err(|e| anyhow::anyhow!("Invalid DERIVATION_PATH {:?}: {}", path, e))?,
+                None => builder.index(*index)?,
+            };
+            builder
+                .build()
+                .map_err(|e| anyhow::anyhow!("MNEMONIC could not be used: {}", e))
+        }
+        WalletSource::Keystore { path, password } => {
+            PrivateKeySigner::decrypt_keystore(path, password)
+                .map_err(|e| anyhow::anyhow!("Could not decrypt keystore {}: {}", path, e))
+        }
+    }
+}
+
+/// Convenience constructor for a Multicall3 sub-call that may fail without reverting the batch.
+pub fn call3(target: Address, call_data: Vec<u8>) -> Call3 {
+    Call3 {
+        target,
+        allowFailure: true,
+        callData: Bytes::from(call_data),
+    }
 }
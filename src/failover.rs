@@ -0,0 +1,114 @@
+use crate::ethereum::endpoint_label;
+use crate::retry::failure_worth_retrying;
+use alloy::rpc::json_rpc::{RequestPacket, ResponsePacket};
+use alloy::transports::http::{Client, Http};
+use alloy::transports::{TransportError, TransportFut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::Service;
+use tracing::warn;
+use url::Url;
+
+/// RPC endpoints in order of preference, and the one requests currently go to.
+#[derive(Debug)]
+pub struct Endpoints {
+    urls: Vec<Url>,
+    active: AtomicUsize,
+}
+
+impl Endpoints {
+    /// Starts on the first of `urls`, which must not be empty.
+    pub fn new(urls: Vec<Url>) -> Self {
+        assert!(!urls.is_empty(), "at least one RPC endpoint is required");
+        Self {
+            urls,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn urls(&self) -> &[Url] {
+        &self.urls
+    }
+
+    /// The endpoint requests are sent to first.
+    pub fn active(&self) -> &Url {
+        &self.urls[self.active.load(Ordering::Relaxed)]
+    }
+
+    /// Moves off endpoint `failed` to the one after it (wrapping around), unless a
+    /// concurrent request has already moved on, so one outage advances the list once.
+    fn fail_over(&self, failed: usize) -> usize {
+        let next = (failed + 1) % self.urls.len();
+        let _ = self
+            .active
+            .compare_exchange(failed, next, Ordering::Relaxed, Ordering::Relaxed);
+        next
+    }
+}
+
+/// HTTP transport over several endpoints. Requests go to the active endpoint; one that
+/// fails with a connection error, rate limit or server error is sent to the next
+/// endpoint, which becomes the active one. Only when every endpoint has failed does the
+/// error reach the caller (and the retry layer above, which starts over after its backoff).
+#[derive(Debug, Clone)]
+pub struct FailoverTransport {
+    endpoints: Arc<Endpoints>,
+    transports: Arc<[Http<Client>]>,
+}
+
+impl FailoverTransport {
+    pub fn new(endpoints: Arc<Endpoints>) -> Self {
+        let transports = endpoints.urls.iter().cloned().map(Http::new).collect();
+        Self {
+            endpoints,
+            transports,
+        }
+    }
+
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    /// Whether every endpoint is on this machine, as alloy's `Http::guess_local` judges.
+    pub fn is_local(&self) -> bool {
+        self.transports.iter().all(Http::guess_local)
+    }
+}
+
+impl Service<RequestPacket> for FailoverTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // `Http` is always ready: each request gets its own connection from the pool.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let this = self.clone();
+        Box::pin(async move {
+            let count = this.transports.len();
+            let start = this.endpoints.active.load(Ordering::Relaxed);
+            let mut tried = 0;
+            loop {
+                let index = (start + tried) % count;
+                let mut transport = this.transports[index].clone();
+                let result = transport.call(request.clone()).await;
+                tried += 1;
+                let reason = match failure_worth_retrying(&request, &result) {
+                    Some(reason) if tried < count => reason,
+                    _ => return result,
+                };
+                let next = this.endpoints.fail_over(index);
+                warn!(
+                    "RPC endpoint {} failed ({}); failing over to {}",
+                    endpoint_label(&this.endpoints.urls[index]),
+                    reason,
+                    endpoint_label(&this.endpoints.urls[next])
+                );
+            }
+        })
+    }
+}
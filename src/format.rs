@@ -0,0 +1,85 @@
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+
+static THOUSANDS_SEPARATORS: OnceLock<bool> = OnceLock::new();
+
+/// Enables `,` grouping in human-readable amounts. Set once at startup from config.
+pub fn set_thousands_separators(enabled: bool) {
+    let _ = THOUSANDS_SEPARATORS.set(enabled);
+}
+
+fn thousands_separators() -> bool {
+    *THOUSANDS_SEPARATORS.get().unwrap_or(&false)
+}
+
+/// Renders a unix timestamp (seconds) as `{"unix": .., "iso": "YYYY-MM-DDTHH:MM:SSZ"}`.
+pub fn timestamp(unix_secs: u64) -> Value {
+    json!({
+        "unix": unix_secs,
+        "iso": rfc3339(unix_secs)
+    })
+}
+
+/// RFC-3339 UTC rendering of a unix timestamp.
+pub fn rfc3339(unix_secs: u64) -> String {
+    let days = (unix_secs / 86_400) as i64;
+    let secs_of_day = unix_secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        (secs_of_day % 3600) / 60,
+        secs_of_day % 60
+    )
+}
+
+/// Formats a decimal amount string for display, grouping the integer part when enabled.
+/// Machine-readable fields (`raw_*`) should never go through this.
+pub fn display_amount(amount: &str) -> String {
+    if thousands_separators() {
+        group_thousands(amount)
+    } else {
+        amount.to_string()
+    }
+}
+
+pub fn group_thousands(amount: &str) -> String {
+    let (sign, unsigned) = match amount.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", amount),
+    };
+    let (int_part, frac_part) = match unsigned.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (unsigned, None),
+    };
+
+    let mut grouped = String::with_capacity(int_part.len() + int_part.len() / 3);
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    match frac_part {
+        Some(f) => format!("{}{}.{}", sign, grouped, f),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+// Howard Hinnant's days-to-civil algorithm (proleptic Gregorian calendar).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -1,4 +1,21 @@
+pub mod abi;
+pub mod approval;
+pub mod audit;
+pub mod cache;
+pub mod chains;
 pub mod config;
+pub mod denylist;
+pub mod error;
 pub mod ethereum;
+pub mod failover;
+pub mod format;
+pub mod limits;
+pub mod oracle;
+pub mod quoter;
+pub mod retry;
+pub mod rpc;
 pub mod server;
+pub mod session;
+pub mod simulation;
+pub mod tokens;
 pub mod tools;
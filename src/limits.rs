@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// JSON-RPC error code returned when a request is refused for capacity.
+pub const SERVER_BUSY_CODE: i32 = -32000;
+
+/// Caps requests in flight, both server-wide and per client (the stdio session, or the
+/// peer's IP address over HTTP), so one client cannot monopolize a shared deployment.
+/// Requests over either limit are refused immediately rather than queued.
+pub struct RequestLimiter {
+    global: Arc<Semaphore>,
+    global_limit: usize,
+    per_client: usize,
+    clients: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+/// Held for the duration of a request; dropping it frees both slots.
+pub struct RequestPermit {
+    _global: OwnedSemaphorePermit,
+    _client: OwnedSemaphorePermit,
+}
+
+/// Which limit refused a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Busy {
+    Server { limit: usize },
+    Client { limit: usize },
+}
+
+impl fmt::Display for Busy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Busy::Server { limit } => write!(
+                f,
+                "Server busy: {} requests already in flight; retry shortly",
+                limit
+            ),
+            Busy::Client { limit } => write!(
+                f,
+                "Server busy: this client already has {} requests in flight; retry shortly",
+                limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Busy {}
+
+impl RequestLimiter {
+    pub fn new(global: usize, per_client: usize) -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(global.max(1))),
+            global_limit: global.max(1),
+            per_client: per_client.max(1),
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn try_acquire(&self, client_key: &str) -> Result<RequestPermit, Busy> {
+        let client = {
+            let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+            // Outstanding permits hold a clone of their semaphore; idle clients are dropped.
+            clients.retain(|_, s| Arc::strong_count(s) > 1);
+            clients
+                .entry(client_key.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(self.per_client)))
+                .clone()
+        };
+        let client = client.try_acquire_owned().map_err(|_| Busy::Client {
+            limit: self.per_client,
+        })?;
+        let global = self
+            .global
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| Busy::Server {
+                limit: self.global_limit,
+            })?;
+        Ok(RequestPermit {
+            _global: global,
+            _client: client,
+        })
+    }
+
+    /// Requests currently holding a permit.
+    pub fn in_flight(&self) -> usize {
+        self.global_limit - self.global.available_permits()
+    }
+}
@@ -2,9 +2,26 @@ use dotenv::dotenv;
 use tracing::info;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+pub mod abi;
+pub mod approval;
+pub mod audit;
+pub mod cache;
+pub mod chains;
 pub mod config;
+pub mod denylist;
+pub mod error;
 pub mod ethereum;
+pub mod failover;
+pub mod format;
+pub mod limits;
+pub mod oracle;
+pub mod quoter;
+pub mod retry;
+pub mod rpc;
 pub mod server;
+pub mod session;
+pub mod simulation;
+pub mod tokens;
 pub mod tools;
 
 #[tokio::main]
@@ -20,9 +37,10 @@ async fn main() -> anyhow::Result<()> {
     info!("Starting Ethereum Trading MCP Server...");
 
     let config = config::Config::from_env()?;
-    let eth_client = ethereum::EthereumClient::new(&config.rpc_url, &config.private_key).await?;
+    format::set_thousands_separators(config.thousands_separators);
+    let eth_client = ethereum::EthereumClient::from_config(&config).await?;
 
-    server::run(eth_client).await?;
+    server::run(eth_client, config).await?;
 
     Ok(())
 }
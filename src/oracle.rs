@@ -0,0 +1,333 @@
+use crate::chains;
+use crate::ethereum::{
+    call3, decode_result, decode_return, getCurrentBlockTimestampCall, Call3, Call3Result,
+    EthereumClient, MULTICALL3_ADDRESS,
+};
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{address, Address, I256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound);
+    #[allow(missing_docs)]
+    function decimals() external view returns (uint8);
+}
+
+/// Mainnet USDC, USDT and DAI, trusted on chains without a stablecoin list.
+pub const DEFAULT_STABLECOINS: [Address; 3] = [
+    address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+    address!("dAC17F958D2ee523a2206206994597C13D831ec7"),
+    address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
+];
+
+/// How old (by the chain's clock) a token feed's answer may be before `token_feed_price`
+/// refuses it: twice the one-hour heartbeat of the feeds in `chains`.
+pub const DEFAULT_MAX_FEED_AGE_SECS: u64 = 7200;
+
+/// A token's price read from its own Chainlink USD feed.
+#[derive(Debug, Clone)]
+pub struct FeedPrice {
+    pub feed: Address,
+    pub price_usd: Decimal,
+    /// ETH/USD from the same batch, for the price in ETH.
+    pub eth_price_usd: Decimal,
+    /// `updatedAt` of the round read.
+    pub updated_at: u64,
+    pub age_seconds: u64,
+}
+
+impl FeedPrice {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "feed": self.feed.to_string(),
+            "updated_at": self.updated_at,
+            "age_seconds": self.age_seconds
+        })
+    }
+}
+
+/// USD value used for a trusted stablecoin, and where it came from.
+#[derive(Debug, Clone)]
+pub struct StablecoinPeg {
+    pub price_usd: Decimal,
+    /// The Chainlink feed read, or `None` when $1 was assumed.
+    pub feed: Option<Address>,
+}
+
+impl StablecoinPeg {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "price_usd": self.price_usd,
+            "source": match self.feed {
+                Some(_) => "Chainlink USD feed",
+                None => "assumed $1 peg",
+            },
+            "feed": self.feed.map(|f| f.to_string())
+        })
+    }
+}
+
+/// The stablecoins trusted when `STABLECOINS` is unset: the chain's list, bridged
+/// variants included.
+pub fn default_stablecoins(chain_id: u64) -> Vec<Address> {
+    match chains::get(chain_id) {
+        Some(chain) if !chain.stablecoins.is_empty() => {
+            chain.stablecoins.iter().map(|s| s.address).collect()
+        }
+        _ => DEFAULT_STABLECOINS.to_vec(),
+    }
+}
+
+/// Live USD peg of `token` if it is one of the client's trusted stablecoins: its Chainlink
+/// USD feed when one is known, else $1. A feed answer older than `DEFAULT_MAX_FEED_AGE_SECS`
+/// is an error, not a peg.
+pub async fn stablecoin_peg(
+    client: &EthereumClient,
+    token: Address,
+) -> Result<Option<StablecoinPeg>> {
+    if !client.stablecoins.contains(&token) {
+        return Ok(None);
+    }
+    let feed = usd_feed(client.chain_id(), token).filter(|_| !client.assume_stablecoin_peg);
+    let price_usd = match feed {
+        Some(feed) => fresh_prices(client, &[feed], DEFAULT_MAX_FEED_AGE_SECS).await?[0],
+        None => Decimal::ONE,
+    };
+    Ok(Some(StablecoinPeg { price_usd, feed }))
+}
+
+/// Chainlink ETH/USD feed on the client's chain.
+pub fn eth_usd_feed(client: &EthereumClient) -> Result<Address> {
+    let chain = client.chain()?;
+    chain.eth_usd_feed.ok_or_else(|| {
+        anyhow::anyhow!("No Chainlink ETH/USD feed is configured for {}", chain.name)
+    })
+}
+
+/// Chainlink USD feed for `token` on `chain_id`, if one is known.
+pub fn usd_feed(chain_id: u64, token: Address) -> Option<Address> {
+    chains::get(chain_id)?
+        .token_usd_feeds
+        .iter()
+        .find(|(t, _)| *t == token)
+        .map(|(_, feed)| *feed)
+}
+
+/// `token`'s price from its own Chainlink USD feed, or `None` when it has none. An answer
+/// older than `max_age_secs` by the latest block's timestamp is an error, as is any round
+/// `price_from_round` rejects. One `multicall` reads the feed, ETH/USD and the block time.
+pub async fn token_feed_price(
+    client: &EthereumClient,
+    token: Address,
+    max_age_secs: u64,
+) -> Result<Option<FeedPrice>> {
+    let Some(feed) = usd_feed(client.chain_id(), token) else {
+        return Ok(None);
+    };
+    let eth_feed = eth_usd_feed(client)?;
+    let mut calls = price_calls(feed).to_vec();
+    calls.extend(price_calls(eth_feed));
+    calls.push(call3(
+        MULTICALL3_ADDRESS,
+        getCurrentBlockTimestampCall {}.abi_encode(),
+    ));
+    let results = client.multicall(calls).await?;
+    let round = decode_result::<latestRoundDataCall>(&results[0], feed)?;
+    let decimals = decode_result::<decimalsCall>(&results[1], feed)?._0;
+    let price_usd = price_from_round(&round, decimals)?;
+    let eth_price_usd = price_from_results(eth_feed, &results[2..4])?;
+    let now = batch_timestamp(client, &results[4]).await?;
+    let updated_at = round.updatedAt.saturating_to::<u64>();
+    let age_seconds = feed_age(feed, updated_at, now, max_age_secs)?;
+    Ok(Some(FeedPrice {
+        feed,
+        price_usd,
+        eth_price_usd,
+        updated_at,
+        age_seconds,
+    }))
+}
+
+/// Seconds since a feed's `updatedAt` at block time `now`; more than `max_age_secs` is an
+/// error.
+fn feed_age(feed: Address, updated_at: u64, now: u64, max_age_secs: u64) -> Result<u64> {
+    let age_seconds = now.saturating_sub(updated_at);
+    if age_seconds > max_age_secs {
+        return Err(anyhow::anyhow!(
+            "Chainlink feed {} was last updated {}s ago, more than the {}s allowed",
+            feed,
+            age_seconds,
+            max_age_secs
+        ));
+    }
+    Ok(age_seconds)
+}
+
+/// Validated answers of `feeds`, in order, read together with the block time in one
+/// `multicall`. An answer older than `max_age_secs` is an error, as in `token_feed_price`.
+pub async fn fresh_prices(
+    client: &EthereumClient,
+    feeds: &[Address],
+    max_age_secs: u64,
+) -> Result<Vec<Decimal>> {
+    let mut calls: Vec<Call3> = feeds.iter().flat_map(|f| price_calls(*f)).collect();
+    calls.push(call3(
+        MULTICALL3_ADDRESS,
+        getCurrentBlockTimestampCall {}.abi_encode(),
+    ));
+    let results = client.multicall(calls).await?;
+    let now = batch_timestamp(client, &results[2 * feeds.len()]).await?;
+    feeds
+        .iter()
+        .zip(results.chunks(2))
+        .map(|(&feed, results)| {
+            let round = decode_result::<latestRoundDataCall>(&results[0], feed)?;
+            let decimals = decode_result::<decimalsCall>(&results[1], feed)?._0;
+            feed_age(
+                feed,
+                round.updatedAt.saturating_to::<u64>(),
+                now,
+                max_age_secs,
+            )?;
+            price_from_round(&round, decimals)
+        })
+        .collect()
+}
+
+/// The block timestamp from a batched `getCurrentBlockTimestamp` result. Without
+/// Multicall3 there is no such call, so the latest block header is read instead.
+pub async fn batch_timestamp(client: &EthereumClient, result: &Call3Result) -> Result<u64> {
+    match decode_result::<getCurrentBlockTimestampCall>(result, MULTICALL3_ADDRESS) {
+        Ok(block) => Ok(block.timestamp.saturating_to::<u64>()),
+        Err(_) => Ok(client
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Latest, false)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Latest block not found"))?
+            .header
+            .timestamp),
+    }
+}
+
+/// Latest answer of a Chainlink aggregator, scaled by its `decimals()`.
+pub async fn latest_price(client: &EthereumClient, feed: Address) -> Result<Decimal> {
+    let results = client.multicall(price_calls(feed).to_vec()).await?;
+    price_from_results(feed, &results)
+}
+
+/// Answer of a Chainlink aggregator as of block `block`, with the round's `updatedAt`.
+/// Reads state at a past block, which only archive nodes keep beyond the last ~128 blocks;
+/// the error says so. Two plain `eth_call`s, since Multicall3 may postdate the block.
+pub async fn price_at_block(
+    client: &EthereumClient,
+    feed: Address,
+    block: u64,
+) -> Result<(Decimal, u64)> {
+    let read = |data: Vec<u8>| {
+        let req = TransactionRequest::default().to(feed).input(data.into());
+        async move { client.provider.call(&req).block(block.into()).await }
+    };
+    let (round, decimals) = tokio::try_join!(
+        read(latestRoundDataCall {}.abi_encode()),
+        read(decimalsCall {}.abi_encode())
+    )
+    .map_err(|e| {
+        anyhow::anyhow!(
+            "Could not read Chainlink feed {} at block {} ({}); historical reads need an archive node",
+            feed,
+            block,
+            e
+        )
+    })?;
+    let round = decode_return::<latestRoundDataCall>(&round, feed)?;
+    let decimals = decode_return::<decimalsCall>(&decimals, feed)?._0;
+    Ok((
+        price_from_round(&round, decimals)?,
+        round.updatedAt.saturating_to::<u64>(),
+    ))
+}
+
+/// The `latestRoundData` and `decimals` reads behind `latest_price`, for batching with
+/// other calls in one `multicall`.
+pub fn price_calls(feed: Address) -> [Call3; 2] {
+    [
+        call3(feed, latestRoundDataCall {}.abi_encode()),
+        call3(feed, decimalsCall {}.abi_encode()),
+    ]
+}
+
+/// Decodes the two results of `price_calls` into a validated price.
+pub fn price_from_results(feed: Address, results: &[Call3Result]) -> Result<Decimal> {
+    let round = decode_result::<latestRoundDataCall>(&results[0], feed)?;
+    let decimals = decode_result::<decimalsCall>(&results[1], feed)?._0;
+    price_from_round(&round, decimals)
+}
+
+/// Validates a `latestRoundData` result and scales its answer. A non-positive answer, an
+/// incomplete round (`updatedAt == 0`) or an answer carried over from an earlier round
+/// would silently corrupt every price derived from it, so all are errors.
+pub fn price_from_round(round: &latestRoundDataReturn, decimals: u8) -> Result<Decimal> {
+    if round.answer <= I256::ZERO {
+        return Err(anyhow::anyhow!(
+            "Chainlink feed returned non-positive price ({})",
+            round.answer
+        ));
+    }
+    if round.updatedAt.is_zero() {
+        return Err(anyhow::anyhow!(
+            "Chainlink round {} is incomplete (updatedAt = 0)",
+            round.roundId
+        ));
+    }
+    if round.answeredInRound == 0 || round.answeredInRound < round.roundId {
+        return Err(anyhow::anyhow!(
+            "Chainlink answer is stale (answered in round {}, latest round {})",
+            round.answeredInRound,
+            round.roundId
+        ));
+    }
+    let mut price = Decimal::from_str(&round.answer.to_string())?;
+    price.set_scale(u32::from(decimals))?;
+    Ok(price)
+}
+
+/// Oracle rate of `from` in units of `to` (how many `to` one `from` is worth), when both
+/// tokens have a USD feed. Both feeds are read in one batch, and an answer older than
+/// `DEFAULT_MAX_FEED_AGE_SECS` is an error.
+pub async fn pair_rate(
+    client: &EthereumClient,
+    from: Address,
+    to: Address,
+) -> Result<Option<Decimal>> {
+    let (Some(from_feed), Some(to_feed)) = (
+        usd_feed(client.chain_id(), from),
+        usd_feed(client.chain_id(), to),
+    ) else {
+        return Ok(None);
+    };
+    let prices = fresh_prices(client, &[from_feed, to_feed], DEFAULT_MAX_FEED_AGE_SECS).await?;
+    let (from_usd, to_usd) = (prices[0], prices[1]);
+    if to_usd.is_zero() {
+        return Ok(None);
+    }
+    Ok(Some(from_usd / to_usd))
+}
+
+/// How much worse (in percent) `execution_rate` is than `oracle_rate`. Negative when the
+/// execution rate is better than the oracle.
+pub fn deviation_percent(oracle_rate: Decimal, execution_rate: Decimal) -> Decimal {
+    if oracle_rate.is_zero() {
+        return Decimal::ZERO;
+    }
+    (oracle_rate - execution_rate) / oracle_rate * Decimal::from(100)
+}
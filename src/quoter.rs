@@ -0,0 +1,187 @@
+use crate::chains::{QuoterVersion, UniswapV3};
+use crate::ethereum::{describe_call_error, revert_data, EthereumClient};
+use crate::tools::swap::{
+    quoteExactInputSingleCall, quoteExactOutputSingleCall, QuoteExactInputSingleParams,
+    QuoteExactOutputSingleParams, SwapMode,
+};
+use alloy::{
+    primitives::{Address, Bytes, U256},
+    rpc::types::eth::TransactionRequest,
+    sol_types::SolCall,
+    transports::TransportError,
+};
+use anyhow::Result;
+
+// The original Quoter: flat arguments, and only the amount comes back.
+mod v1 {
+    alloy::sol! {
+        #[allow(missing_docs)]
+        function quoteExactInputSingle(address tokenIn, address tokenOut, uint24 fee, uint256 amountIn, uint160 sqrtPriceLimitX96) external returns (uint256 amountOut);
+        #[allow(missing_docs)]
+        function quoteExactOutputSingle(address tokenIn, address tokenOut, uint24 fee, uint256 amountOut, uint160 sqrtPriceLimitX96) external returns (uint256 amountIn);
+    }
+}
+
+/// A single-pool quote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quote {
+    pub amount_in: U256,
+    pub amount_out: U256,
+    /// QuoterV2's gas estimate; zero when the quoter doesn't report one (V1, revert data).
+    pub gas_estimate: U256,
+}
+
+/// The `eth_call` quoting `amount` of `token_in` -> `token_out` through the `fee` pool:
+/// sold for exact_in, bought for exact_out. The Quoter functions are declared
+/// state-changing, so the call carries a `from` (the signer) like a transaction would;
+/// some nodes reject or mis-simulate non-view calls without one.
+pub fn request(
+    client: &EthereumClient,
+    uniswap: &UniswapV3,
+    mode: SwapMode,
+    token_in: Address,
+    token_out: Address,
+    fee: u32,
+    amount: U256,
+) -> TransactionRequest {
+    let data = match (uniswap.quoter_version, mode) {
+        (QuoterVersion::V2, SwapMode::ExactIn) => quoteExactInputSingleCall {
+            params: QuoteExactInputSingleParams {
+                tokenIn: token_in,
+                tokenOut: token_out,
+                amountIn: amount,
+                fee,
+                sqrtPriceLimitX96: U256::ZERO,
+            },
+        }
+        .abi_encode(),
+        (QuoterVersion::V2, SwapMode::ExactOut) => quoteExactOutputSingleCall {
+            params: QuoteExactOutputSingleParams {
+                tokenIn: token_in,
+                tokenOut: token_out,
+                amount,
+                fee,
+                sqrtPriceLimitX96: U256::ZERO,
+            },
+        }
+        .abi_encode(),
+        (QuoterVersion::V1, SwapMode::ExactIn) => v1::quoteExactInputSingleCall {
+            tokenIn: token_in,
+            tokenOut: token_out,
+            fee,
+            amountIn: amount,
+            sqrtPriceLimitX96: U256::ZERO,
+        }
+        .abi_encode(),
+        (QuoterVersion::V1, SwapMode::ExactOut) => v1::quoteExactOutputSingleCall {
+            tokenIn: token_in,
+            tokenOut: token_out,
+            fee,
+            amountOut: amount,
+            sqrtPriceLimitX96: U256::ZERO,
+        }
+        .abi_encode(),
+    };
+    TransactionRequest::default()
+        .from(client.simulation_sender())
+        .to(uniswap.quoter)
+        .input(data.into())
+}
+
+/// Decodes a quote from what the `request` call returned. Return data is decoded for the
+/// configured quoter version. A revert whose data is the swap result (the callback
+/// pattern quoters use internally, which some deployments let escape) is decoded too;
+/// any other revert is an error carrying the node's message.
+pub fn decode(
+    uniswap: &UniswapV3,
+    mode: SwapMode,
+    amount: U256,
+    response: &Result<Bytes, TransportError>,
+) -> Result<Quote> {
+    match response {
+        Ok(data) => decode_return(uniswap.quoter_version, mode, amount, data).ok_or_else(|| {
+            anyhow::anyhow!(
+                "quoter {} returned {} bytes, which is not a Quoter{} {} quote",
+                uniswap.quoter,
+                data.len(),
+                uniswap.quoter_version.as_str().to_uppercase(),
+                mode.as_str()
+            )
+        }),
+        Err(e) => match revert_data(e).and_then(|data| decode_revert(mode, amount, &data)) {
+            Some(quote) => Ok(quote),
+            None => Err(anyhow::anyhow!("quote failed: {}", describe_call_error(e))),
+        },
+    }
+}
+
+/// `request`, sent and decoded.
+pub async fn quote_single(
+    client: &EthereumClient,
+    mode: SwapMode,
+    token_in: Address,
+    token_out: Address,
+    fee: u32,
+    amount: U256,
+) -> Result<Quote> {
+    let uniswap = client.uniswap_v3()?;
+    let req = request(client, &uniswap, mode, token_in, token_out, fee, amount);
+    decode(&uniswap, mode, amount, &client.call_cached(&req).await)
+}
+
+fn decode_return(
+    version: QuoterVersion,
+    mode: SwapMode,
+    amount: U256,
+    data: &[u8],
+) -> Option<Quote> {
+    let (quoted, gas_estimate) = match (version, mode) {
+        (QuoterVersion::V2, SwapMode::ExactIn) => {
+            let q = quoteExactInputSingleCall::abi_decode_returns(data, true).ok()?;
+            (q.amountOut, q.gasEstimate)
+        }
+        (QuoterVersion::V2, SwapMode::ExactOut) => {
+            let q = quoteExactOutputSingleCall::abi_decode_returns(data, true).ok()?;
+            (q.amountIn, q.gasEstimate)
+        }
+        (QuoterVersion::V1, SwapMode::ExactIn) => (
+            v1::quoteExactInputSingleCall::abi_decode_returns(data, true)
+                .ok()?
+                .amountOut,
+            U256::ZERO,
+        ),
+        (QuoterVersion::V1, SwapMode::ExactOut) => (
+            v1::quoteExactOutputSingleCall::abi_decode_returns(data, true)
+                .ok()?
+                .amountIn,
+            U256::ZERO,
+        ),
+    };
+    Some(quote(mode, amount, quoted, gas_estimate))
+}
+
+/// The swap callback reverts with the quoted amount first: 32 bytes from QuoterV1, 96
+/// (amount, sqrtPriceX96After, tickAfter) from QuoterV2. `Error(string)` and custom
+/// errors start with a selector, so their length is never a multiple of 32.
+fn decode_revert(mode: SwapMode, amount: U256, data: &[u8]) -> Option<Quote> {
+    if !(data.len() == 32 || data.len() == 96) {
+        return None;
+    }
+    let quoted = U256::from_be_slice(&data[..32]);
+    Some(quote(mode, amount, quoted, U256::ZERO))
+}
+
+fn quote(mode: SwapMode, amount: U256, quoted: U256, gas_estimate: U256) -> Quote {
+    match mode {
+        SwapMode::ExactIn => Quote {
+            amount_in: amount,
+            amount_out: quoted,
+            gas_estimate,
+        },
+        SwapMode::ExactOut => Quote {
+            amount_in: quoted,
+            amount_out: amount,
+            gas_estimate,
+        },
+    }
+}
@@ -0,0 +1,209 @@
+use alloy::rpc::json_rpc::{ErrorPayload, RequestPacket, ResponsePacket};
+use alloy::transports::{RpcError, TransportError, TransportErrorKind, TransportFut};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
+use tracing::warn;
+
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+pub const DEFAULT_BASE_DELAY_MS: u64 = 250;
+/// Longest wait between two attempts, however many have failed.
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// How often, and how patiently, a failed RPC request is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after it.
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: Duration::from_millis(DEFAULT_BASE_DELAY_MS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: Duration::ZERO,
+        }
+    }
+
+    /// Wait before retry number `attempt` (0 for the first): `base_delay * 2^attempt`,
+    /// capped at 10s, of which the upper half is random so that concurrent requests
+    /// rejected together don't all come back at once.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(1 << attempt.min(16))
+            .min(MAX_DELAY);
+        let half = ceiling / 2;
+        half + half.mul_f64(jitter())
+    }
+}
+
+/// Whether a failed request may succeed if sent again unchanged: rate limits, gateway
+/// and server errors, timeouts and dropped connections. Errors the node reached a
+/// verdict on (reverts, invalid parameters, nonce errors) are deterministic and never are.
+pub fn is_transient(error: &TransportError) -> bool {
+    match error {
+        RpcError::ErrorResp(payload) => payload.is_retry_err(),
+        RpcError::Transport(kind) => match kind {
+            TransportErrorKind::HttpError(e) => matches!(e.status, 408 | 429 | 500 | 502..=504),
+            // Connection failures and timeouts from the HTTP client.
+            TransportErrorKind::Custom(_) | TransportErrorKind::MissingBatchResponse(_) => true,
+            _ => false,
+        },
+        // A body that isn't a JSON-RPC response at all is a gateway's error page; a
+        // response of the wrong shape is a bug on our side and won't change.
+        RpcError::DeserError { text, .. } => {
+            match serde_json::from_str::<serde_json::Value>(text) {
+                Ok(body) => body
+                    .get("error")
+                    .and_then(|e| serde_json::from_value::<ErrorPayload>(e.clone()).ok())
+                    .is_some_and(|e| e.is_retry_err()),
+                Err(_) => true,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Whether the node refused the request for its rate limit, so it was never processed.
+fn is_rate_limited(error: &TransportError) -> bool {
+    match error {
+        RpcError::ErrorResp(payload) => payload.is_retry_err(),
+        RpcError::Transport(TransportErrorKind::HttpError(e)) => e.status == 429,
+        _ => false,
+    }
+}
+
+/// Broadcasts are only retried when rate-limited. After a timeout or a dropped
+/// connection the transaction may already be in the mempool, and sending it again would
+/// turn a success into an "already known" or "nonce too low" error.
+fn sends_transaction(request: &RequestPacket) -> bool {
+    let broadcast =
+        |method: &str| matches!(method, "eth_sendRawTransaction" | "eth_sendTransaction");
+    match request {
+        RequestPacket::Single(req) => broadcast(req.method()),
+        RequestPacket::Batch(reqs) => reqs.iter().any(|req| broadcast(req.method())),
+    }
+}
+
+/// Why `request` failed, when its `result` is a failure that sending it again (here or to
+/// another endpoint) may not repeat; `None` for a success or a deterministic error.
+pub fn failure_worth_retrying(
+    request: &RequestPacket,
+    result: &Result<ResponsePacket, TransportError>,
+) -> Option<String> {
+    let retryable = if sends_transaction(request) {
+        is_rate_limited
+    } else {
+        is_transient
+    };
+    match result {
+        // A single request's error comes back as a successful response carrying an
+        // error payload.
+        Ok(response) => response
+            .as_error()
+            .filter(|payload| retryable(&RpcError::ErrorResp((*payload).clone())))
+            .map(|payload| payload.to_string()),
+        Err(e) => retryable(e).then(|| e.to_string()),
+    }
+}
+
+fn jitter() -> f64 {
+    RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64
+}
+
+/// Transport layer retrying transient failures of any request with `RetryPolicy`.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryLayer {
+    policy: RetryPolicy,
+}
+
+impl RetryLayer {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+impl<S> Layer<S> for RetryLayer {
+    type Service = RetryService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RetryService {
+            inner,
+            policy: self.policy,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryService<S> {
+    inner: S,
+    policy: RetryPolicy,
+}
+
+impl<S> RetryService<S> {
+    /// The transport whose requests are retried.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S> Service<RequestPacket> for RetryService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Send
+        + Clone
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        // The ready service goes to this request; a clone stays behind for the next one.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let policy = self.policy;
+        Box::pin(async move {
+            let mut attempt = 0;
+            loop {
+                let result = inner.call(request.clone()).await;
+                let reason = match failure_worth_retrying(&request, &result) {
+                    Some(reason) => reason,
+                    None => return result,
+                };
+                if attempt >= policy.max_retries {
+                    return result;
+                }
+                let delay = policy.delay(attempt);
+                attempt += 1;
+                warn!(
+                    "RPC request failed ({}); retry {}/{} in {}ms",
+                    reason,
+                    attempt,
+                    policy.max_retries,
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+            }
+        })
+    }
+}
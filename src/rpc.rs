@@ -0,0 +1,49 @@
+use serde_json::{json, Value};
+
+// Lenient readers for raw JSON-RPC objects. Providers disagree on which optional fields
+// they return (pre-Byzantium receipts have no `status`, older nodes omit
+// `effectiveGasPrice`, creations have a null `to`), so nothing here assumes a field exists.
+
+/// A hex (`"0x1a"`) or plain numeric quantity, or `None` if absent or malformed.
+pub fn quantity(obj: &Value, key: &str) -> Option<u128> {
+    match obj.get(key)? {
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => u128::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        },
+        Value::Number(n) => n.as_u64().map(u128::from),
+        _ => None,
+    }
+}
+
+/// A non-null string field.
+pub fn string(obj: &Value, key: &str) -> Option<String> {
+    obj.get(key)?.as_str().map(str::to_string)
+}
+
+/// Normalized receipt. `tx` (the transaction object, if fetched) fills in the gas price
+/// when the receipt has no `effectiveGasPrice`.
+pub fn receipt_summary(receipt: &Value, tx: Option<&Value>) -> Value {
+    let gas_used = quantity(receipt, "gasUsed");
+    let (gas_price, gas_price_source) = match quantity(receipt, "effectiveGasPrice") {
+        Some(p) => (Some(p), Some("receipt")),
+        None => match tx.and_then(|t| quantity(t, "gasPrice")) {
+            Some(p) => (Some(p), Some("transaction.gasPrice")),
+            None => (None, None),
+        },
+    };
+    json!({
+        // Pre-Byzantium receipts carry a state root instead of a status.
+        "success": quantity(receipt, "status").map(|s| s == 1),
+        "block_number": quantity(receipt, "blockNumber").map(|n| n as u64),
+        "block_hash": string(receipt, "blockHash"),
+        "from": string(receipt, "from"),
+        "to": string(receipt, "to"),
+        "contract_address": string(receipt, "contractAddress"),
+        "gas_used": gas_used.map(|g| g.to_string()),
+        "effective_gas_price": gas_price.map(|p| p.to_string()),
+        "effective_gas_price_source": gas_price_source,
+        "fee_paid_wei": gas_used.zip(gas_price).map(|(g, p)| g.saturating_mul(p).to_string()),
+        "logs": receipt.get("logs").and_then(|l| l.as_array()).map_or(0, |l| l.len())
+    })
+}
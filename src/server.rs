@@ -1,11 +1,78 @@
-use crate::ethereum::EthereumClient;
-use crate::tools::{balance::GetBalanceTool, price::GetTokenPriceTool, swap::SwapTokensTool, Tool};
+use crate::audit::AuditLog;
+use crate::config::{Config, Transport};
+use crate::denylist::DenyList;
+use crate::error::ToolError;
+use crate::ethereum::{recording_broadcasts, EthereumClient};
+use crate::limits::{RequestLimiter, SERVER_BUSY_CODE};
+use crate::session::Session;
+use crate::tools::{
+    approve::ApproveTokenTool,
+    balance::GetBalanceTool,
+    balances::GetBalancesTool,
+    batch::BatchReadTool,
+    block::GetBlockTool,
+    chains::ListChainsTool,
+    convert::ConvertTool,
+    cross_rate::GetCrossRateTool,
+    crosscheck::CompareRpcEndpointsTool,
+    discover::DiscoverTokensTool,
+    encode::EncodeCalldataTool,
+    ens::ResolveEnsTool,
+    events::GetBlockRangeEventsTool,
+    explain_tx::ExplainFailedTransactionTool,
+    gas::GetGasPriceTool,
+    gas_cost::GetHistoricalGasCostTool,
+    gas_deadline::GasForDeadlineTool,
+    hash::HashTool,
+    impact::EstimatePriceImpactTool,
+    observations::GetPoolObservationsTool,
+    permit::SignPermitTool,
+    plan::SimulateSwapPlanTool,
+    pool_fees::GetPoolFeesTool,
+    price::{self, GetTokenPriceTool},
+    price_watch::{UnwatchPriceTool, WatchPriceTool},
+    prices::GetPricesTool,
+    profile::AccountProfileTool,
+    rebasing::CheckRebasingTool,
+    reference::GetReferencePricesTool,
+    required_eth::GetRequiredEthTool,
+    safety::CheckTokenSafetyTool,
+    scenario::SimulateSwapScenarioTool,
+    select_fields,
+    send::SendTransactionTool,
+    split::OptimizeSwapSplitTool,
+    split_stream,
+    swap::{self, SwapTokensTool},
+    sweep::MaxSendableEthTool,
+    tool_content,
+    transfers::GetTokenTransfersTool,
+    tx_status::GetTransactionStatusTool,
+    verify::VerifySignatureTool,
+    watch::WatchTransactionTool,
+    with_common_properties, with_progress, with_session,
+    wrap::{UnwrapWethTool, WrapEthTool},
+    Tool,
+};
+use alloy::primitives::TxHash;
+use alloy::providers::Provider;
 use anyhow::Result;
+use dashmap::DashMap;
+use futures::future::join_all;
+use futures::StreamExt;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::io::{self, BufRead};
-use tracing::{debug, error, info};
+use std::convert::Infallible;
+use std::io::{self, BufRead, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tracing::{debug, error, info, warn};
 
 #[derive(Serialize, Deserialize, Debug)]
 struct JsonRpcRequest {
@@ -30,54 +97,1190 @@ struct JsonRpcError {
     data: Option<Value>,
 }
 
-pub async fn run(client: EthereumClient) -> Result<()> {
+/// Every tool the server offers, by name.
+pub fn registered_tools() -> HashMap<String, Box<dyn Tool>> {
     let mut tools: HashMap<String, Box<dyn Tool>> = HashMap::new();
 
-    // Register tools
+    let approve_tool = ApproveTokenTool;
+    tools.insert(approve_tool.name().to_string(), Box::new(approve_tool));
+
+    let ens_tool = ResolveEnsTool;
+    tools.insert(ens_tool.name().to_string(), Box::new(ens_tool));
+
+    let watch_price_tool = WatchPriceTool;
+    tools.insert(
+        watch_price_tool.name().to_string(),
+        Box::new(watch_price_tool),
+    );
+
+    let unwatch_price_tool = UnwatchPriceTool;
+    tools.insert(
+        unwatch_price_tool.name().to_string(),
+        Box::new(unwatch_price_tool),
+    );
+
+    let permit_tool = SignPermitTool;
+    tools.insert(permit_tool.name().to_string(), Box::new(permit_tool));
+
     let balance_tool = GetBalanceTool;
     tools.insert(balance_tool.name().to_string(), Box::new(balance_tool));
 
+    let balances_tool = GetBalancesTool;
+    tools.insert(balances_tool.name().to_string(), Box::new(balances_tool));
+
+    let discover_tool = DiscoverTokensTool;
+    tools.insert(discover_tool.name().to_string(), Box::new(discover_tool));
+
+    let batch_tool = BatchReadTool;
+    tools.insert(batch_tool.name().to_string(), Box::new(batch_tool));
+
+    let block_tool = GetBlockTool;
+    tools.insert(block_tool.name().to_string(), Box::new(block_tool));
+
+    let chains_tool = ListChainsTool;
+    tools.insert(chains_tool.name().to_string(), Box::new(chains_tool));
+
     let price_tool = GetTokenPriceTool;
     tools.insert(price_tool.name().to_string(), Box::new(price_tool));
 
+    let prices_tool = GetPricesTool;
+    tools.insert(prices_tool.name().to_string(), Box::new(prices_tool));
+
+    let reference_tool = GetReferencePricesTool;
+    tools.insert(reference_tool.name().to_string(), Box::new(reference_tool));
+
+    let convert_tool = ConvertTool;
+    tools.insert(convert_tool.name().to_string(), Box::new(convert_tool));
+
+    let cross_rate_tool = GetCrossRateTool;
+    tools.insert(
+        cross_rate_tool.name().to_string(),
+        Box::new(cross_rate_tool),
+    );
+
+    let crosscheck_tool = CompareRpcEndpointsTool;
+    tools.insert(
+        crosscheck_tool.name().to_string(),
+        Box::new(crosscheck_tool),
+    );
+
+    let encode_tool = EncodeCalldataTool;
+    tools.insert(encode_tool.name().to_string(), Box::new(encode_tool));
+
+    let events_tool = GetBlockRangeEventsTool;
+    tools.insert(events_tool.name().to_string(), Box::new(events_tool));
+
+    let explain_tool = ExplainFailedTransactionTool;
+    tools.insert(explain_tool.name().to_string(), Box::new(explain_tool));
+
+    let gas_tool = GetGasPriceTool;
+    tools.insert(gas_tool.name().to_string(), Box::new(gas_tool));
+
+    let gas_cost_tool = GetHistoricalGasCostTool;
+    tools.insert(gas_cost_tool.name().to_string(), Box::new(gas_cost_tool));
+
+    let gas_deadline_tool = GasForDeadlineTool;
+    tools.insert(
+        gas_deadline_tool.name().to_string(),
+        Box::new(gas_deadline_tool),
+    );
+
+    let hash_tool = HashTool;
+    tools.insert(hash_tool.name().to_string(), Box::new(hash_tool));
+
+    let impact_tool = EstimatePriceImpactTool;
+    tools.insert(impact_tool.name().to_string(), Box::new(impact_tool));
+
+    let observations_tool = GetPoolObservationsTool;
+    tools.insert(
+        observations_tool.name().to_string(),
+        Box::new(observations_tool),
+    );
+
+    let pool_fees_tool = GetPoolFeesTool;
+    tools.insert(pool_fees_tool.name().to_string(), Box::new(pool_fees_tool));
+
+    let plan_tool = SimulateSwapPlanTool;
+    tools.insert(plan_tool.name().to_string(), Box::new(plan_tool));
+
+    let profile_tool = AccountProfileTool;
+    tools.insert(profile_tool.name().to_string(), Box::new(profile_tool));
+
+    let rebasing_tool = CheckRebasingTool;
+    tools.insert(rebasing_tool.name().to_string(), Box::new(rebasing_tool));
+
+    let safety_tool = CheckTokenSafetyTool;
+    tools.insert(safety_tool.name().to_string(), Box::new(safety_tool));
+
+    let scenario_tool = SimulateSwapScenarioTool;
+    tools.insert(scenario_tool.name().to_string(), Box::new(scenario_tool));
+
+    let send_tool = SendTransactionTool;
+    tools.insert(send_tool.name().to_string(), Box::new(send_tool));
+
+    let split_tool = OptimizeSwapSplitTool;
+    tools.insert(split_tool.name().to_string(), Box::new(split_tool));
+
     let swap_tool = SwapTokensTool;
     tools.insert(swap_tool.name().to_string(), Box::new(swap_tool));
 
-    let stdin = io::stdin();
-    let mut lines = stdin.lock().lines();
+    let sweep_tool = MaxSendableEthTool;
+    tools.insert(sweep_tool.name().to_string(), Box::new(sweep_tool));
+
+    let required_eth_tool = GetRequiredEthTool;
+    tools.insert(
+        required_eth_tool.name().to_string(),
+        Box::new(required_eth_tool),
+    );
+
+    let transfers_tool = GetTokenTransfersTool;
+    tools.insert(transfers_tool.name().to_string(), Box::new(transfers_tool));
+
+    let verify_tool = VerifySignatureTool;
+    tools.insert(verify_tool.name().to_string(), Box::new(verify_tool));
+
+    let tx_status_tool = GetTransactionStatusTool;
+    tools.insert(tx_status_tool.name().to_string(), Box::new(tx_status_tool));
+
+    let watch_tool = WatchTransactionTool;
+    tools.insert(watch_tool.name().to_string(), Box::new(watch_tool));
+
+    let wrap_tool = WrapEthTool;
+    tools.insert(wrap_tool.name().to_string(), Box::new(wrap_tool));
+
+    let unwrap_tool = UnwrapWethTool;
+    tools.insert(unwrap_tool.name().to_string(), Box::new(unwrap_tool));
+
+    tools
+}
+
+pub async fn run(client: EthereumClient, config: Config) -> Result<()> {
+    let audit_log = match &config.audit_log_path {
+        Some(path) => {
+            info!("Auditing tool calls to {}", path);
+            Some(AuditLog::open(
+                path,
+                &client.signing_key_hex().unwrap_or_default(),
+            )?)
+        }
+        None => None,
+    };
+    let denylist = match &config.denylist_path {
+        Some(path) => {
+            let denylist = DenyList::load(path)?;
+            info!(
+                "Loaded {} deny-listed addresses from {}",
+                denylist.len(),
+                path
+            );
+            denylist
+        }
+        None => DenyList::default(),
+    };
+
+    let tools = registered_tools();
+
+    let limiter = RequestLimiter::new(
+        config.max_concurrent_requests,
+        config.max_concurrent_requests_per_client,
+    );
+    if config.transport == Transport::Http {
+        return serve_http(Arc::new(HttpState {
+            client,
+            config,
+            tools,
+            audit_log,
+            denylist,
+            limiter,
+            sessions: DashMap::new(),
+        }))
+        .await;
+    }
+
+    let grace = Duration::from_secs(config.shutdown_grace_seconds);
+    let state = Arc::new(StdioState {
+        client: RwLock::new(Arc::new(client)),
+        config: RwLock::new(config),
+        tools,
+        audit_log,
+        denylist,
+        limiter,
+        session: Session::new(),
+    });
+    let mut lines = stdin_lines();
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    info!(
+        "MCP Server Ready (session {}). Waiting for JSON-RPC requests on stdin...",
+        state.session.id
+    );
+
+    // Each line is handled as a task of its own, so a slow call doesn't hold up the next
+    // line; every task writes its own output.
+    let mut in_flight = JoinSet::new();
+    let mut signalled = false;
+    loop {
+        tokio::select! {
+            line = lines.recv() => match line {
+                Some(line) => {
+                    let state = state.clone();
+                    in_flight.spawn(async move {
+                        match handle_line(&line, &state).await {
+                            Ok(Some(output)) => println!("{}", output),
+                            Ok(None) => {}
+                            Err(e) => error!("Failed to answer request: {}", e),
+                        }
+                    });
+                }
+                None => {
+                    info!("stdin reached EOF; shutting down");
+                    break;
+                }
+            },
+            Some(Err(e)) = in_flight.join_next() => {
+                error!("Request task failed: {}", e);
+            }
+            signal = &mut shutdown => {
+                info!(
+                    "Received {}; finishing the requests in flight before shutting down",
+                    signal
+                );
+                signalled = true;
+                break;
+            }
+        }
+    }
+    // Requests already read are answered before the server stops; after a signal, only
+    // within the grace period, since a watch can run for an hour.
+    if !signalled {
+        drain(&mut in_flight).await;
+    } else if tokio::time::timeout(grace, drain(&mut in_flight))
+        .await
+        .is_err()
+    {
+        warn!(
+            "{} requests still running after the {}s grace period; dropping them",
+            in_flight.len(),
+            grace.as_secs()
+        );
+        in_flight.abort_all();
+    }
+
+    info!(
+        "Shut down cleanly; session {} handled {} requests",
+        state.session.id,
+        state.session.requests()
+    );
 
-    info!("MCP Server Ready. Waiting for JSON-RPC requests on stdin...");
+    Ok(())
+}
+
+/// Waits for every request task in `in_flight`.
+async fn drain(in_flight: &mut JoinSet<()>) {
+    while let Some(joined) = in_flight.join_next().await {
+        if let Err(e) = joined {
+            error!("Request task failed: {}", e);
+        }
+    }
+}
+
+/// Everything the stdio transport's requests share. `admin/reinitialize` swaps the client
+/// and config; requests already running finish on the client they started with.
+struct StdioState {
+    client: RwLock<Arc<EthereumClient>>,
+    config: RwLock<Config>,
+    tools: HashMap<String, Box<dyn Tool>>,
+    audit_log: Option<AuditLog>,
+    denylist: DenyList,
+    limiter: RequestLimiter,
+    /// One stdio connection per process, so one session per `run`.
+    session: Session,
+}
+
+impl StdioState {
+    fn client(&self) -> Arc<EthereumClient> {
+        self.client
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn config(&self) -> Config {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// stdin as a channel of lines, read on a thread of its own. A blocking read can't be
+/// cancelled, so this way a shutdown signal doesn't wait for the next line, and the
+/// process exits without joining the reader.
+fn stdin_lines() -> mpsc::UnboundedReceiver<String> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            match line {
+                Ok(line) => {
+                    if tx.send(line).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to read stdin: {}", e);
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// Resolves with the signal's name on ctrl-c (SIGINT) or, on Unix, SIGTERM. A handler
+/// that can't be installed is logged and never fires, rather than stopping the server.
+async fn shutdown_signal() -> &'static str {
+    let ctrl_c = async {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => "SIGINT",
+            Err(e) => {
+                error!("Failed to listen for ctrl-c: {}", e);
+                std::future::pending().await
+            }
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                term.recv().await;
+                "SIGTERM"
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending().await
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<&'static str>();
+    tokio::select! {
+        signal = ctrl_c => signal,
+        signal = terminate => signal,
+    }
+}
+
+/// Handles one stdin line: a request, notification or batch. Returns the line to write
+/// back, if any.
+async fn handle_line(line: &str, state: &StdioState) -> Result<Option<String>> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+
+    debug!("Received request: {}", line);
 
-    while let Some(Ok(line)) = lines.next() {
-        if line.trim().is_empty() {
-            continue;
+    let message: Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("Failed to parse JSON-RPC request: {}", e);
+            return Ok(None);
         }
+    };
+    let client = state.client();
+    let session = &state.session;
 
-        debug!("Received request: {}", line);
+    // JSON-RPC 2.0 batch: the requests run concurrently and are answered with one
+    // array holding a response for each request that has an id.
+    if let Value::Array(batch) = message {
+        if batch.is_empty() {
+            let response = error_response(None, -32600, "Invalid Request: empty batch".into());
+            return Ok(Some(serde_json::to_string(&response)?));
+        }
+        let responses = handle_batch(
+            batch,
+            &client,
+            &state.config(),
+            &state.tools,
+            state.audit_log.as_ref(),
+            &state.denylist,
+            session,
+            &state.limiter,
+            &session.id,
+            &print_message,
+        )
+        .await;
+        // A batch of only notifications gets no reply at all.
+        if responses.is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some(serde_json::to_string(&responses)?));
+    }
 
-        let req: JsonRpcRequest = match serde_json::from_str(&line) {
+    let req: JsonRpcRequest = match serde_json::from_value(message) {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Failed to parse JSON-RPC request: {}", e);
+            return Ok(None);
+        }
+    };
+
+    let response = if req.method == "admin/reinitialize" {
+        match state.limiter.try_acquire(&session.id) {
+            Ok(_permit) => handle_reinitialize(&req, state).await,
+            Err(busy) => error_response(req.id.clone(), SERVER_BUSY_CODE, busy.to_string()),
+        }
+    } else if req.method == "admin/config" {
+        handle_config(&req, &client, &state.config(), &state.tools)
+    } else {
+        match dispatch(
+            &req,
+            &client,
+            &state.tools,
+            state.audit_log.as_ref(),
+            &state.denylist,
+            session,
+            &state.limiter,
+            &session.id,
+            &print_message,
+        )
+        .await
+        {
+            Some(response) => response,
+            None => return Ok(None),
+        }
+    };
+
+    Ok(Some(serde_json::to_string(&response)?))
+}
+
+/// Sends each streamed element as its own `notifications/progress` message, ahead of the
+/// response that completes the call. `sent` live events already used the first numbers.
+fn stream_items(token: &Value, items: Vec<Value>, sent: usize, notify: &Notify) {
+    let total = sent + items.len();
+    for (i, item) in items.into_iter().enumerate() {
+        notify(progress_message(token, sent + i + 1, Some(total), item));
+    }
+}
+
+/// A `notifications/progress` message. Live events have no `total`, since the tool is
+/// still running.
+fn progress_message(token: &Value, progress: usize, total: Option<usize>, item: Value) -> Value {
+    let mut params = json!({
+        "progressToken": token,
+        "progress": progress,
+        "item": item
+    });
+    if let Some(total) = total {
+        params["total"] = json!(total);
+    }
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/progress",
+        "params": params
+    })
+}
+
+/// Runs a tool, forwarding what it reports through `report_progress` as it happens.
+/// Returns the outcome and the number of events sent.
+async fn call_with_progress(
+    tool: &dyn Tool,
+    client: &EthereumClient,
+    args: Value,
+    token: &Value,
+    notify: &Notify,
+) -> (Result<Value>, usize) {
+    let (sink, mut events) = mpsc::unbounded_channel();
+    let forward = async {
+        let mut sent = 0;
+        while let Some(item) = events.recv().await {
+            sent += 1;
+            notify(progress_message(token, sent, None, item));
+        }
+        sent
+    };
+    tokio::join!(with_progress(sink, tool.call(client, args)), forward)
+}
+
+/// Where server-to-client messages sent ahead of a response (progress notifications) go.
+type Notify = dyn Fn(Value) + Send + Sync;
+
+/// Writes one message as a stdout line (NDJSON).
+fn print_message(message: Value) {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if let Err(e) = writeln!(out, "{}", message) {
+        error!("Failed to write message: {}", e);
+    }
+}
+
+/// Runs a JSON-RPC 2.0 batch concurrently, returning a response for each request that has
+/// an id. `admin/reinitialize` is refused since it would swap the client under the others.
+#[allow(clippy::too_many_arguments)]
+async fn handle_batch(
+    batch: Vec<Value>,
+    client: &EthereumClient,
+    config: &Config,
+    tools: &HashMap<String, Box<dyn Tool>>,
+    audit_log: Option<&AuditLog>,
+    denylist: &DenyList,
+    session: &Session,
+    limiter: &RequestLimiter,
+    client_key: &str,
+    notify: &Notify,
+) -> Vec<JsonRpcResponse> {
+    join_all(batch.into_iter().map(|item| async move {
+        let req: JsonRpcRequest = match serde_json::from_value(item) {
             Ok(r) => r,
             Err(e) => {
-                error!("Failed to parse JSON-RPC request: {}", e);
-                continue;
+                return Some(error_response(
+                    None,
+                    -32600,
+                    format!("Invalid Request: {}", e),
+                ))
             }
         };
+        // Swapping the client under concurrently running requests is not allowed.
+        let response = if req.method == "admin/reinitialize" {
+            Some(error_response(
+                req.id.clone(),
+                -32600,
+                "admin/reinitialize cannot be sent in a batch".into(),
+            ))
+        } else if req.method == "admin/config" {
+            Some(handle_config(&req, client, config, tools))
+        } else {
+            dispatch(
+                &req, client, tools, audit_log, denylist, session, limiter, client_key, notify,
+            )
+            .await
+        };
+        req.id.as_ref()?;
+        response
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Everything the HTTP transport's connections share.
+struct HttpState {
+    client: EthereumClient,
+    config: Config,
+    tools: HashMap<String, Box<dyn Tool>>,
+    audit_log: Option<AuditLog>,
+    denylist: DenyList,
+    limiter: RequestLimiter,
+    /// Sessions by their `Mcp-Session-Id`, from `initialize` until the client's DELETE or
+    /// `HTTP_SESSION_IDLE_SECONDS` without use.
+    sessions: DashMap<String, Arc<Session>>,
+}
 
-        let response = handle_request(&req, &client, &tools).await;
+impl HttpState {
+    fn session_ttl(&self) -> Duration {
+        Duration::from_secs(self.config.http_session_idle_seconds)
+    }
 
-        let response_str = serde_json::to_string(&response)?;
-        println!("{}", response_str);
+    /// The open session with this id, marked as used; one idle past the TTL is dropped.
+    fn session(&self, id: &str) -> Option<Arc<Session>> {
+        let session = self.sessions.get(id).map(|s| s.clone())?;
+        if session.idle_for() > self.session_ttl() {
+            self.sessions.remove(id);
+            info!("session {} expired", id);
+            return None;
+        }
+        session.touch();
+        Some(session)
     }
 
+    /// Registers a session opened by `initialize` after dropping idle ones; `false` when
+    /// `HTTP_MAX_SESSIONS` are still open.
+    fn open_session(&self, session: &Arc<Session>) -> bool {
+        let ttl = self.session_ttl();
+        self.sessions.retain(|_, s| s.idle_for() <= ttl);
+        if self.sessions.len() >= self.config.http_max_sessions {
+            return false;
+        }
+        self.sessions.insert(session.id.clone(), session.clone());
+        true
+    }
+}
+
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Serves JSON-RPC over HTTP at `config.http_bind` until the process is stopped.
+async fn serve_http(state: Arc<HttpState>) -> Result<()> {
+    let addr: SocketAddr = state.config.http_bind.parse().map_err(|e| {
+        anyhow::anyhow!(
+            "HTTP_BIND '{}' is not an address: {}",
+            state.config.http_bind,
+            e
+        )
+    })?;
+    let grace = Duration::from_secs(state.config.shutdown_grace_seconds);
+    let make_service = make_service_fn(move |conn: &AddrStream| {
+        let state = state.clone();
+        let peer = conn.remote_addr();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle_http(state.clone(), peer, req))) }
+    });
+    let server = hyper::Server::try_bind(&addr)?.serve(make_service);
+    info!(
+        "MCP Server Ready. Waiting for JSON-RPC requests on http://{}/",
+        addr
+    );
+    // Stops accepting connections on a signal and lets open exchanges finish, for at most
+    // the grace period.
+    let signalled = tokio::sync::Notify::new();
+    let graceful = server.with_graceful_shutdown(async {
+        let signal = shutdown_signal().await;
+        info!(
+            "Received {}; finishing requests in flight before shutting down",
+            signal
+        );
+        signalled.notify_one();
+    });
+    tokio::select! {
+        served = graceful => served?,
+        _ = async {
+            signalled.notified().await;
+            tokio::time::sleep(grace).await;
+        } => {
+            warn!(
+                "Requests still running after the {}s grace period; dropping them",
+                grace.as_secs()
+            );
+            return Ok(());
+        }
+    }
+    info!("Shut down cleanly");
     Ok(())
 }
 
+/// One HTTP exchange. A POST to `/` carries a request, notification or batch; anything that
+/// needs an answer is answered as a `text/event-stream` of progress notifications followed
+/// by the response. `initialize` starts a session whose id comes back in `Mcp-Session-Id`;
+/// later requests send it back, and a DELETE with it ends the session.
+async fn handle_http(
+    state: Arc<HttpState>,
+    peer: SocketAddr,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let status = |code: StatusCode, message: &str| {
+        let mut response = Response::new(Body::from(message.to_string()));
+        *response.status_mut() = code;
+        Ok(response)
+    };
+    if req.uri().path() != "/" {
+        return status(StatusCode::NOT_FOUND, "Not found");
+    }
+    let header = |name| req.headers().get(name).and_then(|v| v.to_str().ok());
+    // Browsers send an Origin with cross-site requests: refusing foreign ones keeps web
+    // pages (including DNS-rebinding ones) away from the signing tools.
+    if let Some(origin) = header("origin").filter(|o| !is_local_origin(o)) {
+        warn!("Refused HTTP request from origin {}", origin);
+        return status(StatusCode::FORBIDDEN, "Origin not allowed");
+    }
+    if let Some(token) = &state.config.http_auth_token {
+        let presented = header("authorization").and_then(|a| a.strip_prefix("Bearer "));
+        if !presented.is_some_and(|p| secrets_match(p, token)) {
+            return status(StatusCode::UNAUTHORIZED, "Missing or wrong bearer token");
+        }
+    }
+    let session_id = req
+        .headers()
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let known = session_id.as_deref().and_then(|id| state.session(id));
+    if session_id.is_some() && known.is_none() {
+        return status(StatusCode::NOT_FOUND, "Unknown or expired session");
+    }
+    match *req.method() {
+        Method::POST => {}
+        Method::DELETE => {
+            return match session_id {
+                Some(id) => {
+                    state.sessions.remove(&id);
+                    info!("session {} closed", id);
+                    status(StatusCode::NO_CONTENT, "")
+                }
+                None => status(StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id"),
+            };
+        }
+        _ => {
+            return status(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "POST JSON-RPC requests to /",
+            )
+        }
+    }
+
+    // Only JSON: a form or text/plain POST is what a page can send without a preflight.
+    let json_body = header("content-type")
+        .and_then(|t| t.split(';').next())
+        .is_some_and(|t| t.trim().eq_ignore_ascii_case("application/json"));
+    if !json_body {
+        return status(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Content-Type must be application/json",
+        );
+    }
+
+    let body = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => body,
+        Err(e) => return status(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            let response = error_response(None, -32700, format!("Parse error: {}", e));
+            let mut response = Response::new(Body::from(
+                serde_json::to_string(&response).unwrap_or_default(),
+            ));
+            *response.status_mut() = StatusCode::BAD_REQUEST;
+            return Ok(response);
+        }
+    };
+    let session = match known {
+        Some(session) => session,
+        None => {
+            let session = Arc::new(Session::new());
+            // Only `initialize` opens a lasting session; other session-less requests get a
+            // throwaway one.
+            if message["method"] == "initialize" {
+                if !state.open_session(&session) {
+                    return status(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Too many open sessions; close one with DELETE or retry later",
+                    );
+                }
+                info!("session {} opened over HTTP", session.id);
+            }
+            session
+        }
+    };
+    let expects_reply = match &message {
+        Value::Array(batch) => batch.is_empty() || batch.iter().any(|m| !m["id"].is_null()),
+        single => !single["id"].is_null(),
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+    let session_header = session.id.clone();
+    tokio::spawn(async move {
+        let notify = {
+            let tx = tx.clone();
+            move |message: Value| {
+                let _ = tx.send(message);
+            }
+        };
+        let reply = http_reply(&state, &session, peer, message, &notify).await;
+        // A long call keeps its session alive until it answers.
+        session.touch();
+        if let Some(reply) = reply {
+            let _ = tx.send(reply);
+        }
+    });
+    if !expects_reply {
+        return status(StatusCode::ACCEPTED, "");
+    }
+    let events = futures::stream::poll_fn(move |cx| rx.poll_recv(cx))
+        .map(|message| Ok::<_, Infallible>(format!("event: message\ndata: {}\n\n", message)));
+    let response = Response::builder()
+        .header("content-type", "text/event-stream")
+        .header("cache-control", "no-cache")
+        .header(SESSION_HEADER, session_header)
+        .body(Body::wrap_stream(events));
+    Ok(response.unwrap_or_else(|_| Response::new(Body::empty())))
+}
+
+/// Whether an `Origin` header names this machine.
+fn is_local_origin(origin: &str) -> bool {
+    let Some(("http" | "https", rest)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match rest.strip_prefix('[') {
+        Some(v6) => v6.split_once(']').map(|(host, _)| host),
+        None => rest.split(':').next(),
+    };
+    matches!(host, Some("localhost" | "127.0.0.1" | "::1"))
+}
+
+/// Compares a presented token without stopping at the first differing byte.
+fn secrets_match(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// The JSON-RPC reply to one HTTP message, if it needs one.
+async fn http_reply(
+    state: &HttpState,
+    session: &Session,
+    peer: SocketAddr,
+    message: Value,
+    notify: &Notify,
+) -> Option<Value> {
+    // Sessions are opened and dropped at will, so the peer's IP is the client key.
+    let client_key = peer.ip().to_string();
+    if let Value::Array(batch) = message {
+        if batch.is_empty() {
+            let response = error_response(None, -32600, "Invalid Request: empty batch".into());
+            return serde_json::to_value(response).ok();
+        }
+        let responses = handle_batch(
+            batch,
+            &state.client,
+            &state.config,
+            &state.tools,
+            state.audit_log.as_ref(),
+            &state.denylist,
+            session,
+            &state.limiter,
+            &client_key,
+            notify,
+        )
+        .await;
+        return (!responses.is_empty())
+            .then(|| serde_json::to_value(responses).ok())
+            .flatten();
+    }
+    let req: JsonRpcRequest = match serde_json::from_value(message) {
+        Ok(r) => r,
+        Err(e) => {
+            let response = error_response(None, -32600, format!("Invalid Request: {}", e));
+            return serde_json::to_value(response).ok();
+        }
+    };
+    let response = match req.method.as_str() {
+        // Other connections may be mid-request on the current client.
+        "admin/reinitialize" => Some(error_response(
+            req.id.clone(),
+            -32600,
+            "admin/reinitialize is not available over HTTP; restart the server to reload".into(),
+        )),
+        "admin/config" => Some(handle_config(
+            &req,
+            &state.client,
+            &state.config,
+            &state.tools,
+        )),
+        _ => {
+            dispatch(
+                &req,
+                &state.client,
+                &state.tools,
+                state.audit_log.as_ref(),
+                &state.denylist,
+                session,
+                &state.limiter,
+                &client_key,
+                notify,
+            )
+            .await
+        }
+    };
+    response.and_then(|r| serde_json::to_value(r).ok())
+}
+
+fn error_response(id: Option<Value>, code: i32, message: String) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".into(),
+        result: None,
+        error: Some(JsonRpcError {
+            code,
+            message,
+            data: None,
+        }),
+        id,
+    }
+}
+
+/// Runs one request under the concurrency limits. `None` for notifications
+/// (`notifications/initialized`, `notifications/cancelled`, ...), which get no response.
+#[allow(clippy::too_many_arguments)]
+async fn dispatch(
+    req: &JsonRpcRequest,
+    client: &EthereumClient,
+    tools: &HashMap<String, Box<dyn Tool>>,
+    audit_log: Option<&AuditLog>,
+    denylist: &DenyList,
+    session: &Session,
+    limiter: &RequestLimiter,
+    client_key: &str,
+    notify: &Notify,
+) -> Option<JsonRpcResponse> {
+    if req.method.starts_with("notifications/") {
+        debug!("Notification: {}", req.method);
+        return None;
+    }
+    let _permit = match limiter.try_acquire(client_key) {
+        Ok(permit) => permit,
+        Err(busy) => {
+            return Some(error_response(
+                req.id.clone(),
+                SERVER_BUSY_CODE,
+                busy.to_string(),
+            ))
+        }
+    };
+    Some(handle_request(req, client, tools, audit_log, denylist, session, notify).await)
+}
+
+/// `admin/reinitialize`: rebuilds the client from a fresh read of the environment and swaps
+/// it in. The old client stays in place if anything fails, and requests already running
+/// finish on it. The audit log and deny-list are not reloaded.
+async fn handle_reinitialize(req: &JsonRpcRequest, state: &StdioState) -> JsonRpcResponse {
+    let error = |code: i32, message: String| error_response(req.id.clone(), code, message);
+    let config = state.config();
+    if !config.admin_methods {
+        return error(
+            -32601,
+            "Admin methods are disabled (set ENABLE_ADMIN_METHODS=true)".into(),
+        );
+    }
+    let allow_chain_change = req
+        .params
+        .as_ref()
+        .and_then(|p| p.get("allow_chain_change"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    match reinitialize(&state.client(), allow_chain_change).await {
+        Ok((new_client, new_config, result)) => {
+            info!("Client reinitialized: {}", result);
+            *state.client.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(new_client);
+            // Keep the admin switch as it was: a reload must not lock the operator out.
+            *state.config.write().unwrap_or_else(|e| e.into_inner()) = Config {
+                admin_methods: config.admin_methods,
+                transport: config.transport,
+                http_bind: config.http_bind.clone(),
+                ..new_config
+            };
+            JsonRpcResponse {
+                jsonrpc: "2.0".into(),
+                result: Some(result),
+                error: None,
+                id: req.id.clone(),
+            }
+        }
+        Err(e) => {
+            error!("Reinitialize failed: {}", e);
+            error(-32603, format!("Reinitialize failed: {}", e))
+        }
+    }
+}
+
+/// `admin/config`: the effective configuration, secrets redacted, plus what the server
+/// derived from it at startup (chain, signer, registered tools, swap defaults).
+fn handle_config(
+    req: &JsonRpcRequest,
+    client: &EthereumClient,
+    config: &Config,
+    tools: &HashMap<String, Box<dyn Tool>>,
+) -> JsonRpcResponse {
+    if !config.admin_methods {
+        return error_response(
+            req.id.clone(),
+            -32601,
+            "Admin methods are disabled (set ENABLE_ADMIN_METHODS=true)".into(),
+        );
+    }
+    let mut tool_names: Vec<&str> = tools.keys().map(|k| k.as_str()).collect();
+    tool_names.sort_unstable();
+    let result = json!({
+        "chain_id": client.chain_id(),
+        "rpc_endpoint": client.active_endpoint(),
+        "signer_address": client.signer_address.map(|a| a.to_string()),
+        "read_only": client.signer_address.is_none(),
+        "multicall_available": client.multicall_available,
+        "call_cache": client.call_cache.as_ref().map(|cache| cache.stats()),
+        "registry_tokens": client.tokens.tokens().len(),
+        "tools": tool_names,
+        "swap_defaults": {
+            "fee_tiers": price::FEE_TIERS,
+            "slippage_tolerance_percent": swap::DEFAULT_SLIPPAGE_PERCENT
+        },
+        "config": config.redacted()
+    });
+    JsonRpcResponse {
+        jsonrpc: "2.0".into(),
+        result: Some(result),
+        error: None,
+        id: req.id.clone(),
+    }
+}
+
+async fn reinitialize(
+    current: &EthereumClient,
+    allow_chain_change: bool,
+) -> Result<(EthereumClient, Config, Value)> {
+    let previous_chain_id = current.chain_id();
+
+    dotenv::dotenv().ok();
+    let config = Config::from_env()?;
+    let client = EthereumClient::from_config(&config).await?;
+    let chain_id = client.chain_id();
+    if previous_chain_id != chain_id && !allow_chain_change {
+        return Err(anyhow::anyhow!(
+            "new endpoint is on chain {} but the server was on chain {}; pass allow_chain_change: true to switch",
+            chain_id,
+            previous_chain_id
+        ));
+    }
+    let block_number = client.provider.get_block_number().await?;
+    let result = json!({
+        "chain_id": chain_id,
+        "previous_chain_id": previous_chain_id,
+        "block_number": block_number
+    });
+    Ok((client, config, result))
+}
+
+/// MCP protocol revisions this server speaks, newest first.
+pub const SUPPORTED_PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Result of the MCP `initialize` handshake. The client's requested `protocolVersion` is
+/// echoed when supported; otherwise the newest supported revision is offered, and the
+/// client decides whether to continue.
+pub fn initialize_result(params: Option<&Value>) -> Value {
+    let requested = params
+        .and_then(|p| p.get("protocolVersion"))
+        .and_then(|v| v.as_str());
+    let protocol_version = requested
+        .filter(|v| SUPPORTED_PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": protocol_version,
+        "capabilities": {
+            "tools": {}
+        },
+        "serverInfo": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION")
+        }
+    })
+}
+
+/// The time limit for one call of `tool`: the client's tool timeout plus whatever the
+/// call may spend waiting by design. `None` when the timeout is disabled.
+pub fn tool_time_limit(client: &EthereumClient, tool: &dyn Tool, args: &Value) -> Option<Duration> {
+    client
+        .tool_timeout
+        .map(|timeout| timeout + tool.wait_budget(args).unwrap_or_default())
+}
+
+/// Runs a tool call, abandoning it with a `ToolError::Timeout` once `limit` passes, so a
+/// hung RPC can't hold the request open indefinitely. The timeout lists any transaction
+/// the call had already broadcast, so the client checks on it rather than retrying.
+pub async fn within_time_limit(
+    tool_name: &str,
+    limit: Option<Duration>,
+    broadcasts: Arc<Mutex<Vec<TxHash>>>,
+    call: impl std::future::Future<Output = (Result<Value>, usize)>,
+) -> (Result<Value>, usize) {
+    let call = recording_broadcasts(broadcasts.clone(), call);
+    let Some(limit) = limit else {
+        return call.await;
+    };
+    match tokio::time::timeout(limit, call).await {
+        Ok(outcome) => outcome,
+        Err(_) => {
+            warn!("{} timed out after {}s", tool_name, limit.as_secs_f64());
+            let tx_hashes: Vec<String> = broadcasts
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .map(|hash| hash.to_string())
+                .collect();
+            let mut message = format!(
+                "{} timed out after {}s (TOOL_TIMEOUT_SECONDS)",
+                tool_name,
+                limit.as_secs_f64()
+            );
+            if !tx_hashes.is_empty() {
+                message.push_str(&format!(
+                    " after broadcasting {}; check with get_transaction_status before retrying",
+                    tx_hashes.join(", ")
+                ));
+            }
+            let error = ToolError::Timeout { message, tx_hashes };
+            (Err(error.into()), 0)
+        }
+    }
+}
+
+/// How long `health` waits for the RPC's block number before reporting it unreachable.
+const HEALTH_RPC_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The `health` result: crate version, chain and whether the RPC answers a block number
+/// within `HEALTH_RPC_TIMEOUT`.
+pub async fn health_result(client: &EthereumClient) -> Value {
+    let started = Instant::now();
+    let (block_number, error) =
+        match tokio::time::timeout(HEALTH_RPC_TIMEOUT, client.provider.get_block_number()).await {
+            Ok(Ok(number)) => (Some(number), None),
+            Ok(Err(e)) => (None, Some(e.to_string())),
+            Err(_) => (
+                None,
+                Some(format!(
+                    "no answer within {} ms",
+                    HEALTH_RPC_TIMEOUT.as_millis()
+                )),
+            ),
+        };
+    json!({
+        "status": if block_number.is_some() { "ok" } else { "degraded" },
+        "version": env!("CARGO_PKG_VERSION"),
+        "chain_id": client.chain_id(),
+        "block_number": block_number,
+        "rpc": {
+            "endpoint": client.active_endpoint(),
+            "reachable": block_number.is_some(),
+            "latency_ms": started.elapsed().as_millis() as u64,
+            "error": error
+        }
+    })
+}
+
 async fn handle_request(
     req: &JsonRpcRequest,
     client: &EthereumClient,
     tools: &HashMap<String, Box<dyn Tool>>,
+    audit_log: Option<&AuditLog>,
+    denylist: &DenyList,
+    session: &Session,
+    notify: &Notify,
 ) -> JsonRpcResponse {
+    let seq = session.next_request();
+    debug!("session {} request #{}: {}", session.id, seq, req.method);
     match req.method.as_str() {
+        "initialize" => {
+            if let Some(client_info) = req.params.as_ref().and_then(|p| p.get("clientInfo")) {
+                info!("session {} client: {}", session.id, client_info);
+            }
+            JsonRpcResponse {
+                jsonrpc: "2.0".into(),
+                result: Some(initialize_result(req.params.as_ref())),
+                error: None,
+                id: req.id.clone(),
+            }
+        }
+        // MCP liveness check: an empty result.
+        "ping" => JsonRpcResponse {
+            jsonrpc: "2.0".into(),
+            result: Some(json!({})),
+            error: None,
+            id: req.id.clone(),
+        },
+        "health" => JsonRpcResponse {
+            jsonrpc: "2.0".into(),
+            result: Some(health_result(client).await),
+            error: None,
+            id: req.id.clone(),
+        },
         "tools/list" => {
             let tool_list: Vec<Value> = tools
                 .values()
@@ -85,7 +1288,7 @@ async fn handle_request(
                     json!({
                         "name": t.name(),
                         "description": t.description(),
-                        "inputSchema": t.schema()
+                        "inputSchema": with_common_properties(t.schema())
                     })
                 })
                 .collect();
@@ -102,32 +1305,93 @@ async fn handle_request(
                 let name = params.get("name").and_then(|v| v.as_str());
                 let args = params.get("arguments").cloned().unwrap_or(json!({}));
 
+                let progress_token = params.get("_meta").and_then(|m| m.get("progressToken"));
+                let stream = args.get("stream").filter(|s| !s.is_null());
+                if stream.is_some() && progress_token.is_none() {
+                    return error_response(
+                        req.id.clone(),
+                        -32602,
+                        "stream requires params._meta.progressToken".into(),
+                    );
+                }
+
                 if let Some(tool_name) = name {
                     if let Some(tool) = tools.get(tool_name) {
-                        match tool.call(client, args).await {
-                            Ok(result) => JsonRpcResponse {
-                                jsonrpc: "2.0".into(),
-                                // Hybrid approach: Standard MCP 'content' for compatibility, plus 'data' for agents.
-                                result: Some(json!({
-                                    "content": [{
-                                        "type": "text",
-                                        "text": serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string())
-                                    }],
-                                    "data": result
-                                })),
-                                error: None,
-                                id: req.id.clone(),
-                            },
-                            Err(e) => JsonRpcResponse {
-                                jsonrpc: "2.0".into(),
-                                result: None,
-                                error: Some(JsonRpcError {
-                                    code: -32603,
-                                    message: format!("Tool execution failed: {}", e),
-                                    data: None,
-                                }),
-                                id: req.id.clone(),
-                            },
+                        let started = Instant::now();
+                        let broadcasts = Arc::new(Mutex::new(Vec::new()));
+                        // Deny-listed addresses are refused before the tool sees them.
+                        let (outcome, sent) = match denylist.check_args(&args, &client.tokens) {
+                            Ok(()) => {
+                                let call = async {
+                                    // ENS names are resolved here, inside the time limit.
+                                    if let Err(e) = denylist.check_ens_names(&args, client).await {
+                                        return (Err(e), 0);
+                                    }
+                                    match progress_token {
+                                        Some(token) => {
+                                            call_with_progress(
+                                                tool.as_ref(),
+                                                client,
+                                                args.clone(),
+                                                token,
+                                                notify,
+                                            )
+                                            .await
+                                        }
+                                        None => (tool.call(client, args.clone()).await, 0),
+                                    }
+                                };
+                                let limit = tool_time_limit(client, tool.as_ref(), &args);
+                                let call = with_session(session.id.clone(), call);
+                                within_time_limit(tool_name, limit, broadcasts.clone(), call).await
+                            }
+                            Err(e) => (Err(e), 0),
+                        };
+                        if let Some(audit_log) = audit_log {
+                            let broadcasts =
+                                broadcasts.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                            audit_log.record(
+                                tool_name,
+                                &args,
+                                &outcome,
+                                &broadcasts,
+                                started.elapsed(),
+                            );
+                        }
+                        // Trimmed after auditing so the log keeps the full result.
+                        let outcome = outcome
+                            .and_then(|result| select_fields(result, args.get("fields")))
+                            .and_then(|result| split_stream(result, stream));
+                        match outcome {
+                            Ok((result, items)) => {
+                                if let Some(token) = progress_token {
+                                    stream_items(token, items, sent, notify);
+                                }
+                                JsonRpcResponse {
+                                    jsonrpc: "2.0".into(),
+                                    // Hybrid approach: Standard MCP 'content' for compatibility, plus 'data' for agents.
+                                    // Large fields the tool marks go out as resources, not text.
+                                    result: Some(json!({
+                                        "content": tool_content(tool_name, &result, tool.large_fields()),
+                                        "data": result
+                                    })),
+                                    error: None,
+                                    id: req.id.clone(),
+                                }
+                            }
+                            Err(e) => {
+                                let kind = ToolError::classify(&e);
+                                JsonRpcResponse {
+                                    jsonrpc: "2.0".into(),
+                                    result: None,
+                                    error: Some(JsonRpcError {
+                                        code: kind.code(),
+                                        message: format!("Tool execution failed: {}", e),
+                                        data: Some(kind.data()),
+                                    }),
+                                    id: req.id.clone(),
+                                }
+                            }
                         }
                     } else {
                         JsonRpcResponse {
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// State scoped to one client connection. Over stdio that is one `run` invocation, so
+/// nothing here outlives the process or is shared with other server instances.
+/// Stateful features (confirmation tokens, idempotency keys, per-session address books)
+/// belong here rather than on the shared `EthereumClient`.
+#[derive(Debug)]
+pub struct Session {
+    /// 128 random bits. Over HTTP the id is the `Mcp-Session-Id` and scopes the session's
+    /// watches, so it must not be guessable.
+    pub id: String,
+    /// Unix time the session started.
+    pub started_at: u64,
+    /// Atomic so the requests of one JSON-RPC batch can be handled concurrently.
+    requests: AtomicU64,
+    /// Unix time in milliseconds the session was last used.
+    last_seen_ms: AtomicU64,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut id = [0u8; 16];
+        getrandom::getrandom(&mut id).expect("the OS random number generator is unavailable");
+        Self {
+            id: hex::encode(id),
+            started_at: now.as_secs(),
+            requests: AtomicU64::new(0),
+            last_seen_ms: AtomicU64::new(now.as_millis() as u64),
+        }
+    }
+
+    /// Counts a request against the session and returns its 1-based sequence number.
+    pub fn next_request(&self) -> u64 {
+        self.requests.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Marks the session as used now.
+    pub fn touch(&self) {
+        self.last_seen_ms.store(unix_millis(), Ordering::Relaxed);
+    }
+
+    /// Time since the session was last used.
+    pub fn idle_for(&self) -> Duration {
+        Duration::from_millis(
+            unix_millis().saturating_sub(self.last_seen_ms.load(Ordering::Relaxed)),
+        )
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,154 @@
+use crate::ethereum::EthereumClient;
+use alloy::{
+    primitives::{Address, B256, I256, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+    sol,
+    sol_types::SolEvent,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    event Transfer(address indexed from, address indexed to, uint256 value);
+}
+
+/// Net balance change of one account in one asset (`token == None` means native ETH).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub token: Option<Address>,
+    pub account: Address,
+    pub delta: I256,
+}
+
+impl BalanceChange {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "token": self.token.map(|t| t.to_string()).unwrap_or_else(|| "ETH".into()),
+            "account": self.account.to_string(),
+            "delta": self.delta.to_string()
+        })
+    }
+}
+
+/// Runs `tx` through `debug_traceCall` with geth's callTracer (logs enabled) and returns
+/// the ETH and ERC20 balance deltas it would cause. Errors if the node lacks the debug API.
+pub async fn trace_balance_changes(
+    client: &EthereumClient,
+    tx: &TransactionRequest,
+) -> Result<Vec<BalanceChange>> {
+    let trace: Value = client
+        .provider
+        .raw_request(
+            "debug_traceCall".into(),
+            (
+                tx,
+                "latest",
+                json!({ "tracer": "callTracer", "tracerConfig": { "withLog": true } }),
+            ),
+        )
+        .await?;
+    balance_changes_from_trace(&trace)
+}
+
+/// Folds a callTracer frame tree into per-(token, account) deltas.
+/// Value transfers of successful CALL frames count as ETH movements, and `Transfer` logs
+/// count as ERC20 movements. Frames that reverted are skipped along with their children.
+pub fn balance_changes_from_trace(trace: &Value) -> Result<Vec<BalanceChange>> {
+    let mut deltas: BTreeMap<(Option<Address>, Address), I256> = BTreeMap::new();
+    collect_frame(trace, &mut deltas)?;
+
+    Ok(deltas
+        .into_iter()
+        .filter(|(_, delta)| !delta.is_zero())
+        .map(|((token, account), delta)| BalanceChange {
+            token,
+            account,
+            delta,
+        })
+        .collect())
+}
+
+fn collect_frame(
+    frame: &Value,
+    deltas: &mut BTreeMap<(Option<Address>, Address), I256>,
+) -> Result<()> {
+    if frame.get("error").is_some() {
+        return Ok(());
+    }
+
+    let call_type = frame.get("type").and_then(|v| v.as_str()).unwrap_or("CALL");
+    let value = parse_quantity(frame.get("value"))?;
+    if !value.is_zero() && call_type != "DELEGATECALL" && call_type != "STATICCALL" {
+        let from = parse_address(frame.get("from"))?;
+        let to = parse_address(frame.get("to"))?;
+        apply_delta(deltas, None, from, value, false);
+        apply_delta(deltas, None, to, value, true);
+    }
+
+    if let Some(logs) = frame.get("logs").and_then(|v| v.as_array()) {
+        for log in logs {
+            let topics: Vec<B256> = log
+                .get("topics")
+                .and_then(|v| v.as_array())
+                .map(|ts| {
+                    ts.iter()
+                        .filter_map(|t| t.as_str().and_then(|s| B256::from_str(s).ok()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            // ERC721 also emits Transfer but with the id indexed (4 topics); only ERC20 has 3.
+            if topics.len() != 3 || topics[0] != Transfer::SIGNATURE_HASH {
+                continue;
+            }
+            let token = parse_address(log.get("address"))?;
+            let from = Address::from_word(topics[1]);
+            let to = Address::from_word(topics[2]);
+            let data = log.get("data").and_then(|v| v.as_str()).unwrap_or("0x");
+            let amount = U256::from_str(if data == "0x" { "0" } else { data })?;
+            apply_delta(deltas, Some(token), from, amount, false);
+            apply_delta(deltas, Some(token), to, amount, true);
+        }
+    }
+
+    if let Some(calls) = frame.get("calls").and_then(|v| v.as_array()) {
+        for call in calls {
+            collect_frame(call, deltas)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_delta(
+    deltas: &mut BTreeMap<(Option<Address>, Address), I256>,
+    token: Option<Address>,
+    account: Address,
+    amount: U256,
+    credit: bool,
+) {
+    let amount = I256::from_raw(amount);
+    let entry = deltas.entry((token, account)).or_insert(I256::ZERO);
+    *entry = if credit {
+        entry.saturating_add(amount)
+    } else {
+        entry.saturating_sub(amount)
+    };
+}
+
+fn parse_address(v: Option<&Value>) -> Result<Address> {
+    let s = v
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Trace frame is missing an address field"))?;
+    Ok(Address::from_str(s)?)
+}
+
+fn parse_quantity(v: Option<&Value>) -> Result<U256> {
+    match v.and_then(|v| v.as_str()) {
+        Some(s) => Ok(U256::from_str(s)?),
+        None => Ok(U256::ZERO),
+    }
+}
@@ -0,0 +1,338 @@
+use crate::ethereum::{decode_return, EthereumClient};
+use alloy::{
+    primitives::{address, Address},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::{Context, Result};
+use futures::future::join_all;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+sol! {
+    #[allow(missing_docs)]
+    function decimals() external view returns (uint8);
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub address: Address,
+    pub decimals: u8,
+    /// Curated entry. Built-ins are verified; list entries are unless marked `"verified": false`.
+    #[serde(default = "verified_by_default")]
+    pub verified: bool,
+    /// Chain the entry belongs to (token-list `chainId`); `None` matches any chain.
+    #[serde(default, alias = "chainId")]
+    pub chain_id: Option<u64>,
+}
+
+fn verified_by_default() -> bool {
+    true
+}
+
+/// How a symbol that several registry entries share is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolPolicy {
+    /// Use the single verified match; an unverified token only when it is the sole match.
+    PreferVerified,
+    /// Resolve symbols to verified tokens only.
+    VerifiedOnly,
+}
+
+impl SymbolPolicy {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "prefer_verified" => Ok(Self::PreferVerified),
+            "verified_only" => Ok(Self::VerifiedOnly),
+            other => Err(anyhow::anyhow!(
+                "Invalid SYMBOL_RESOLUTION value '{}': expected prefer_verified or verified_only",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PreferVerified => "prefer_verified",
+            Self::VerifiedOnly => "verified_only",
+        }
+    }
+}
+
+/// Outcome of resolving a symbol against the registry.
+#[derive(Debug, Clone)]
+pub struct SymbolResolution<'a> {
+    pub token: &'a TokenInfo,
+    /// Registry entries sharing the symbol, including the chosen one.
+    pub candidates: usize,
+}
+
+impl SymbolResolution<'_> {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "symbol": self.token.symbol,
+            "address": self.token.address.to_string(),
+            "verified": self.token.verified,
+            "candidates": self.candidates,
+            "decision": match (self.token.verified, self.candidates) {
+                (true, 1) => "only registry match (verified)",
+                (true, _) => "verified match preferred over unverified tokens with the same symbol",
+                (false, _) => "only registry match (unverified); double-check the address",
+            }
+        })
+    }
+}
+
+/// What to do with registry entries whose on-chain `decimals()` disagrees with the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenListValidation {
+    Off,
+    Warn,
+    Drop,
+}
+
+impl TokenListValidation {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "0" | "false" | "off" => Ok(Self::Off),
+            "1" | "true" | "warn" => Ok(Self::Warn),
+            "drop" => Ok(Self::Drop),
+            other => Err(anyhow::anyhow!(
+                "Invalid VALIDATE_TOKEN_LIST value '{}': expected false, warn, or drop",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Warn => "warn",
+            Self::Drop => "drop",
+        }
+    }
+}
+
+// Accepts either a bare array or the `{"tokens": [...]}` shape used by token lists.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TokenListFile {
+    Bare(Vec<TokenInfo>),
+    Wrapped { tokens: Vec<TokenInfo> },
+}
+
+/// Symbol -> token lookup used when callers pass a symbol instead of an address.
+#[derive(Debug, Clone)]
+pub struct TokenRegistry {
+    tokens: Vec<TokenInfo>,
+    policy: SymbolPolicy,
+}
+
+impl TokenRegistry {
+    pub fn builtin() -> Self {
+        let token = |symbol: &str, address: Address, decimals: u8| TokenInfo {
+            symbol: symbol.into(),
+            address,
+            decimals,
+            verified: true,
+            chain_id: Some(1),
+        };
+        Self {
+            tokens: vec![
+                token(
+                    "USDC",
+                    address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+                    6,
+                ),
+                token(
+                    "WETH",
+                    address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+                    18,
+                ),
+                token(
+                    "WBTC",
+                    address!("2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"),
+                    8,
+                ),
+            ],
+            policy: SymbolPolicy::PreferVerified,
+        }
+    }
+
+    /// Built-in tokens plus any from a JSON token list file. A file entry replaces built-ins
+    /// with its symbol; entries within the file may share a symbol and are disambiguated by
+    /// `resolve_symbol`.
+    pub fn load(token_list_path: Option<&str>) -> Result<Self> {
+        let mut registry = Self::builtin();
+        if let Some(path) = token_list_path {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read token list at {}", path))?;
+            let tokens = match serde_json::from_str::<TokenListFile>(&contents)
+                .with_context(|| format!("Failed to parse token list at {}", path))?
+            {
+                TokenListFile::Bare(tokens) | TokenListFile::Wrapped { tokens } => tokens,
+            };
+            let builtin_count = registry.tokens.len();
+            let mut from_file: Vec<TokenInfo> = Vec::new();
+            for token in tokens {
+                from_file.retain(|t| t.address != token.address || t.chain_id != token.chain_id);
+                from_file.push(token);
+            }
+            registry.tokens.truncate(builtin_count);
+            registry.tokens.retain(|b| {
+                !from_file
+                    .iter()
+                    .any(|t| t.symbol.eq_ignore_ascii_case(&b.symbol))
+            });
+            registry.tokens.extend(from_file);
+        }
+        Ok(registry)
+    }
+
+    pub fn tokens(&self) -> &[TokenInfo] {
+        &self.tokens
+    }
+
+    pub fn with_policy(mut self, policy: SymbolPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Drops entries tagged with a different chain id than the connected chain.
+    pub fn for_chain(mut self, chain_id: u64) -> Self {
+        let before = self.tokens.len();
+        self.tokens
+            .retain(|t| t.chain_id.is_none_or(|c| c == chain_id));
+        if self.tokens.len() < before {
+            info!(
+                "Token registry: dropped {} entries for other chains",
+                before - self.tokens.len()
+            );
+        }
+        self
+    }
+
+    /// Every registry entry with `symbol`, verified or not.
+    pub fn candidates<'a>(&'a self, symbol: &'a str) -> impl Iterator<Item = &'a TokenInfo> {
+        self.tokens
+            .iter()
+            .filter(move |t| t.symbol.eq_ignore_ascii_case(symbol))
+    }
+
+    /// Resolves `symbol` under the registry's policy. `Ok(None)` when nothing matches; an
+    /// error when the match is ambiguous (several verified tokens, or only unverified ones
+    /// that the policy rejects), so callers must pass an explicit address instead.
+    pub fn resolve_symbol(&self, symbol: &str) -> Result<Option<SymbolResolution<'_>>> {
+        let candidates: Vec<&TokenInfo> = self
+            .tokens
+            .iter()
+            .filter(|t| t.symbol.eq_ignore_ascii_case(symbol))
+            .collect();
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+        let listing = || {
+            candidates
+                .iter()
+                .map(|t| {
+                    format!(
+                        "{}{}",
+                        t.address,
+                        if t.verified { "" } else { " (unverified)" }
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let verified: Vec<&TokenInfo> = candidates.iter().copied().filter(|t| t.verified).collect();
+        let token = match (verified.as_slice(), self.policy) {
+            ([token], _) => *token,
+            ([], SymbolPolicy::PreferVerified) if candidates.len() == 1 => candidates[0],
+            ([], SymbolPolicy::VerifiedOnly) => {
+                return Err(anyhow::anyhow!(
+                "Symbol {} only matches unverified tokens ({}); pass the token address explicitly",
+                symbol,
+                listing()
+            ))
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Symbol {} is ambiguous ({}); pass the token address explicitly",
+                    symbol,
+                    listing()
+                ))
+            }
+        };
+        if candidates.len() > 1 {
+            warn!(
+                "Symbol {} is ambiguous ({}); resolved to verified {}",
+                symbol,
+                listing(),
+                token.address
+            );
+        }
+        Ok(Some(SymbolResolution {
+            token,
+            candidates: candidates.len(),
+        }))
+    }
+
+    /// `resolve_symbol`, treating ambiguous symbols as unknown.
+    pub fn resolve(&self, symbol: &str) -> Option<&TokenInfo> {
+        self.resolve_symbol(symbol).ok().flatten().map(|r| r.token)
+    }
+
+    /// Checks each entry's decimals against the chain, warning on mismatches and
+    /// unreachable tokens. With `TokenListValidation::Drop` those entries are removed.
+    pub async fn validate(self, client: &EthereumClient, mode: TokenListValidation) -> Self {
+        if mode == TokenListValidation::Off {
+            return self;
+        }
+        let policy = self.policy;
+
+        let checks = join_all(
+            self.tokens
+                .iter()
+                .map(|token| onchain_decimals(client, token.address)),
+        )
+        .await;
+
+        let mut tokens = Vec::with_capacity(self.tokens.len());
+        for (token, onchain) in self.tokens.into_iter().zip(checks) {
+            let problem = match onchain {
+                Ok(d) if d == token.decimals => None,
+                Ok(d) => Some(format!(
+                    "registry says {} decimals but chain reports {}",
+                    token.decimals, d
+                )),
+                Err(e) => Some(format!("decimals() call failed: {}", e)),
+            };
+            match problem {
+                None => tokens.push(token),
+                Some(problem) => {
+                    warn!(
+                        "Token list entry {} ({}): {}",
+                        token.symbol, token.address, problem
+                    );
+                    if mode == TokenListValidation::Warn {
+                        tokens.push(token);
+                    }
+                }
+            }
+        }
+        info!("Token registry validated: {} entries kept", tokens.len());
+        Self { tokens, policy }
+    }
+}
+
+async fn onchain_decimals(client: &EthereumClient, token: Address) -> Result<u8> {
+    let req = TransactionRequest::default()
+        .to(token)
+        .input(decimalsCall {}.abi_encode().into());
+    let res = client.provider.call(&req).await?;
+    Ok(decode_return::<decimalsCall>(&res, token)?._0)
+}
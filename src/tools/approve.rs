@@ -0,0 +1,141 @@
+use super::{resolve_token, transaction_sender, Tool};
+use crate::approval::{self, approveCall};
+use crate::error::ToolError;
+use crate::ethereum::{revert_data, revert_reason, EthereumClient};
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+pub struct ApproveTokenTool;
+
+#[async_trait::async_trait]
+impl Tool for ApproveTokenTool {
+    fn name(&self) -> &'static str {
+        "approve_token"
+    }
+
+    fn description(&self) -> &'static str {
+        "Approve a spender (e.g. the Uniswap router) to spend an ERC20 token from the server wallet, or simulate the approval. With unsigned (always in read-only mode), return the approval as a fully populated EIP-1559 transaction for an external signer instead of sending it."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token_address": {
+                    "type": "string",
+                    "description": "Address or registry symbol of the ERC20 token"
+                },
+                "spender": {
+                    "type": "string",
+                    "description": "Address allowed to spend the tokens"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Allowance in base units, or \"max\" for type(uint256).max"
+                },
+                "simulate_only": {
+                    "type": "boolean",
+                    "description": "Run the approval via eth_call instead of sending it. Default false."
+                },
+                "private": {
+                    "type": "boolean",
+                    "description": "Send only to the configured PRIVATE_RPC_URL relay instead of the public mempool. Default false."
+                },
+                "unsigned": {
+                    "type": "boolean",
+                    "description": "Return the approval unsigned, with nonce, gas and fees filled, instead of signing and sending it. Always on in read-only mode. Default false."
+                },
+                "from": {
+                    "type": "string",
+                    "description": "With unsigned: the token owner that will sign (address or ENS name). Default: the server wallet; required in read-only mode."
+                }
+            },
+            "required": ["token_address", "spender", "amount"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let (owner, unsigned) = transaction_sender(client, &args).await?;
+        let token = resolve_token(
+            client,
+            args["token_address"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("token_address"))?,
+        )?;
+        let spender = Address::from_str(
+            args["spender"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("spender"))?,
+        )?;
+        let amount = match args["amount"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("amount"))?
+            .trim()
+        {
+            "max" | "MAX" => U256::MAX,
+            amount => U256::from_str(amount).map_err(|_| {
+                anyhow::anyhow!(
+                    "amount must be a base-unit integer or \"max\", got {:?}",
+                    amount
+                )
+            })?,
+        };
+        let simulate_only = args
+            .get("simulate_only")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let private = args
+            .get("private")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let tx = approval::approve_request(token, spender, amount);
+        let call_data = approveCall { spender, amount }.abi_encode();
+        let mut result = json!({
+            "token": token.to_string(),
+            "owner": owner.to_string(),
+            "spender": spender.to_string(),
+            "amount": amount.to_string(),
+            "unlimited": amount == U256::MAX,
+            "data": format!("0x{}", hex::encode(&call_data))
+        });
+
+        if simulate_only {
+            // Some tokens (USDT) return nothing from approve, which is still a success.
+            let simulation = match client.provider.call(&tx.clone().from(owner)).await {
+                Ok(data) if data.is_empty() => {
+                    json!({"status": "ok", "message": "call succeeded (token returns no value)"})
+                }
+                Ok(data) => match approveCall::abi_decode_returns(&data, true) {
+                    Ok(ret) if ret._0 => json!({"status": "ok", "returned": true}),
+                    Ok(_) => json!({"status": "error", "message": "approve returned false"}),
+                    Err(_) => json!({"status": "ok", "message": "call succeeded"}),
+                },
+                Err(e) => json!({
+                    "status": "error",
+                    "message": e.to_string(),
+                    "revert_reason": revert_data(&e).as_deref().and_then(revert_reason)
+                }),
+            };
+            result["simulation"] = simulation;
+            return Ok(result);
+        }
+
+        if unsigned {
+            result["unsigned_transaction"] =
+                client.unsigned_transaction(tx, owner).await?.to_json();
+            return Ok(result);
+        }
+        let submission = client.submit_transaction(tx, private).await?;
+        for (key, value) in submission.to_json().as_object().into_iter().flatten() {
+            result[key] = value.clone();
+        }
+        Ok(result)
+    }
+}
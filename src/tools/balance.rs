@@ -1,15 +1,10 @@
-use super::Tool;
-use crate::ethereum::EthereumClient;
-use alloy::{
-    primitives::{Address, U256},
-    providers::Provider,
-    sol,
-    sol_types::SolCall,
-};
+use super::{resolve_address, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{decode_return, EthereumClient};
+use crate::format::display_amount;
+use alloy::{primitives::U256, providers::Provider, sol, sol_types::SolCall};
 use anyhow::Result;
-use rust_decimal::Decimal;
 use serde_json::{json, Value};
-use std::str::FromStr;
 
 sol! {
     #[allow(missing_docs)]
@@ -52,14 +47,14 @@ impl Tool for GetBalanceTool {
     async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
         let address_str = args["address"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Missing address"))?;
-        let address = Address::from_str(address_str)?;
+            .ok_or_else(|| ToolError::missing("address"))?;
+        let address = resolve_address(client, address_str).await?;
 
         let token_address_str = args.get("token_address").and_then(|v| v.as_str());
 
         if let Some(token_addr_str) = token_address_str {
             // ERC20 Balance
-            let token_address = Address::from_str(token_addr_str)?;
+            let token_address = resolve_address(client, token_addr_str).await?;
 
             let call_data = balanceOfCall { account: address }.abi_encode();
 
@@ -68,28 +63,16 @@ impl Tool for GetBalanceTool {
                 .input(call_data.into());
 
             let result = client.provider.call(&tx_req).await?;
-            let balance: U256 = balanceOfCall::abi_decode_returns(&result, true)?._0;
+            let balance: U256 = decode_return::<balanceOfCall>(&result, token_address)?._0;
 
-            // Get decimals
-            let decimals_data = decimalsCall {}.abi_encode();
-            let decimals_req = alloy::rpc::types::eth::TransactionRequest::default()
-                .to(token_address)
-                .input(decimals_data.into());
-            let decimals_res = client.provider.call(&decimals_req).await?;
-            let decimals: u8 = decimalsCall::abi_decode_returns(&decimals_res, true)?._0;
-
-            // Get symbol
-            let symbol_data = symbolCall {}.abi_encode();
-            let symbol_req = alloy::rpc::types::eth::TransactionRequest::default()
-                .to(token_address)
-                .input(symbol_data.into());
-            let symbol_res = client.provider.call(&symbol_req).await?;
-            let symbol: String = symbolCall::abi_decode_returns(&symbol_res, true)?._0;
+            // Decimals and symbol are cached per token after the first read.
+            let metadata = client.get_token_metadata(token_address).await?;
+            let (decimals, symbol) = (metadata.decimals, metadata.symbol);
 
             let formatted = format_units(balance, decimals)?;
 
             Ok(json!({
-                "balance": formatted,
+                "balance": display_amount(&formatted),
                 "raw_balance": balance.to_string(),
                 "symbol": symbol,
                 "decimals": decimals
@@ -100,7 +83,7 @@ impl Tool for GetBalanceTool {
             let formatted = format_units(balance, 18)?;
 
             Ok(json!({
-                "balance": formatted,
+                "balance": display_amount(&formatted),
                 "raw_balance": balance.to_string(),
                 "symbol": "ETH",
                 "decimals": 18
@@ -109,20 +92,34 @@ impl Tool for GetBalanceTool {
     }
 }
 
-fn format_units(value: U256, decimals: u8) -> Result<String> {
-    let s = value.to_string();
-    let d = Decimal::from_str(&s)?;
-    let scale = pow10_decimal(decimals as i32)?;
-    Ok((d / scale).normalize().to_string())
+/// Decodes ERC20 `symbol()` return data. Older tokens (MKR, SAI) declare `bytes32`
+/// instead of `string`; that word is read up to its first zero byte.
+pub fn decode_symbol(data: &[u8]) -> Option<String> {
+    if let Ok(symbol) = symbolCall::abi_decode_returns(data, true) {
+        return Some(symbol._0);
+    }
+    if data.len() != 32 {
+        return None;
+    }
+    let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    String::from_utf8(data[..end].to_vec()).ok()
 }
 
-fn pow10_decimal(exp: i32) -> Result<Decimal> {
-    if exp < 0 {
-        let positive = pow10_decimal(-exp)?;
-        return Ok(Decimal::ONE / positive);
+/// `value / 10^decimals` as a plain decimal string with trailing zeros trimmed. Done on
+/// the digit string, so it is exact for any `U256`; `Decimal` tops out near 7.9e28.
+pub fn format_units(value: U256, decimals: u8) -> Result<String> {
+    let digits = value.to_string();
+    let decimals = usize::from(decimals);
+    let (whole, fraction) = if digits.len() > decimals {
+        digits.split_at(digits.len() - decimals)
+    } else {
+        ("0", digits.as_str())
+    };
+    let fraction = format!("{:0>width$}", fraction, width = decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        Ok(whole.to_string())
+    } else {
+        Ok(format!("{}.{}", whole, fraction))
     }
-
-    let exp_usize = usize::try_from(exp).unwrap_or(0);
-    let s = format!("1{}", "0".repeat(exp_usize));
-    Ok(Decimal::from_str(&s)?)
 }
@@ -0,0 +1,203 @@
+use super::balance::{balanceOfCall, decimalsCall, decode_symbol, format_units, symbolCall};
+use super::swap::NATIVE_ETH;
+use super::{check_batch_size, resolve_address, resolve_token, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{
+    call3, getEthBalanceCall, Call3, Call3Result, EthereumClient, MULTICALL3_ADDRESS,
+};
+use crate::format::display_amount;
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+pub struct GetBalancesTool;
+
+#[async_trait::async_trait]
+impl Tool for GetBalancesTool {
+    fn name(&self) -> &'static str {
+        "get_balances"
+    }
+
+    fn description(&self) -> &'static str {
+        "Balances of many tokens (and native ETH) for one address in a single Multicall3 batch. A token whose calls fail gets a per-entry error instead of failing the batch."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "The wallet address (or ENS name) to check balances for"
+                },
+                "token_addresses": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "ERC20 token addresses (or registry symbols), at most MAX_BATCH_SIZE (default 100). \"ETH\" or 0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE is the native ETH balance."
+                }
+            },
+            "required": ["address", "token_addresses"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let address_str = args["address"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("address"))?;
+        let tokens: Vec<&str> = args["token_addresses"]
+            .as_array()
+            .ok_or_else(|| ToolError::missing("token_addresses"))?
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .ok_or_else(|| anyhow::anyhow!("token_addresses must be an array of strings"))
+            })
+            .collect::<Result<_>>()?;
+        check_batch_size(client, tokens.len())?;
+        let address = resolve_address(client, address_str).await?;
+
+        // One batch: getEthBalance for native entries, balanceOf/decimals/symbol for the
+        // rest. Without Multicall3 there is no getEthBalance, so the node is asked directly.
+        let batched_native = client.multicall_available;
+        let entries: Vec<Result<Entry, String>> = tokens
+            .iter()
+            .map(|token| parse_entry(client, token))
+            .collect();
+        let mut calls: Vec<Call3> = Vec::new();
+        for entry in entries.iter().flatten() {
+            calls.extend(entry.calls(address, batched_native));
+        }
+        let results = client.multicall(calls).await?;
+        let native_balance =
+            if !batched_native && entries.iter().flatten().any(|e| *e == Entry::Native) {
+                Some(client.provider.get_balance(address).await?)
+            } else {
+                None
+            };
+
+        let mut results = results.iter();
+        let balances: Vec<Value> = tokens
+            .iter()
+            .zip(entries)
+            .map(|(token, entry)| {
+                let outcome = entry.and_then(|entry| {
+                    let taken: Vec<&Call3Result> = results
+                        .by_ref()
+                        .take(entry.calls(address, batched_native).len())
+                        .collect();
+                    entry.balance(&taken, native_balance)
+                });
+                match outcome {
+                    Ok(mut balance) => {
+                        balance["token"] = json!(token);
+                        balance["status"] = json!("ok");
+                        balance
+                    }
+                    Err(error) => json!({
+                        "token": token,
+                        "status": "error",
+                        "error": error
+                    }),
+                }
+            })
+            .collect();
+
+        let succeeded = balances.iter().filter(|b| b["status"] == "ok").count();
+        Ok(json!({
+            "address": address.to_string(),
+            "balances": balances,
+            "summary": {
+                "requested": tokens.len(),
+                "succeeded": succeeded,
+                "failed": tokens.len() - succeeded
+            }
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Native,
+    Token(Address),
+}
+
+fn parse_entry(client: &EthereumClient, token: &str) -> Result<Entry, String> {
+    if token.eq_ignore_ascii_case("ETH") {
+        return Ok(Entry::Native);
+    }
+    match resolve_token(client, token) {
+        Ok(address) if address == NATIVE_ETH => Ok(Entry::Native),
+        Ok(address) => Ok(Entry::Token(address)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+impl Entry {
+    /// The entry's reads in the batch; none for native ETH unless `batched_native`.
+    fn calls(&self, account: Address, batched_native: bool) -> Vec<Call3> {
+        match *self {
+            Entry::Native if !batched_native => Vec::new(),
+            Entry::Native => vec![call3(
+                MULTICALL3_ADDRESS,
+                getEthBalanceCall { addr: account }.abi_encode(),
+            )],
+            Entry::Token(token) => vec![
+                call3(token, balanceOfCall { account }.abi_encode()),
+                call3(token, decimalsCall {}.abi_encode()),
+                call3(token, symbolCall {}.abi_encode()),
+            ],
+        }
+    }
+
+    /// The entry's balance from its results in the batch, or `native` when the ETH balance
+    /// was read outside it.
+    fn balance(&self, results: &[&Call3Result], native: Option<U256>) -> Result<Value, String> {
+        let (symbol, raw_balance, decimals) = match *self {
+            Entry::Native => {
+                let balance = match native {
+                    Some(balance) => balance,
+                    None => decode::<getEthBalanceCall>(results[0])
+                        .map(|r| r.balance)
+                        .ok_or("Multicall3 getEthBalance failed")?,
+                };
+                (Some("ETH".to_string()), balance, 18)
+            }
+            Entry::Token(_) => {
+                let (Some(balance), Some(decimals)) = (
+                    decode::<balanceOfCall>(results[0]).map(|r| r._0),
+                    decode::<decimalsCall>(results[1]).map(|r| r._0),
+                ) else {
+                    return Err(
+                        "balanceOf/decimals call failed; not an ERC20 on this chain?".to_string(),
+                    );
+                };
+                let symbol = Some(results[2])
+                    .filter(|r| r.success)
+                    .and_then(|r| decode_symbol(&r.returnData));
+                (symbol, balance, decimals)
+            }
+        };
+        let formatted = format_units(raw_balance, decimals).map_err(|e| e.to_string())?;
+        Ok(json!({
+            "token_address": match *self {
+                Entry::Native => Value::Null,
+                Entry::Token(token) => json!(token.to_string()),
+            },
+            "symbol": symbol,
+            "balance": display_amount(&formatted),
+            "raw_balance": raw_balance.to_string(),
+            "decimals": decimals
+        }))
+    }
+}
+
+fn decode<C: SolCall>(result: &Call3Result) -> Option<C::Return> {
+    if !result.success {
+        return None;
+    }
+    C::abi_decode_returns(&result.returnData, true).ok()
+}
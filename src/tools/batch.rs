@@ -0,0 +1,305 @@
+use super::balance::{balanceOfCall, format_units, GetBalanceTool};
+use super::balances::GetBalancesTool;
+use super::chains::ListChainsTool;
+use super::convert::ConvertTool;
+use super::encode::EncodeCalldataTool;
+use super::events::GetBlockRangeEventsTool;
+use super::gas::GetGasPriceTool;
+use super::gas_deadline::GasForDeadlineTool;
+use super::hash::HashTool;
+use super::observations::GetPoolObservationsTool;
+use super::pool_fees::GetPoolFeesTool;
+use super::price::GetTokenPriceTool;
+use super::prices::GetPricesTool;
+use super::profile::AccountProfileTool;
+use super::rebasing::CheckRebasingTool;
+use super::transfers::GetTokenTransfersTool;
+use super::{check_batch_size, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{call3, getEthBalanceCall, Call3, EthereumClient, MULTICALL3_ADDRESS};
+use crate::format::display_amount;
+use alloy::{
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use futures::future::join_all;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+pub struct BatchReadTool;
+
+/// How one sub-call is answered.
+enum Plan {
+    /// `get_balance`, answered by the Multicall3 entry at `slot`; `token` is `None` for ETH.
+    Balance {
+        slot: usize,
+        token: Option<Address>,
+    },
+    /// Raw `eth_call`, answered by the Multicall3 entry at `slot`.
+    Call {
+        slot: usize,
+    },
+    /// Run through the tool itself, concurrently with the batch.
+    Dispatch,
+    Invalid(String),
+}
+
+#[async_trait::async_trait]
+impl Tool for BatchReadTool {
+    fn name(&self) -> &'static str {
+        "batch_read"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run several read-only tool calls at once. Balance reads and raw eth_calls share one Multicall3 request; other read-only tools run concurrently. Reports the RPC round trips saved."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "calls": {
+                    "type": "array",
+                    "description": "Up to MAX_BATCH_SIZE calls (default 100), each {\"name\": <read-only tool or \"eth_call\">, \"arguments\": {...}}. eth_call takes {\"to\", \"data\"}. Tools that sign or send are refused.",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "arguments": { "type": "object" }
+                        },
+                        "required": ["name"]
+                    }
+                }
+            },
+            "required": ["calls"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let calls = args
+            .get("calls")
+            .filter(|c| !c.is_null())
+            .ok_or_else(|| ToolError::missing("calls"))?
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("calls must be an array"))?;
+        if calls.is_empty() {
+            return Err(anyhow::anyhow!("calls must have at least one entry"));
+        }
+        check_batch_size(client, calls.len())?;
+
+        // Without Multicall3 the "batch" would be the same calls one by one, so everything
+        // is dispatched as-is instead.
+        let mut aggregate: Vec<Call3> = Vec::new();
+        let plans: Vec<Plan> = calls
+            .iter()
+            .map(|call| {
+                let Some(name) = call["name"].as_str() else {
+                    return Plan::Invalid("missing name".into());
+                };
+                if !client.multicall_available {
+                    return Plan::Dispatch;
+                }
+                let arguments = &call["arguments"];
+                let planned = match name {
+                    "get_balance" => balance_call(arguments).map(|(c, token)| {
+                        aggregate.push(c);
+                        Plan::Balance {
+                            slot: aggregate.len() - 1,
+                            token,
+                        }
+                    }),
+                    "eth_call" => raw_call(arguments).map(|c| {
+                        aggregate.push(c);
+                        Plan::Call {
+                            slot: aggregate.len() - 1,
+                        }
+                    }),
+                    _ => Ok(Plan::Dispatch),
+                };
+                planned.unwrap_or_else(|e| Plan::Invalid(e.to_string()))
+            })
+            .collect();
+
+        let mut tokens: Vec<Address> = plans
+            .iter()
+            .filter_map(|p| match p {
+                Plan::Balance {
+                    token: Some(token), ..
+                } => Some(*token),
+                _ => None,
+            })
+            .collect();
+        tokens.sort();
+        tokens.dedup();
+
+        let dispatched = calls
+            .iter()
+            .zip(&plans)
+            .filter(|(_, plan)| matches!(plan, Plan::Dispatch))
+            .map(|(call, _)| {
+                dispatch(
+                    client,
+                    call["name"].as_str().unwrap_or_default(),
+                    call.get("arguments").cloned().unwrap_or(json!({})),
+                )
+            });
+        let aggregated_count = aggregate.len();
+        let (batch, dispatched, metadata) = futures::join!(
+            async {
+                if aggregate.is_empty() {
+                    Ok(Vec::new())
+                } else {
+                    client.multicall(aggregate).await
+                }
+            },
+            join_all(dispatched),
+            join_all(tokens.iter().map(|t| client.get_token_metadata(*t)))
+        );
+        let batch = batch?;
+        let metadata: HashMap<Address, _> = tokens.into_iter().zip(metadata).collect();
+
+        let mut dispatched = dispatched.into_iter();
+        let results: Vec<Value> = calls
+            .iter()
+            .zip(plans)
+            .map(|(call, plan)| {
+                let outcome = match plan {
+                    Plan::Balance { slot, token } => {
+                        balance_result(&batch[slot], token.map(|t| (t, &metadata[&t])))
+                    }
+                    Plan::Call { slot } => Ok(json!({
+                        "success": batch[slot].success,
+                        "return_data": batch[slot].returnData.to_string()
+                    })),
+                    Plan::Dispatch => dispatched
+                        .next()
+                        .unwrap_or_else(|| Err(anyhow::anyhow!("not dispatched"))),
+                    Plan::Invalid(message) => Err(anyhow::anyhow!(message)),
+                };
+                match outcome {
+                    Ok(result) => json!({ "name": call["name"], "result": result }),
+                    Err(e) => json!({ "name": call["name"], "error": e.to_string() }),
+                }
+            })
+            .collect();
+
+        Ok(json!({
+            "results": results,
+            "aggregated_calls": aggregated_count,
+            "dispatched_calls": calls.len() - aggregated_count,
+            "rpc_round_trips_saved": aggregated_count.saturating_sub(1),
+            "multicall_available": client.multicall_available,
+            "note": "Aggregated reads (get_balance and eth_call) all come from one Multicall3 request at the same block. Dispatched tools make their own RPC calls, concurrently. Token decimals and symbols are cached per client after the first read."
+        }))
+    }
+}
+
+/// Runs a read-only tool (or a single `eth_call`) on its own.
+async fn dispatch(client: &EthereumClient, name: &str, args: Value) -> Result<Value> {
+    match name {
+        "eth_call" => {
+            let call = raw_call(&args)?;
+            let req = TransactionRequest::default()
+                .to(call.target)
+                .input(call.callData.into());
+            let data = client.provider.call(&req).await?;
+            Ok(json!({ "success": true, "return_data": data.to_string() }))
+        }
+        "get_balance" => GetBalanceTool.call(client, args).await,
+        "get_balances" => GetBalancesTool.call(client, args).await,
+        "list_chains" => ListChainsTool.call(client, args).await,
+        "get_token_price" => GetTokenPriceTool.call(client, args).await,
+        "get_token_prices" => GetPricesTool.call(client, args).await,
+        "convert_amount" => ConvertTool.call(client, args).await,
+        "encode_calldata" => EncodeCalldataTool.call(client, args).await,
+        "get_block_range_events" => GetBlockRangeEventsTool.call(client, args).await,
+        "get_token_transfers" => GetTokenTransfersTool.call(client, args).await,
+        "get_pool_observations" => GetPoolObservationsTool.call(client, args).await,
+        "get_pool_fees" => GetPoolFeesTool.call(client, args).await,
+        "get_gas_price" => GetGasPriceTool.call(client, args).await,
+        "recommend_gas_for_deadline" => GasForDeadlineTool.call(client, args).await,
+        "compute_hash" => HashTool.call(client, args).await,
+        "get_account_profile" => AccountProfileTool.call(client, args).await,
+        "check_rebasing" => CheckRebasingTool.call(client, args).await,
+        other => Err(anyhow::anyhow!(
+            "{} is not a read-only tool batch_read can run",
+            other
+        )),
+    }
+}
+
+/// The Multicall3 entry for a `get_balance` call: `getEthBalance` for ETH, else the token's
+/// `balanceOf`.
+fn balance_call(args: &Value) -> Result<(Call3, Option<Address>)> {
+    let address = Address::from_str(
+        args["address"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("address"))?,
+    )?;
+    match args.get("token_address").and_then(|v| v.as_str()) {
+        Some(token) => {
+            let token = Address::from_str(token)?;
+            Ok((
+                call3(token, balanceOfCall { account: address }.abi_encode()),
+                Some(token),
+            ))
+        }
+        None => Ok((
+            call3(
+                MULTICALL3_ADDRESS,
+                getEthBalanceCall { addr: address }.abi_encode(),
+            ),
+            None,
+        )),
+    }
+}
+
+fn raw_call(args: &Value) -> Result<Call3> {
+    let to = Address::from_str(
+        args["to"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("to"))?,
+    )?;
+    let data = Bytes::from_str(
+        args["data"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("data"))?,
+    )?;
+    Ok(call3(to, data.to_vec()))
+}
+
+/// A batched balance in `get_balance`'s shape.
+fn balance_result(
+    result: &crate::ethereum::Call3Result,
+    token: Option<(Address, &Result<crate::ethereum::TokenMetadata>)>,
+) -> Result<Value> {
+    if !result.success || result.returnData.len() != 32 {
+        return Err(anyhow::anyhow!(
+            "balance read failed{}",
+            token
+                .map(|(t, _)| format!("; is {} an ERC20 token?", t))
+                .unwrap_or_default()
+        ));
+    }
+    let balance = U256::from_be_slice(&result.returnData);
+    let (decimals, symbol) = match token {
+        Some((_, metadata)) => {
+            let metadata = metadata
+                .as_ref()
+                .map_err(|e| anyhow::anyhow!("decimals unavailable: {}", e))?;
+            (metadata.decimals, json!(metadata.symbol))
+        }
+        None => (18, json!("ETH")),
+    };
+    let formatted = format_units(balance, decimals)?;
+    Ok(json!({
+        "balance": display_amount(&formatted),
+        "raw_balance": balance.to_string(),
+        "symbol": symbol,
+        "decimals": decimals
+    }))
+}
@@ -0,0 +1,100 @@
+use super::{u64_arg, Tool};
+use crate::ethereum::EthereumClient;
+use crate::{format, rpc};
+use alloy::{primitives::B256, providers::Provider};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+pub(crate) const BLOCK_TAGS: [&str; 5] = ["latest", "pending", "safe", "finalized", "earliest"];
+
+pub struct GetBlockTool;
+
+#[async_trait::async_trait]
+impl Tool for GetBlockTool {
+    fn name(&self) -> &'static str {
+        "get_block"
+    }
+
+    fn description(&self) -> &'static str {
+        "Block header details: number, hash, parent hash, timestamp, gas used and limit, base fee per gas and transaction count, for the latest block or one given by number, hash or tag."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "block": {
+                    "type": ["integer", "string"],
+                    "description": "Block number, 32-byte block hash, or a tag: \"latest\", \"pending\", \"safe\", \"finalized\" or \"earliest\". Default latest."
+                }
+            }
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let tag = args["block"].as_str().map(str::trim);
+        let block: Option<Value> = match tag {
+            None => {
+                let at = match u64_arg(&args, "block")? {
+                    Some(number) => format!("0x{:x}", number),
+                    None => "latest".to_string(),
+                };
+                client
+                    .provider
+                    .raw_request("eth_getBlockByNumber".into(), (at, false))
+                    .await?
+            }
+            Some(tag) if BLOCK_TAGS.contains(&tag) => {
+                client
+                    .provider
+                    .raw_request("eth_getBlockByNumber".into(), (tag, false))
+                    .await?
+            }
+            Some(hash) if hash.len() == 66 => {
+                let hash = B256::from_str(hash)
+                    .map_err(|e| anyhow::anyhow!("Invalid block hash {}: {}", hash, e))?;
+                client
+                    .provider
+                    .raw_request("eth_getBlockByHash".into(), (hash, false))
+                    .await?
+            }
+            Some(_) => {
+                let number = u64_arg(&args, "block").ok().flatten().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "block must be a block number, a block hash or one of {}, got {}",
+                        BLOCK_TAGS.join(", "),
+                        args["block"]
+                    )
+                })?;
+                client
+                    .provider
+                    .raw_request(
+                        "eth_getBlockByNumber".into(),
+                        (format!("0x{:x}", number), false),
+                    )
+                    .await?
+            }
+        };
+        let block = block.filter(|b| !b.is_null()).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Block {} not found",
+                tag.unwrap_or(&args["block"].to_string())
+            )
+        })?;
+
+        let timestamp = rpc::quantity(&block, "timestamp").map(|t| format::timestamp(t as u64));
+        Ok(json!({
+            // Pending blocks have no number or hash yet.
+            "number": rpc::quantity(&block, "number").map(|n| n as u64),
+            "hash": rpc::string(&block, "hash"),
+            "parent_hash": rpc::string(&block, "parentHash"),
+            "timestamp": timestamp,
+            "gas_used": rpc::quantity(&block, "gasUsed").map(|g| g as u64),
+            "gas_limit": rpc::quantity(&block, "gasLimit").map(|g| g as u64),
+            // Absent before London.
+            "base_fee_per_gas": rpc::quantity(&block, "baseFeePerGas").map(|f| f.to_string()),
+            "transaction_count": block["transactions"].as_array().map_or(0, |t| t.len())
+        }))
+    }
+}
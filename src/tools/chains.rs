@@ -0,0 +1,62 @@
+use super::Tool;
+use crate::chains::{self, ChainConfig};
+use crate::ethereum::EthereumClient;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+pub struct ListChainsTool;
+
+#[async_trait::async_trait]
+impl Tool for ListChainsTool {
+    fn name(&self) -> &'static str {
+        "list_chains"
+    }
+
+    fn description(&self) -> &'static str {
+        "List the chains this server knows, which one the RPC is connected to, and which Uniswap V3 / Chainlink contracts are configured on each."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, _args: Value) -> Result<Value> {
+        let active_chain_id = client.chain_id();
+
+        let chains: Vec<Value> = chains::supported()
+            .iter()
+            .map(|c| chain_json(c, c.chain_id == active_chain_id))
+            .collect();
+
+        Ok(json!({
+            "active_chain_id": active_chain_id,
+            "active_chain_supported": chains::get(active_chain_id).is_some(),
+            "chains": chains,
+            "note": "Price and swap tools currently use the Ethereum Mainnet deployments; other chains list their addresses for reference."
+        }))
+    }
+}
+
+fn chain_json(chain: &ChainConfig, active: bool) -> Value {
+    json!({
+        "chain_id": chain.chain_id,
+        "name": chain.name,
+        "active": active,
+        "block_time_seconds": chain.block_time_seconds,
+        "weth": chain.weth.to_string(),
+        "uniswap_v3": chain.uniswap_v3.map(|u| json!({
+            "factory": u.factory.to_string(),
+            "quoter": u.quoter.to_string(),
+            "quoter_version": u.quoter_version.as_str(),
+            "swap_router": u.swap_router.to_string()
+        })),
+        "chainlink": {
+            "eth_usd_feed": chain.eth_usd_feed.map(|f| f.to_string()),
+            "token_usd_feeds": chain.token_usd_feeds.len()
+        },
+        "ens_registry": chain.ens_registry.map(|r| r.to_string())
+    })
+}
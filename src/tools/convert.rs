@@ -0,0 +1,147 @@
+use super::price::{price_args_for, GetTokenPriceTool};
+use super::Tool;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use alloy::primitives::Address;
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+
+pub struct ConvertTool;
+
+#[async_trait::async_trait]
+impl Tool for ConvertTool {
+    fn name(&self) -> &'static str {
+        "convert_amount"
+    }
+
+    fn description(&self) -> &'static str {
+        "Convert an amount of one token into another using live prices. A pure valuation: no pool depth or slippage is considered."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "from_token": {
+                    "type": "string",
+                    "description": "Symbol or address of the token to convert from"
+                },
+                "to_token": {
+                    "type": "string",
+                    "description": "Symbol or address of the token to convert to"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Amount of from_token in human units (e.g., \"0.5\")"
+                }
+            },
+            "required": ["from_token", "to_token", "amount"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let from_token = args["from_token"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("from_token"))?;
+        let to_token = args["to_token"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("to_token"))?;
+        let amount = Decimal::from_str(
+            args["amount"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("amount"))?,
+        )?;
+
+        // The price tool prices every token against its WETH pool, so when one side is
+        // ETH the pool rate is used directly instead of going through USD. Trusted
+        // stablecoins are priced at their live USD peg instead.
+        let mut pegs = Map::new();
+        let (rate, source) = match (is_eth(client, from_token), is_eth(client, to_token)) {
+            (true, true) => (Decimal::ONE, "identity (ETH/WETH)"),
+            (false, true) => {
+                let from_price = fetch_price(client, from_token).await?;
+                let source = eth_rate_source(&from_price);
+                collect_peg(&mut pegs, from_token, &from_price);
+                (decimal_field(&from_price, "price_eth")?, source)
+            }
+            (true, false) => {
+                let to_price = fetch_price(client, to_token).await?;
+                let source = eth_rate_source(&to_price);
+                collect_peg(&mut pegs, to_token, &to_price);
+                let to_eth = decimal_field(&to_price, "price_eth")?;
+                (Decimal::ONE / non_zero(to_eth, to_token)?, source)
+            }
+            (false, false) => {
+                let from_price = fetch_price(client, from_token).await?;
+                let to_price = fetch_price(client, to_token).await?;
+                collect_peg(&mut pegs, from_token, &from_price);
+                collect_peg(&mut pegs, to_token, &to_price);
+                let from_usd = decimal_field(&from_price, "price_usd")?;
+                let to_usd = decimal_field(&to_price, "price_usd")?;
+                (from_usd / non_zero(to_usd, to_token)?, "USD prices")
+            }
+        };
+        let pegs = (!pegs.is_empty()).then_some(Value::Object(pegs));
+
+        Ok(json!({
+            "stablecoin_pegs": pegs,
+            "from_token": from_token,
+            "to_token": to_token,
+            "amount_in": amount,
+            "amount_out": (amount * rate).normalize(),
+            "rate": rate.normalize(),
+            "source": source,
+            "note": "Price-based valuation; a real swap would also incur fees, price impact and slippage."
+        }))
+    }
+}
+
+/// `ETH`, `WETH`, or the connected chain's WETH address.
+pub(crate) fn is_eth(client: &EthereumClient, token: &str) -> bool {
+    token.eq_ignore_ascii_case("ETH")
+        || token.eq_ignore_ascii_case("WETH")
+        || client
+            .chain()
+            .is_ok_and(|chain| Address::from_str(token).ok() == Some(chain.weth))
+}
+
+pub(crate) async fn fetch_price(client: &EthereumClient, token: &str) -> Result<Value> {
+    GetTokenPriceTool
+        .price(client, price_args_for(token))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to price {}: {}", token, e))
+}
+
+/// Stablecoins are priced from their peg and ETH/USD, not from a pool.
+fn eth_rate_source(price: &Value) -> &'static str {
+    if price.get("stablecoin_peg").is_some() {
+        "stablecoin peg and Chainlink ETH/USD"
+    } else {
+        "Uniswap V3 pool rate"
+    }
+}
+
+fn collect_peg(pegs: &mut Map<String, Value>, token: &str, price: &Value) {
+    if let Some(peg) = price.get("stablecoin_peg") {
+        pegs.insert(token.to_string(), peg.clone());
+    }
+}
+
+pub(crate) fn decimal_field(price: &Value, key: &str) -> Result<Decimal> {
+    let v = price
+        .get(key)
+        .ok_or_else(|| anyhow::anyhow!("Price response missing {}", key))?;
+    Ok(serde_json::from_value(v.clone())?)
+}
+
+pub(crate) fn non_zero(value: Decimal, token: &str) -> Result<Decimal> {
+    if value.is_zero() {
+        return Err(anyhow::anyhow!(
+            "Price of {} is zero; cannot convert",
+            token
+        ));
+    }
+    Ok(value)
+}
@@ -0,0 +1,149 @@
+use super::convert::{decimal_field, fetch_price, is_eth, non_zero};
+use super::price::{liquid_pools, pool_price, slot0Call};
+use super::{resolve_token, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{call3, decode_result, EthereumClient};
+use alloy::{primitives::Address, sol_types::SolCall};
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+pub struct GetCrossRateTool;
+
+#[async_trait::async_trait]
+impl Tool for GetCrossRateTool {
+    fn name(&self) -> &'static str {
+        "get_cross_rate"
+    }
+
+    fn description(&self) -> &'static str {
+        "Exchange rate between any two tokens (e.g. UNI priced in AAVE): from the deepest direct Uniswap V3 pool between them when one exists, else by dividing their USD prices."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "base_token": {
+                    "type": "string",
+                    "description": "Symbol or address of the token being priced"
+                },
+                "quote_token": {
+                    "type": "string",
+                    "description": "Symbol or address of the token the price is expressed in"
+                }
+            },
+            "required": ["base_token", "quote_token"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let base_arg = args["base_token"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("base_token"))?;
+        let quote_arg = args["quote_token"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("quote_token"))?;
+        let base = token_address(client, base_arg)?;
+        let quote = token_address(client, quote_arg)?;
+        let base_meta = client.get_token_metadata(base).await?;
+        let quote_meta = client.get_token_metadata(quote).await?;
+        let token = |arg: &str, address: Address, symbol: &Option<String>| {
+            json!({
+                "input": arg,
+                "address": address.to_string(),
+                "symbol": symbol
+            })
+        };
+        let mut res = json!({
+            "base_token": token(base_arg, base, &base_meta.symbol),
+            "quote_token": token(quote_arg, quote, &quote_meta.symbol),
+        });
+
+        if base == quote {
+            res["rate"] = json!(Decimal::ONE);
+            res["inverse_rate"] = json!(Decimal::ONE);
+            res["method"] = json!("identity");
+            return Ok(res);
+        }
+
+        // A pool quoting the pair itself beats a rate through USD, which compounds two
+        // prices' errors and can miss a pair-specific premium.
+        let direct =
+            match direct_pool_rate(client, base, quote, base_meta.decimals, quote_meta.decimals)
+                .await
+            {
+                Ok(direct) => direct,
+                Err(e) => {
+                    res["direct_pool_error"] = json!(e.to_string());
+                    None
+                }
+            };
+        let rate = match direct {
+            Some((rate, pool)) => {
+                res["method"] = json!("direct_pool");
+                res["pool"] = pool;
+                rate
+            }
+            None => {
+                let base_price = fetch_price(client, base_arg).await?;
+                let quote_price = fetch_price(client, quote_arg).await?;
+                let base_usd = decimal_field(&base_price, "price_usd")?;
+                let quote_usd = decimal_field(&quote_price, "price_usd")?;
+                res["method"] = json!("usd_cross");
+                res["usd_prices"] = json!({
+                    "base": {"price_usd": base_usd, "source": base_price["source"]},
+                    "quote": {"price_usd": quote_usd, "source": quote_price["source"]}
+                });
+                base_usd / non_zero(quote_usd, quote_arg)?
+            }
+        };
+        res["rate"] = json!(rate.round_dp(18).normalize());
+        res["inverse_rate"] = json!((Decimal::ONE / non_zero(rate, base_arg)?)
+            .round_dp(18)
+            .normalize());
+        Ok(res)
+    }
+}
+
+/// `ETH` stands for WETH, so that ETH pairs find their pools.
+fn token_address(client: &EthereumClient, token: &str) -> Result<Address> {
+    if is_eth(client, token) {
+        return Ok(client.chain()?.weth);
+    }
+    resolve_token(client, token)
+}
+
+/// Price of `base` in `quote` from the deepest liquid pool pairing them, with that pool's
+/// details, or `None` when no fee tier has one.
+async fn direct_pool_rate(
+    client: &EthereumClient,
+    base: Address,
+    quote: Address,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Result<Option<(Decimal, Value)>> {
+    let pools = liquid_pools(client, &[(base, quote)]).await?;
+    let Some(deepest) = pools[0].first() else {
+        return Ok(None);
+    };
+    let results = client
+        .multicall(vec![call3(deepest.pool, slot0Call {}.abi_encode())])
+        .await?;
+    let slot0 = decode_result::<slot0Call>(&results[0], deepest.pool)?;
+    let rate = pool_price(
+        slot0.sqrtPriceX96,
+        base < quote,
+        base_decimals,
+        quote_decimals,
+    )?;
+    Ok(Some((
+        rate,
+        json!({
+            "address": deepest.pool.to_string(),
+            "fee": deepest.fee,
+            "liquidity": deepest.liquidity.to_string(),
+            "tiers_found": pools[0].len()
+        }),
+    )))
+}
@@ -0,0 +1,195 @@
+use super::balance::GetBalanceTool;
+use super::price::GetTokenPriceTool;
+use super::{f64_arg, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{endpoint_label, EthereumClient};
+use alloy::providers::Provider;
+use anyhow::Result;
+use futures::future::join_all;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+const DEFAULT_MAX_BLOCK_LAG: u64 = 2;
+const DEFAULT_TOLERANCE_PERCENT: f64 = 0.5;
+
+pub struct CompareRpcEndpointsTool;
+
+/// One endpoint's answer to the cross-checked query.
+struct Reading {
+    endpoint: String,
+    primary: bool,
+    block_number: Option<u64>,
+    value: Result<Value>,
+}
+
+#[async_trait::async_trait]
+impl Tool for CompareRpcEndpointsTool {
+    fn name(&self) -> &'static str {
+        "compare_rpc_endpoints"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a balance or price query against the primary RPC and every ETHEREUM_RPC_URLS endpoint, reporting each answer and block number and flagging stale or disagreeing endpoints."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "enum": ["get_balance", "get_token_price"],
+                    "description": "Tool to run on every endpoint"
+                },
+                "arguments": {
+                    "type": "object",
+                    "description": "Arguments for the query tool, e.g. { \"address\": \"0x...\" } for get_balance or { \"token_symbol\": \"UNI\" } for get_token_price"
+                },
+                "max_block_lag": {
+                    "type": "integer",
+                    "description": "Flag endpoints more than this many blocks behind the highest one. Default 2."
+                },
+                "tolerance_percent": {
+                    "type": "number",
+                    "description": "Prices within this percent of the primary's count as agreeing. Balances must match exactly. Default 0.5."
+                }
+            },
+            "required": ["query", "arguments"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let query = args["query"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("query"))?;
+        let (tool, field): (Box<dyn Tool>, &str) = match query {
+            "get_balance" => (Box::new(GetBalanceTool), "raw_balance"),
+            "get_token_price" => (Box::new(GetTokenPriceTool), "price_eth"),
+            other => {
+                return Err(anyhow::anyhow!(
+                    "query must be get_balance or get_token_price, got {}",
+                    other
+                ))
+            }
+        };
+        let arguments = args
+            .get("arguments")
+            .filter(|a| !a.is_null())
+            .ok_or_else(|| ToolError::missing("arguments"))?
+            .as_object()
+            .map(|a| Value::Object(a.clone()))
+            .ok_or_else(|| anyhow::anyhow!("arguments must be an object"))?;
+        let max_block_lag = u64_arg(&args, "max_block_lag")?.unwrap_or(DEFAULT_MAX_BLOCK_LAG);
+        let tolerance = match query {
+            "get_balance" => Decimal::ZERO,
+            _ => Decimal::from_f64(
+                f64_arg(&args, "tolerance_percent")?.unwrap_or(DEFAULT_TOLERANCE_PERCENT),
+            )
+            .unwrap_or(Decimal::ZERO),
+        };
+        if client.extra_endpoints.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No secondary endpoints configured; set ETHEREUM_RPC_URLS to compare against"
+            ));
+        }
+
+        let mut targets = vec![("primary".to_string(), true, client.clone())];
+        for (url, provider) in &client.extra_endpoints {
+            targets.push((
+                endpoint_label(url),
+                false,
+                client.on_provider(provider.clone()),
+            ));
+        }
+        let readings: Vec<Reading> = join_all(targets.into_iter().map(|(endpoint, primary, c)| {
+            let (tool, arguments) = (&tool, arguments.clone());
+            async move {
+                let block_number = c.provider.get_block_number().await.ok();
+                let value = tool.call(&c, arguments).await.and_then(|result| {
+                    result
+                        .get(field)
+                        .cloned()
+                        .ok_or_else(|| anyhow::anyhow!("response has no {}", field))
+                });
+                Reading {
+                    endpoint,
+                    primary,
+                    block_number,
+                    value,
+                }
+            }
+        }))
+        .await;
+
+        let highest = readings.iter().filter_map(|r| r.block_number).max();
+        // The primary's answer is the reference; if it failed, the first endpoint that answered.
+        let reference = readings
+            .iter()
+            .find(|r| r.primary && r.value.is_ok())
+            .or_else(|| readings.iter().find(|r| r.value.is_ok()))
+            .and_then(|r| r.value.as_ref().ok().cloned());
+
+        let mut stale = Vec::new();
+        let mut disagreeing = Vec::new();
+        let endpoints: Vec<Value> = readings
+            .iter()
+            .map(|r| {
+                let lag = highest.zip(r.block_number).map(|(h, b)| h - b);
+                if lag.is_some_and(|lag| lag > max_block_lag) || r.block_number.is_none() {
+                    stale.push(r.endpoint.clone());
+                }
+                let agrees = match (&r.value, &reference) {
+                    (Ok(value), Some(reference)) => Some(agree(value, reference, tolerance)),
+                    _ => None,
+                };
+                if agrees == Some(false) {
+                    disagreeing.push(r.endpoint.clone());
+                }
+                json!({
+                    "endpoint": r.endpoint,
+                    "primary": r.primary,
+                    "block_number": r.block_number,
+                    "lag_blocks": lag,
+                    field: r.value.as_ref().ok(),
+                    "agrees": agrees,
+                    "error": r.value.as_ref().err().map(|e| e.to_string())
+                })
+            })
+            .collect();
+        let failed = readings.iter().filter(|r| r.value.is_err()).count();
+
+        Ok(json!({
+            "query": query,
+            "compared_field": field,
+            "reference_value": reference,
+            "endpoints": endpoints,
+            "highest_block": highest,
+            "stale_endpoints": stale,
+            "disagreeing_endpoints": disagreeing,
+            "failed_endpoints": failed,
+            "consistent": stale.is_empty() && disagreeing.is_empty() && failed == 0,
+            "note": "Each endpoint answers at its own latest block, so small price differences between endpoints at different heights are expected; a balance or price mismatch at the same height points at a bad endpoint."
+        }))
+    }
+}
+
+/// Exact match, or for numeric values a relative difference within `tolerance_percent`.
+fn agree(value: &Value, reference: &Value, tolerance_percent: Decimal) -> bool {
+    if value == reference {
+        return true;
+    }
+    let number = |v: &Value| match v {
+        // Display amounts may carry thousands separators.
+        Value::String(s) => Decimal::from_str(&s.replace(',', "")).ok(),
+        Value::Number(n) => n.as_f64().and_then(Decimal::from_f64),
+        _ => None,
+    };
+    match (number(value), number(reference)) {
+        (Some(v), Some(r)) if !r.is_zero() => {
+            ((v - r) / r).abs() * Decimal::from(100) <= tolerance_percent
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,171 @@
+use super::balances::GetBalancesTool;
+use super::events::{get_logs_chunked, DEFAULT_BLOCK_CHUNK};
+use super::transfers::Transfer;
+use super::{resolve_address, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use alloy::{
+    primitives::Address,
+    providers::Provider,
+    rpc::types::eth::{Filter, Log},
+    sol_types::SolEvent,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashSet};
+
+const DEFAULT_PAGE_BLOCKS: u64 = 10_000;
+const MAX_PAGE_BLOCKS: u64 = 50_000;
+
+pub struct DiscoverTokensTool;
+
+#[async_trait::async_trait]
+impl Tool for DiscoverTokensTool {
+    fn name(&self) -> &'static str {
+        "discover_tokens"
+    }
+
+    fn description(&self) -> &'static str {
+        "Discover the ERC20 tokens an address has interacted with by scanning Transfer events to and from it over a block range, then return its current nonzero balances of those tokens. Paginated by block cursor: each page scans up to page_blocks blocks."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "The wallet address (or ENS name) to discover tokens for"
+                },
+                "from_block": {
+                    "type": "integer",
+                    "description": "First block of the range (inclusive)"
+                },
+                "to_block": {
+                    "type": ["integer", "string"],
+                    "description": "Last block of the range (inclusive) or \"latest\". Default latest."
+                },
+                "page_blocks": {
+                    "type": "integer",
+                    "description": "Blocks scanned per page. Default 10000, at most 50000."
+                },
+                "block_chunk": {
+                    "type": "integer",
+                    "description": "Maximum blocks per eth_getLogs request. Default 2000."
+                },
+                "cursor": {
+                    "type": "string",
+                    "description": "next_cursor from a previous page to continue the scan"
+                }
+            },
+            "required": ["address", "from_block"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let address_str = args["address"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("address"))?;
+        let from_block =
+            u64_arg(&args, "from_block")?.ok_or_else(|| ToolError::missing("from_block"))?;
+        let page_blocks = u64_arg(&args, "page_blocks")?.unwrap_or(DEFAULT_PAGE_BLOCKS);
+        if page_blocks == 0 || page_blocks > MAX_PAGE_BLOCKS {
+            return Err(ToolError::invalid_argument(format!(
+                "page_blocks must be between 1 and {}",
+                MAX_PAGE_BLOCKS
+            )));
+        }
+        let block_chunk = u64_arg(&args, "block_chunk")?.unwrap_or(DEFAULT_BLOCK_CHUNK);
+        let address = resolve_address(client, address_str).await?;
+        let to_block = match args.get("to_block") {
+            None | Some(Value::Null) => client.provider.get_block_number().await?,
+            Some(Value::String(s)) if s == "latest" => client.provider.get_block_number().await?,
+            Some(_) => u64_arg(&args, "to_block")
+                .ok()
+                .flatten()
+                .ok_or_else(|| anyhow::anyhow!("to_block must be a block number or \"latest\""))?,
+        };
+        let start = match args.get("cursor").and_then(|v| v.as_str()) {
+            Some(cursor) => cursor
+                .parse::<u64>()
+                .map_err(|_| ToolError::invalid_argument("Invalid cursor"))?,
+            None => from_block,
+        };
+        if start > to_block {
+            return Err(ToolError::invalid_argument(format!(
+                "Start block {} is after to_block {}",
+                start, to_block
+            )));
+        }
+        let end = to_block.min(start.saturating_add(page_blocks - 1));
+
+        // Two scans, since the address is the indexed sender in one and the receiver in the other.
+        let transfers = Filter::new().event_signature(Transfer::SIGNATURE_HASH);
+        let topic = address.into_word();
+        let mut logs = get_logs_chunked(
+            client,
+            &transfers.clone().topic1(topic),
+            start,
+            end,
+            block_chunk,
+        )
+        .await?;
+        logs.extend(
+            get_logs_chunked(client, &transfers.topic2(topic), start, end, block_chunk).await?,
+        );
+        let (tokens, transfer_count) = erc20_tokens(&logs);
+
+        let mut holdings = Vec::new();
+        let mut unreadable = Vec::new();
+        let token_list: Vec<String> = tokens.iter().map(|t| t.to_string()).collect();
+        for batch in token_list.chunks(client.max_batch_size.max(1)) {
+            let result = GetBalancesTool
+                .call(
+                    client,
+                    json!({ "address": address.to_string(), "token_addresses": batch }),
+                )
+                .await?;
+            for entry in result["balances"].as_array().into_iter().flatten() {
+                match entry["status"].as_str() {
+                    Some("ok") if entry["raw_balance"] != "0" => holdings.push(json!({
+                        "token_address": entry["token_address"],
+                        "symbol": entry["symbol"],
+                        "balance": entry["balance"],
+                        "raw_balance": entry["raw_balance"],
+                        "decimals": entry["decimals"]
+                    })),
+                    Some("ok") => {}
+                    _ => unreadable.push(json!({
+                        "token_address": entry["token"],
+                        "error": entry["error"]
+                    })),
+                }
+            }
+        }
+        let next_cursor = (end < to_block).then(|| (end + 1).to_string());
+
+        Ok(json!({
+            "address": address.to_string(),
+            "from_block": start,
+            "to_block": end,
+            "transfer_count": transfer_count,
+            "tokens_found": tokens.len(),
+            "holdings": holdings,
+            "unreadable_tokens": unreadable,
+            "next_cursor": next_cursor
+        }))
+    }
+}
+
+/// The distinct token contracts among `logs` and how many ERC20 transfers they hold.
+/// ERC721 shares the Transfer topic but also indexes the token id, so its logs carry four
+/// topics and are left out. Self-transfers match both scans and are counted once.
+fn erc20_tokens(logs: &[Log]) -> (BTreeSet<Address>, usize) {
+    let erc20: HashSet<(Option<u64>, Option<u64>, Address)> = logs
+        .iter()
+        .filter(|l| l.topics().len() == 3)
+        .map(|l| (l.block_number, l.log_index, l.address()))
+        .collect();
+    let tokens = erc20.iter().map(|(_, _, token)| *token).collect();
+    (tokens, erc20.len())
+}
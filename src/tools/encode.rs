@@ -0,0 +1,91 @@
+use super::Tool;
+use crate::abi::json_to_dyn_value;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use alloy::{
+    dyn_abi::{JsonAbiExt, Specifier},
+    json_abi::Function,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+pub struct EncodeCalldataTool;
+
+#[async_trait::async_trait]
+impl Tool for EncodeCalldataTool {
+    fn name(&self) -> &'static str {
+        "encode_calldata"
+    }
+
+    fn large_fields(&self) -> &'static [&'static str] {
+        &["/calldata"]
+    }
+
+    fn description(&self) -> &'static str {
+        "Encode a function call to hex calldata from a human-readable signature and a list of argument values. No RPC calls."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "function_signature": {
+                    "type": "string",
+                    "description": "e.g. 'transfer(address to, uint256 amount)' or 'function approve(address,uint256)'"
+                },
+                "args": {
+                    "type": "array",
+                    "description": "Argument values in order. Integers as decimal or 0x-hex strings, bytes as 0x-hex, arrays and tuples as JSON arrays."
+                }
+            },
+            "required": ["function_signature"]
+        })
+    }
+
+    async fn call(&self, _client: &EthereumClient, args: Value) -> Result<Value> {
+        let signature = args["function_signature"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("function_signature"))?;
+        let function = Function::parse(signature)
+            .map_err(|e| anyhow::anyhow!("Invalid function signature: {}", e))?;
+        let values = match args.get("args") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(values)) => values.clone(),
+            Some(_) => return Err(anyhow::anyhow!("args must be an array")),
+        };
+
+        if values.len() != function.inputs.len() {
+            return Err(anyhow::anyhow!(
+                "{} expects {} arguments, got {}",
+                function.signature(),
+                function.inputs.len(),
+                values.len()
+            ));
+        }
+
+        let encoded_args = function
+            .inputs
+            .iter()
+            .zip(&values)
+            .enumerate()
+            .map(|(i, (param, value))| {
+                let ty = param.resolve()?;
+                let name = if param.name.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", param.name)
+                };
+                json_to_dyn_value(&ty, value)
+                    .map_err(|e| anyhow::anyhow!("argument {} ({}{}): {}", i, ty, name, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let calldata = function.abi_encode_input(&encoded_args)?;
+
+        Ok(json!({
+            "signature": function.signature(),
+            "selector": function.selector().to_string(),
+            "calldata": format!("0x{}", hex::encode(&calldata)),
+            "calldata_length": calldata.len()
+        }))
+    }
+}
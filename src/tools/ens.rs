@@ -0,0 +1,210 @@
+use super::Tool;
+use crate::ethereum::{call3, decode_result, decode_return, EthereumClient};
+use alloy::{
+    primitives::{keccak256, Address, B256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    function owner(bytes32 node) external view returns (address);
+    #[allow(missing_docs)]
+    function resolver(bytes32 node) external view returns (address);
+    #[allow(missing_docs)]
+    function addr(bytes32 node) external view returns (address);
+    #[allow(missing_docs)]
+    function name(bytes32 node) external view returns (string);
+}
+
+pub struct ResolveEnsTool;
+
+/// A name resolved through the registry and its resolver.
+pub struct Resolution {
+    pub name: String,
+    pub node: B256,
+    pub resolver: Address,
+    pub address: Address,
+}
+
+#[async_trait::async_trait]
+impl Tool for ResolveEnsTool {
+    fn name(&self) -> &'static str {
+        "resolve_ens"
+    }
+
+    fn description(&self) -> &'static str {
+        "Resolve an ENS name (e.g. vitalik.eth) to its address through the mainnet ENS registry, or look up an address's primary ENS name (reverse record, checked against the forward record)."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "ENS name to resolve to an address"
+                },
+                "address": {
+                    "type": "string",
+                    "description": "Address whose primary ENS name to look up (reverse resolution). Give either name or address."
+                }
+            }
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let name = args.get("name").and_then(|v| v.as_str());
+        let address = args.get("address").and_then(|v| v.as_str());
+        match (name, address) {
+            (Some(name), None) => {
+                let resolved = resolve(client, name).await?;
+                Ok(json!({
+                    "mode": "forward",
+                    "name": resolved.name,
+                    "address": resolved.address.to_string(),
+                    "node": resolved.node.to_string(),
+                    "resolver": resolved.resolver.to_string()
+                }))
+            }
+            (None, Some(address)) => {
+                let address = Address::from_str(address)?;
+                let (name, resolver) = reverse(client, address).await?;
+                // Anyone can set a reverse record claiming any name, so it only counts if
+                // the name resolves back to the address.
+                let verified = match &name {
+                    Some(name) => resolve(client, name)
+                        .await
+                        .map(|r| r.address == address)
+                        .unwrap_or(false),
+                    None => false,
+                };
+                Ok(json!({
+                    "mode": "reverse",
+                    "address": address.to_string(),
+                    "name": name,
+                    "verified": verified,
+                    "resolver": resolver.map(|r| r.to_string()),
+                    "note": "name is null when the address has no primary name set. verified is true only when the name's forward record points back to the address; treat an unverified name as untrusted."
+                }))
+            }
+            _ => Err(anyhow::anyhow!("Pass exactly one of name or address")),
+        }
+    }
+}
+
+/// Whether `value` looks like an ENS name rather than an address or token symbol.
+pub fn is_ens_name(value: &str) -> bool {
+    let value = value.trim();
+    value.len() > 4 && value.to_lowercase().ends_with(".eth")
+}
+
+/// Lowercases `name` and rejects empty labels. Full UTS-46 normalization is not applied,
+/// so names with non-ASCII characters must already be normalized.
+pub fn normalize(name: &str) -> Result<String> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() || name.split('.').any(|label| label.is_empty()) {
+        return Err(anyhow::anyhow!("{:?} is not a valid ENS name", name));
+    }
+    if name.chars().any(char::is_whitespace) {
+        return Err(anyhow::anyhow!("ENS name {:?} contains whitespace", name));
+    }
+    Ok(name)
+}
+
+/// EIP-137 namehash of an already normalized name.
+pub fn namehash(name: &str) -> B256 {
+    name.rsplit('.')
+        .filter(|label| !label.is_empty())
+        .fold(B256::ZERO, |node, label| {
+            let mut input = [0u8; 64];
+            input[..32].copy_from_slice(node.as_slice());
+            input[32..].copy_from_slice(keccak256(label.as_bytes()).as_slice());
+            keccak256(input)
+        })
+}
+
+fn registry(client: &EthereumClient) -> Result<Address> {
+    let chain = client.chain()?;
+    chain.ens_registry.ok_or_else(|| {
+        anyhow::anyhow!(
+            "ENS names resolve on Ethereum Mainnet only; the RPC is on {}. Pass a 0x address instead.",
+            chain.name
+        )
+    })
+}
+
+/// Resolves `name` to its ETH address. Unregistered names, names without a resolver and
+/// names without an address record are errors.
+pub async fn resolve(client: &EthereumClient, name: &str) -> Result<Resolution> {
+    let registry = registry(client)?;
+    let name = normalize(name)?;
+    let node = namehash(&name);
+    let results = client
+        .multicall(vec![
+            call3(registry, ownerCall { node }.abi_encode()),
+            call3(registry, resolverCall { node }.abi_encode()),
+        ])
+        .await?;
+    let owner = decode_result::<ownerCall>(&results[0], registry)?._0;
+    let resolver = decode_result::<resolverCall>(&results[1], registry)?._0;
+    if resolver == Address::ZERO {
+        return Err(if owner == Address::ZERO {
+            anyhow::anyhow!("ENS name {} is not registered", name)
+        } else {
+            anyhow::anyhow!("ENS name {} has no resolver set", name)
+        });
+    }
+    let req = TransactionRequest::default()
+        .to(resolver)
+        .input(addrCall { node }.abi_encode().into());
+    let address = decode_return::<addrCall>(&client.provider.call(&req).await?, resolver)?._0;
+    if address == Address::ZERO {
+        return Err(anyhow::anyhow!(
+            "ENS name {} has no address record on its resolver {}",
+            name,
+            resolver
+        ));
+    }
+    Ok(Resolution {
+        name,
+        node,
+        resolver,
+        address,
+    })
+}
+
+/// The primary name `address` claims through its `<addr>.addr.reverse` record, with the
+/// reverse resolver. The name is `None` when no reverse record is set, and is not verified.
+pub async fn reverse(
+    client: &EthereumClient,
+    address: Address,
+) -> Result<(Option<String>, Option<Address>)> {
+    let registry = registry(client)?;
+    let node = namehash(&format!("{}.addr.reverse", hex::encode(address)));
+    let req = TransactionRequest::default()
+        .to(registry)
+        .input(resolverCall { node }.abi_encode().into());
+    let resolver = decode_return::<resolverCall>(&client.provider.call(&req).await?, registry)?._0;
+    if resolver == Address::ZERO {
+        return Ok((None, None));
+    }
+    let req = TransactionRequest::default()
+        .to(resolver)
+        .input(nameCall { node }.abi_encode().into());
+    // A resolver without a `name` record reverts or returns an empty string.
+    let name = client
+        .provider
+        .call(&req)
+        .await
+        .ok()
+        .and_then(|data| nameCall::abi_decode_returns(&data, true).ok())
+        .map(|r| r._0)
+        .filter(|name| !name.is_empty());
+    Ok((name, Some(resolver)))
+}
@@ -0,0 +1,195 @@
+use super::{u64_arg, Tool};
+use crate::abi::dyn_value_to_json;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use alloy::{
+    dyn_abi::EventExt,
+    json_abi::Event,
+    primitives::Address,
+    providers::Provider,
+    rpc::types::eth::{Filter, Log},
+};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+
+// Most providers cap eth_getLogs ranges somewhere between 2k and 10k blocks.
+pub(crate) const DEFAULT_BLOCK_CHUNK: u64 = 2_000;
+
+pub struct GetBlockRangeEventsTool;
+
+#[async_trait::async_trait]
+impl Tool for GetBlockRangeEventsTool {
+    fn name(&self) -> &'static str {
+        "get_block_range_events"
+    }
+
+    fn description(&self) -> &'static str {
+        "Fetch and decode contract events matching a human-readable event signature over a block range. Paginated by block cursor."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "event_signature": {
+                    "type": "string",
+                    "description": "Event signature, e.g. \"Transfer(address,address,uint256)\" or \"Transfer(address indexed from, address indexed to, uint256 value)\". Without `indexed` markers, the leading parameters are assumed indexed to match each log's topic count."
+                },
+                "contract_address": {
+                    "type": "string",
+                    "description": "Optional contract to filter by. If omitted, matches any emitter."
+                },
+                "from_block": {
+                    "type": "integer",
+                    "description": "First block of the range (inclusive)"
+                },
+                "to_block": {
+                    "type": ["integer", "string"],
+                    "description": "Last block of the range (inclusive) or \"latest\". Default latest."
+                },
+                "cursor": {
+                    "type": "string",
+                    "description": "next_cursor from a previous page to continue the scan"
+                },
+                "block_chunk": {
+                    "type": "integer",
+                    "description": "Maximum blocks scanned per page. Default 2000."
+                }
+            },
+            "required": ["event_signature", "from_block"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let signature = args["event_signature"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("event_signature"))?;
+        let event = Event::parse(signature)
+            .map_err(|e| anyhow::anyhow!("Invalid event signature: {}", e))?;
+        let contract = args
+            .get("contract_address")
+            .and_then(|v| v.as_str())
+            .map(Address::from_str)
+            .transpose()?;
+        let from_block =
+            u64_arg(&args, "from_block")?.ok_or_else(|| ToolError::missing("from_block"))?;
+        let to_block = match args.get("to_block") {
+            None | Some(Value::Null) => client.provider.get_block_number().await?,
+            Some(Value::String(s)) if s == "latest" => client.provider.get_block_number().await?,
+            Some(_) => u64_arg(&args, "to_block")
+                .ok()
+                .flatten()
+                .ok_or_else(|| anyhow::anyhow!("to_block must be a block number or \"latest\""))?,
+        };
+        let start = match args.get("cursor").and_then(|v| v.as_str()) {
+            Some(cursor) => cursor
+                .parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("Invalid cursor"))?,
+            None => from_block,
+        };
+        let block_chunk = u64_arg(&args, "block_chunk")?
+            .unwrap_or(DEFAULT_BLOCK_CHUNK)
+            .max(1);
+
+        if start > to_block {
+            return Err(anyhow::anyhow!(
+                "Start block {} is after to_block {}",
+                start,
+                to_block
+            ));
+        }
+        let end = to_block.min(start.saturating_add(block_chunk - 1));
+
+        let topic0 = event.selector();
+        let mut filter = Filter::new()
+            .from_block(start)
+            .to_block(end)
+            .event_signature(topic0);
+        if let Some(contract) = contract {
+            filter = filter.address(contract);
+        }
+        let logs = client.provider.get_logs(&filter).await?;
+
+        let events: Vec<Value> = logs.iter().map(|log| decode_log(&event, log)).collect();
+        let next_cursor = (end < to_block).then(|| (end + 1).to_string());
+
+        Ok(json!({
+            "event": event.full_signature(),
+            "topic0": topic0.to_string(),
+            "from_block": start,
+            "to_block": end,
+            "count": events.len(),
+            "events": events,
+            "next_cursor": next_cursor
+        }))
+    }
+}
+
+/// Fetches `filter`'s logs over `[from_block, to_block]`, at most `block_chunk` blocks per
+/// `eth_getLogs` request so the range stays within provider limits.
+pub(crate) async fn get_logs_chunked(
+    client: &EthereumClient,
+    filter: &Filter,
+    from_block: u64,
+    to_block: u64,
+    block_chunk: u64,
+) -> Result<Vec<Log>> {
+    let block_chunk = block_chunk.max(1);
+    let mut logs = Vec::new();
+    let mut start = from_block;
+    while start <= to_block {
+        let end = to_block.min(start.saturating_add(block_chunk - 1));
+        let chunk = filter.clone().from_block(start).to_block(end);
+        logs.extend(client.provider.get_logs(&chunk).await?);
+        start = end + 1;
+    }
+    Ok(logs)
+}
+
+fn decode_log(event: &Event, log: &Log) -> Value {
+    let topics = log.topics();
+    let mut entry = json!({
+        "address": log.address().to_string(),
+        "block_number": log.block_number,
+        "transaction_hash": log.transaction_hash.map(|h| h.to_string()),
+        "log_index": log.log_index
+    });
+
+    // Plain type lists carry no `indexed` markers; infer them from the topic count.
+    let mut event = event.clone();
+    if event.inputs.iter().all(|p| !p.indexed) {
+        let indexed = topics.len().saturating_sub(1);
+        for (i, param) in event.inputs.iter_mut().enumerate() {
+            param.indexed = i < indexed;
+        }
+    }
+
+    match event.decode_log(log.data(), true) {
+        Ok(decoded) => {
+            let mut indexed = decoded.indexed.iter();
+            let mut body = decoded.body.iter();
+            let mut fields = Map::new();
+            for (i, param) in event.inputs.iter().enumerate() {
+                let value = if param.indexed {
+                    indexed.next()
+                } else {
+                    body.next()
+                };
+                let name = if param.name.is_empty() {
+                    format!("arg{}", i)
+                } else {
+                    param.name.clone()
+                };
+                fields.insert(name, value.map(dyn_value_to_json).unwrap_or(Value::Null));
+            }
+            entry["args"] = Value::Object(fields);
+        }
+        Err(e) => {
+            entry["decode_error"] = json!(e.to_string());
+            entry["topics"] = json!(topics.iter().map(|t| t.to_string()).collect::<Vec<_>>());
+            entry["data"] = json!(format!("0x{}", hex::encode(&log.data().data)));
+        }
+    }
+    entry
+}
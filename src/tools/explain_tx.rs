@@ -0,0 +1,459 @@
+use super::watch::get_transaction;
+use super::Tool;
+use crate::abi::dyn_value_to_json;
+use crate::error::ToolError;
+use crate::ethereum::{revert_data, EthereumClient};
+use crate::rpc;
+use alloy::{
+    dyn_abi::JsonAbiExt,
+    json_abi::{Error as AbiError, Function},
+    primitives::TxHash,
+    providers::Provider,
+    sol_types::{Panic, Revert, SolError},
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// Functions recognised in a failed transaction's calldata: token calls and the Uniswap
+/// routers this server builds transactions for. Struct params are written as unnamed
+/// tuples, since the signature parser rejects names inside a tuple.
+const KNOWN_FUNCTIONS: [&str; 22] = [
+    "transfer(address to, uint256 amount)",
+    "transferFrom(address from, address to, uint256 amount)",
+    "approve(address spender, uint256 amount)",
+    "permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s)",
+    "deposit()",
+    "withdraw(uint256 amount)",
+    "exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160) params)",
+    "exactInputSingle((address,address,uint24,address,uint256,uint256,uint160) params)",
+    "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160) params)",
+    "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint160) params)",
+    "exactInput((bytes,address,uint256,uint256,uint256) params)",
+    "exactInput((bytes,address,uint256,uint256) params)",
+    "exactOutput((bytes,address,uint256,uint256,uint256) params)",
+    "exactOutput((bytes,address,uint256,uint256) params)",
+    "multicall(bytes[] data)",
+    "multicall(uint256 deadline, bytes[] data)",
+    "unwrapWETH9(uint256 amountMinimum, address recipient)",
+    "refundETH()",
+    "execute(bytes commands, bytes[] inputs, uint256 deadline)",
+    "execute(bytes commands, bytes[] inputs)",
+    "swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline)",
+    "swapExactETHForTokens(uint256 amountOutMin, address[] path, address to, uint256 deadline)",
+];
+
+/// Custom errors decoded without being passed in: OpenZeppelin 5 ERC20, Permit2 and the
+/// Universal Router, with what each usually means for the sender.
+const KNOWN_ERRORS: [(&str, &str); 12] = [
+    (
+        "ERC20InsufficientBalance(address sender, uint256 balance, uint256 needed)",
+        "the sender's token balance is below the amount moved",
+    ),
+    (
+        "ERC20InsufficientAllowance(address spender, uint256 allowance, uint256 needed)",
+        "the spender's allowance is below the amount moved; approve it first",
+    ),
+    (
+        "ERC20InvalidReceiver(address receiver)",
+        "tokens were sent to an address the token rejects, such as the zero address",
+    ),
+    (
+        "ERC20InvalidSender(address sender)",
+        "tokens were moved from an address the token rejects",
+    ),
+    (
+        "ERC20InvalidApprover(address approver)",
+        "the approval came from an address the token rejects",
+    ),
+    (
+        "ERC20InvalidSpender(address spender)",
+        "the approval named an invalid spender, such as the zero address",
+    ),
+    (
+        "AllowanceExpired(uint256 deadline)",
+        "the Permit2 allowance expired; renew it",
+    ),
+    (
+        "InsufficientAllowance(uint256 amount)",
+        "the Permit2 allowance is below the amount moved",
+    ),
+    (
+        "SignatureExpired(uint256 signatureDeadline)",
+        "the permit signature's deadline passed before it was used",
+    ),
+    (
+        "V3TooLittleReceived()",
+        "the output fell below the minimum: the price moved past the slippage tolerance",
+    ),
+    (
+        "V3TooMuchRequested()",
+        "the input needed exceeded the maximum: the price moved past the slippage tolerance",
+    ),
+    (
+        "TransactionDeadlinePassed()",
+        "the deadline passed before the transaction was mined",
+    ),
+];
+
+/// `Error(string)` reasons whose meaning isn't obvious from the text, mostly Uniswap's
+/// abbreviated `require` messages.
+const REASON_HINTS: [(&str, &str); 11] = [
+    ("STF", "safeTransferFrom failed: the sender's token balance, or its allowance for the router, is below the input amount"),
+    ("TF", "a token transfer failed"),
+    ("ST", "a token transfer out of the router failed"),
+    ("Too little received", "the output fell below amountOutMinimum: the price moved past the slippage tolerance"),
+    ("Too much requested", "the input needed exceeded amountInMaximum: the price moved past the slippage tolerance"),
+    ("Transaction too old", "the deadline passed before the transaction was mined"),
+    ("Insufficient WETH9", "the router held less WETH than unwrapWETH9's minimum"),
+    ("SPL", "the sqrtPriceLimitX96 is outside the pool's current price range"),
+    ("AS", "the swap amount was zero"),
+    ("LOK", "the pool was locked; it was re-entered mid-swap"),
+    ("UniswapV2Router: INSUFFICIENT_OUTPUT_AMOUNT", "the output fell below amountOutMin: the price moved past the slippage tolerance"),
+];
+
+pub struct ExplainFailedTransactionTool;
+
+#[async_trait::async_trait]
+impl Tool for ExplainFailedTransactionTool {
+    fn name(&self) -> &'static str {
+        "explain_failed_transaction"
+    }
+
+    fn description(&self) -> &'static str {
+        "Explain why a mined transaction failed: recovers the revert data with debug_traceTransaction, or by replaying the transaction with eth_call at the block before it, and decodes it (Error(string), Panic codes, common ERC20/Permit2/Uniswap custom errors, or signatures you pass). Returns a readable explanation, the function called and the gas used."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tx_hash": {
+                    "type": "string",
+                    "description": "Hash of the failed transaction"
+                },
+                "error_signatures": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Extra custom errors to decode against, e.g. 'InsufficientLiquidity(uint256 available)'"
+                }
+            },
+            "required": ["tx_hash"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let tx_hash = TxHash::from_str(
+            args["tx_hash"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("tx_hash"))?,
+        )?;
+        let custom_errors = match args.get("error_signatures") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(signatures)) => signatures
+                .iter()
+                .map(|s| {
+                    let s = s
+                        .as_str()
+                        .ok_or_else(|| anyhow::anyhow!("error_signatures must be strings"))?;
+                    AbiError::parse(s)
+                        .map_err(|e| anyhow::anyhow!("Invalid error signature {}: {}", s, e))
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Some(_) => return Err(anyhow::anyhow!("error_signatures must be an array")),
+        };
+
+        let tx = get_transaction(client, tx_hash)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Transaction {} is unknown to this node", tx_hash))?;
+        let receipt: Option<Value> = client
+            .provider
+            .raw_request("eth_getTransactionReceipt".into(), (tx_hash,))
+            .await?;
+        let receipt = receipt.filter(|r| !r.is_null()).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Transaction {} has no receipt yet; it is still pending",
+                tx_hash
+            )
+        })?;
+        let summary = rpc::receipt_summary(&receipt, Some(&tx));
+        let input = hex_field(&tx, "input").unwrap_or_default();
+        let function = called_function(&input);
+        let gas_limit = rpc::quantity(&tx, "gas").map(|g| g as u64);
+        let gas_used = rpc::quantity(&receipt, "gasUsed").map(|g| g as u64);
+        let block = summary["block_number"].as_u64();
+
+        if summary["success"].as_bool() != Some(false) {
+            return Ok(json!({
+                "tx_hash": tx_hash.to_string(),
+                "status": if summary["success"].as_bool() == Some(true) { "success" } else { "mined" },
+                "block_number": block,
+                "function": function,
+                "gas_used": gas_used,
+                "gas_limit": gas_limit,
+                "source": Value::Null,
+                "revert": Value::Null,
+                "reverted_in": Value::Null,
+                "explanation": "The transaction did not revert; there is no failure to explain."
+            }));
+        }
+
+        // The trace replays the transaction exactly, after the ones before it in its block.
+        // Without the debug API, eth_call at the parent block is the closest replay.
+        let (source, revert, frame_error, reverted_in) = match trace(client, tx_hash).await {
+            Some(frame) => (
+                "debug_traceTransaction",
+                hex_field(&frame, "output"),
+                rpc::string(&frame, "error"),
+                innermost_failure(&frame),
+            ),
+            None => {
+                let call = json!({
+                    "from": tx["from"],
+                    "to": tx["to"],
+                    "input": tx["input"],
+                    "value": tx["value"],
+                    "gas": tx["gas"]
+                });
+                let at = format!("0x{:x}", block.unwrap_or(1).saturating_sub(1));
+                let replay: Result<alloy::primitives::Bytes, _> = client
+                    .provider
+                    .raw_request("eth_call".into(), (call, at))
+                    .await;
+                match replay {
+                    Ok(_) => ("eth_call", None, None, Value::Null),
+                    Err(e) => (
+                        "eth_call",
+                        Some(revert_data(&e).unwrap_or_default()),
+                        Some(e.to_string()),
+                        Value::Null,
+                    ),
+                }
+            }
+        };
+
+        let decoded = revert
+            .as_deref()
+            .map(|data| decode_revert(data, &custom_errors));
+        let out_of_gas = frame_error
+            .as_deref()
+            .is_some_and(|e| e.contains("out of gas"))
+            || (revert.as_deref().is_none_or(|d| d.is_empty())
+                && gas_used.is_some()
+                && gas_used == gas_limit);
+        let explanation = explain(
+            source,
+            decoded.as_ref(),
+            out_of_gas,
+            gas_used,
+            gas_limit,
+            block,
+        );
+
+        Ok(json!({
+            "tx_hash": tx_hash.to_string(),
+            "status": "reverted",
+            "block_number": block,
+            "from": rpc::string(&tx, "from"),
+            "to": rpc::string(&tx, "to"),
+            "function": function,
+            "gas_used": gas_used,
+            "gas_limit": gas_limit,
+            "fee_paid_wei": summary["fee_paid_wei"],
+            "source": source,
+            "revert": decoded,
+            "reverted_in": reverted_in,
+            "out_of_gas": out_of_gas,
+            "explanation": explanation,
+            "note": if source == "eth_call" {
+                "Replayed with eth_call at the parent block, without the transactions mined before it in the same block, so a failure caused by those (e.g. a price move) may not reproduce."
+            } else {
+                "Replayed with debug_traceTransaction, in the transaction's exact position in its block."
+            }
+        }))
+    }
+}
+
+/// The top callTracer frame of the transaction, or `None` if the node has no debug API.
+async fn trace(client: &EthereumClient, tx_hash: TxHash) -> Option<Value> {
+    let frame: Value = client
+        .provider
+        .raw_request(
+            "debug_traceTransaction".into(),
+            (tx_hash, json!({ "tracer": "callTracer" })),
+        )
+        .await
+        .ok()?;
+    frame.is_object().then_some(frame)
+}
+
+/// Follows the failed sub-calls down to the deepest one, where the revert started.
+fn innermost_failure(frame: &Value) -> Value {
+    let mut current = frame;
+    let mut depth = 0;
+    while let Some(child) = current["calls"].as_array().and_then(|calls| {
+        calls
+            .iter()
+            .rev()
+            .find(|c| c.get("error").is_some_and(|e| !e.is_null()))
+    }) {
+        current = child;
+        depth += 1;
+    }
+    let input = hex_field(current, "input").unwrap_or_default();
+    json!({
+        "depth": depth,
+        "type": rpc::string(current, "type"),
+        "to": rpc::string(current, "to"),
+        "function": called_function(&input)["signature"],
+        "error": rpc::string(current, "error"),
+        "revert_reason": rpc::string(current, "revertReason")
+    })
+}
+
+/// The called function: its selector, and its signature and arguments when it's known.
+fn called_function(input: &[u8]) -> Value {
+    if input.len() < 4 {
+        return json!({
+            "selector": Value::Null,
+            "signature": if input.is_empty() { Some("(plain ETH transfer)") } else { None },
+            "arguments": Value::Null
+        });
+    }
+    let selector = &input[..4];
+    let known = KNOWN_FUNCTIONS
+        .iter()
+        .filter_map(|s| Function::parse(s).ok())
+        .find(|f| f.selector().as_slice() == selector);
+    json!({
+        "selector": format!("0x{}", hex::encode(selector)),
+        "signature": known.as_ref().map(|f| f.signature()),
+        "arguments": known
+            .and_then(|f| f.abi_decode_input(&input[4..], false).ok())
+            .map(|values| values.iter().map(dyn_value_to_json).collect::<Vec<_>>())
+    })
+}
+
+/// Decodes revert data as `Error(string)`, `Panic(uint256)`, a known custom error or one
+/// of `custom`.
+pub(crate) fn decode_revert(data: &[u8], custom: &[AbiError]) -> Value {
+    let raw = format!("0x{}", hex::encode(data));
+    if data.is_empty() {
+        return json!({ "kind": "empty", "data": raw });
+    }
+    if let Ok(revert) = Revert::abi_decode(data, false) {
+        let reason = revert.reason().to_string();
+        return json!({
+            "kind": "error_string",
+            "reason": reason,
+            "hint": REASON_HINTS.iter().find(|(r, _)| *r == reason).map(|(_, hint)| hint),
+            "data": raw
+        });
+    }
+    if let Ok(panic) = Panic::abi_decode(data, false) {
+        return json!({
+            "kind": "panic",
+            "code": format!("0x{:02x}", panic.code),
+            "reason": panic.kind().map_or("unknown panic code", |k| k.as_str()),
+            "data": raw
+        });
+    }
+    let selector = &data[..data.len().min(4)];
+    let known = custom
+        .iter()
+        .map(|e| (e.clone(), None))
+        .chain(
+            KNOWN_ERRORS
+                .iter()
+                .filter_map(|(s, hint)| Some((AbiError::parse(s).ok()?, Some(*hint)))),
+        )
+        .find(|(e, _)| e.selector().as_slice() == selector);
+    match known {
+        Some((error, hint)) => {
+            let arguments = error
+                .abi_decode_input(&data[selector.len()..], false)
+                .ok()
+                .map(|values| {
+                    error
+                        .inputs
+                        .iter()
+                        .zip(&values)
+                        .map(|(param, value)| (param.name.clone(), dyn_value_to_json(value)))
+                        .collect::<serde_json::Map<_, _>>()
+                });
+            json!({
+                "kind": "custom_error",
+                "name": error.name,
+                "signature": error.signature(),
+                "arguments": arguments,
+                "hint": hint,
+                "data": raw
+            })
+        }
+        None => json!({
+            "kind": "unknown_custom_error",
+            "selector": format!("0x{}", hex::encode(selector)),
+            "data": raw
+        }),
+    }
+}
+
+fn explain(
+    source: &str,
+    revert: Option<&Value>,
+    out_of_gas: bool,
+    gas_used: Option<u64>,
+    gas_limit: Option<u64>,
+    block: Option<u64>,
+) -> String {
+    if out_of_gas {
+        return format!(
+            "The transaction ran out of gas: it used {} of its {} gas limit. Resend it with a higher gas limit.",
+            gas_used.map_or("?".into(), |g| g.to_string()),
+            gas_limit.map_or("?".into(), |g| g.to_string())
+        );
+    }
+    let Some(revert) = revert else {
+        return format!(
+            "Replaying the transaction at block {} succeeded, so the failure depended on transactions mined before it in block {} (e.g. a price move or a competing trade).",
+            block.map_or("?".into(), |b| b.saturating_sub(1).to_string()),
+            block.map_or("?".into(), |b| b.to_string())
+        );
+    };
+    let with_hint = |text: String| match revert["hint"].as_str() {
+        Some(hint) => format!("{}: {}.", text, hint),
+        None => format!("{}.", text),
+    };
+    match revert["kind"].as_str() {
+        Some("error_string") => with_hint(format!(
+            "It reverted with the reason \"{}\"",
+            revert["reason"].as_str().unwrap_or_default()
+        )),
+        Some("panic") => format!(
+            "It hit a Solidity panic {}: {}.",
+            revert["code"].as_str().unwrap_or_default(),
+            revert["reason"].as_str().unwrap_or_default()
+        ),
+        Some("custom_error") => with_hint(format!(
+            "It reverted with the custom error {}{}",
+            revert["name"].as_str().unwrap_or_default(),
+            revert["arguments"]
+                .as_object()
+                .filter(|args| !args.is_empty())
+                .map(|args| format!(" {}", Value::Object(args.clone())))
+                .unwrap_or_default()
+        )),
+        Some("unknown_custom_error") => format!(
+            "It reverted with an unrecognised custom error (selector {}). Pass the contract's error signatures in error_signatures to decode it.",
+            revert["selector"].as_str().unwrap_or_default()
+        ),
+        _ => format!(
+            "It reverted without a reason{}, e.g. a require without a message or a call to an address with no code.",
+            if source == "eth_call" { " in the replay" } else { "" }
+        ),
+    }
+}
+
+fn hex_field(obj: &Value, key: &str) -> Option<Vec<u8>> {
+    hex::decode(obj.get(key)?.as_str()?.trim_start_matches("0x")).ok()
+}
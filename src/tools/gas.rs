@@ -0,0 +1,137 @@
+use super::balance::format_units;
+use super::{u64_arg, Tool};
+use crate::ethereum::EthereumClient;
+use alloy::{
+    eips::BlockNumberOrTag, primitives::U256, providers::Provider, rpc::types::eth::FeeHistory,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+const DEFAULT_BLOCKS: u64 = 10;
+// eth_feeHistory is capped at 1024 blocks by geth and most providers.
+const MAX_BLOCKS: u64 = 1024;
+const REWARD_PERCENTILES: [f64; 3] = [25.0, 50.0, 75.0];
+
+/// EIP-1559 fee suggestion derived from fee history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuggestedFees {
+    pub next_base_fee: u128,
+    /// Median tip per percentile in `REWARD_PERCENTILES`, over non-empty blocks.
+    pub priority_fees: [u128; 3],
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+    pub blocks_sampled: usize,
+}
+
+pub struct GetGasPriceTool;
+
+#[async_trait::async_trait]
+impl Tool for GetGasPriceTool {
+    fn name(&self) -> &'static str {
+        "get_gas_price"
+    }
+
+    fn description(&self) -> &'static str {
+        "Current gas price, plus suggested EIP-1559 maxFeePerGas / maxPriorityFeePerGas from recent fee history, in wei and gwei."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "blocks": {
+                    "type": "integer",
+                    "description": "Fee history window in blocks (1-1024). Default 10."
+                }
+            }
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let blocks = u64_arg(&args, "blocks")?.unwrap_or(DEFAULT_BLOCKS);
+        if blocks == 0 || blocks > MAX_BLOCKS {
+            return Err(anyhow::anyhow!(
+                "blocks must be between 1 and {}",
+                MAX_BLOCKS
+            ));
+        }
+
+        let (gas_price, history) = futures::join!(
+            client.provider.get_gas_price(),
+            client
+                .provider
+                .get_fee_history(blocks, BlockNumberOrTag::Latest, &REWARD_PERCENTILES)
+        );
+        let gas_price = gas_price?;
+        // Pre-London chains and some nodes have no fee history; the legacy price still works.
+        let suggestion = history
+            .ok()
+            .and_then(|h| suggest_fees(&h).map(|fees| (h.oldest_block, fees)));
+
+        let wei = |value: u128| -> Result<Value> {
+            Ok(json!({
+                "wei": value.to_string(),
+                "gwei": format_units(U256::from(value), 9)?
+            }))
+        };
+        let eip1559 = match &suggestion {
+            Some((oldest_block, fees)) => json!({
+                "next_base_fee": wei(fees.next_base_fee)?,
+                "max_fee_per_gas": wei(fees.max_fee_per_gas)?,
+                "max_priority_fee_per_gas": wei(fees.max_priority_fee_per_gas)?,
+                "priority_fee_percentiles": {
+                    "p25": wei(fees.priority_fees[0])?,
+                    "p50": wei(fees.priority_fees[1])?,
+                    "p75": wei(fees.priority_fees[2])?
+                },
+                "history_blocks": blocks,
+                "oldest_block": oldest_block,
+                "blocks_sampled": fees.blocks_sampled
+            }),
+            None => Value::Null,
+        };
+
+        Ok(json!({
+            "gas_price": wei(gas_price)?,
+            "eip1559_supported": suggestion.is_some(),
+            "eip1559": eip1559,
+            "note": "max_fee_per_gas is twice the next base fee plus the median tip, so it survives several full blocks of base-fee increases; only the actual base fee plus the tip is paid. Use recommend_gas_for_deadline for a specific inclusion deadline."
+        }))
+    }
+}
+
+/// Median tip at each percentile across blocks that had transactions, and a max fee of
+/// `2 * next_base_fee + tip`. `None` when the history has no base fee (pre-London).
+pub fn suggest_fees(history: &FeeHistory) -> Option<SuggestedFees> {
+    let next_base_fee = *history.base_fee_per_gas.last()?;
+    if next_base_fee == 0 {
+        return None;
+    }
+    // Empty blocks report zero rewards and would drag the medians down.
+    let rewards: Vec<&Vec<u128>> = history
+        .reward
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .zip(history.gas_used_ratio.iter().chain(std::iter::repeat(&1.0)))
+        .filter(|(_, used)| **used > 0.0)
+        .map(|(rewards, _)| rewards)
+        .collect();
+    let median = |index: usize| {
+        let mut tips: Vec<u128> = rewards
+            .iter()
+            .filter_map(|r| r.get(index).copied())
+            .collect();
+        tips.sort_unstable();
+        tips.get(tips.len() / 2).copied().unwrap_or_default()
+    };
+    let priority_fees = [median(0), median(1), median(2)];
+    let max_priority_fee_per_gas = priority_fees[1];
+    Some(SuggestedFees {
+        next_base_fee,
+        priority_fees,
+        max_priority_fee_per_gas,
+        max_fee_per_gas: next_base_fee * 2 + max_priority_fee_per_gas,
+        blocks_sampled: rewards.len(),
+    })
+}
@@ -0,0 +1,123 @@
+use super::balance::format_units;
+use super::watch::get_transaction;
+use super::Tool;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use crate::{format, oracle, rpc};
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{TxHash, U256},
+    providers::Provider,
+};
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+pub struct GetHistoricalGasCostTool;
+
+#[async_trait::async_trait]
+impl Tool for GetHistoricalGasCostTool {
+    fn name(&self) -> &'static str {
+        "get_historical_gas_cost"
+    }
+
+    fn description(&self) -> &'static str {
+        "Gas cost of a mined transaction in ETH and in USD at the time it was mined, priced with the Chainlink ETH/USD answer as of its block. Reading the feed at a past block needs an archive node."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tx_hash": {
+                    "type": "string",
+                    "description": "Hash of the mined transaction"
+                }
+            },
+            "required": ["tx_hash"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let tx_hash = TxHash::from_str(
+            args["tx_hash"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("tx_hash"))?,
+        )?;
+        let receipt: Option<Value> = client
+            .provider
+            .raw_request("eth_getTransactionReceipt".into(), (tx_hash,))
+            .await?;
+        let receipt = receipt.filter(|r| !r.is_null()).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Transaction {} has no receipt; it is pending or unknown to this node",
+                tx_hash
+            )
+        })?;
+        let tx = if rpc::quantity(&receipt, "effectiveGasPrice").is_none() {
+            get_transaction(client, tx_hash).await?
+        } else {
+            None
+        };
+        let summary = rpc::receipt_summary(&receipt, tx.as_ref());
+        let block = summary["block_number"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Receipt of {} has no block number", tx_hash))?;
+        let execution_fee = summary["fee_paid_wei"]
+            .as_str()
+            .and_then(|f| f.parse::<u128>().ok())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Receipt of {} has no gas used or effective gas price",
+                    tx_hash
+                )
+            })?;
+        // Blob transactions also pay for blob gas, and OP-stack receipts carry the L1 data fee.
+        let blob_fee = rpc::quantity(&receipt, "blobGasUsed")
+            .zip(rpc::quantity(&receipt, "blobGasPrice"))
+            .map(|(used, price)| used.saturating_mul(price));
+        let l1_fee = rpc::quantity(&receipt, "l1Fee");
+        let total_fee = execution_fee
+            .saturating_add(blob_fee.unwrap_or(0))
+            .saturating_add(l1_fee.unwrap_or(0));
+
+        let timestamp = client
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Number(block), false)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Block {} not found", block))?
+            .header
+            .timestamp;
+        let feed = oracle::eth_usd_feed(client)?;
+        let (eth_price_usd, updated_at) = oracle::price_at_block(client, feed, block).await?;
+
+        let fee_eth = Decimal::from_i128_with_scale(i128::try_from(total_fee)?, 18);
+        let wei = |fee: Option<u128>| fee.map(|f| f.to_string());
+        Ok(json!({
+            "tx_hash": tx_hash.to_string(),
+            "block_number": block,
+            "block_timestamp": format::timestamp(timestamp),
+            "gas_used": summary["gas_used"],
+            "effective_gas_price": summary["effective_gas_price"],
+            "effective_gas_price_gwei": summary["effective_gas_price"]
+                .as_str()
+                .and_then(|p| U256::from_str(p).ok())
+                .map(|p| format_units(p, 9))
+                .transpose()?,
+            "execution_fee_wei": execution_fee.to_string(),
+            "blob_fee_wei": wei(blob_fee),
+            "l1_fee_wei": wei(l1_fee),
+            "fee_wei": total_fee.to_string(),
+            "fee_eth": format_units(U256::from(total_fee), 18)?,
+            "eth_price_usd": eth_price_usd.normalize(),
+            "fee_usd": (fee_eth * eth_price_usd).round_dp(2).normalize(),
+            "price_source": {
+                "feed": feed.to_string(),
+                "block_number": block,
+                "updated_at": format::timestamp(updated_at),
+                "note": "Chainlink ETH/USD answer as of the transaction's block (the latest round at the end of that block)."
+            }
+        }))
+    }
+}
@@ -0,0 +1,145 @@
+use super::balance::format_units;
+use super::{u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use alloy::{
+    eips::BlockNumberOrTag, primitives::U256, providers::Provider, rpc::types::eth::FeeHistory,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+// Blocks of fee history analysed per recommendation.
+const HISTORY_BLOCKS: u64 = 20;
+const REWARD_PERCENTILES: [f64; 5] = [10.0, 25.0, 50.0, 75.0, 90.0];
+const MAX_TARGET_BLOCKS: u64 = 20;
+
+/// Fee recommendation for landing within a block deadline, derived from fee history.
+#[derive(Debug, Clone)]
+pub struct DeadlineFees {
+    pub target_blocks: u64,
+    /// Reward percentile the priority fee is taken from.
+    pub percentile: f64,
+    pub next_base_fee: u128,
+    /// Highest base fee reachable within the window if every block is full.
+    pub max_base_fee: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+    pub blocks_sampled: usize,
+}
+
+pub struct GasForDeadlineTool;
+
+#[async_trait::async_trait]
+impl Tool for GasForDeadlineTool {
+    fn name(&self) -> &'static str {
+        "recommend_gas_for_deadline"
+    }
+
+    fn description(&self) -> &'static str {
+        "Recommend maxFeePerGas / maxPriorityFeePerGas likely to get a transaction included within N blocks, from eth_feeHistory percentile analysis."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "target_blocks": {
+                    "type": "integer",
+                    "description": "Inclusion deadline in blocks (1-20). 1 means the next block."
+                }
+            },
+            "required": ["target_blocks"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let target_blocks =
+            u64_arg(&args, "target_blocks")?.ok_or_else(|| ToolError::missing("target_blocks"))?;
+        if target_blocks == 0 || target_blocks > MAX_TARGET_BLOCKS {
+            return Err(anyhow::anyhow!(
+                "target_blocks must be between 1 and {}",
+                MAX_TARGET_BLOCKS
+            ));
+        }
+
+        let history = client
+            .provider
+            .get_fee_history(
+                HISTORY_BLOCKS,
+                BlockNumberOrTag::Latest,
+                &REWARD_PERCENTILES,
+            )
+            .await?;
+        let fees = recommend_for_deadline(&history, target_blocks)?;
+
+        let gwei = |wei: u128| format_units(U256::from(wei), 9);
+        Ok(json!({
+            "target_blocks": fees.target_blocks,
+            "max_fee_per_gas": fees.max_fee_per_gas.to_string(),
+            "max_priority_fee_per_gas": fees.max_priority_fee_per_gas.to_string(),
+            "max_fee_per_gas_gwei": gwei(fees.max_fee_per_gas)?,
+            "max_priority_fee_per_gas_gwei": gwei(fees.max_priority_fee_per_gas)?,
+            "basis": {
+                "history_blocks": HISTORY_BLOCKS,
+                "oldest_block": history.oldest_block,
+                "blocks_sampled": fees.blocks_sampled,
+                "priority_fee_percentile": fees.percentile,
+                "next_base_fee": fees.next_base_fee.to_string(),
+                "max_base_fee_in_window": fees.max_base_fee.to_string(),
+                "avg_gas_used_ratio": average(&history.gas_used_ratio)
+            },
+            "note": "maxFeePerGas covers the base fee rising 12.5% in every block of the window; only the actual base fee plus the priority fee is paid."
+        }))
+    }
+}
+
+/// Picks a reward percentile by urgency (tighter deadline, higher percentile), takes the
+/// median of that percentile across non-empty recent blocks as the tip, and budgets the
+/// base fee for its worst case: +12.5% per full block until the deadline.
+pub fn recommend_for_deadline(history: &FeeHistory, target_blocks: u64) -> Result<DeadlineFees> {
+    let next_base_fee = *history.base_fee_per_gas.last().ok_or_else(|| {
+        anyhow::anyhow!("eth_feeHistory returned no base fees (pre-London chain or node?)")
+    })?;
+    let (index, percentile) = match target_blocks {
+        1 => (4, REWARD_PERCENTILES[4]),
+        2 => (3, REWARD_PERCENTILES[3]),
+        3..=4 => (2, REWARD_PERCENTILES[2]),
+        5..=8 => (1, REWARD_PERCENTILES[1]),
+        _ => (0, REWARD_PERCENTILES[0]),
+    };
+
+    // Empty blocks report zero rewards and would drag the estimate down.
+    let mut tips: Vec<u128> = history
+        .reward
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .zip(history.gas_used_ratio.iter().chain(std::iter::repeat(&1.0)))
+        .filter(|(_, used)| **used > 0.0)
+        .filter_map(|(rewards, _)| rewards.get(index).copied())
+        .collect();
+    tips.sort_unstable();
+    let max_priority_fee_per_gas = tips.get(tips.len() / 2).copied().unwrap_or_default();
+
+    let mut max_base_fee = next_base_fee;
+    for _ in 1..target_blocks {
+        max_base_fee += max_base_fee / 8;
+    }
+
+    Ok(DeadlineFees {
+        target_blocks,
+        percentile,
+        next_base_fee,
+        max_base_fee,
+        max_priority_fee_per_gas,
+        max_fee_per_gas: max_base_fee + max_priority_fee_per_gas,
+        blocks_sampled: tips.len(),
+    })
+}
+
+fn average(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
@@ -0,0 +1,110 @@
+use super::Tool;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use alloy::{
+    json_abi::{Event, Function},
+    primitives::keccak256,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+pub struct HashTool;
+
+#[async_trait::async_trait]
+impl Tool for HashTool {
+    fn name(&self) -> &'static str {
+        "compute_hash"
+    }
+
+    fn description(&self) -> &'static str {
+        "Hashing utilities: keccak256 of hex or UTF-8 input, 4-byte function selectors, and event topic0 hashes. No RPC calls."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["keccak256", "selector", "event_topic"],
+                    "description": "keccak256: hash `input`. selector: 4-byte selector of a function signature. event_topic: topic0 of an event signature."
+                },
+                "input": {
+                    "type": "string",
+                    "description": "Data to hash, or a signature like 'transfer(address,uint256)' / 'event Transfer(address indexed from, address indexed to, uint256 value)'"
+                },
+                "encoding": {
+                    "type": "string",
+                    "enum": ["auto", "hex", "utf8"],
+                    "description": "How keccak256 reads `input`. auto (default) treats 0x-prefixed input as hex, anything else as UTF-8."
+                }
+            },
+            "required": ["operation", "input"]
+        })
+    }
+
+    async fn call(&self, _client: &EthereumClient, args: Value) -> Result<Value> {
+        let operation = args["operation"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("operation"))?;
+        let input = args["input"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("input"))?;
+
+        match operation {
+            "keccak256" => {
+                let encoding = args
+                    .get("encoding")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("auto");
+                let (bytes, used) = decode_input(input, encoding)?;
+                Ok(json!({
+                    "operation": operation,
+                    "encoding": used,
+                    "input_length": bytes.len(),
+                    "hash": keccak256(&bytes).to_string()
+                }))
+            }
+            "selector" => {
+                let function = Function::parse(input)
+                    .map_err(|e| anyhow::anyhow!("Invalid function signature: {}", e))?;
+                Ok(json!({
+                    "operation": operation,
+                    "signature": function.signature(),
+                    "selector": function.selector().to_string()
+                }))
+            }
+            "event_topic" => {
+                let event = Event::parse(input)
+                    .map_err(|e| anyhow::anyhow!("Invalid event signature: {}", e))?;
+                Ok(json!({
+                    "operation": operation,
+                    "signature": event.signature(),
+                    "topic0": event.selector().to_string()
+                }))
+            }
+            other => Err(anyhow::anyhow!(
+                "Unknown operation: {} (expected keccak256, selector or event_topic)",
+                other
+            )),
+        }
+    }
+}
+
+/// Reads `input` as hex or UTF-8 per `encoding` (`auto` treats a 0x prefix as hex).
+/// Returns the bytes and the encoding actually used.
+pub(crate) fn decode_input(input: &str, encoding: &str) -> Result<(Vec<u8>, &'static str)> {
+    match encoding {
+        "hex" => Ok((hex::decode(input.trim_start_matches("0x"))?, "hex")),
+        "utf8" => Ok((input.as_bytes().to_vec(), "utf8")),
+        "auto" => match input.strip_prefix("0x") {
+            Some(h) => Ok((
+                hex::decode(h)
+                    .map_err(|e| anyhow::anyhow!("0x-prefixed input is not valid hex: {}", e))?,
+                "hex",
+            )),
+            None => Ok((input.as_bytes().to_vec(), "utf8")),
+        },
+        other => Err(anyhow::anyhow!("Unknown encoding: {}", other)),
+    }
+}
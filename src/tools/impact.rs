@@ -0,0 +1,289 @@
+use super::balance::format_units;
+use super::price::{
+    check_pool_state, getPoolCall, liquidityCall, pool_price, pow10_decimal, quote_tokens,
+    slot0Call, token0Call, QuoteToken, FEE_TIERS,
+};
+use super::swap::SwapMode;
+use super::{resolve_token_with_decision, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{call3, decode_result, EthereumClient};
+use crate::{oracle, quoter};
+use alloy::{
+    primitives::{Address, U256},
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+pub struct EstimatePriceImpactTool;
+
+/// An initialized pool pairing the token with one of the quote tokens.
+struct PricedPool {
+    quote: QuoteToken,
+    quote_decimals: u8,
+    quote_usd: Decimal,
+    fee: u32,
+    pool: Address,
+    /// Token price in quote units at the pool's current `sqrtPriceX96`.
+    spot: Decimal,
+}
+
+#[async_trait::async_trait]
+impl Tool for EstimatePriceImpactTool {
+    fn name(&self) -> &'static str {
+        "estimate_price_impact"
+    }
+
+    fn description(&self) -> &'static str {
+        "Estimate how far selling a given size of a token would move its price: quotes the sale through every Uniswap V3 pool against WETH or a stablecoin and reports each pool's execution price and impact versus spot, plus the best pool. Nothing is traded."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token_address": {
+                    "type": "string",
+                    "description": "Address or registry symbol of the token being sold"
+                },
+                "size": {
+                    "type": "string",
+                    "description": "Trade size in human units of size_unit (e.g. \"1000000\" for $1M with size_unit \"usd\")"
+                },
+                "size_unit": {
+                    "type": "string",
+                    "enum": ["token", "usd"],
+                    "description": "Whether size is in tokens (default) or USD. USD sizes are converted at the median spot price across the token's pools."
+                }
+            },
+            "required": ["token_address", "size"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let (token, token_resolution) = resolve_token_with_decision(
+            client,
+            args["token_address"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("token_address"))?,
+        )?;
+        let size = Decimal::from_str(
+            args["size"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("size"))?,
+        )?;
+        if size <= Decimal::ZERO {
+            return Err(anyhow::anyhow!("size must be positive"));
+        }
+        let size_in_usd = match args.get("size_unit").and_then(|v| v.as_str()) {
+            None | Some("token") => false,
+            Some("usd") => true,
+            Some(other) => {
+                return Err(anyhow::anyhow!(
+                    "size_unit must be \"token\" or \"usd\", got {:?}",
+                    other
+                ))
+            }
+        };
+
+        let pools = priced_pools(client, token).await?;
+        if pools.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No initialized Uniswap V3 pool pairs {} with WETH or a stablecoin",
+                token
+            ));
+        }
+
+        // USD sizes use the median spot so one thin, off-market pool can't skew the size.
+        let mut spots_usd: Vec<Decimal> = pools.iter().map(|p| p.spot * p.quote_usd).collect();
+        spots_usd.sort();
+        let token_usd = spots_usd[spots_usd.len() / 2];
+        let size_tokens = if size_in_usd {
+            if token_usd.is_zero() {
+                return Err(anyhow::anyhow!("Token has a zero spot price"));
+            }
+            size / token_usd
+        } else {
+            size
+        };
+        let token_decimals = client.get_token_metadata(token).await?.decimals;
+        let raw_in = U256::from_str(
+            &(size_tokens * pow10_decimal(i32::from(token_decimals))?)
+                .trunc()
+                .to_string(),
+        )?;
+        if raw_in.is_zero() {
+            return Err(anyhow::anyhow!("size is below one base unit of the token"));
+        }
+
+        let quotes = join_all(pools.iter().map(|p| quote_sale(client, token, p, raw_in))).await;
+        let mut entries: Vec<(Option<Decimal>, Value)> = pools
+            .iter()
+            .zip(quotes)
+            .map(|(p, quoted)| match quoted {
+                Ok(out) => {
+                    let execution = out / size_tokens;
+                    let impact = if p.spot.is_zero() {
+                        Decimal::ZERO
+                    } else {
+                        (p.spot - execution) / p.spot * Decimal::from(100)
+                    };
+                    let out_usd = out * p.quote_usd;
+                    (
+                        Some(out_usd),
+                        json!({
+                            "quote_token": p.quote.symbol,
+                            "fee": p.fee,
+                            "pool": p.pool.to_string(),
+                            "spot_price": p.spot.round_dp(18).normalize(),
+                            "execution_price": execution.round_dp(18).normalize(),
+                            "execution_price_usd": (execution * p.quote_usd).round_dp(8).normalize(),
+                            "price_impact_percent": impact.round_dp(4).normalize(),
+                            "amount_out": out.normalize().to_string(),
+                            "amount_out_usd": out_usd.round_dp(2).normalize()
+                        }),
+                    )
+                }
+                Err(e) => (
+                    None,
+                    json!({
+                        "quote_token": p.quote.symbol,
+                        "fee": p.fee,
+                        "pool": p.pool.to_string(),
+                        "error": e.to_string()
+                    }),
+                ),
+            })
+            .collect();
+        // Best output (in USD) first; pools that couldn't fill the size last.
+        entries.sort_by_key(|(out, _)| std::cmp::Reverse(*out));
+        let best = entries
+            .first()
+            .filter(|(out, _)| out.is_some())
+            .map(|(_, entry)| entry.clone());
+
+        Ok(json!({
+            "token": token.to_string(),
+            "size_tokens": size_tokens.round_dp(u32::from(token_decimals)).normalize().to_string(),
+            "size_usd": (size_tokens * token_usd).round_dp(2).normalize(),
+            "spot_price_usd": token_usd.round_dp(8).normalize(),
+            "best": best,
+            "pools": entries.into_iter().map(|(_, entry)| entry).collect::<Vec<_>>(),
+            "token_resolution": token_resolution,
+            "note": "price_impact_percent is how far the execution price falls below the pool's spot price, pool fee included. Each pool is quoted for the full size on its own; splitting across pools (see optimize_swap_split) can do better. Quote prices are in the quote token; USD values use Chainlink ETH/USD and stablecoin pegs."
+        }))
+    }
+}
+
+/// Every initialized pool of `token` against WETH or a chain stablecoin, across fee tiers,
+/// with its spot price. Pool lookups and pool state each take one Multicall3 batch.
+async fn priced_pools(client: &EthereumClient, token: Address) -> Result<Vec<PricedPool>> {
+    let factory = client.uniswap_v3()?.factory;
+    let pairs: Vec<(QuoteToken, u32)> = quote_tokens(client, token)?
+        .into_iter()
+        .flat_map(|q| FEE_TIERS.iter().map(move |fee| (q, *fee)))
+        .collect();
+    let lookups = pairs
+        .iter()
+        .map(|(q, fee)| {
+            call3(
+                factory,
+                getPoolCall {
+                    tokenA: token,
+                    tokenB: q.address,
+                    fee: *fee,
+                }
+                .abi_encode(),
+            )
+        })
+        .collect();
+    let mut found = Vec::new();
+    for ((quote, fee), result) in pairs.into_iter().zip(client.multicall(lookups).await?) {
+        let pool = decode_result::<getPoolCall>(&result, factory)?.pool;
+        if pool != Address::ZERO {
+            found.push((quote, fee, pool));
+        }
+    }
+    if found.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let feed = oracle::eth_usd_feed(client)?;
+    let mut calls: Vec<_> = found
+        .iter()
+        .flat_map(|(_, _, pool)| {
+            [
+                call3(*pool, slot0Call {}.abi_encode()),
+                call3(*pool, liquidityCall {}.abi_encode()),
+                call3(*pool, token0Call {}.abi_encode()),
+            ]
+        })
+        .collect();
+    calls.extend(oracle::price_calls(feed));
+    let results = client.multicall(calls).await?;
+    let eth_usd = oracle::price_from_results(feed, &results[found.len() * 3..])?;
+    let token_decimals = client.get_token_metadata(token).await?.decimals;
+
+    let weth = client.chain()?.weth;
+    let mut pools = Vec::new();
+    for (i, (quote, fee, pool)) in found.into_iter().enumerate() {
+        let state = &results[i * 3..i * 3 + 3];
+        let Ok(slot0) = decode_result::<slot0Call>(&state[0], pool) else {
+            continue;
+        };
+        let Ok(liquidity) = decode_result::<liquidityCall>(&state[1], pool) else {
+            continue;
+        };
+        // Empty and uninitialized pools have no price to move.
+        if check_pool_state(slot0.sqrtPriceX96, slot0.unlocked, liquidity._0).is_err() {
+            continue;
+        }
+        let token0 = decode_result::<token0Call>(&state[2], pool)?._0;
+        let quote_decimals = client.get_token_metadata(quote.address).await?.decimals;
+        let quote_usd = if quote.address == weth {
+            eth_usd
+        } else {
+            oracle::stablecoin_peg(client, quote.address)
+                .await?
+                .map_or(Decimal::ONE, |peg| peg.price_usd)
+        };
+        pools.push(PricedPool {
+            quote,
+            quote_decimals,
+            quote_usd,
+            fee,
+            pool,
+            spot: pool_price(
+                slot0.sqrtPriceX96,
+                token0 == token,
+                token_decimals,
+                quote_decimals,
+            )?,
+        });
+    }
+    Ok(pools)
+}
+
+/// Quoter output, in human units of the quote token, for selling `raw_in` through `pool`.
+async fn quote_sale(
+    client: &EthereumClient,
+    token: Address,
+    pool: &PricedPool,
+    raw_in: U256,
+) -> Result<Decimal> {
+    let out = quoter::quote_single(
+        client,
+        SwapMode::ExactIn,
+        token,
+        pool.quote.address,
+        pool.fee,
+        raw_in,
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!("{} (not enough liquidity?)", e))?
+    .amount_out;
+    Ok(Decimal::from_str(&format_units(out, pool.quote_decimals)?)?)
+}
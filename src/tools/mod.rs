@@ -1,9 +1,63 @@
+pub mod approve;
 pub mod balance;
+pub mod balances;
+pub mod batch;
+pub mod block;
+pub mod chains;
+pub mod convert;
+pub mod cross_rate;
+pub mod crosscheck;
+pub mod discover;
+pub mod encode;
+pub mod ens;
+pub mod events;
+pub mod explain_tx;
+pub mod gas;
+pub mod gas_cost;
+pub mod gas_deadline;
+pub mod hash;
+pub mod impact;
+pub mod observations;
+pub mod permit;
+pub mod plan;
+pub mod pool_fees;
 pub mod price;
+pub mod price_watch;
+pub mod prices;
+pub mod profile;
+pub mod rebasing;
+pub mod reference;
+pub mod required_eth;
+pub mod safety;
+pub mod scenario;
+pub mod send;
+pub mod split;
 pub mod swap;
+pub mod sweep;
+pub mod transfers;
+pub mod tx_status;
+pub mod verify;
+pub mod watch;
+pub mod wrap;
 
+use crate::error::ToolError;
 use crate::ethereum::EthereumClient;
-use serde_json::Value;
+use alloy::primitives::Address;
+use base64::Engine;
+use serde_json::{json, Map, Value};
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Items a batch tool accepts in one call unless `MAX_BATCH_SIZE` says otherwise.
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
+/// Seconds a tool call may run unless `TOOL_TIMEOUT_SECONDS` says otherwise.
+pub const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 30;
+
+/// Length from which a field listed in `Tool::large_fields` leaves the text content.
+pub const LARGE_FIELD_MIN_CHARS: usize = 512;
 
 #[async_trait::async_trait]
 pub trait Tool: Send + Sync {
@@ -11,4 +65,373 @@ pub trait Tool: Send + Sync {
     fn description(&self) -> &'static str;
     fn schema(&self) -> Value;
     async fn call(&self, client: &EthereumClient, args: Value) -> anyhow::Result<Value>;
+
+    /// JSON pointers to result fields that can be large, such as calldata (`*` matches any
+    /// array index). `tool_content` attaches them as resources instead of inlining them.
+    fn large_fields(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// How long a call with `args` may spend waiting by design (for a confirmation or a
+    /// price move). The server adds it to the tool timeout.
+    fn wait_budget(&self, _args: &Value) -> Option<Duration> {
+        None
+    }
+}
+
+tokio::task_local! {
+    /// Live events of the running tool call, sent on as `notifications/progress` when the
+    /// caller passed `params._meta.progressToken`.
+    static PROGRESS: UnboundedSender<Value>;
+    /// Id of the session the running tool call came from.
+    static SESSION: String;
+}
+
+/// Runs a tool call with `report_progress` feeding `sink`.
+pub async fn with_progress<F: Future>(sink: UnboundedSender<Value>, call: F) -> F::Output {
+    PROGRESS.scope(sink, call).await
+}
+
+/// Runs a tool call on behalf of session `session_id`.
+pub async fn with_session<F: Future>(session_id: String, call: F) -> F::Output {
+    SESSION.scope(session_id, call).await
+}
+
+/// The session the running tool call came from; `None` when the tool is called directly
+/// rather than through the server.
+pub fn session_id() -> Option<String> {
+    SESSION.try_with(|id| id.clone()).ok()
+}
+
+/// Sends `item` to the caller while the tool is still running. `false` when the call has
+/// no progress token, so the caller only sees what the result includes.
+pub fn report_progress(item: Value) -> bool {
+    PROGRESS
+        .try_with(|sink| sink.send(item).is_ok())
+        .unwrap_or(false)
+}
+
+/// Adds the server-wide `fields` and `stream` arguments to a tool's input schema.
+pub fn with_common_properties(mut schema: Value) -> Value {
+    if let Some(props) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        props.insert(
+            "fields".to_string(),
+            json!({
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Return only these top-level fields of the result. Default: the full response."
+            }),
+        );
+        props.insert(
+            "stream".to_string(),
+            json!({
+                "type": "string",
+                "description": "Name of a top-level array in the result to deliver as one notifications/progress event per element before the final response. Requires params._meta.progressToken."
+            }),
+        );
+    }
+    schema
+}
+
+/// Resolves a token argument given as an address or a registry symbol.
+pub fn resolve_token(client: &EthereumClient, token: &str) -> anyhow::Result<Address> {
+    resolve_token_with_decision(client, token).map(|(address, _)| address)
+}
+
+/// `resolve_token`, also returning how a symbol was resolved (see
+/// `SymbolResolution::to_json`), or `null` when `token` was already an address.
+pub fn resolve_token_with_decision(
+    client: &EthereumClient,
+    token: &str,
+) -> anyhow::Result<(Address, Value)> {
+    if let Ok(addr) = Address::from_str(token) {
+        return Ok((addr, Value::Null));
+    }
+    match client.tokens.resolve_symbol(token)? {
+        Some(resolution) => Ok((resolution.token.address, resolution.to_json())),
+        None => Err(ToolError::invalid_argument(format!(
+            "Unknown token symbol {}. Please provide its address.",
+            token
+        ))),
+    }
+}
+
+/// Parses an address argument, resolving ENS names (`vitalik.eth`) through the registry.
+pub async fn resolve_address(client: &EthereumClient, value: &str) -> anyhow::Result<Address> {
+    if let Ok(address) = Address::from_str(value.trim()) {
+        return Ok(address);
+    }
+    if ens::is_ens_name(value) {
+        return Ok(ens::resolve(client, value).await?.address);
+    }
+    Err(ToolError::invalid_argument(format!(
+        "{:?} is not an address or an ENS name",
+        value
+    )))
+}
+
+/// The account a transaction-building tool builds for, and whether the transaction is
+/// returned unsigned for an external signer instead of being sent: with `unsigned: true`,
+/// and always in read-only mode. Unsigned transactions are built for `from` (default: the
+/// server wallet); signed ones can only come from the server wallet.
+pub async fn transaction_sender(
+    client: &EthereumClient,
+    args: &Value,
+) -> anyhow::Result<(Address, bool)> {
+    let unsigned = args
+        .get("unsigned")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+        || client.signer_address.is_none();
+    let from = match args.get("from").and_then(|v| v.as_str()) {
+        Some(from) => Some(resolve_address(client, from).await?),
+        None => None,
+    };
+    match (from, unsigned) {
+        (Some(from), true) => Ok((from, true)),
+        (None, true) => client.signer_address.map(|a| (a, true)).ok_or_else(|| {
+            ToolError::invalid_argument(
+                "Missing from: the server is in read-only mode (no PRIVATE_KEY, MNEMONIC or KEYSTORE_PATH), so transactions are built unsigned for the address that will sign them"
+            )
+        }),
+        (Some(from), false) if client.signer_address != Some(from) => Err(ToolError::invalid_argument(format!(
+            "from {} is not the server wallet; pass unsigned: true to build the transaction for another signer",
+            from
+        ))),
+        (_, false) => Ok((client.require_signer()?, false)),
+    }
+}
+
+/// Refuses a batch of `len` items above the client's `max_batch_size`, which keeps the
+/// Multicall (or the fan-out of calls) it turns into within what providers accept.
+pub fn check_batch_size(client: &EthereumClient, len: usize) -> anyhow::Result<()> {
+    if len > client.max_batch_size {
+        return Err(ToolError::invalid_argument(format!(
+            "batch size {} exceeds maximum {}; split the request",
+            len, client.max_batch_size
+        )));
+    }
+    Ok(())
+}
+
+/// Reads an optional unsigned integer argument, accepting a JSON number or a numeric string
+/// (`3000` or `"3000"`). Missing or null is `None`; anything else non-numeric is an error.
+pub fn u64_arg(args: &Value, key: &str) -> anyhow::Result<Option<u64>> {
+    let parsed = match args.get(key) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::Number(n)) => n.as_u64().or_else(|| {
+            n.as_f64()
+                .filter(|f| f.fract() == 0.0 && *f >= 0.0 && *f <= u64::MAX as f64)
+                .map(|f| f as u64)
+        }),
+        Some(Value::String(s)) => s.trim().parse::<u64>().ok(),
+        Some(_) => None,
+    };
+    parsed.map(Some).ok_or_else(|| {
+        ToolError::invalid_argument(format!(
+            "{} must be a non-negative integer, got {}",
+            key, args[key]
+        ))
+    })
+}
+
+/// Reads an optional Uniswap V3 `fee` argument, which must be one of `FEE_TIERS`; anything
+/// else (including values too large for a uint24) is refused rather than truncated.
+pub fn fee_arg(args: &Value) -> anyhow::Result<Option<u32>> {
+    let Some(fee) = u64_arg(args, "fee")? else {
+        return Ok(None);
+    };
+    price::FEE_TIERS
+        .into_iter()
+        .find(|tier| u64::from(*tier) == fee)
+        .map(Some)
+        .ok_or_else(|| {
+            ToolError::invalid_argument(format!(
+                "fee must be one of {:?}, got {}",
+                price::FEE_TIERS,
+                fee
+            ))
+        })
+}
+
+/// Reads an optional numeric argument, accepting a JSON number or a numeric string
+/// (`0.5` or `"0.5"`). Missing or null is `None`; anything else non-numeric is an error.
+pub fn f64_arg(args: &Value, key: &str) -> anyhow::Result<Option<f64>> {
+    let parsed = match args.get(key) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.trim().parse::<f64>().ok().filter(|f| f.is_finite()),
+        Some(_) => None,
+    };
+    parsed.map(Some).ok_or_else(|| {
+        ToolError::invalid_argument(format!("{} must be a number, got {}", key, args[key]))
+    })
+}
+
+/// Takes the array named by `stream` (the caller's `stream` argument) out of `result`, so
+/// its elements can be sent ahead of the response. The array is replaced by
+/// `{"streamed_items": n}`. A missing or null `stream` leaves the result whole.
+pub fn split_stream(
+    mut result: Value,
+    stream: Option<&Value>,
+) -> anyhow::Result<(Value, Vec<Value>)> {
+    let field = match stream {
+        None | Some(Value::Null) => return Ok((result, Vec::new())),
+        Some(Value::String(field)) => field,
+        Some(_) => return Err(ToolError::invalid_argument("stream must be a field name")),
+    };
+    match result.get_mut(field) {
+        Some(Value::Array(items)) => {
+            let items = std::mem::take(items);
+            result[field] = json!({ "streamed_items": items.len() });
+            Ok((result, items))
+        }
+        _ => {
+            let mut arrays: Vec<&String> = result
+                .as_object()
+                .map(|o| {
+                    o.iter()
+                        .filter(|(_, v)| v.is_array())
+                        .map(|(k, _)| k)
+                        .collect()
+                })
+                .unwrap_or_default();
+            arrays.sort();
+            Err(ToolError::invalid_argument(format!(
+                "stream: {} is not a top-level array of the result; arrays: {}",
+                field,
+                if arrays.is_empty() {
+                    "none".to_string()
+                } else {
+                    arrays
+                        .iter()
+                        .map(|k| k.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            )))
+        }
+    }
+}
+
+/// Trims `result` to the top-level keys listed in `fields` (the caller's `fields` argument).
+/// A missing or null `fields` returns the result unchanged.
+pub fn select_fields(result: Value, fields: Option<&Value>) -> anyhow::Result<Value> {
+    let fields = match fields {
+        None | Some(Value::Null) => return Ok(result),
+        Some(Value::Array(f)) => f
+            .iter()
+            .map(|f| {
+                f.as_str().ok_or_else(|| {
+                    ToolError::invalid_argument("fields must be an array of strings")
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?,
+        Some(_) => {
+            return Err(ToolError::invalid_argument(
+                "fields must be an array of strings",
+            ))
+        }
+    };
+    let Value::Object(mut object) = result else {
+        return Err(ToolError::invalid_argument(
+            "fields can only be used on object results",
+        ));
+    };
+
+    let unknown: Vec<&str> = fields
+        .iter()
+        .copied()
+        .filter(|f| !object.contains_key(*f))
+        .collect();
+    if !unknown.is_empty() {
+        let mut available: Vec<&String> = object.keys().collect();
+        available.sort();
+        return Err(ToolError::invalid_argument(format!(
+            "Unknown field(s) {}; available: {}",
+            unknown.join(", "),
+            available
+                .iter()
+                .map(|k| k.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    let mut selected = Map::new();
+    for field in fields {
+        if let Some(v) = object.remove(field) {
+            selected.insert(field.to_string(), v);
+        }
+    }
+    Ok(Value::Object(selected))
+}
+
+/// MCP `content` for a tool result: the result as pretty JSON text, with each string field
+/// matched by `large_fields` of at least `LARGE_FIELD_MIN_CHARS` replaced there by a
+/// placeholder and attached as an embedded resource instead, as a base64 `blob` when it is
+/// 0x-hex and as `text` otherwise. The `data` of the response keeps every field whole.
+pub fn tool_content(tool: &str, result: &Value, large_fields: &[&str]) -> Vec<Value> {
+    let mut pointers = Vec::new();
+    for pattern in large_fields {
+        let segments: Vec<&str> = pattern.split('/').skip(1).collect();
+        expand_pointer(result, &segments, String::new(), &mut pointers);
+    }
+
+    let mut text = result.clone();
+    let mut resources = Vec::new();
+    for pointer in pointers {
+        let Some(slot) = text.pointer_mut(&pointer) else {
+            continue;
+        };
+        let Some(value) = slot.as_str().filter(|v| v.len() >= LARGE_FIELD_MIN_CHARS) else {
+            continue;
+        };
+        let uri = format!("tool://{}{}", tool, pointer);
+        let bytes = value.strip_prefix("0x").and_then(|h| hex::decode(h).ok());
+        let (placeholder, resource) = match bytes {
+            Some(bytes) => (
+                format!("<{} bytes, attached as resource {}>", bytes.len(), uri),
+                json!({
+                    "uri": uri,
+                    "mimeType": "application/octet-stream",
+                    "blob": base64::engine::general_purpose::STANDARD.encode(&bytes)
+                }),
+            ),
+            None => (
+                format!("<{} characters, attached as resource {}>", value.len(), uri),
+                json!({ "uri": uri, "mimeType": "text/plain", "text": value }),
+            ),
+        };
+        *slot = json!(placeholder);
+        resources.push(json!({ "type": "resource", "resource": resource }));
+    }
+
+    let mut content = vec![json!({
+        "type": "text",
+        "text": serde_json::to_string_pretty(&text).unwrap_or_else(|_| text.to_string())
+    })];
+    content.extend(resources);
+    content
+}
+
+/// Concrete JSON pointers in `value` matching `segments`, where `*` is any array index.
+fn expand_pointer(value: &Value, segments: &[&str], prefix: String, out: &mut Vec<String>) {
+    let Some((first, rest)) = segments.split_first() else {
+        out.push(prefix);
+        return;
+    };
+    match (*first, value) {
+        ("*", Value::Array(items)) => {
+            for (i, item) in items.iter().enumerate() {
+                expand_pointer(item, rest, format!("{}/{}", prefix, i), out);
+            }
+        }
+        (key, Value::Object(object)) => {
+            if let Some(child) = object.get(key) {
+                expand_pointer(child, rest, format!("{}/{}", prefix, key), out);
+            }
+        }
+        _ => {}
+    }
 }
@@ -0,0 +1,175 @@
+use super::price::slot0Call;
+use super::{u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{
+    decode_return, getCurrentBlockTimestampCall, EthereumClient, MULTICALL3_ADDRESS,
+};
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    function observations(uint256 index) external view returns (uint32 blockTimestamp, int56 tickCumulative, uint160 secondsPerLiquidityCumulativeX128, bool initialized);
+}
+
+// Mainnet block time; a pool records at most one observation per block.
+const SECONDS_PER_BLOCK: u64 = 12;
+
+pub struct GetPoolObservationsTool;
+
+#[async_trait::async_trait]
+impl Tool for GetPoolObservationsTool {
+    fn name(&self) -> &'static str {
+        "get_pool_observations"
+    }
+
+    fn description(&self) -> &'static str {
+        "Read a Uniswap V3 pool's full slot0 and oracle observation buffer, and report how far back a TWAP can reach."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pool_address": {
+                    "type": "string",
+                    "description": "Uniswap V3 pool address"
+                },
+                "twap_window_seconds": {
+                    "type": "integer",
+                    "description": "Optional TWAP window to check against the pool's observation history"
+                }
+            },
+            "required": ["pool_address"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let pool = Address::from_str(
+            args["pool_address"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("pool_address"))?,
+        )?;
+        let window = u64_arg(&args, "twap_window_seconds")?;
+
+        let slot0 = slot0Call::abi_decode_returns(
+            &pool_call(client, pool, slot0Call {}.abi_encode()).await?,
+            true,
+        )
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "{} does not look like a Uniswap V3 pool (slot0 failed)",
+                pool
+            )
+        })?;
+        if slot0.observationCardinality == 0 {
+            return Err(anyhow::anyhow!("Pool {} is not initialized", pool));
+        }
+
+        // The slot after the newest observation is the oldest once the ring buffer has
+        // wrapped; until then it is uninitialized and the oldest is slot 0.
+        let mut oldest_index =
+            (slot0.observationIndex as u64 + 1) % slot0.observationCardinality as u64;
+        let (oldest, now) = futures::join!(
+            observation(client, pool, oldest_index),
+            block_timestamp(client)
+        );
+        let mut oldest = oldest?;
+        if !oldest.initialized {
+            oldest_index = 0;
+            oldest = observation(client, pool, 0).await?;
+        }
+        let now = now?;
+        let max_window = now.saturating_sub(oldest.blockTimestamp as u64);
+
+        let recommendation = window.map(|window| {
+            let supported = window <= max_window;
+            let needed = window.div_ceil(SECONDS_PER_BLOCK).max(1);
+            json!({
+                "twap_window_seconds": window,
+                "supported": supported,
+                "suggested_cardinality": needed,
+                "message": if supported {
+                    format!(
+                        "A {}s TWAP fits within the {}s of recorded history.",
+                        window, max_window
+                    )
+                } else if slot0.observationCardinalityNext as u64 >= needed {
+                    format!(
+                        "Only {}s of history is available. The buffer is already growing to {} slots; retry once enough blocks with swaps have passed.",
+                        max_window, slot0.observationCardinalityNext
+                    )
+                } else {
+                    format!(
+                        "Only {}s of history is available. Call increaseObservationCardinalityNext({}) on the pool (about one slot per {}s block), or use a shorter window.",
+                        max_window, needed, SECONDS_PER_BLOCK
+                    )
+                }
+            })
+        });
+
+        Ok(json!({
+            "pool": pool.to_string(),
+            "slot0": {
+                "sqrt_price_x96": slot0.sqrtPriceX96.to_string(),
+                "tick": slot0.tick,
+                "observation_index": slot0.observationIndex,
+                "observation_cardinality": slot0.observationCardinality,
+                "observation_cardinality_next": slot0.observationCardinalityNext,
+                "fee_protocol": slot0.feeProtocol,
+                "unlocked": slot0.unlocked
+            },
+            "oldest_observation": {
+                "index": oldest_index,
+                "block_timestamp": oldest.blockTimestamp,
+                "tick_cumulative": oldest.tickCumulative.to_string(),
+                "initialized": oldest.initialized
+            },
+            "current_timestamp": now,
+            "max_twap_window_seconds": max_window,
+            "recommendation": recommendation,
+            "note": "Observations are written at most once per block and only when the pool is touched, so the reachable window also depends on trading activity."
+        }))
+    }
+}
+
+async fn pool_call(client: &EthereumClient, pool: Address, data: Vec<u8>) -> Result<Vec<u8>> {
+    let req = TransactionRequest::default().to(pool).input(data.into());
+    Ok(client.provider.call(&req).await?.to_vec())
+}
+
+async fn observation(
+    client: &EthereumClient,
+    pool: Address,
+    index: u64,
+) -> Result<observationsReturn> {
+    let data = observationsCall {
+        index: U256::from(index),
+    }
+    .abi_encode();
+    decode_return::<observationsCall>(&pool_call(client, pool, data).await?, pool)
+}
+
+/// Current block timestamp, read through Multicall3 so it comes from the same node state
+/// as the pool calls.
+async fn block_timestamp(client: &EthereumClient) -> Result<u64> {
+    let res = pool_call(
+        client,
+        MULTICALL3_ADDRESS,
+        getCurrentBlockTimestampCall {}.abi_encode(),
+    )
+    .await?;
+    Ok(
+        decode_return::<getCurrentBlockTimestampCall>(&res, MULTICALL3_ADDRESS)?
+            .timestamp
+            .saturating_to(),
+    )
+}
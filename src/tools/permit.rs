@@ -0,0 +1,248 @@
+use super::swap::swap_deadline;
+use super::{resolve_token, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{call3, decode_result, describe_call_error, EthereumClient};
+use alloy::{
+    primitives::{keccak256, Address, B256, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+    sol,
+    sol_types::{Eip712Domain, SolCall, SolStruct},
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    struct Permit {
+        address owner;
+        address spender;
+        uint256 value;
+        uint256 nonce;
+        uint256 deadline;
+    }
+    #[allow(missing_docs)]
+    function nonces(address owner) external view returns (uint256);
+    #[allow(missing_docs)]
+    function DOMAIN_SEPARATOR() external view returns (bytes32);
+    #[allow(missing_docs)]
+    function PERMIT_TYPEHASH() external view returns (bytes32);
+    #[allow(missing_docs)]
+    function name() external view returns (string);
+    #[allow(missing_docs)]
+    function version() external view returns (string);
+    #[allow(missing_docs)]
+    function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+}
+
+/// Seconds from the latest block until the permit expires when `deadline_seconds` is omitted.
+const DEFAULT_PERMIT_SECONDS: u64 = 3600;
+
+pub struct SignPermitTool;
+
+#[async_trait::async_trait]
+impl Tool for SignPermitTool {
+    fn name(&self) -> &'static str {
+        "sign_permit"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sign an EIP-2612 permit from the server wallet, letting a spender use an ERC20 allowance without an approve transaction. Reads the token's nonce and domain, checks the signature against the token with eth_call, and returns v/r/s, the deadline and ready-made permit calldata. Nothing is sent."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token_address": {
+                    "type": "string",
+                    "description": "Address or registry symbol of the ERC20 token"
+                },
+                "spender": {
+                    "type": "string",
+                    "description": "Address allowed to spend the tokens"
+                },
+                "value": {
+                    "type": "string",
+                    "description": "Allowance in base units, or \"max\" for type(uint256).max"
+                },
+                "deadline_seconds": {
+                    "type": "integer",
+                    "description": "Seconds from the latest block until the permit expires. Default 3600."
+                }
+            },
+            "required": ["token_address", "spender", "value"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let token = resolve_token(
+            client,
+            args["token_address"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("token_address"))?,
+        )?;
+        let spender = Address::from_str(
+            args["spender"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("spender"))?,
+        )?;
+        let value = match args["value"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("value"))?
+            .trim()
+        {
+            "max" | "MAX" => U256::MAX,
+            value => U256::from_str(value).map_err(|_| {
+                anyhow::anyhow!(
+                    "value must be a base-unit integer or \"max\", got {:?}",
+                    value
+                )
+            })?,
+        };
+        let deadline_seconds =
+            u64_arg(&args, "deadline_seconds")?.unwrap_or(DEFAULT_PERMIT_SECONDS);
+        if deadline_seconds == 0 {
+            return Err(anyhow::anyhow!("deadline_seconds must be positive"));
+        }
+        let owner = client.require_signer()?;
+
+        let results = client
+            .multicall(vec![
+                call3(token, noncesCall { owner }.abi_encode()),
+                call3(token, DOMAIN_SEPARATORCall {}.abi_encode()),
+                call3(token, PERMIT_TYPEHASHCall {}.abi_encode()),
+                call3(token, nameCall {}.abi_encode()),
+                call3(token, versionCall {}.abi_encode()),
+            ])
+            .await?;
+        let nonce = decode_result::<noncesCall>(&results[0], token)
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "{} does not support EIP-2612 permit: nonces(address) is not callable",
+                    token
+                )
+            })?
+            ._0;
+        // DAI-style permits sign (holder, spender, nonce, expiry, allowed) instead and would
+        // reject an EIP-2612 signature.
+        let permit_typehash = keccak256(Permit::eip712_encode_type().as_bytes());
+        if let Ok(typehash) = decode_result::<PERMIT_TYPEHASHCall>(&results[2], token) {
+            if typehash._0 != permit_typehash {
+                return Err(anyhow::anyhow!(
+                    "{} has a non-standard permit (PERMIT_TYPEHASH {} is not EIP-2612's; DAI-style?)",
+                    token,
+                    typehash._0
+                ));
+            }
+        }
+        let on_chain_separator = decode_result::<DOMAIN_SEPARATORCall>(&results[1], token)
+            .ok()
+            .map(|r| r._0);
+        let name = decode_result::<nameCall>(&results[3], token)
+            .ok()
+            .map(|r| r._0);
+        let (version, version_source) = match decode_result::<versionCall>(&results[4], token) {
+            Ok(r) => (r._0, "version()"),
+            Err(_) => ("1".to_string(), "default"),
+        };
+
+        let domain = Eip712Domain::new(
+            name.clone().map(Cow::Owned),
+            Some(Cow::Owned(version.clone())),
+            Some(U256::from(client.chain_id())),
+            Some(token),
+            None,
+        );
+        let constructed_separator = name.as_ref().map(|_| domain.separator());
+        // The token checks signatures against its own separator, so that one wins.
+        let (separator, domain_source) = match (on_chain_separator, constructed_separator) {
+            (Some(separator), _) => (separator, "DOMAIN_SEPARATOR()"),
+            (None, Some(separator)) => (separator, "constructed from name/version/chainId"),
+            (None, None) => {
+                return Err(anyhow::anyhow!(
+                    "{} does not support EIP-2612 permit: neither DOMAIN_SEPARATOR() nor name() is callable",
+                    token
+                ))
+            }
+        };
+
+        let (deadline, deadline_base) = swap_deadline(client, deadline_seconds).await;
+        let deadline = U256::from(deadline);
+        let message = Permit {
+            owner,
+            spender,
+            value,
+            nonce,
+            deadline,
+        };
+        let mut digest_input = [0u8; 66];
+        digest_input[..2].copy_from_slice(&[0x19, 0x01]);
+        digest_input[2..34].copy_from_slice(separator.as_slice());
+        digest_input[34..].copy_from_slice(message.eip712_hash_struct().as_slice());
+        let digest = keccak256(digest_input);
+        let signature = client.sign_hash(&digest)?;
+        let v = signature.v().y_parity_byte() + 27;
+        let r = B256::from(signature.r());
+        let s = B256::from(signature.s());
+
+        let calldata = permitCall {
+            owner,
+            spender,
+            value,
+            deadline,
+            v,
+            r,
+            s,
+        }
+        .abi_encode();
+        // The token's own check is the real test of support: a wrong domain, nonce or
+        // signature scheme reverts here.
+        let req = TransactionRequest::default()
+            .to(token)
+            .input(calldata.clone().into());
+        client.provider.call(&req).await.map_err(|e| {
+            anyhow::anyhow!(
+                "{} rejected the signed permit in simulation, so it likely does not support EIP-2612: {}",
+                token,
+                describe_call_error(&e)
+            )
+        })?;
+
+        Ok(json!({
+            "token": token.to_string(),
+            "owner": owner.to_string(),
+            "spender": spender.to_string(),
+            "value": value.to_string(),
+            "nonce": nonce.to_string(),
+            "deadline": deadline.to_string(),
+            "deadline_seconds": deadline_seconds,
+            "deadline_base": deadline_base,
+            "v": v,
+            "r": r.to_string(),
+            "s": s.to_string(),
+            "signature": format!("0x{}", hex::encode(signature.as_bytes())),
+            "digest": digest.to_string(),
+            "domain": {
+                "name": name,
+                "version": version,
+                "version_source": version_source,
+                "chain_id": client.chain_id(),
+                "verifying_contract": token.to_string()
+            },
+            "domain_separator": separator.to_string(),
+            "domain_source": domain_source,
+            "domain_matches_token": match (on_chain_separator, constructed_separator) {
+                (Some(a), Some(b)) => Some(a == b),
+                _ => None,
+            },
+            "permit_calldata": {
+                "to": token.to_string(),
+                "data": format!("0x{}", hex::encode(&calldata))
+            },
+            "note": "Checked with eth_call against the token's permit(). Anyone may submit permit_calldata (or pass v/r/s to a contract that calls permit) before the deadline; it is spent once the owner's nonce moves on. domain_matches_token false means the token's DOMAIN_SEPARATOR differs from the one built from name/version/chainId; the token's own separator was signed."
+        }))
+    }
+}
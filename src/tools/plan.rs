@@ -0,0 +1,449 @@
+use super::balance::format_units;
+use super::swap::{
+    decode_amount_out, ensure_erc20, exact_input_single_calldata, slippage_arg, slippage_bounds,
+    swap_deadline, with_unwrap, ExactInputSingleParams, SwapMode, DEFAULT_DEADLINE_SECONDS,
+};
+use super::{fee_arg, resolve_token_with_decision, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{describe_call_error, EthereumClient};
+use crate::quoter;
+use alloy::{
+    primitives::{keccak256, Address, B256, U256},
+    providers::Provider,
+    rpc::types::eth::{
+        state::{AccountOverride, StateOverride},
+        TransactionRequest,
+    },
+    sol,
+    sol_types::{SolCall, SolValue},
+};
+use anyhow::Result;
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    function approve(address spender, uint256 amount) external returns (bool);
+    #[allow(missing_docs)]
+    function allowance(address owner, address spender) external view returns (uint256);
+    #[allow(missing_docs)]
+    function balanceOf(address account) external view returns (uint256);
+}
+
+// Storage slots probed when looking for a token's balance/allowance mapping.
+const MAX_PROBED_SLOT: u64 = 20;
+
+pub struct SimulateSwapPlanTool;
+
+/// One side of the swap: the ERC20 traded, and whether the user holds it as native ETH.
+struct Leg {
+    token: Address,
+    native: bool,
+    decimals: u8,
+    /// How a symbol argument was resolved; `null` for addresses and native ETH.
+    resolution: Value,
+}
+
+#[async_trait::async_trait]
+impl Tool for SimulateSwapPlanTool {
+    fn name(&self) -> &'static str {
+        "simulate_swap_plan"
+    }
+
+    fn large_fields(&self) -> &'static [&'static str] {
+        &["/transactions/*/data"]
+    }
+
+    fn description(&self) -> &'static str {
+        "End-to-end dry run of a swap for the signer: balance check, approval, Uniswap V3 quote, router eth_call (wrapping ETH in and unwrapping WETH out as needed), with a per-step report and the net result."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "from_token": {
+                    "type": "string",
+                    "description": "Token to sell: address, registry symbol, or \"ETH\" for native ETH"
+                },
+                "to_token": {
+                    "type": "string",
+                    "description": "Token to buy: address, registry symbol, or \"ETH\" to unwrap to native ETH"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Amount of from_token to sell (in base units)"
+                },
+                "fee": {
+                    "type": "integer",
+                    "description": "Pool fee tier (e.g., 500, 3000, 10000). Default 3000."
+                },
+                "slippage_tolerance": {
+                    "type": "number",
+                    "description": "Slippage tolerance in percentage, from 0 to 100. Default 0.5."
+                }
+            },
+            "required": ["from_token", "to_token", "amount"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let amount_in = U256::from_str(
+            args["amount"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("amount"))?,
+        )?;
+        let (from, to) = futures::try_join!(
+            resolve_leg(client, &args, "from_token"),
+            resolve_leg(client, &args, "to_token")
+        )?;
+        if from.token == to.token {
+            return Err(anyhow::anyhow!(
+                "from_token and to_token are the same asset; use wrap/unwrap instead of a swap"
+            ));
+        }
+        let fee = fee_arg(&args)?.unwrap_or(3000);
+        let slippage_percent = slippage_arg(&args)?;
+        let signer = client.require_signer()?;
+        let uniswap = client.uniswap_v3()?;
+        let router = uniswap.swap_router;
+
+        let mut steps = Vec::new();
+        let mut transactions = Vec::new();
+        // Simulation-only state: whatever balance/allowance the real wallet is missing.
+        let mut overrides = StateOverride::default();
+
+        // 1. Funds
+        let balance = if from.native {
+            client.provider.get_balance(signer).await?
+        } else {
+            read_u256(
+                client,
+                from.token,
+                balanceOfCall { account: signer }.abi_encode(),
+                None,
+            )
+            .await?
+        };
+        let funded = balance >= amount_in;
+        let mut simulated_funds = false;
+        if !funded {
+            if from.native {
+                overrides.insert(
+                    signer,
+                    AccountOverride {
+                        balance: Some(amount_in),
+                        ..Default::default()
+                    },
+                );
+                simulated_funds = true;
+            } else if let Some(slot) = find_mapping_slot(client, from.token, signer, None).await {
+                add_slot_override(&mut overrides, from.token, slot, amount_in);
+                simulated_funds = true;
+            }
+        }
+        steps.push(json!({
+            "step": "balance",
+            "status": if funded { "ok" } else { "failed" },
+            "balance": balance.to_string(),
+            "required": amount_in.to_string(),
+            "message": if funded {
+                None
+            } else if simulated_funds {
+                Some("Insufficient balance; later steps are simulated as if it were funded")
+            } else {
+                Some("Insufficient balance, and the token's balance slot could not be overridden; later steps will revert")
+            }
+        }));
+
+        // 2. Approval (ERC20 input only)
+        let mut approval_ok = true;
+        if !from.native {
+            let allowance = read_u256(
+                client,
+                from.token,
+                allowanceCall {
+                    owner: signer,
+                    spender: router,
+                }
+                .abi_encode(),
+                None,
+            )
+            .await?;
+            if allowance >= amount_in {
+                steps.push(json!({
+                    "step": "approve",
+                    "status": "ok",
+                    "allowance": allowance.to_string(),
+                    "message": "Router already approved"
+                }));
+            } else {
+                let approve_data = approveCall {
+                    spender: router,
+                    amount: amount_in,
+                }
+                .abi_encode();
+                let approve_req = TransactionRequest::default()
+                    .from(signer)
+                    .to(from.token)
+                    .input(approve_data.clone().into());
+                let approve_result = client.provider.call(&approve_req).await;
+                approval_ok = approve_result.is_ok();
+                let overridden =
+                    match find_mapping_slot(client, from.token, signer, Some(router)).await {
+                        Some(slot) => {
+                            add_slot_override(&mut overrides, from.token, slot, amount_in);
+                            true
+                        }
+                        None => false,
+                    };
+                steps.push(json!({
+                    "step": "approve",
+                    "status": if approval_ok { "action_required" } else { "failed" },
+                    "allowance": allowance.to_string(),
+                    "error": approve_result.err().map(|e| describe_call_error(&e)),
+                    "message": if overridden {
+                        "approve(router, amount) must be sent first; the swap below is simulated with it in place"
+                    } else {
+                        "approve(router, amount) must be sent first; its storage slot could not be overridden, so the swap below runs without it"
+                    }
+                }));
+                if approval_ok {
+                    transactions.push(json!({
+                        "to": from.token.to_string(),
+                        "data": format!("0x{}", hex::encode(&approve_data)),
+                        "value": "0",
+                        "description": "ERC20.approve(SwapRouter, amount)"
+                    }));
+                }
+            }
+        }
+
+        // 3. Quote
+        let quote = quoter::quote_single(
+            client,
+            SwapMode::ExactIn,
+            from.token,
+            to.token,
+            fee,
+            amount_in,
+        )
+        .await;
+        let (quoted_out, gas_estimate) = match quote {
+            Ok(q) => {
+                steps.push(json!({
+                    "step": "quote",
+                    "status": "ok",
+                    "amount_out": q.amount_out.to_string(),
+                    "gas_estimate": q.gas_estimate.to_string()
+                }));
+                (q.amount_out, q.gas_estimate)
+            }
+            Err(e) => {
+                steps.push(json!({
+                    "step": "quote",
+                    "status": "failed",
+                    "error": e.to_string(),
+                    "message": format!("No usable pool at fee tier {}", fee)
+                }));
+                return Ok(json!({
+                    "would_succeed": false,
+                    "steps": steps,
+                    "transactions": transactions,
+                    "net_result": Value::Null
+                }));
+            }
+        };
+        let (minimum_out, _) =
+            slippage_bounds(SwapMode::ExactIn, amount_in, quoted_out, slippage_percent)?;
+
+        // 4. Swap (and unwrap), as one router transaction from the signer
+        let (deadline, _) = swap_deadline(client, DEFAULT_DEADLINE_SECONDS).await;
+        let swap_call = exact_input_single_calldata(
+            &uniswap,
+            ExactInputSingleParams {
+                tokenIn: from.token,
+                tokenOut: to.token,
+                fee,
+                // When unwrapping, the router keeps the WETH until unwrapWETH9 pays it out.
+                recipient: if to.native { router } else { signer },
+                deadline: U256::from(deadline),
+                amountIn: amount_in,
+                amountOutMinimum: minimum_out,
+                sqrtPriceLimitX96: U256::ZERO,
+            },
+        );
+        let swap_data = if to.native {
+            with_unwrap(swap_call, minimum_out, signer)
+        } else {
+            swap_call
+        };
+        let value = if from.native { amount_in } else { U256::ZERO };
+        let swap_req = TransactionRequest::default()
+            .from(signer)
+            .to(router)
+            .value(value)
+            .input(swap_data.clone().into());
+        let swap_result = client.provider.call(&swap_req).overrides(&overrides).await;
+        let simulated_out = match &swap_result {
+            Ok(data) => Some(decode_amount_out(data, to.native)),
+            Err(_) => None,
+        };
+        let swap_ok = simulated_out.is_some();
+        if from.native {
+            steps.push(json!({
+                "step": "wrap",
+                "status": if swap_ok { "ok" } else { "failed" },
+                "message": "Native ETH is sent as msg.value; the router wraps it to WETH within the swap"
+            }));
+        }
+        steps.push(json!({
+            "step": "swap",
+            "status": if swap_ok { "ok" } else { "failed" },
+            "amount_out": simulated_out.flatten().map(|a| a.to_string()),
+            "minimum_out": minimum_out.to_string(),
+            "error": swap_result.as_ref().err().map(describe_call_error)
+        }));
+        if to.native {
+            steps.push(json!({
+                "step": "unwrap",
+                "status": if swap_ok { "ok" } else { "failed" },
+                "message": "unwrapWETH9 runs in the same router multicall as the swap"
+            }));
+        }
+        transactions.push(json!({
+            "to": router.to_string(),
+            "data": format!("0x{}", hex::encode(&swap_data)),
+            "value": value.to_string(),
+            "description": if to.native {
+                "SwapRouter.multicall(exactInputSingle, unwrapWETH9)"
+            } else {
+                "SwapRouter.exactInputSingle"
+            }
+        }));
+
+        let received = simulated_out.flatten().unwrap_or(quoted_out);
+        let spent_human = Decimal::from_str(&format_units(amount_in, from.decimals)?)?;
+        let received_human = Decimal::from_str(&format_units(received, to.decimals)?)?;
+        let would_succeed = funded && approval_ok && swap_ok;
+
+        Ok(json!({
+            "would_succeed": would_succeed,
+            "steps": steps,
+            "transactions": transactions,
+            "net_result": {
+                "spent": spent_human.normalize().to_string(),
+                "raw_spent": amount_in.to_string(),
+                "received": received_human.normalize().to_string(),
+                "raw_received": received.to_string(),
+                "minimum_received": format_units(minimum_out, to.decimals)?,
+                "rate": if spent_human.is_zero() { None } else { Some((received_human / spent_human).round_dp(8)) },
+                "swap_gas_estimate": gas_estimate.to_string()
+            },
+            "token_resolution": {
+                "from_token": from.resolution,
+                "to_token": to.resolution
+            },
+            "note": "Simulated with eth_call against the latest block; approvals and missing funds are overridden only inside the simulation. Send the transactions in order."
+        }))
+    }
+}
+
+async fn resolve_leg(client: &EthereumClient, args: &Value, key: &str) -> Result<Leg> {
+    let raw = args[key].as_str().ok_or_else(|| ToolError::missing(key))?;
+    if raw.eq_ignore_ascii_case("ETH") {
+        return Ok(Leg {
+            token: client.chain()?.weth,
+            native: true,
+            decimals: 18,
+            resolution: Value::Null,
+        });
+    }
+    let (token, resolution) = resolve_token_with_decision(client, raw)?;
+    Ok(Leg {
+        token,
+        native: false,
+        decimals: ensure_erc20(client, token, key).await?,
+        resolution,
+    })
+}
+
+async fn read_u256(
+    client: &EthereumClient,
+    token: Address,
+    call_data: Vec<u8>,
+    overrides: Option<&StateOverride>,
+) -> Result<U256> {
+    let req = TransactionRequest::default()
+        .to(token)
+        .input(call_data.into());
+    let call = client.provider.call(&req);
+    let res = match overrides {
+        Some(overrides) => call.overrides(overrides).await?,
+        None => call.await?,
+    };
+    Ok(U256::abi_decode(&res, true)?)
+}
+
+/// Finds the storage slot holding `token`'s balance of `owner` (or, with a spender, the
+/// owner's allowance for it) by writing a marker into candidate slots and reading it back.
+/// Covers Solidity (`keccak(key . slot)`) and Vyper (`keccak(slot . key)`) mapping layouts.
+async fn find_mapping_slot(
+    client: &EthereumClient,
+    token: Address,
+    owner: Address,
+    spender: Option<Address>,
+) -> Option<B256> {
+    let marker = U256::from(0x5eed_f00du64) << 64;
+    let call_data = match spender {
+        Some(spender) => allowanceCall { owner, spender }.abi_encode(),
+        None => balanceOfCall { account: owner }.abi_encode(),
+    };
+    let candidates: Vec<B256> = (0..MAX_PROBED_SLOT)
+        .flat_map(|slot| {
+            let slot = U256::from(slot);
+            let solidity = match spender {
+                Some(spender) => {
+                    keccak256((spender, keccak256((owner, slot).abi_encode())).abi_encode())
+                }
+                None => keccak256((owner, slot).abi_encode()),
+            };
+            let vyper = match spender {
+                Some(spender) => {
+                    keccak256((keccak256((slot, owner).abi_encode()), spender).abi_encode())
+                }
+                None => keccak256((slot, owner).abi_encode()),
+            };
+            [solidity, vyper]
+        })
+        .collect();
+    let probes = candidates.iter().map(|slot| {
+        let mut overrides = StateOverride::default();
+        add_slot_override(&mut overrides, token, *slot, marker);
+        let call_data = call_data.clone();
+        async move {
+            read_u256(client, token, call_data, Some(&overrides))
+                .await
+                .ok()
+                == Some(marker)
+        }
+    });
+    let hits = join_all(probes).await;
+    candidates
+        .into_iter()
+        .zip(hits)
+        .find(|(_, hit)| *hit)
+        .map(|(slot, _)| slot)
+}
+
+fn add_slot_override(overrides: &mut StateOverride, account: Address, slot: B256, value: U256) {
+    overrides
+        .entry(account)
+        .or_default()
+        .state_diff
+        .get_or_insert_with(HashMap::new)
+        .insert(slot, B256::from(value));
+}
@@ -0,0 +1,169 @@
+use super::balance::format_units;
+use super::price::{feeCall, liquidityCall, slot0Call, token0Call, token1Call};
+use super::Tool;
+use crate::error::ToolError;
+use crate::ethereum::{call3, Call3Result, EthereumClient};
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    function feeGrowthGlobal0X128() external view returns (uint256);
+    #[allow(missing_docs)]
+    function feeGrowthGlobal1X128() external view returns (uint256);
+    #[allow(missing_docs)]
+    function protocolFees() external view returns (uint128 token0, uint128 token1);
+    #[allow(missing_docs)]
+    function decimals() external view returns (uint8);
+}
+
+pub struct GetPoolFeesTool;
+
+#[async_trait::async_trait]
+impl Tool for GetPoolFeesTool {
+    fn name(&self) -> &'static str {
+        "get_pool_fees"
+    }
+
+    fn description(&self) -> &'static str {
+        "Read a Uniswap V3 pool's fee tier, protocol fee setting, uncollected protocol fees, and global fee growth per unit of liquidity."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pool_address": {
+                    "type": "string",
+                    "description": "Uniswap V3 pool address"
+                }
+            },
+            "required": ["pool_address"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let pool = Address::from_str(
+            args["pool_address"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("pool_address"))?,
+        )?;
+
+        let results = client
+            .multicall(vec![
+                call3(pool, slot0Call {}.abi_encode()),
+                call3(pool, feeCall {}.abi_encode()),
+                call3(pool, liquidityCall {}.abi_encode()),
+                call3(pool, token0Call {}.abi_encode()),
+                call3(pool, token1Call {}.abi_encode()),
+                call3(pool, feeGrowthGlobal0X128Call {}.abi_encode()),
+                call3(pool, feeGrowthGlobal1X128Call {}.abi_encode()),
+                call3(pool, protocolFeesCall {}.abi_encode()),
+            ])
+            .await?;
+        let not_a_pool = || {
+            anyhow::anyhow!(
+                "{} does not look like a Uniswap V3 pool (fee state unreadable)",
+                pool
+            )
+        };
+        let slot0 = decode::<slot0Call>(&results[0]).ok_or_else(not_a_pool)?;
+        let fee = decode::<feeCall>(&results[1]).ok_or_else(not_a_pool)?._0;
+        let liquidity = decode::<liquidityCall>(&results[2])
+            .ok_or_else(not_a_pool)?
+            ._0;
+        let token0 = decode::<token0Call>(&results[3]).ok_or_else(not_a_pool)?._0;
+        let token1 = decode::<token1Call>(&results[4]).ok_or_else(not_a_pool)?._0;
+        let growth0 = decode::<feeGrowthGlobal0X128Call>(&results[5])
+            .ok_or_else(not_a_pool)?
+            ._0;
+        let growth1 = decode::<feeGrowthGlobal1X128Call>(&results[6])
+            .ok_or_else(not_a_pool)?
+            ._0;
+        let protocol = decode::<protocolFeesCall>(&results[7]).ok_or_else(not_a_pool)?;
+
+        let decimals = client
+            .multicall(vec![
+                call3(token0, decimalsCall {}.abi_encode()),
+                call3(token1, decimalsCall {}.abi_encode()),
+            ])
+            .await?;
+        let decimals0 = decode::<decimalsCall>(&decimals[0]).map(|d| d._0);
+        let decimals1 = decode::<decimalsCall>(&decimals[1]).map(|d| d._0);
+
+        // feeProtocol packs two 4-bit denominators: the protocol takes 1/N of swap fees
+        // paid in each token, and 0 means off.
+        let protocol0 = slot0.feeProtocol % 16;
+        let protocol1 = slot0.feeProtocol >> 4;
+
+        Ok(json!({
+            "pool": pool.to_string(),
+            "token0": token0.to_string(),
+            "token1": token1.to_string(),
+            "fee_tier": fee,
+            "fee_percent": Decimal::new(i64::from(fee), 4).normalize(),
+            "liquidity": liquidity.to_string(),
+            "protocol_fee": {
+                "enabled": slot0.feeProtocol != 0,
+                "token0_share": protocol_share(protocol0),
+                "token1_share": protocol_share(protocol1),
+                "raw_fee_protocol": slot0.feeProtocol
+            },
+            "uncollected_protocol_fees": {
+                "token0": token_amount(U256::from(protocol.token0), decimals0)?,
+                "token1": token_amount(U256::from(protocol.token1), decimals1)?
+            },
+            "fee_growth_global": {
+                "token0_x128": growth0.to_string(),
+                "token1_x128": growth1.to_string(),
+                "token0_per_unit_liquidity": q128_to_decimal_string(growth0),
+                "token1_per_unit_liquidity": q128_to_decimal_string(growth1)
+            },
+            "note": "Fee growth is cumulative LP fees per unit of liquidity since the pool was created, in raw token units. It only grows (modulo 2^256 overflow), so the difference between two reads over the same period compares pool activity; multiplying by a position's liquidity approximates its fees while that liquidity was in range."
+        }))
+    }
+}
+
+fn decode<C: SolCall>(result: &Call3Result) -> Option<C::Return> {
+    if !result.success {
+        return None;
+    }
+    C::abi_decode_returns(&result.returnData, true).ok()
+}
+
+fn protocol_share(denominator: u8) -> Value {
+    if denominator == 0 {
+        Value::Null
+    } else {
+        json!(format!("1/{}", denominator))
+    }
+}
+
+fn token_amount(raw: U256, decimals: Option<u8>) -> Result<Value> {
+    Ok(json!({
+        "raw": raw.to_string(),
+        "formatted": decimals.map(|d| format_units(raw, d)).transpose()?,
+        "decimals": decimals
+    }))
+}
+
+/// A Q128.128 value as a decimal string, with 18 fractional digits.
+fn q128_to_decimal_string(x: U256) -> String {
+    let whole: U256 = x >> 128usize;
+    let fraction: U256 =
+        ((x & U256::from(u128::MAX)) * U256::from(10u64).pow(U256::from(18))) >> 128usize;
+    let fraction = format!("{:0>18}", fraction.to_string());
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
@@ -1,7 +1,12 @@
-use super::Tool;
-use crate::ethereum::EthereumClient;
+use super::balance::{balanceOfCall, format_units};
+use super::swap::SwapMode;
+use super::{resolve_address, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{call3, decode_result, decode_return, EthereumClient};
+use crate::format::display_amount;
+use crate::{oracle, quoter};
 use alloy::{
-    primitives::{Address, I256},
+    primitives::{Address, U256},
     providers::Provider,
     sol,
     sol_types::SolCall,
@@ -11,14 +16,6 @@ use rust_decimal::Decimal;
 use serde_json::{json, Value};
 use std::str::FromStr;
 
-// Simplified Chainlink Aggregator Interface
-sol! {
-    #[allow(missing_docs)]
-    function latestAnswer() external view returns (int256);
-    #[allow(missing_docs)]
-    function decimals() external view returns (uint8); // Used for Chainlink + ERC20 decimal fetch
-}
-
 // Uniswap V3 Factory Interface
 sol! {
     #[allow(missing_docs)]
@@ -31,6 +28,54 @@ sol! {
     function slot0() external view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked);
     #[allow(missing_docs)]
     function token0() external view returns (address);
+    #[allow(missing_docs)]
+    function token1() external view returns (address);
+    #[allow(missing_docs)]
+    function fee() external view returns (uint24);
+    #[allow(missing_docs)]
+    function liquidity() external view returns (uint128);
+    #[allow(missing_docs)]
+    function observe(uint32[] secondsAgos) external view returns (int56[] tickCumulatives, uint160[] secondsPerLiquidityCumulativeX128s);
+}
+
+/// Uniswap V3 fee tiers, in hundredths of a basis point.
+pub const FEE_TIERS: [u32; 4] = [100, 500, 3000, 10000];
+
+/// Default `twap_window` for `price_source: "twap"`, in seconds.
+pub const DEFAULT_TWAP_WINDOW_SECS: u32 = 1800;
+
+/// Where a pool-derived price comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceSource {
+    /// `slot0`'s current sqrtPriceX96; movable within a single block.
+    Spot,
+    /// The pool oracle's time-weighted average tick over a window.
+    Twap { window: u32 },
+}
+
+impl PriceSource {
+    fn from_args(args: &Value) -> Result<Self> {
+        let window = u64_arg(args, "twap_window")?;
+        match args.get("price_source").and_then(|v| v.as_str()) {
+            None | Some("spot") if window.is_none() => Ok(Self::Spot),
+            None | Some("spot") => Err(ToolError::invalid_argument(
+                "twap_window requires price_source \"twap\"",
+            )),
+            Some("twap") => {
+                let window = window.unwrap_or(u64::from(DEFAULT_TWAP_WINDOW_SECS));
+                match u32::try_from(window) {
+                    Ok(window) if window > 0 => Ok(Self::Twap { window }),
+                    _ => Err(ToolError::invalid_argument(
+                        "twap_window must be between 1 and 4294967295 seconds",
+                    )),
+                }
+            }
+            Some(other) => Err(ToolError::invalid_argument(format!(
+                "Invalid price_source '{}': expected spot or twap",
+                other
+            ))),
+        }
+    }
 }
 
 pub struct GetTokenPriceTool;
@@ -42,7 +87,7 @@ impl Tool for GetTokenPriceTool {
     }
 
     fn description(&self) -> &'static str {
-        "Get the current price of a token in USD or ETH. Uses Chainlink for ETH/USD and Uniswap V3 for others."
+        "Get the current price of a token in USD or ETH. Uses Chainlink for ETH/USD and for tokens with their own Chainlink USD feed (e.g. LINK, UNI, AAVE), unless the feed is stale, and Uniswap V3 for others."
     }
 
     fn schema(&self) -> Value {
@@ -56,6 +101,31 @@ impl Tool for GetTokenPriceTool {
                 "token_address": {
                     "type": "string",
                     "description": "Address of the token (required for non-standard tokens)"
+                },
+                "include_mid_price": {
+                    "type": "boolean",
+                    "description": "Also return the tick-derived mid price and compare it with the executable price of a reference-size sell quoted on the same pool. Default false."
+                },
+                "reference_amount": {
+                    "type": "string",
+                    "description": "With include_mid_price: size of the reference sell in human units of the token. Default 1."
+                },
+                "pool_address": {
+                    "type": "string",
+                    "description": "Price from this pool (token against WETH or one of the chain's stablecoins) instead of picking the deepest pool across fee tiers. Must be the factory's pool for the pair."
+                },
+                "max_price_age_seconds": {
+                    "type": "integer",
+                    "description": "For tokens with their own Chainlink feed: oldest feed answer (by the latest block's timestamp) to use before falling back to Uniswap V3. Default 7200."
+                },
+                "price_source": {
+                    "type": "string",
+                    "enum": ["spot", "twap"],
+                    "description": "How the Uniswap V3 price is read: \"spot\" (default) from the pool's current slot0, or \"twap\" from the pool oracle's time-weighted average tick over twap_window, which a single block can't move. twap skips the token's own Chainlink feed."
+                },
+                "twap_window": {
+                    "type": "integer",
+                    "description": "With price_source \"twap\": averaging window in seconds. Default 1800."
                 }
             },
             "required": ["token_symbol"]
@@ -63,11 +133,26 @@ impl Tool for GetTokenPriceTool {
     }
 
     async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let mut result = self.price(client, args).await?;
+        for key in ["price_usd", "price_eth"] {
+            if let Some(amount) = result[key].as_str().map(display_amount) {
+                result[key] = json!(amount);
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl GetTokenPriceTool {
+    /// The `get_token_price` result with plain decimal prices, for tools that compute with
+    /// them; `call` renders them for display.
+    pub async fn price(&self, client: &EthereumClient, args: Value) -> Result<Value> {
         let symbol = args["token_symbol"]
             .as_str()
-            .unwrap_or("ETH")
+            .ok_or_else(|| ToolError::missing("token_symbol"))?
             .to_uppercase();
         let token_address_str = args.get("token_address").and_then(|v| v.as_str());
+        let price_source = PriceSource::from_args(&args)?;
 
         // 1. ETH Price via Chainlink
         if symbol == "ETH" {
@@ -81,144 +166,720 @@ impl Tool for GetTokenPriceTool {
         }
 
         // 2. Resolve Token Address
-        let token_address = if let Some(addr) = token_address_str {
-            Address::from_str(addr)?
+        let (token_address, symbol_resolution) = if let Some(addr) = token_address_str {
+            (resolve_address(client, addr).await?, Value::Null)
         } else {
-            // Common token mappings
-            match symbol.as_str() {
-                "USDC" => Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48")?,
-                "WETH" => Address::from_str("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2")?,
-                "WBTC" => Address::from_str("0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599")?,
-                _ => {
+            let resolution = client.tokens.resolve_symbol(&symbol)?.ok_or_else(|| {
+                anyhow::anyhow!("Unknown token symbol. Please provide token_address.")
+            })?;
+            (resolution.token.address, resolution.to_json())
+        };
+
+        // Trusted stablecoins are valued at their live peg rather than a pool price.
+        if let Some(peg) = oracle::stablecoin_peg(client, token_address).await? {
+            let eth_price_usd = self.get_eth_price_chainlink(client).await?;
+            return Ok(json!({
+                "symbol": symbol,
+                "price_eth": peg.price_usd / eth_price_usd,
+                "price_usd": peg.price_usd,
+                "source": "Stablecoin peg",
+                "stablecoin_peg": peg.to_json(),
+                "symbol_resolution": symbol_resolution
+            }));
+        }
+
+        // A token's own Chainlink feed beats a pool price, unless the caller asked about a
+        // pool or the answer is stale; the reason for skipping a feed is reported.
+        let pool_requested = args.get("pool_address").is_some()
+            || price_source != PriceSource::Spot
+            || args
+                .get("include_mid_price")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+        let mut oracle_error = Value::Null;
+        if !pool_requested {
+            let max_age = u64_arg(&args, "max_price_age_seconds")?
+                .unwrap_or(oracle::DEFAULT_MAX_FEED_AGE_SECS);
+            match oracle::token_feed_price(client, token_address, max_age).await {
+                Ok(Some(feed)) => {
+                    return Ok(json!({
+                        "symbol": symbol,
+                        "price_eth": feed.price_usd / feed.eth_price_usd,
+                        "price_usd": feed.price_usd,
+                        "source": "Chainlink Oracle",
+                        "oracle": feed.to_json(),
+                        "symbol_resolution": symbol_resolution
+                    }));
+                }
+                Ok(None) => {}
+                Err(e) => oracle_error = json!(e.to_string()),
+            }
+        }
+
+        // 3. Get Price via Uniswap V3 against WETH, or the chain's stablecoins if the token
+        // has no WETH pool.
+        let candidates = quote_tokens(client, token_address)?;
+        let mut fee_tiers = Value::Null;
+        let mut route_depths = Value::Null;
+        let (quote, pool_address, fee) = match args.get("pool_address").and_then(|v| v.as_str()) {
+            Some(pool) => {
+                let pool = Address::from_str(pool)?;
+                let (quote, pair) =
+                    verify_routed_pool(client, pool, token_address, &candidates).await?;
+                (quote, pool, pair.fee)
+            }
+            None => {
+                // Each quote token's deepest fee tier (by in-range liquidity) is its route;
+                // among routes, the pool holding the most of the token wins.
+                let pairs: Vec<(Address, Address)> = candidates
+                    .iter()
+                    .map(|q| (token_address, q.address))
+                    .collect();
+                let pools = liquid_pools(client, &pairs).await?;
+                let routes: Vec<(&QuoteToken, Vec<TierPool>)> = candidates
+                    .iter()
+                    .zip(pools)
+                    .filter(|(_, tiers)| !tiers.is_empty())
+                    .collect();
+                if routes.is_empty() {
                     return Err(anyhow::anyhow!(
-                        "Unknown token symbol. Please provide token_address."
-                    ))
+                        "No Uniswap V3 pool with liquidity found for {} against WETH or stablecoins at fee tiers {:?}",
+                        symbol,
+                        FEE_TIERS
+                    ));
                 }
+                let (best, depths) = deepest_route(client, token_address, &routes).await?;
+                route_depths = depths;
+                let (quote, tiers) = &routes[best];
+                fee_tiers = json!(tiers
+                    .iter()
+                    .map(|t| json!({
+                        "fee": t.fee,
+                        "pool": t.pool.to_string(),
+                        "liquidity": t.liquidity.to_string()
+                    }))
+                    .collect::<Vec<_>>());
+                (**quote, tiers[0].pool, tiers[0].fee)
             }
         };
 
-        // 3. Get Price via Uniswap V3 (Token/ETH or Token/USDC)
-        // Find pool against WETH.
-        let weth_address = Address::from_str("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2")?;
-        let factory_address = Address::from_str("0x1F98431c8aD98523631AE4a59f267346ea31F984")?; // Uniswap V3 Factory
-
-        // Try 0.3% fee tier (3000)
-        let fee = 3000;
-        let get_pool_data = getPoolCall {
-            tokenA: token_address,
-            tokenB: weth_address,
-            fee,
-        }
-        .abi_encode();
-        let tx_req = alloy::rpc::types::eth::TransactionRequest::default()
-            .to(factory_address)
-            .input(get_pool_data.into());
-
-        let pool_res = client.provider.call(&tx_req).await?;
-        let pool_return = getPoolCall::abi_decode_returns(&pool_res, true)?;
-        let pool_address: Address = pool_return.pool;
-
-        if pool_address == Address::ZERO {
-            return Err(anyhow::anyhow!(
-                "No Uniswap V3 pool found for {}/WETH (0.3%)",
-                symbol
+        // Pool state and the ETH/USD answer in one Multicall3 batch; decimals come from the
+        // client's cache and are only fetched (concurrently) on a miss.
+        let feed = oracle::eth_usd_feed(client)?;
+        let mut calls = vec![
+            call3(pool_address, slot0Call {}.abi_encode()),
+            call3(pool_address, liquidityCall {}.abi_encode()),
+            call3(pool_address, token0Call {}.abi_encode()),
+        ];
+        calls.extend(oracle::price_calls(feed));
+        if let PriceSource::Twap { window } = price_source {
+            calls.push(call3(
+                pool_address,
+                observeCall {
+                    secondsAgos: vec![window, 0],
+                }
+                .abi_encode(),
             ));
         }
+        let (results, token_decimals, quote_decimals, quote_peg) = futures::join!(
+            client.multicall(calls),
+            self.get_erc20_decimals(client, token_address),
+            self.get_erc20_decimals(client, quote.address),
+            oracle::stablecoin_peg(client, quote.address)
+        );
+        let (results, token_decimals, quote_decimals, quote_peg) =
+            (results?, token_decimals?, quote_decimals?, quote_peg?);
 
-        // Get slot0 (sqrtPriceX96)
-        let slot0_data = slot0Call {}.abi_encode();
-        let slot0_req = alloy::rpc::types::eth::TransactionRequest::default()
-            .to(pool_address)
-            .input(slot0_data.into());
-        let slot0_res = client.provider.call(&slot0_req).await?;
-        let slot0_return = slot0Call::abi_decode_returns(&slot0_res, true)?;
+        let slot0_return = decode_result::<slot0Call>(&results[0], pool_address)?;
         let sqrt_price_x96 = slot0_return.sqrtPriceX96;
 
+        // Refuse to derive a price from an uninitialized, empty, or mid-swap pool.
+        let liquidity = decode_result::<liquidityCall>(&results[1], pool_address)?._0;
+        check_pool_state(sqrt_price_x96, slot0_return.unlocked, liquidity)?;
+
         // Check token0 order to calculate price correctly
-        let token0_data = token0Call {}.abi_encode();
-        let token0_req = alloy::rpc::types::eth::TransactionRequest::default()
-            .to(pool_address)
-            .input(token0_data.into());
-        let token0_res = client.provider.call(&token0_req).await?;
-        let token0_return = token0Call::abi_decode_returns(&token0_res, true)?;
-        let token0: Address = token0_return._0;
-
-        // Fetch decimals for token and WETH to adjust the price correctly.
-        let token_decimals = self.get_erc20_decimals(client, token_address).await?;
-        let weth_decimals = self.get_erc20_decimals(client, weth_address).await?;
-
-        // price1 / price0 = (sqrtPriceX96 / 2^96)^2 * 10^(dec0 - dec1)
-        // Where token0/token1 follow the pool order.
-        // Avoid overflowing Decimal by dividing down by 2^96 in smaller steps (2^32 * 2^32 * 2^32).
-        let sqrt_price = Decimal::from_str(&sqrt_price_x96.to_string())?;
-        let q32 = Decimal::from(4_294_967_296u64); // 2^32 fits comfortably
-        let sqrt_ratio = sqrt_price / q32 / q32 / q32; // sqrtPriceX96 / 2^96
-        let mut price_ratio = sqrt_ratio * sqrt_ratio;
-
-        // Decimal adjustment for differing token decimals
-        let decimal_adjust = pow10_decimal(i32::from(token_decimals) - i32::from(weth_decimals))?;
-        price_ratio *= decimal_adjust;
-
-        let price_in_eth = if token0 == token_address {
-            // token0 = token, token1 = WETH -> price_ratio is WETH per token
-            price_ratio
-        } else {
-            // token0 = WETH, token1 = token -> invert
-            Decimal::ONE / price_ratio
+        let token0: Address = decode_result::<token0Call>(&results[2], pool_address)?._0;
+
+        let token_is_token0 = token0 == token_address;
+        let spot_in_quote = pool_price(
+            sqrt_price_x96,
+            token_is_token0,
+            token_decimals,
+            quote_decimals,
+        )?;
+        let (price_in_quote, twap_tick) = match price_source {
+            PriceSource::Spot => (spot_in_quote, None),
+            PriceSource::Twap { window } => {
+                let observed = decode_result::<observeCall>(&results[5], pool_address)
+                    .map_err(|e| {
+                        anyhow::anyhow!(
+                            "Pool {} cannot serve a {}s TWAP ({}); get_pool_observations shows how far back its history reaches",
+                            pool_address,
+                            window,
+                            e
+                        )
+                    })?;
+                let tick = twap_tick(&observed.tickCumulatives, window)?;
+                let price = tick_price(tick)
+                    .and_then(|ratio| {
+                        oriented_price(ratio, token_is_token0, token_decimals, quote_decimals)
+                    })
+                    .ok_or_else(|| anyhow::anyhow!("TWAP price out of range"))?;
+                (price, Some(tick))
+            }
         };
 
-        let eth_price_usd = self.get_eth_price_chainlink(client).await?;
+        let eth_price_usd = oracle::price_from_results(feed, &results[3..])?;
+        // A stablecoin quote is converted at its live peg (or $1 if it isn't trusted).
+        let quote_in_eth = if quote.address == client.chain()?.weth {
+            Decimal::ONE
+        } else {
+            quote_peg.as_ref().map_or(Decimal::ONE, |p| p.price_usd) / eth_price_usd
+        };
+        let price_in_eth = price_in_quote * quote_in_eth;
         let price_usd = price_in_eth * eth_price_usd;
+        let spot_in_eth = spot_in_quote * quote_in_eth;
+        let twap = match (price_source, twap_tick) {
+            (PriceSource::Twap { window }, Some(tick)) => json!({
+                "window_seconds": window,
+                "average_tick": tick,
+                "spot_price_eth": spot_in_eth,
+                "spot_deviation_percent": if price_in_eth.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    ((spot_in_eth - price_in_eth) / price_in_eth * Decimal::from(100)).round_dp(4)
+                }
+            }),
+            _ => Value::Null,
+        };
+
+        let mid_price = if args
+            .get("include_mid_price")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            let reference_amount = match args.get("reference_amount").and_then(|v| v.as_str()) {
+                Some(a) => Decimal::from_str(a)?,
+                None => Decimal::ONE,
+            };
+            let tick_price_eth = tick_price(slot0_return.tick)
+                .and_then(|ratio| {
+                    oriented_price(ratio, token_is_token0, token_decimals, quote_decimals)
+                })
+                .and_then(|price| price.checked_mul(quote_in_eth));
+            let reference = reference_quote(
+                client,
+                token_address,
+                fee,
+                reference_amount,
+                token_decimals,
+                (quote.address, quote_decimals, quote_in_eth),
+                spot_in_eth,
+            )
+            .await;
+            json!({
+                "tick": slot0_return.tick,
+                "tick_price_eth": tick_price_eth,
+                "spot_price_eth": spot_in_eth,
+                "reference": reference.unwrap_or_else(|e| json!({ "error": e.to_string() })),
+                "note": "spot_price_eth (from sqrtPriceX96) is the price for an infinitesimal trade. tick_price_eth is 1.0001^tick, the lower edge of the current tick, within 0.01% of spot. The reference sell's shortfall includes the pool fee."
+            })
+        } else {
+            Value::Null
+        };
 
         Ok(json!({
             "symbol": symbol,
             "price_eth": price_in_eth,
             "price_usd": price_usd,
-            "source": "Uniswap V3 (Derived from ETH pair)",
+            "source": format!("Uniswap V3 (Derived from {} pair)", quote.symbol),
+            "price_source": match price_source {
+                PriceSource::Spot => "spot",
+                PriceSource::Twap { .. } => "twap",
+            },
+            "twap": twap,
             "pool_fee": fee,
-            "pool": pool_address
+            "pool": pool_address,
+            "routing": {
+                "quote_token": quote.symbol,
+                "quote_address": quote.address.to_string(),
+                "bridged": quote.bridged,
+                "quote_price_usd": quote_in_eth * eth_price_usd,
+                "candidates": candidates.iter().map(|c| c.symbol).collect::<Vec<_>>(),
+                "fee_tiers": fee_tiers,
+                "routes": route_depths
+            },
+            "mid_price": mid_price,
+            "oracle_error": oracle_error,
+            "symbol_resolution": symbol_resolution
         }))
     }
 }
 
 impl GetTokenPriceTool {
     async fn get_eth_price_chainlink(&self, client: &EthereumClient) -> Result<Decimal> {
-        let price_feed_address = Address::from_str("0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419")?; // Mainnet ETH/USD
+        oracle::latest_price(client, oracle::eth_usd_feed(client)?).await
+    }
+
+    async fn get_erc20_decimals(&self, client: &EthereumClient, token: Address) -> Result<u8> {
+        Ok(client.get_token_metadata(token).await?.decimals)
+    }
+}
+
+/// `1.0001^tick`: the raw token1/token0 price at the lower edge of `tick`. `None` if it
+/// does not fit a `Decimal` (only for extreme ticks).
+pub fn tick_price(tick: i32) -> Option<Decimal> {
+    let base = Decimal::new(10001, 4);
+    let mut result = Decimal::ONE;
+    let mut power = base;
+    let mut exp = tick.unsigned_abs();
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.checked_mul(power)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            power = power.checked_mul(power)?;
+        }
+    }
+    if tick < 0 {
+        Decimal::ONE.checked_div(result)
+    } else {
+        Some(result)
+    }
+}
+
+/// The arithmetic mean tick over `window` seconds from `observe([window, 0])`'s tick
+/// cumulatives, rounded toward negative infinity as Uniswap's `OracleLibrary.consult` does.
+pub fn twap_tick<T: Copy + TryInto<i64>>(tick_cumulatives: &[T], window: u32) -> Result<i32> {
+    let [start, end] = tick_cumulatives else {
+        return Err(anyhow::anyhow!(
+            "observe returned {} tick cumulatives, expected 2",
+            tick_cumulatives.len()
+        ));
+    };
+    let cumulative = |c: T| {
+        c.try_into()
+            .map_err(|_| anyhow::anyhow!("Tick cumulative out of range"))
+    };
+    let delta = cumulative(*end)? - cumulative(*start)?;
+    let window = i64::from(window);
+    let mut tick = delta / window;
+    if delta < 0 && delta % window != 0 {
+        tick -= 1;
+    }
+    i32::try_from(tick).map_err(|_| anyhow::anyhow!("TWAP tick {} out of range", tick))
+}
 
-        let call_data = latestAnswerCall {}.abi_encode();
-        let tx_req = alloy::rpc::types::eth::TransactionRequest::default()
-            .to(price_feed_address)
-            .input(call_data.into());
+/// Price of a pool's token in units of the other token, from `sqrtPriceX96`.
+pub fn pool_price(
+    sqrt_price_x96: U256,
+    token_is_token0: bool,
+    token_decimals: u8,
+    quote_decimals: u8,
+) -> Result<Decimal> {
+    let sqrt_ratio = match Decimal::from_str(&sqrt_price_x96.to_string()) {
+        // Avoid overflowing Decimal by dividing down by 2^96 in smaller steps (2^32 * 2^32 * 2^32).
+        Ok(sqrt_price) => {
+            let q32 = Decimal::from(4_294_967_296u64); // 2^32 fits comfortably
+            sqrt_price / q32 / q32 / q32
+        }
+        // Beyond Decimal's range (e.g. USDC/WETH, where token0 has far fewer decimals): split
+        // into whole and fractional parts of 2^96 in U256 first.
+        Err(_) => {
+            let fraction_mask = (U256::from(1) << 96usize) - U256::from(1);
+            let whole = Decimal::from_str(&(sqrt_price_x96 >> 96usize).to_string())?;
+            let mut fraction = Decimal::from_str(
+                &(((sqrt_price_x96 & fraction_mask) * U256::from(10).pow(U256::from(28)))
+                    >> 96usize)
+                    .to_string(),
+            )?;
+            fraction.set_scale(28)?;
+            whole
+                .checked_add(fraction)
+                .ok_or_else(|| anyhow::anyhow!("Pool price out of range"))?
+                .normalize()
+        }
+    };
+    oriented_price(
+        sqrt_ratio
+            .checked_mul(sqrt_ratio)
+            .ok_or_else(|| anyhow::anyhow!("Pool price out of range"))?,
+        token_is_token0,
+        token_decimals,
+        quote_decimals,
+    )
+    .ok_or_else(|| anyhow::anyhow!("Pool price out of range"))
+}
 
-        let result = client.provider.call(&tx_req).await?;
-        let price_raw: I256 = latestAnswerCall::abi_decode_returns(&result, true)?._0;
+/// Turns a raw token1/token0 ratio into the price of the token in quote units:
+/// token1 per token0 in human units is `raw * 10^(dec0 - dec1)`, inverted when the token
+/// is token1.
+pub fn oriented_price(
+    raw_ratio: Decimal,
+    token_is_token0: bool,
+    token_decimals: u8,
+    quote_decimals: u8,
+) -> Option<Decimal> {
+    let (dec0, dec1) = if token_is_token0 {
+        (token_decimals, quote_decimals)
+    } else {
+        (quote_decimals, token_decimals)
+    };
+    let ratio = raw_ratio.checked_mul(pow10_decimal(i32::from(dec0) - i32::from(dec1)).ok()?)?;
+    if token_is_token0 {
+        Some(ratio)
+    } else {
+        Decimal::ONE.checked_div(ratio)
+    }
+}
 
-        let decimals_data = decimalsCall {}.abi_encode();
-        let decimals_req = alloy::rpc::types::eth::TransactionRequest::default()
-            .to(price_feed_address)
-            .input(decimals_data.into());
-        let decimals_res = client.provider.call(&decimals_req).await?;
-        let decimals: u8 = decimalsCall::abi_decode_returns(&decimals_res, true)?._0;
+/// Quotes selling `amount` (human units) of `token` into the quote token on the priced pool
+/// and compares the executable price, converted to ETH, with the spot mid price.
+async fn reference_quote(
+    client: &EthereumClient,
+    token: Address,
+    fee: u32,
+    amount: Decimal,
+    token_decimals: u8,
+    (quote, quote_decimals, quote_in_eth): (Address, u8, Decimal),
+    spot_price_eth: Decimal,
+) -> Result<Value> {
+    if amount <= Decimal::ZERO {
+        return Err(anyhow::anyhow!("reference_amount must be positive"));
+    }
+    let raw_in = U256::from_str(
+        &(amount * pow10_decimal(i32::from(token_decimals))?)
+            .trunc()
+            .to_string(),
+    )?;
+    let out = quoter::quote_single(client, SwapMode::ExactIn, token, quote, fee, raw_in)
+        .await?
+        .amount_out;
+    let out_eth = Decimal::from_str(&format_units(out, quote_decimals)?)? * quote_in_eth;
+    let executable = out_eth / amount;
+    let shortfall = if spot_price_eth.is_zero() {
+        Decimal::ZERO
+    } else {
+        (spot_price_eth - executable) / spot_price_eth * Decimal::from(100)
+    };
+    Ok(json!({
+        "amount": amount.normalize().to_string(),
+        "quoted_out_eth": out_eth.normalize().to_string(),
+        "executable_price_eth": executable.round_dp(18),
+        "shortfall_vs_spot_percent": shortfall.round_dp(4),
+        "pool_fee_percent": Decimal::new(i64::from(fee), 4)
+    }))
+}
 
-        let price_decimal = Decimal::from_str(&price_raw.to_string())?;
-        let scale = pow10_decimal(decimals as i32)?;
-        let price_usd = price_decimal / scale;
+/// Builds `get_token_price` arguments from either a symbol or a token address.
+pub(crate) fn price_args_for(token: &str) -> Value {
+    if Address::from_str(token).is_ok() {
+        json!({ "token_symbol": token, "token_address": token })
+    } else {
+        json!({ "token_symbol": token })
+    }
+}
+
+/// Token pair and fee tier a pool reports about itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolPair {
+    pub token0: Address,
+    pub token1: Address,
+    pub fee: u32,
+}
 
-        Ok(price_usd)
+pub(crate) async fn factory_pool(
+    client: &EthereumClient,
+    token_a: Address,
+    token_b: Address,
+    fee: u32,
+) -> Result<Address> {
+    let data = getPoolCall {
+        tokenA: token_a,
+        tokenB: token_b,
+        fee,
     }
+    .abi_encode();
+    let factory = client.uniswap_v3()?.factory;
+    let req = alloy::rpc::types::eth::TransactionRequest::default()
+        .to(factory)
+        .input(data.into());
+    let res = client.provider.call(&req).await?;
+    Ok(decode_return::<getPoolCall>(&res, factory)?.pool)
+}
 
-    async fn get_erc20_decimals(&self, client: &EthereumClient, token: Address) -> Result<u8> {
-        let decimals_data = decimalsCall {}.abi_encode();
-        let decimals_req = alloy::rpc::types::eth::TransactionRequest::default()
-            .to(token)
-            .input(decimals_data.into());
-        let decimals_res = client.provider.call(&decimals_req).await?;
-        let decimals: u8 = decimalsCall::abi_decode_returns(&decimals_res, true)?._0;
-        Ok(decimals)
+/// A token prices are quoted against: WETH or one of the chain's stablecoins.
+#[derive(Debug, Clone, Copy)]
+pub struct QuoteToken {
+    pub symbol: &'static str,
+    pub address: Address,
+    pub bridged: bool,
+}
+
+/// WETH, then the chain's stablecoins in preference order, minus `token` itself.
+pub fn quote_tokens(client: &EthereumClient, token: Address) -> Result<Vec<QuoteToken>> {
+    let chain = client.chain()?;
+    let weth = QuoteToken {
+        symbol: "WETH",
+        address: chain.weth,
+        bridged: false,
+    };
+    Ok(std::iter::once(weth)
+        .chain(chain.stablecoins.iter().map(|s| QuoteToken {
+            symbol: s.symbol,
+            address: s.address,
+            bridged: s.bridged,
+        }))
+        .filter(|q| q.address != token)
+        .collect())
+}
+
+/// A pair's pool at one fee tier.
+#[derive(Debug, Clone, Copy)]
+pub struct TierPool {
+    pub fee: u32,
+    pub pool: Address,
+    pub liquidity: u128,
+}
+
+/// For each of `pairs`, its pools across `FEE_TIERS` that pass `check_pool_state`, deepest
+/// in-range liquidity first. The factory lookups take one Multicall3 batch and the pools'
+/// `slot0`/`liquidity` another.
+pub async fn liquid_pools(
+    client: &EthereumClient,
+    pairs: &[(Address, Address)],
+) -> Result<Vec<Vec<TierPool>>> {
+    let factory = client.uniswap_v3()?.factory;
+    let keys: Vec<(usize, u32)> = (0..pairs.len())
+        .flat_map(|i| FEE_TIERS.iter().map(move |fee| (i, *fee)))
+        .collect();
+    let lookups = keys
+        .iter()
+        .map(|(i, fee)| {
+            call3(
+                factory,
+                getPoolCall {
+                    tokenA: pairs[*i].0,
+                    tokenB: pairs[*i].1,
+                    fee: *fee,
+                }
+                .abi_encode(),
+            )
+        })
+        .collect();
+    let mut found = Vec::new();
+    for ((i, fee), result) in keys.into_iter().zip(client.multicall(lookups).await?) {
+        let pool = decode_result::<getPoolCall>(&result, factory)?.pool;
+        if pool != Address::ZERO {
+            found.push((i, fee, pool));
+        }
+    }
+
+    let state = client
+        .multicall(
+            found
+                .iter()
+                .flat_map(|(_, _, pool)| {
+                    [
+                        call3(*pool, slot0Call {}.abi_encode()),
+                        call3(*pool, liquidityCall {}.abi_encode()),
+                    ]
+                })
+                .collect(),
+        )
+        .await?;
+    let mut pools = vec![Vec::new(); pairs.len()];
+    for (n, (i, fee, pool)) in found.into_iter().enumerate() {
+        let (Ok(slot0), Ok(liquidity)) = (
+            decode_result::<slot0Call>(&state[2 * n], pool),
+            decode_result::<liquidityCall>(&state[2 * n + 1], pool),
+        ) else {
+            continue;
+        };
+        if check_pool_state(slot0.sqrtPriceX96, slot0.unlocked, liquidity._0).is_ok() {
+            pools[i].push(TierPool {
+                fee,
+                pool,
+                liquidity: liquidity._0,
+            });
+        }
+    }
+    for tiers in &mut pools {
+        tiers.sort_by_key(|t| std::cmp::Reverse(t.liquidity));
+    }
+    Ok(pools)
+}
+
+/// Index of the route whose pool holds the most `token`, and each route's depth for the
+/// response. In-range liquidity isn't comparable across pairs, but the token balance is in
+/// the same units for every route. A single route needs no reads; ties keep the earlier
+/// (WETH-first) route.
+async fn deepest_route(
+    client: &EthereumClient,
+    token: Address,
+    routes: &[(&QuoteToken, Vec<TierPool>)],
+) -> Result<(usize, Value)> {
+    let balances: Vec<Option<U256>> = if routes.len() == 1 {
+        vec![None]
+    } else {
+        client
+            .multicall(
+                routes
+                    .iter()
+                    .map(|(_, tiers)| {
+                        call3(
+                            token,
+                            balanceOfCall {
+                                account: tiers[0].pool,
+                            }
+                            .abi_encode(),
+                        )
+                    })
+                    .collect(),
+            )
+            .await?
+            .iter()
+            .map(|r| decode_result::<balanceOfCall>(r, token).ok().map(|b| b._0))
+            .collect()
+    };
+    let mut best = 0;
+    for (i, balance) in balances.iter().enumerate() {
+        if balance.unwrap_or_default() > balances[best].unwrap_or_default() {
+            best = i;
+        }
+    }
+    let depths = routes
+        .iter()
+        .zip(&balances)
+        .map(|((quote, tiers), balance)| {
+            json!({
+                "quote_token": quote.symbol,
+                "pool": tiers[0].pool.to_string(),
+                "fee": tiers[0].fee,
+                "token_balance": balance.map(|b| b.to_string())
+            })
+        })
+        .collect();
+    Ok((best, depths))
+}
+
+/// Verifies a caller-supplied pool pairs `token` with one of the candidates and returns that
+/// candidate.
+async fn verify_routed_pool(
+    client: &EthereumClient,
+    pool: Address,
+    token: Address,
+    candidates: &[QuoteToken],
+) -> Result<(QuoteToken, PoolPair)> {
+    let results = client
+        .multicall(vec![
+            call3(pool, token0Call {}.abi_encode()),
+            call3(pool, token1Call {}.abi_encode()),
+        ])
+        .await?;
+    let not_a_pool = || anyhow::anyhow!("{} does not look like a Uniswap V3 pool", pool);
+    let token0 = decode_result::<token0Call>(&results[0], pool)
+        .map_err(|_| not_a_pool())?
+        ._0;
+    let token1 = decode_result::<token1Call>(&results[1], pool)
+        .map_err(|_| not_a_pool())?
+        ._0;
+    let counterpart = if token0 == token { token1 } else { token0 };
+    let quote = candidates
+        .iter()
+        .find(|q| q.address == counterpart)
+        .copied()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Pool {} does not pair the token with WETH or a known stablecoin",
+                pool
+            )
+        })?;
+    let pair = verify_pool(client, pool, token, quote.address).await?;
+    Ok((quote, pair))
+}
+
+/// Reads `token0`/`token1`/`fee` from a caller-supplied pool and checks that it trades
+/// exactly `token_a`/`token_b` (in either order) and that the factory maps that pair and
+/// fee back to it, so a look-alike contract can't stand in for a Uniswap pool.
+pub async fn verify_pool(
+    client: &EthereumClient,
+    pool: Address,
+    token_a: Address,
+    token_b: Address,
+) -> Result<PoolPair> {
+    let read = |data: Vec<u8>| {
+        let req = alloy::rpc::types::eth::TransactionRequest::default()
+            .to(pool)
+            .input(data.into());
+        async move { client.provider.call(&req).await }
+    };
+    let (token0, token1, fee) = futures::join!(
+        read(token0Call {}.abi_encode()),
+        read(token1Call {}.abi_encode()),
+        read(feeCall {}.abi_encode())
+    );
+    let not_a_pool = || anyhow::anyhow!("{} does not look like a Uniswap V3 pool", pool);
+    let pair = PoolPair {
+        token0: token0Call::abi_decode_returns(&token0?, true)
+            .map_err(|_| not_a_pool())?
+            ._0,
+        token1: token1Call::abi_decode_returns(&token1?, true)
+            .map_err(|_| not_a_pool())?
+            ._0,
+        fee: feeCall::abi_decode_returns(&fee?, true)
+            .map_err(|_| not_a_pool())?
+            ._0,
+    };
+    let matches = (pair.token0 == token_a && pair.token1 == token_b)
+        || (pair.token0 == token_b && pair.token1 == token_a);
+    if !matches {
+        return Err(anyhow::anyhow!(
+            "Pool {} trades {}/{}, not {}/{}",
+            pool,
+            pair.token0,
+            pair.token1,
+            token_a,
+            token_b
+        ));
+    }
+    let canonical = factory_pool(client, pair.token0, pair.token1, pair.fee).await?;
+    if canonical != pool {
+        return Err(anyhow::anyhow!(
+            "Pool {} is not the Uniswap V3 factory pool for this pair at fee {} (that is {})",
+            pool,
+            pair.fee,
+            canonical
+        ));
+    }
+    Ok(pair)
+}
+
+/// Validates that a pool's `slot0` and `liquidity()` describe a usable price.
+/// An initialized pool reports `unlocked = true` outside of a swap; `false` with a
+/// non-zero price means the read happened mid-reentrancy and may be inconsistent.
+pub fn check_pool_state(sqrt_price_x96: U256, unlocked: bool, liquidity: u128) -> Result<()> {
+    if sqrt_price_x96.is_zero() {
+        return Err(anyhow::anyhow!(
+            "Pool not yet initialized (sqrtPriceX96 is zero)"
+        ));
+    }
+    if !unlocked {
+        return Err(anyhow::anyhow!(
+            "Pool is locked (slot0.unlocked = false); price may be inconsistent mid-swap"
+        ));
+    }
+    if liquidity == 0 {
+        return Err(anyhow::anyhow!(
+            "Pool has no in-range liquidity; spot price is not meaningful"
+        ));
     }
+    Ok(())
 }
 
-fn pow10_decimal(exp: i32) -> Result<Decimal> {
+/// `10^exp` as a `Decimal`; negative exponents give the reciprocal.
+pub fn pow10_decimal(exp: i32) -> Result<Decimal> {
     if exp == 0 {
         return Ok(Decimal::ONE);
     }
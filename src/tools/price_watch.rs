@@ -0,0 +1,349 @@
+use super::price::{price_args_for, GetTokenPriceTool};
+use super::{report_progress, session_id, u64_arg, Tool};
+use crate::chains;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use crate::format;
+use alloy::{primitives::keccak256, providers::Provider};
+use anyhow::Result;
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+use tracing::{debug, warn};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const MAX_TIMEOUT_SECS: u64 = 3600;
+const DEFAULT_POLL_SECS: u64 = 3;
+
+/// Running watches by session and id, so `unwatch_price` can stop one from another request
+/// of the same session, and only from that session.
+static WATCHES: OnceLock<DashMap<WatchKey, Arc<Notify>>> = OnceLock::new();
+static WATCH_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// The calling session's id (empty outside the server) and the watch id.
+type WatchKey = (String, String);
+
+fn watches() -> &'static DashMap<WatchKey, Arc<Notify>> {
+    WATCHES.get_or_init(DashMap::new)
+}
+
+fn watch_key(watch_id: &str) -> WatchKey {
+    (session_id().unwrap_or_default(), watch_id.to_string())
+}
+
+/// Drops the watch from the registry however the call ends.
+struct Registration(WatchKey);
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        watches().remove(&self.0);
+    }
+}
+
+pub struct WatchPriceTool;
+
+#[async_trait::async_trait]
+impl Tool for WatchPriceTool {
+    fn name(&self) -> &'static str {
+        "watch_price"
+    }
+
+    fn description(&self) -> &'static str {
+        "Watch a token's USD price block by block and alert when it crosses an above/below threshold. Alerts are sent as progress notifications while the call runs (pass a progressToken) and returned at the end. Stops at the first alert unless repeat is set; stop it early with unwatch_price."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token": {
+                    "type": "string",
+                    "description": "Token symbol (e.g. ETH, USDC) or address, priced as in get_token_price"
+                },
+                "above": {
+                    "type": "string",
+                    "description": "Alert when the USD price rises to or above this level"
+                },
+                "below": {
+                    "type": "string",
+                    "description": "Alert when the USD price falls to or below this level"
+                },
+                "repeat": {
+                    "type": "boolean",
+                    "description": "Keep watching after an alert; a level re-arms once the price moves back across it. Default false."
+                },
+                "timeout_seconds": {
+                    "type": "integer",
+                    "description": "Stop watching after this long (default 300, at most 3600)."
+                },
+                "poll_interval_seconds": {
+                    "type": "integer",
+                    "description": "Delay between new-block checks. Defaults to the chain's block time, between 1 and 3 seconds."
+                },
+                "watch_id": {
+                    "type": "string",
+                    "description": "Id to register the watch under, for unwatch_price. Generated when omitted and sent as the first progress event."
+                }
+            },
+            "required": ["token"]
+        })
+    }
+
+    fn wait_budget(&self, args: &Value) -> Option<Duration> {
+        let secs = u64_arg(args, "timeout_seconds").ok().flatten();
+        Some(Duration::from_secs(
+            secs.unwrap_or(DEFAULT_TIMEOUT_SECS).min(MAX_TIMEOUT_SECS),
+        ))
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let token = args["token"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("token"))?;
+        let above = decimal_arg(&args, "above")?;
+        let below = decimal_arg(&args, "below")?;
+        if above.is_none() && below.is_none() {
+            return Err(anyhow::anyhow!("Pass at least one of above or below"));
+        }
+        if let (Some(above), Some(below)) = (above, below) {
+            if below >= above {
+                return Err(anyhow::anyhow!(
+                    "below ({}) must be less than above ({})",
+                    below,
+                    above
+                ));
+            }
+        }
+        let repeat = args
+            .get("repeat")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let timeout_secs = u64_arg(&args, "timeout_seconds")?.unwrap_or(DEFAULT_TIMEOUT_SECS);
+        if timeout_secs == 0 || timeout_secs > MAX_TIMEOUT_SECS {
+            return Err(anyhow::anyhow!(
+                "timeout_seconds must be between 1 and {}",
+                MAX_TIMEOUT_SECS
+            ));
+        }
+        let timeout = Duration::from_secs(timeout_secs);
+        let block_time = chains::block_time_seconds(client.chain_id());
+        let poll = match u64_arg(&args, "poll_interval_seconds")? {
+            Some(secs) => Duration::from_secs(secs.max(1)),
+            None => Duration::from_secs_f64(block_time.clamp(1.0, DEFAULT_POLL_SECS as f64)),
+        };
+
+        let watch_id = match args.get("watch_id").and_then(|v| v.as_str()) {
+            Some(id) => id.to_string(),
+            None => new_watch_id(),
+        };
+        let stop = Arc::new(Notify::new());
+        let key = watch_key(&watch_id);
+        match watches().entry(key.clone()) {
+            dashmap::mapref::entry::Entry::Occupied(_) => {
+                return Err(anyhow::anyhow!(
+                    "A watch with id {} is already running",
+                    watch_id
+                ))
+            }
+            dashmap::mapref::entry::Entry::Vacant(slot) => {
+                slot.insert(stop.clone());
+            }
+        }
+        let _registration = Registration(key);
+        report_progress(json!({ "event": "watching", "watch_id": watch_id, "token": token }));
+
+        // The RPC is plain HTTP, so new blocks are found by polling the block number; the
+        // price is re-read once per new block.
+        let price_args = price_args_for(token);
+        let mut levels: Vec<Level> = [("above", above), ("below", below)]
+            .into_iter()
+            .filter_map(|(side, threshold)| {
+                threshold.map(|threshold| Level {
+                    side,
+                    threshold,
+                    armed: true,
+                })
+            })
+            .collect();
+        let mut alerts: Vec<Value> = Vec::new();
+        let mut last_block = None;
+        let mut last_price = None;
+        let mut reads = 0u64;
+        let mut read_errors = 0u64;
+        let started = Instant::now();
+        let status = loop {
+            let block = client.provider.get_block_number().await?;
+            if last_block != Some(block) {
+                last_block = Some(block);
+                match read_price(client, &price_args).await {
+                    Ok(price) => {
+                        reads += 1;
+                        last_price = Some(price);
+                        for level in levels.iter_mut() {
+                            if !level.crossed(price) {
+                                continue;
+                            }
+                            let alert = json!({
+                                "event": "alert",
+                                "watch_id": watch_id,
+                                "token": token,
+                                "condition": level.side,
+                                "threshold": level.threshold.normalize().to_string(),
+                                "price_usd": price.normalize().to_string(),
+                                "block_number": block,
+                                "timestamp": format::timestamp(unix_now())
+                            });
+                            debug!("watch {}: {}", watch_id, alert);
+                            report_progress(alert.clone());
+                            alerts.push(alert);
+                        }
+                    }
+                    // The first read fails the call; later blips are counted and skipped.
+                    Err(e) if reads == 0 => return Err(e),
+                    Err(e) => {
+                        warn!("watch {}: price read failed: {}", watch_id, e);
+                        read_errors += 1;
+                    }
+                }
+                if !alerts.is_empty() && !repeat {
+                    break "triggered";
+                }
+            }
+            if started.elapsed() + poll > timeout {
+                break "timeout";
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(poll) => {}
+                _ = stop.notified() => break "unwatched",
+            }
+        };
+
+        Ok(json!({
+            "watch_id": watch_id,
+            "token": token,
+            "status": status,
+            "above": above.map(|a| a.normalize().to_string()),
+            "below": below.map(|b| b.normalize().to_string()),
+            "repeat": repeat,
+            "alerts": alerts,
+            "last_price_usd": last_price.map(|p| p.normalize().to_string()),
+            "last_block": last_block,
+            "price_reads": reads,
+            "read_errors": read_errors,
+            "watched_seconds": started.elapsed().as_secs(),
+            "note": "A level alerts when the price is at or beyond it, including on the first read, and re-arms (with repeat) once the price moves back. Blocks are found by polling, so one that arrives and is replaced within a poll interval can be skipped."
+        }))
+    }
+}
+
+pub struct UnwatchPriceTool;
+
+#[async_trait::async_trait]
+impl Tool for UnwatchPriceTool {
+    fn name(&self) -> &'static str {
+        "unwatch_price"
+    }
+
+    fn description(&self) -> &'static str {
+        "Stop a running watch_price call of this session by its watch_id. The watch then returns with status \"unwatched\" and the alerts seen so far."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "watch_id": {
+                    "type": "string",
+                    "description": "Id reported by watch_price (or passed to it)"
+                }
+            },
+            "required": ["watch_id"]
+        })
+    }
+
+    async fn call(&self, _client: &EthereumClient, args: Value) -> Result<Value> {
+        let watch_id = args["watch_id"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("watch_id"))?;
+        let stop = watches()
+            .get(&watch_key(watch_id))
+            .map(|entry| entry.clone())
+            .ok_or_else(|| {
+                anyhow::anyhow!("No running watch with id {} in this session", watch_id)
+            })?;
+        // A stored permit, so the stop is not lost if the watch is mid-read.
+        stop.notify_one();
+        Ok(json!({ "watch_id": watch_id, "stopped": true }))
+    }
+}
+
+/// One alert level and whether it can fire again.
+struct Level {
+    side: &'static str,
+    threshold: Decimal,
+    armed: bool,
+}
+
+impl Level {
+    /// Whether `price` fires this level: it must be at or beyond the threshold while armed.
+    /// A fired level stays disarmed until the price is back on the near side.
+    fn crossed(&mut self, price: Decimal) -> bool {
+        let beyond = match self.side {
+            "above" => price >= self.threshold,
+            _ => price <= self.threshold,
+        };
+        let fired = beyond && self.armed;
+        self.armed = !beyond;
+        fired
+    }
+}
+
+async fn read_price(client: &EthereumClient, price_args: &Value) -> Result<Decimal> {
+    let res = GetTokenPriceTool.price(client, price_args.clone()).await?;
+    let price = res["price_usd"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("get_token_price returned no price_usd"))?;
+    Ok(Decimal::from_str(price)?)
+}
+
+fn decimal_arg(args: &Value, key: &str) -> Result<Option<Decimal>> {
+    let parsed = match args.get(key) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(s)) => Decimal::from_str(s.trim()).ok(),
+        Some(Value::Number(n)) => Decimal::from_str(&n.to_string()).ok(),
+        Some(_) => None,
+    };
+    match parsed {
+        Some(value) if value > Decimal::ZERO => Ok(Some(value)),
+        _ => Err(anyhow::anyhow!(
+            "{} must be a positive number, got {}",
+            key,
+            args[key]
+        )),
+    }
+}
+
+fn new_watch_id() -> String {
+    let seed = format!(
+        "{}:{}",
+        unix_now_nanos(),
+        WATCH_SEQ.fetch_add(1, Ordering::Relaxed)
+    );
+    hex::encode(&keccak256(seed)[..6])
+}
+
+fn unix_now() -> u64 {
+    (unix_now_nanos() / 1_000_000_000) as u64
+}
+
+fn unix_now_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
@@ -0,0 +1,98 @@
+use super::price::{price_args_for, GetTokenPriceTool};
+use super::{check_batch_size, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use anyhow::Result;
+use futures::future::join_all;
+use serde_json::{json, Value};
+
+pub struct GetPricesTool;
+
+#[async_trait::async_trait]
+impl Tool for GetPricesTool {
+    fn name(&self) -> &'static str {
+        "get_token_prices"
+    }
+
+    fn description(&self) -> &'static str {
+        "Get USD/ETH prices for many tokens at once. Each token is priced independently; failures are reported per entry without failing the batch."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tokens": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Token symbols (e.g., ETH, USDC) or token addresses, at most MAX_BATCH_SIZE (default 100)"
+                },
+                "retries": {
+                    "type": "integer",
+                    "description": "Extra attempts for a token whose lookup fails. Default 1."
+                }
+            },
+            "required": ["tokens"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let tokens: Vec<String> = args["tokens"]
+            .as_array()
+            .ok_or_else(|| ToolError::missing("tokens"))?
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow::anyhow!("tokens must be an array of strings"))
+            })
+            .collect::<Result<_>>()?;
+        check_batch_size(client, tokens.len())?;
+        let retries = u64_arg(&args, "retries")?.unwrap_or(1);
+
+        // join_all preserves input order, so results line up with `tokens`.
+        let results = join_all(
+            tokens
+                .iter()
+                .map(|token| price_with_retry(client, token, retries)),
+        )
+        .await;
+
+        let succeeded = results.iter().filter(|r| r["status"] == "ok").count();
+        Ok(json!({
+            "prices": results,
+            "summary": {
+                "requested": tokens.len(),
+                "succeeded": succeeded,
+                "failed": tokens.len() - succeeded
+            }
+        }))
+    }
+}
+
+async fn price_with_retry(client: &EthereumClient, token: &str, retries: u64) -> Value {
+    let price_args = price_args_for(token);
+
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match GetTokenPriceTool.call(client, price_args.clone()).await {
+            Ok(price) => {
+                return json!({
+                    "token": token,
+                    "status": "ok",
+                    "price": price
+                })
+            }
+            Err(e) if attempts > retries => {
+                return json!({
+                    "token": token,
+                    "status": "error",
+                    "error": e.to_string(),
+                    "attempts": attempts
+                })
+            }
+            Err(_) => continue,
+        }
+    }
+}
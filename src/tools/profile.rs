@@ -0,0 +1,203 @@
+use super::balance::{decode_symbol, format_units};
+use super::convert::is_eth;
+use super::price::GetTokenPriceTool;
+use super::{check_batch_size, resolve_token, Tool};
+use crate::ethereum::{call3, getEthBalanceCall, Call3Result, EthereumClient, MULTICALL3_ADDRESS};
+use crate::format::display_amount;
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    function balanceOf(address account) external view returns (uint256);
+    #[allow(missing_docs)]
+    function decimals() external view returns (uint8);
+    #[allow(missing_docs)]
+    function symbol() external view returns (string);
+}
+
+pub struct AccountProfileTool;
+
+struct Holding {
+    symbol: String,
+    address: Option<Address>,
+    raw_balance: U256,
+    decimals: u8,
+}
+
+#[async_trait::async_trait]
+impl Tool for AccountProfileTool {
+    fn name(&self) -> &'static str {
+        "get_account_profile"
+    }
+
+    fn description(&self) -> &'static str {
+        "One-call wallet overview: ETH balance, nonce, contract check, watchlist token balances, and total USD value."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Address to profile. Defaults to the server's signer."
+                },
+                "tokens": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Watchlist of token symbols or addresses, at most MAX_BATCH_SIZE (default 100). Defaults to every token in the server's token registry."
+                }
+            }
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let address = match args.get("address").and_then(|v| v.as_str()) {
+            Some(addr) => Address::from_str(addr)?,
+            None => client.signer_address.ok_or_else(|| {
+                anyhow::anyhow!("Pass address: the server is read-only and has no wallet address")
+            })?,
+        };
+        let watchlist: Vec<Address> = match args.get("tokens").and_then(|v| v.as_array()) {
+            Some(tokens) => {
+                check_batch_size(client, tokens.len())?;
+                tokens
+                    .iter()
+                    .map(|t| {
+                        let t = t
+                            .as_str()
+                            .ok_or_else(|| anyhow::anyhow!("tokens must be an array of strings"))?;
+                        resolve_token(client, t)
+                    })
+                    .collect::<Result<_>>()?
+            }
+            None => client.tokens.tokens().iter().map(|t| t.address).collect(),
+        };
+
+        // One batch for the ETH balance plus balanceOf/decimals/symbol per watched token.
+        let mut calls = vec![call3(
+            MULTICALL3_ADDRESS,
+            getEthBalanceCall { addr: address }.abi_encode(),
+        )];
+        for token in &watchlist {
+            calls.push(call3(
+                *token,
+                balanceOfCall { account: address }.abi_encode(),
+            ));
+            calls.push(call3(*token, decimalsCall {}.abi_encode()));
+            calls.push(call3(*token, symbolCall {}.abi_encode()));
+        }
+        let results = client.multicall(calls).await?;
+
+        let nonce = client.provider.get_transaction_count(address).await?;
+        let code = client.provider.get_code_at(address).await?;
+
+        let eth_balance = decode::<getEthBalanceCall>(&results[0])
+            .map(|r| r.balance)
+            .ok_or_else(|| anyhow::anyhow!("Multicall3 getEthBalance failed"))?;
+
+        let mut holdings = vec![Holding {
+            symbol: "ETH".into(),
+            address: None,
+            raw_balance: eth_balance,
+            decimals: 18,
+        }];
+        let mut errors = Vec::new();
+        for (i, token) in watchlist.iter().enumerate() {
+            let base = 1 + i * 3;
+            let balance = decode::<balanceOfCall>(&results[base]).map(|r| r._0);
+            let decimals = decode::<decimalsCall>(&results[base + 1]).map(|r| r._0);
+            let symbol = Some(&results[base + 2])
+                .filter(|r| r.success)
+                .and_then(|r| decode_symbol(&r.returnData));
+            match (balance, decimals) {
+                (Some(raw_balance), Some(decimals)) => holdings.push(Holding {
+                    symbol: symbol.unwrap_or_else(|| token.to_string()),
+                    address: Some(*token),
+                    raw_balance,
+                    decimals,
+                }),
+                _ => errors.push(json!({
+                    "token": token.to_string(),
+                    "error": "balanceOf/decimals call failed; not an ERC20 on this chain?"
+                })),
+            }
+        }
+
+        // Price only what is actually held.
+        let held: Vec<&Holding> = holdings
+            .iter()
+            .filter(|h| !h.raw_balance.is_zero())
+            .collect();
+        let prices = join_all(held.iter().map(|h| price_usd(client, h))).await;
+
+        let mut total_usd = Decimal::ZERO;
+        let mut unpriced = Vec::new();
+        let mut balances = Vec::new();
+        for (holding, price) in held.iter().zip(prices) {
+            let balance = format_units(holding.raw_balance, holding.decimals)?;
+            let value_usd = match &price {
+                Ok(p) => {
+                    let value = Decimal::from_str(&balance)? * p;
+                    total_usd += value;
+                    Some(value.round_dp(2))
+                }
+                Err(_) => {
+                    unpriced.push(holding.symbol.clone());
+                    None
+                }
+            };
+            balances.push(json!({
+                "symbol": holding.symbol,
+                "token_address": holding.address.map(|a| a.to_string()),
+                "balance": display_amount(&balance),
+                "raw_balance": holding.raw_balance.to_string(),
+                "decimals": holding.decimals,
+                "price_usd": price.ok(),
+                "value_usd": value_usd
+            }));
+        }
+
+        Ok(json!({
+            "address": address.to_string(),
+            "is_contract": !code.is_empty(),
+            "nonce": nonce,
+            "eth_balance": display_amount(&format_units(eth_balance, 18)?),
+            "holdings": balances,
+            "total_value_usd": total_usd.round_dp(2),
+            "unpriced_tokens": unpriced,
+            "token_errors": errors,
+            "watchlist_size": watchlist.len()
+        }))
+    }
+}
+
+fn decode<C: SolCall>(result: &Call3Result) -> Option<C::Return> {
+    if !result.success {
+        return None;
+    }
+    C::abi_decode_returns(&result.returnData, true).ok()
+}
+
+async fn price_usd(client: &EthereumClient, holding: &Holding) -> Result<Decimal> {
+    let args = match holding.address {
+        Some(addr) if !is_eth(client, &addr.to_string()) => json!({
+            "token_symbol": holding.symbol,
+            "token_address": addr.to_string()
+        }),
+        // ETH and WETH both use the Chainlink ETH/USD price.
+        _ => json!({ "token_symbol": "ETH" }),
+    };
+    let price = GetTokenPriceTool.price(client, args).await?;
+    Ok(serde_json::from_value(price["price_usd"].clone())?)
+}
@@ -0,0 +1,244 @@
+use super::{resolve_token, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{call3, Call3Result, EthereumClient};
+use alloy::{
+    primitives::{b256, keccak256, Address, U256},
+    providers::Provider,
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    function totalSupply() external view returns (uint256);
+    #[allow(missing_docs)]
+    function balanceOf(address account) external view returns (uint256);
+    #[allow(missing_docs)]
+    function getTotalShares() external view returns (uint256);
+    #[allow(missing_docs)]
+    function sharesOf(address account) external view returns (uint256);
+    #[allow(missing_docs)]
+    function scaledTotalSupply() external view returns (uint256);
+    #[allow(missing_docs)]
+    function scaledBalanceOf(address user) external view returns (uint256);
+}
+
+// bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)
+const EIP1967_IMPLEMENTATION_SLOT: alloy::primitives::B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a9a7ca30c4e6a1d3ec");
+
+/// Functions that only rebasing or share-accounted tokens expose, with the pattern each
+/// one indicates.
+pub const REBASE_SIGNATURES: [(&str, &str); 9] = [
+    ("rebase(uint256,int256)", "supply rebase (Ampleforth-style)"),
+    (
+        "rebase(uint256,uint256)",
+        "supply rebase (Olympus sOHM-style)",
+    ),
+    ("rebase()", "supply rebase"),
+    ("gonsForBalance(uint256)", "gons/fragments accounting"),
+    ("sharesOf(address)", "share accounting (Lido stETH-style)"),
+    (
+        "getPooledEthByShares(uint256)",
+        "share accounting (Lido stETH-style)",
+    ),
+    (
+        "scaledBalanceOf(address)",
+        "scaled balances (Aave aToken-style)",
+    ),
+    (
+        "rebasingCreditsPerToken()",
+        "credits accounting (Origin OUSD-style)",
+    ),
+    ("changeSupply(uint256)", "supply rebase (Origin OUSD-style)"),
+];
+
+pub struct CheckRebasingTool;
+
+#[async_trait::async_trait]
+impl Tool for CheckRebasingTool {
+    fn name(&self) -> &'static str {
+        "check_rebasing"
+    }
+
+    fn description(&self) -> &'static str {
+        "Flag tokens whose balances change without transfers (rebasing or share-accounted, e.g. stETH, AMPL, aTokens). Returns rebasing: true/false/\"unknown\" with the basis."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token_address": {
+                    "type": "string",
+                    "description": "Address or registry symbol of the ERC20 token"
+                },
+                "holder": {
+                    "type": "string",
+                    "description": "Optional holder whose balanceOf is also compared with its internal shares."
+                }
+            },
+            "required": ["token_address"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let token = resolve_token(
+            client,
+            args["token_address"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("token_address"))?,
+        )?;
+        let holder = args
+            .get("holder")
+            .and_then(|v| v.as_str())
+            .map(Address::from_str)
+            .transpose()?;
+
+        let code = client.provider.get_code_at(token).await?;
+        if code.is_empty() {
+            return Err(anyhow::anyhow!("{} has no contract code", token));
+        }
+        let slot = client
+            .provider
+            .get_storage_at(token, U256::from_be_bytes(EIP1967_IMPLEMENTATION_SLOT.0))
+            .await?;
+        let implementation = Address::from_word(slot.into());
+        let mut scanned = code.to_vec();
+        let implementation = if implementation == Address::ZERO {
+            None
+        } else {
+            scanned.extend_from_slice(&client.provider.get_code_at(implementation).await?);
+            Some(implementation)
+        };
+
+        let selectors_found: Vec<Value> = REBASE_SIGNATURES
+            .iter()
+            .filter(|(signature, _)| has_selector(&scanned, signature))
+            .map(|(signature, pattern)| json!({ "signature": signature, "pattern": pattern }))
+            .collect();
+
+        let share_accounting = share_accounting(client, token, holder).await?;
+        let mismatches: Vec<&Value> = share_accounting
+            .iter()
+            .filter(|c| c["mismatch"] == true)
+            .collect();
+
+        let mut basis = Vec::new();
+        if !mismatches.is_empty() {
+            basis.push(format!(
+                "balances differ from internal share accounting ({})",
+                mismatches
+                    .iter()
+                    .filter_map(|c| c["check"].as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if !selectors_found.is_empty() {
+            basis.push("bytecode exposes known rebase/share functions".to_string());
+        }
+        // A proxy whose logic lives at an unknown address can't be judged from its code.
+        let readable = has_selector(&scanned, "balanceOf(address)");
+        let rebasing = if !basis.is_empty() {
+            json!(true)
+        } else if readable {
+            basis.push("no known rebase functions or share accounting found".to_string());
+            json!(false)
+        } else {
+            basis.push(
+                "balanceOf is not in the scanned bytecode (unrecognised proxy?); share checks found nothing"
+                    .to_string(),
+            );
+            json!("unknown")
+        };
+
+        Ok(json!({
+            "token": token.to_string(),
+            "rebasing": rebasing,
+            "basis": basis,
+            "selectors_found": selectors_found,
+            "share_accounting": share_accounting,
+            "implementation": implementation.map(|a| a.to_string()),
+            "note": "A rebasing token's balanceOf changes without Transfer events, so balances read earlier go stale and amounts sent or swapped can arrive a few wei short. Wrapped versions (e.g. wstETH) are non-rebasing. rebasing: false only means no known pattern was found."
+        }))
+    }
+}
+
+/// Whether `code` pushes the 4-byte selector of `signature`, as a Solidity dispatcher does.
+pub fn has_selector(code: &[u8], signature: &str) -> bool {
+    let hash = keccak256(signature.as_bytes());
+    let needle = [0x63, hash[0], hash[1], hash[2], hash[3]];
+    code.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Compares totals (and `holder`'s balance, if given) with the internal share values that
+/// Lido-style and Aave-style tokens expose. Checks the token doesn't implement are skipped.
+async fn share_accounting(
+    client: &EthereumClient,
+    token: Address,
+    holder: Option<Address>,
+) -> Result<Vec<Value>> {
+    let mut calls = vec![
+        call3(token, totalSupplyCall {}.abi_encode()),
+        call3(token, getTotalSharesCall {}.abi_encode()),
+        call3(token, scaledTotalSupplyCall {}.abi_encode()),
+    ];
+    if let Some(account) = holder {
+        calls.push(call3(token, balanceOfCall { account }.abi_encode()));
+        calls.push(call3(token, sharesOfCall { account }.abi_encode()));
+        calls.push(call3(
+            token,
+            scaledBalanceOfCall { user: account }.abi_encode(),
+        ));
+    }
+    let results = client.multicall(calls).await?;
+
+    let total_supply = decode_u256(&results[0]);
+    let mut checks = vec![
+        compare(
+            "totalSupply vs getTotalShares",
+            total_supply,
+            decode_u256(&results[1]),
+        ),
+        compare(
+            "totalSupply vs scaledTotalSupply",
+            total_supply,
+            decode_u256(&results[2]),
+        ),
+    ];
+    if holder.is_some() {
+        let balance = decode_u256(&results[3]);
+        checks.push(compare(
+            "balanceOf vs sharesOf",
+            balance,
+            decode_u256(&results[4]),
+        ));
+        checks.push(compare(
+            "balanceOf vs scaledBalanceOf",
+            balance,
+            decode_u256(&results[5]),
+        ));
+    }
+    Ok(checks.into_iter().flatten().collect())
+}
+
+fn compare(check: &str, balance: Option<U256>, shares: Option<U256>) -> Option<Value> {
+    let (balance, shares) = (balance?, shares?);
+    Some(json!({
+        "check": check,
+        "balance": balance.to_string(),
+        "shares": shares.to_string(),
+        "mismatch": balance != shares
+    }))
+}
+
+fn decode_u256(result: &Call3Result) -> Option<U256> {
+    if !result.success || result.returnData.len() != 32 {
+        return None;
+    }
+    Some(U256::from_be_slice(&result.returnData))
+}
@@ -0,0 +1,106 @@
+use super::price::{liquid_pools, pool_price, slot0Call, token0Call, FEE_TIERS};
+use super::Tool;
+use crate::ethereum::{
+    call3, decode_result, getCurrentBlockTimestampCall, EthereumClient, MULTICALL3_ADDRESS,
+};
+use crate::oracle::{self, decimalsCall, latestRoundDataCall};
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+pub struct GetReferencePricesTool;
+
+#[async_trait::async_trait]
+impl Tool for GetReferencePricesTool {
+    fn name(&self) -> &'static str {
+        "get_reference_prices"
+    }
+
+    fn description(&self) -> &'static str {
+        "Diagnostic: the reference values behind every USD-denominated output. Returns the Chainlink ETH/USD answer (with round id and update time), the WETH/USDC spot price from the deepest Uniswap V3 pool, and how far apart they are."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, _args: Value) -> Result<Value> {
+        let chain = client.chain()?;
+        let feed = oracle::eth_usd_feed(client)?;
+        // Native USDC where the chain has it, else its first stablecoin.
+        let usdc = chain
+            .stablecoins
+            .iter()
+            .find(|s| s.symbol == "USDC" && !s.bridged)
+            .or_else(|| chain.stablecoins.first())
+            .ok_or_else(|| anyhow::anyhow!("No stablecoin is configured for {}", chain.name))?;
+
+        let tiers = liquid_pools(client, &[(chain.weth, usdc.address)]).await?;
+        let pool = tiers.into_iter().flatten().next().ok_or_else(|| {
+            anyhow::anyhow!(
+                "No Uniswap V3 WETH/{} pool with liquidity at fee tiers {:?}",
+                usdc.symbol,
+                FEE_TIERS
+            )
+        })?;
+
+        // Pool state, the feed's round and the block time in one batch.
+        let calls = vec![
+            call3(pool.pool, slot0Call {}.abi_encode()),
+            call3(pool.pool, token0Call {}.abi_encode()),
+            call3(feed, latestRoundDataCall {}.abi_encode()),
+            call3(feed, decimalsCall {}.abi_encode()),
+            call3(
+                MULTICALL3_ADDRESS,
+                getCurrentBlockTimestampCall {}.abi_encode(),
+            ),
+        ];
+        let (results, weth_meta, usdc_meta) = futures::join!(
+            client.multicall(calls),
+            client.get_token_metadata(chain.weth),
+            client.get_token_metadata(usdc.address)
+        );
+        let (results, weth_meta, usdc_meta) = (results?, weth_meta?, usdc_meta?);
+
+        let slot0 = decode_result::<slot0Call>(&results[0], pool.pool)?;
+        let token0 = decode_result::<token0Call>(&results[1], pool.pool)?._0;
+        let pool_price_usdc = pool_price(
+            slot0.sqrtPriceX96,
+            token0 == chain.weth,
+            weth_meta.decimals,
+            usdc_meta.decimals,
+        )?;
+
+        let round = decode_result::<latestRoundDataCall>(&results[2], feed)?;
+        let feed_decimals = decode_result::<decimalsCall>(&results[3], feed)?._0;
+        let eth_price_usd = oracle::price_from_round(&round, feed_decimals)?;
+        let now = oracle::batch_timestamp(client, &results[4]).await?;
+        let updated_at = round.updatedAt.saturating_to::<u64>();
+
+        Ok(json!({
+            "chainlink_eth_usd": {
+                "feed": feed.to_string(),
+                "price_usd": eth_price_usd,
+                "round_id": round.roundId.to_string(),
+                "answered_in_round": round.answeredInRound.to_string(),
+                "updated_at": updated_at,
+                "age_seconds": now.saturating_sub(updated_at)
+            },
+            "uniswap_weth_usdc": {
+                "pool": pool.pool.to_string(),
+                "fee": pool.fee,
+                "liquidity": pool.liquidity.to_string(),
+                "quote_token": usdc.symbol,
+                "quote_address": usdc.address.to_string(),
+                "tick": slot0.tick,
+                "price": pool_price_usdc.round_dp(6)
+            },
+            // Positive when the pool prices WETH below Chainlink.
+            "deviation_percent": oracle::deviation_percent(eth_price_usd, pool_price_usdc).round_dp(4),
+            "block_timestamp": now
+        }))
+    }
+}
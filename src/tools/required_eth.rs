@@ -0,0 +1,270 @@
+use super::balance::format_units;
+use super::price::{price_args_for, GetTokenPriceTool};
+use super::send::wei_arg;
+use super::swap::{swap_token, SwapTokensTool};
+use super::sweep::budget_fees;
+use super::{resolve_token, u64_arg, Tool};
+use crate::approval;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use crate::format::display_amount;
+use alloy::{
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+};
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+// Intrinsic cost of any transaction, added to the Quoter's swap-only gas estimate.
+const BASE_TX_GAS: u64 = 21_000;
+
+/// Tools whose transaction can be planned without sending anything.
+const PLANNABLE_TOOLS: [&str; 3] = ["swap_tokens", "send_transaction", "approve_token"];
+
+pub struct GetRequiredEthTool;
+
+/// One transaction of the plan, in the order it would be sent.
+struct Step {
+    description: String,
+    to: Address,
+    data: Bytes,
+    value: U256,
+    /// Caller-supplied gas limit, used instead of `eth_estimateGas`.
+    gas_limit: Option<u64>,
+    /// Used when `eth_estimateGas` fails, e.g. for a swap whose approval isn't sent yet.
+    fallback_gas: Option<(u64, &'static str)>,
+}
+
+#[async_trait::async_trait]
+impl Tool for GetRequiredEthTool {
+    fn name(&self) -> &'static str {
+        "get_required_eth"
+    }
+
+    fn description(&self) -> &'static str {
+        "Compute the ETH the server wallet needs for a planned operation: value sent plus gas at current fees, including any approval a swap needs first. Takes a tool call (swap_tokens, send_transaction, approve_token) or a raw transaction, and returns the total, the signer's balance and the shortfall. Nothing is sent."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tool": {
+                    "type": "string",
+                    "enum": PLANNABLE_TOOLS,
+                    "description": "Tool whose transaction(s) to cost. swap_tokens is always run without execute."
+                },
+                "arguments": {
+                    "type": "object",
+                    "description": "Arguments for tool, as it would be called"
+                },
+                "transaction": {
+                    "type": "object",
+                    "description": "Raw transaction from the signer, instead of tool: {\"to\", \"data\", \"value\" (wei), \"gas_limit\"}. Only to is required.",
+                    "properties": {
+                        "to": { "type": "string" },
+                        "data": { "type": "string" },
+                        "value": { "type": "string" },
+                        "gas_limit": { "type": "integer" }
+                    },
+                    "required": ["to"]
+                }
+            }
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let empty = json!({});
+        let steps = match (
+            args.get("tool").and_then(|v| v.as_str()),
+            args.get("transaction"),
+        ) {
+            (Some(tool), None) => {
+                let arguments = args.get("arguments").unwrap_or(&empty);
+                plan_tool(client, tool, arguments).await?
+            }
+            (None, Some(tx)) => vec![raw_step(tx, "raw transaction")?],
+            _ => return Err(anyhow::anyhow!("Pass exactly one of tool or transaction")),
+        };
+
+        let owner = client.require_signer()?;
+        let balance = client.provider.get_balance(owner).await?;
+        let (max_fee_per_gas, max_priority_fee_per_gas) = budget_fees(client).await?;
+        let fee = U256::from(max_fee_per_gas);
+
+        let mut total_value = U256::ZERO;
+        let mut total_gas_cost = U256::ZERO;
+        let mut transactions = Vec::with_capacity(steps.len());
+        for step in &steps {
+            let (gas, gas_source) = match step.gas_limit {
+                Some(gas) => (gas, "gas_limit"),
+                None => {
+                    let tx = TransactionRequest::default()
+                        .from(owner)
+                        .to(step.to)
+                        .value(step.value)
+                        .input(step.data.clone().into());
+                    match (client.provider.estimate_gas(&tx).await, step.fallback_gas) {
+                        (Ok(gas), _) => (gas as u64, "eth_estimateGas"),
+                        (Err(_), Some(fallback)) => fallback,
+                        (Err(e), None) => {
+                            return Err(anyhow::anyhow!(
+                                "Could not estimate gas for {}: {}",
+                                step.description,
+                                e
+                            ))
+                        }
+                    }
+                }
+            };
+            let gas_cost = U256::from(gas) * fee;
+            total_value += step.value;
+            total_gas_cost += gas_cost;
+            transactions.push(json!({
+                "description": step.description,
+                "to": step.to.to_string(),
+                "value": display_amount(&format_units(step.value, 18)?),
+                "raw_value": step.value.to_string(),
+                "gas": gas,
+                "gas_source": gas_source,
+                "gas_cost": display_amount(&format_units(gas_cost, 18)?),
+                "raw_gas_cost": gas_cost.to_string()
+            }));
+        }
+        let required = total_value + total_gas_cost;
+        let shortfall = required.saturating_sub(balance);
+
+        // Best effort: the shortfall is exact in ETH either way.
+        let eth_price_usd = match GetTokenPriceTool.price(client, price_args_for("ETH")).await {
+            Ok(price) => price["price_usd"]
+                .as_str()
+                .and_then(|p| Decimal::from_str(p).ok()),
+            Err(_) => None,
+        };
+        let usd = |wei: U256| -> Result<Option<String>> {
+            let eth = Decimal::from_str(&format_units(wei, 18)?)?;
+            Ok(eth_price_usd.map(|p| (eth * p).round_dp(2).normalize().to_string()))
+        };
+
+        Ok(json!({
+            "address": owner.to_string(),
+            "transactions": transactions,
+            "total_value": display_amount(&format_units(total_value, 18)?),
+            "total_gas_cost": display_amount(&format_units(total_gas_cost, 18)?),
+            "required": display_amount(&format_units(required, 18)?),
+            "raw_required": required.to_string(),
+            "balance": display_amount(&format_units(balance, 18)?),
+            "raw_balance": balance.to_string(),
+            "shortfall": display_amount(&format_units(shortfall, 18)?),
+            "raw_shortfall": shortfall.to_string(),
+            "sufficient": shortfall.is_zero(),
+            "shortfall_usd": usd(shortfall)?,
+            "eth_price_usd": eth_price_usd.map(|p| p.normalize().to_string()),
+            "max_fee_per_gas": max_fee_per_gas.to_string(),
+            "max_priority_fee_per_gas": max_priority_fee_per_gas.to_string(),
+            "note": "Gas is budgeted at maxFeePerGas, so the real cost is usually lower. A swap's gas falls back to the Quoter estimate plus 21000 when it can't be estimated before its approval is sent. ERC20 balances aren't checked."
+        }))
+    }
+}
+
+/// The transactions `tool` would send with `arguments`.
+async fn plan_tool(client: &EthereumClient, tool: &str, arguments: &Value) -> Result<Vec<Step>> {
+    match tool {
+        "send_transaction" => Ok(vec![raw_step(arguments, "send_transaction")?]),
+        "approve_token" => {
+            let token = resolve_token(
+                client,
+                arguments["token_address"]
+                    .as_str()
+                    .ok_or_else(|| ToolError::missing("token_address"))?,
+            )?;
+            let spender = Address::from_str(
+                arguments["spender"]
+                    .as_str()
+                    .ok_or_else(|| ToolError::missing("spender"))?,
+            )?;
+            let amount = match arguments["amount"].as_str().map(str::trim) {
+                Some("max") | Some("MAX") => U256::MAX,
+                Some(amount) => U256::from_str(amount)?,
+                None => return Err(ToolError::missing("amount")),
+            };
+            Ok(vec![approve_step(token, spender, amount)])
+        }
+        "swap_tokens" => {
+            let mut arguments = arguments.clone();
+            arguments["execute"] = json!(false);
+            let swap = SwapTokensTool.call(client, arguments.clone()).await?;
+            let tx = &swap["transaction"];
+            let router = Address::from_str(tx["to"].as_str().unwrap_or_default())?;
+            let quote_gas = u64::from_str(swap["gas_estimate_simulation"].as_str().unwrap_or("0"))
+                .unwrap_or_default();
+            let mut steps = Vec::new();
+            // The same allowance check swap_tokens makes before executing.
+            let (from_token, from_native) = swap_token(client, &arguments, "from_token").await?;
+            if !from_native {
+                let needed = U256::from_str(swap["maximum_input"].as_str().unwrap_or("0"))?;
+                let current =
+                    approval::allowance(client, from_token, client.require_signer()?, router)
+                        .await?;
+                if current < needed {
+                    steps.push(approve_step(
+                        from_token,
+                        router,
+                        client.approval_policy.amount(needed),
+                    ));
+                }
+            }
+            steps.push(Step {
+                description: format!(
+                    "swap_tokens: {}",
+                    tx["description"].as_str().unwrap_or("swap")
+                ),
+                to: router,
+                data: Bytes::from_str(tx["data"].as_str().unwrap_or_default())?,
+                value: U256::from_str(tx["value"].as_str().unwrap_or("0"))?,
+                gas_limit: None,
+                fallback_gas: (quote_gas > 0)
+                    .then_some((quote_gas + BASE_TX_GAS, "quoter estimate")),
+            });
+            Ok(steps)
+        }
+        other => Err(anyhow::anyhow!(
+            "Cannot plan {}; supported tools are {}",
+            other,
+            PLANNABLE_TOOLS.join(", ")
+        )),
+    }
+}
+
+fn approve_step(token: Address, spender: Address, amount: U256) -> Step {
+    let tx = approval::approve_request(token, spender, amount);
+    Step {
+        description: format!("approve {} for {}", spender, token),
+        to: token,
+        data: tx.input.input().cloned().unwrap_or_default(),
+        value: U256::ZERO,
+        gas_limit: None,
+        fallback_gas: None,
+    }
+}
+
+/// A `send_transaction`-shaped transaction: `to`, optional `data`, `value` and `gas_limit`.
+fn raw_step(tx: &Value, description: &str) -> Result<Step> {
+    let to = Address::from_str(tx["to"].as_str().ok_or_else(|| ToolError::missing("to"))?)?;
+    let data = match tx.get("data").and_then(|v| v.as_str()) {
+        Some(data) => Bytes::from_str(data)
+            .map_err(|e| anyhow::anyhow!("data must be hex calldata: {}", e))?,
+        None => Bytes::new(),
+    };
+    Ok(Step {
+        description: format!("{} to {}", description, to),
+        to,
+        data,
+        value: wei_arg(tx, "value")?.unwrap_or_default(),
+        gas_limit: u64_arg(tx, "gas_limit")?,
+        fallback_gas: None,
+    })
+}
@@ -0,0 +1,358 @@
+use super::swap::{exactInputSingleCall, exact_input_single_calldata, ExactInputSingleParams};
+use super::{fee_arg, Tool};
+use crate::chains::UniswapV3;
+use crate::error::ToolError;
+use crate::ethereum::{
+    aggregate3ValueCall, decode_return, Call3Result, Call3Value, EthereumClient, MULTICALL3_ADDRESS,
+};
+use alloy::{
+    primitives::{address, Address, Bytes, U256, U512},
+    providers::Provider,
+    rpc::types::eth::{
+        state::{AccountOverride, StateOverride},
+        TransactionRequest,
+    },
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+sol! {
+    #[allow(missing_docs)]
+    function deposit() external payable;
+    #[allow(missing_docs)]
+    function approve(address spender, uint256 amount) external returns (bool);
+    #[allow(missing_docs)]
+    function transfer(address to, uint256 amount) external returns (bool);
+    #[allow(missing_docs)]
+    function balanceOf(address account) external view returns (uint256);
+}
+
+// Fresh recipient for the plain-transfer tax probe.
+const PROBE_RECIPIENT: Address = address!("000000000000000000000000000000000000dEaD");
+
+// Thresholds, in percent, above which a tax or loss is reported as a red flag.
+const HIGH_TAX_PERCENT: u64 = 10;
+const HIGH_ROUND_TRIP_LOSS_PERCENT: u64 = 20;
+const NEAR_ZERO_SELL_PERCENT: u64 = 1;
+
+pub struct CheckTokenSafetyTool;
+
+#[async_trait::async_trait]
+impl Tool for CheckTokenSafetyTool {
+    fn name(&self) -> &'static str {
+        "check_token_safety"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pre-trade honeypot check: simulates a small WETH buy, a transfer, and a sell of the token via eth_call with state overrides, reporting blocked sells and buy/sell/transfer taxes."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token_address": {
+                    "type": "string",
+                    "description": "Token to check"
+                },
+                "fee": {
+                    "type": "integer",
+                    "description": "Uniswap V3 fee tier of the token/WETH pool. Default 3000."
+                },
+                "amount_eth": {
+                    "type": "string",
+                    "description": "Size of the simulated buy in wei of WETH. Default 10000000000000000 (0.01 ETH)."
+                }
+            },
+            "required": ["token_address"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let token = Address::from_str(
+            args["token_address"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("token_address"))?,
+        )?;
+        let fee = fee_arg(&args)?.unwrap_or(3000);
+        let amount_in = match args.get("amount_eth").and_then(|v| v.as_str()) {
+            Some(a) => U256::from_str(a)?,
+            None => U256::from(10_000_000_000_000_000u64),
+        };
+
+        // The simulation trades as Multicall3 itself, so there is no per-call fallback.
+        if !client.multicall_available {
+            return Err(anyhow::anyhow!(
+                "check_token_safety requires Multicall3, which is not deployed on this chain"
+            ));
+        }
+
+        let uniswap = client.uniswap_v3()?;
+        let (weth, router) = (client.chain()?.weth, uniswap.swap_router);
+
+        // Phase 1: buy with Multicall3 as the trader and see what actually lands.
+        let buy_calls = buy_steps(&uniswap, weth, token, fee, amount_in);
+        let buy = simulate(client, amount_in, buy_calls.clone()).await?;
+        let (buy_ok, quoted_out) =
+            match exactInputSingleCall::abi_decode_returns(&buy[3].returnData, true) {
+                Ok(r) if buy[3].success => (true, r.amountOut),
+                _ => (false, U256::ZERO),
+            };
+        if !buy_ok {
+            return Ok(json!({
+                "token_address": token.to_string(),
+                "verdict": "unknown",
+                "red_flags": ["Buy reverts; no WETH pool at this fee tier or buys are blocked"],
+                "buy": { "success": false, "revert_data": hex_data(&buy[3].returnData) }
+            }));
+        }
+        // Multicall3 and the probe address can hold dust already, so every amount is a delta.
+        let received = balance_delta(&buy[0], &buy[4]);
+
+        // Phase 2: same buy, then a plain transfer of half and a sell of the other half.
+        let half = received / U256::from(2);
+        let mut calls = buy_calls;
+        calls.push(value_call(
+            token,
+            balanceOfCall {
+                account: PROBE_RECIPIENT,
+            }
+            .abi_encode(),
+        ));
+        calls.push(value_call(
+            token,
+            transferCall {
+                to: PROBE_RECIPIENT,
+                amount: half,
+            }
+            .abi_encode(),
+        ));
+        calls.push(value_call(
+            token,
+            balanceOfCall {
+                account: PROBE_RECIPIENT,
+            }
+            .abi_encode(),
+        ));
+        calls.push(value_call(
+            token,
+            approveCall {
+                spender: router,
+                amount: half,
+            }
+            .abi_encode(),
+        ));
+        let weth_balance = value_call(
+            weth,
+            balanceOfCall {
+                account: MULTICALL3_ADDRESS,
+            }
+            .abi_encode(),
+        );
+        calls.push(weth_balance.clone());
+        calls.push(value_call(
+            router,
+            router_swap(&uniswap, token, weth, fee, half),
+        ));
+        calls.push(weth_balance);
+        let full = simulate(client, amount_in, calls).await?;
+
+        let transfer_ok = full[6].success;
+        let probe_received = balance_delta(&full[5], &full[7]);
+        let sell_ok = full[10].success;
+        let weth_back = if sell_ok {
+            balance_delta(&full[9], &full[11])
+        } else {
+            U256::ZERO
+        };
+
+        let buy_tax = percent_shortfall(received, quoted_out);
+        let mut red_flags = Vec::new();
+        if buy_tax > Decimal::from(HIGH_TAX_PERCENT) {
+            red_flags.push(format!(
+                "High buy tax: {}% of the pool output never arrived",
+                buy_tax.round_dp(2)
+            ));
+        }
+        let transfer_tax = if transfer_ok {
+            Some(percent_shortfall(probe_received, half))
+        } else {
+            red_flags.push("Plain transfers revert".to_string());
+            None
+        };
+        if let Some(tax) = transfer_tax.filter(|t| *t > Decimal::from(HIGH_TAX_PERCENT)) {
+            red_flags.push(format!("High transfer tax: {}%", tax.round_dp(2)));
+        }
+
+        // Half the bought tokens were sold, so compare against half the WETH spent.
+        let round_trip_loss = percent_shortfall(weth_back, amount_in / U256::from(2));
+        if !sell_ok {
+            red_flags.push(
+                "Sell reverts (honeypot, or a fee-on-transfer token V3 pools cannot accept)"
+                    .to_string(),
+            );
+        } else if round_trip_loss > Decimal::from(100 - NEAR_ZERO_SELL_PERCENT) {
+            red_flags.push("Sell returns near-zero WETH".to_string());
+        } else if round_trip_loss > Decimal::from(HIGH_ROUND_TRIP_LOSS_PERCENT) {
+            red_flags.push(format!(
+                "Buy+sell round trip loses {}%",
+                round_trip_loss.round_dp(2)
+            ));
+        }
+
+        let verdict = if !sell_ok || round_trip_loss > Decimal::from(100 - NEAR_ZERO_SELL_PERCENT) {
+            "likely_honeypot"
+        } else if red_flags.is_empty() {
+            "no_red_flags"
+        } else {
+            "caution"
+        };
+
+        Ok(json!({
+            "token_address": token.to_string(),
+            "verdict": verdict,
+            "red_flags": red_flags,
+            "buy": {
+                "weth_in": amount_in.to_string(),
+                "pool_output": quoted_out.to_string(),
+                "received": received.to_string(),
+                "tax_percent": buy_tax.round_dp(2)
+            },
+            "transfer": {
+                "success": transfer_ok,
+                "sent": half.to_string(),
+                "received": probe_received.to_string(),
+                "tax_percent": transfer_tax.map(|t| t.round_dp(2))
+            },
+            "sell": {
+                "success": sell_ok,
+                "tokens_in": half.to_string(),
+                "weth_out": weth_back.to_string(),
+                "round_trip_loss_percent": if sell_ok { Some(round_trip_loss.round_dp(2)) } else { None },
+                "revert_data": if sell_ok { None } else { Some(hex_data(&full[10].returnData)) }
+            },
+            "note": "Simulated with Multicall3 as the trader. Tokens that special-case contracts, tx.origin, or specific wallets can behave differently for real users."
+        }))
+    }
+}
+
+fn buy_steps(
+    uniswap: &UniswapV3,
+    weth: Address,
+    token: Address,
+    fee: u32,
+    amount_in: U256,
+) -> Vec<Call3Value> {
+    let router = uniswap.swap_router;
+    let token_balance = value_call(
+        token,
+        balanceOfCall {
+            account: MULTICALL3_ADDRESS,
+        }
+        .abi_encode(),
+    );
+    vec![
+        token_balance.clone(),
+        Call3Value {
+            target: weth,
+            allowFailure: false,
+            value: amount_in,
+            callData: Bytes::from(depositCall {}.abi_encode()),
+        },
+        value_call(
+            weth,
+            approveCall {
+                spender: router,
+                amount: amount_in,
+            }
+            .abi_encode(),
+        ),
+        value_call(router, router_swap(uniswap, weth, token, fee, amount_in)),
+        token_balance,
+    ]
+}
+
+fn router_swap(
+    uniswap: &UniswapV3,
+    token_in: Address,
+    token_out: Address,
+    fee: u32,
+    amount_in: U256,
+) -> Vec<u8> {
+    exact_input_single_calldata(
+        uniswap,
+        ExactInputSingleParams {
+            tokenIn: token_in,
+            tokenOut: token_out,
+            fee,
+            recipient: MULTICALL3_ADDRESS,
+            deadline: U256::MAX,
+            amountIn: amount_in,
+            amountOutMinimum: U256::ZERO,
+            sqrtPriceLimitX96: U256::ZERO,
+        },
+    )
+}
+
+fn value_call(target: Address, call_data: Vec<u8>) -> Call3Value {
+    Call3Value {
+        target,
+        allowFailure: true,
+        value: U256::ZERO,
+        callData: Bytes::from(call_data),
+    }
+}
+
+// Runs the batch from the signer (or the read-only placeholder) with enough overridden ETH to fund the WETH deposit.
+async fn simulate(
+    client: &EthereumClient,
+    value: U256,
+    calls: Vec<Call3Value>,
+) -> Result<Vec<Call3Result>> {
+    let mut overrides = StateOverride::default();
+    overrides.insert(
+        client.simulation_sender(),
+        AccountOverride {
+            balance: Some(value * U256::from(10)),
+            ..Default::default()
+        },
+    );
+    let req = TransactionRequest::default()
+        .from(client.simulation_sender())
+        .to(MULTICALL3_ADDRESS)
+        .value(value)
+        .input(aggregate3ValueCall { calls }.abi_encode().into());
+    let res = client.provider.call(&req).overrides(&overrides).await?;
+    Ok(decode_return::<aggregate3ValueCall>(&res, MULTICALL3_ADDRESS)?.returnData)
+}
+
+fn balance_result(result: &Call3Result) -> U256 {
+    if !result.success {
+        return U256::ZERO;
+    }
+    balanceOfCall::abi_decode_returns(&result.returnData, true)
+        .map(|r| r._0)
+        .unwrap_or(U256::ZERO)
+}
+
+fn balance_delta(before: &Call3Result, after: &Call3Result) -> U256 {
+    balance_result(after).saturating_sub(balance_result(before))
+}
+
+// How much of `expected` is missing from `actual`, as a percentage to two decimals. Worked
+// out in basis points over U512, since token amounts can exceed what a Decimal holds.
+pub fn percent_shortfall(actual: U256, expected: U256) -> Decimal {
+    if expected.is_zero() || actual >= expected {
+        return Decimal::ZERO;
+    }
+    let bps = U512::from(expected - actual) * U512::from(10_000) / U512::from(expected);
+    Decimal::new(bps.to::<i64>(), 2)
+}
+
+fn hex_data(data: &Bytes) -> String {
+    format!("0x{}", hex::encode(data))
+}
@@ -0,0 +1,375 @@
+use super::balance::format_units;
+use super::block::BLOCK_TAGS;
+use super::price::factory_pool;
+use super::swap::SwapMode;
+use super::{f64_arg, fee_arg, resolve_token, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use crate::quoter;
+use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
+    primitives::{Address, B256, U256},
+    providers::Provider,
+    rpc::types::eth::state::StateOverride,
+};
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// Uniswap V3 pool storage: `slot0` packs sqrtPriceX96 (bits 0-159) with the tick (bits
+// 160-183) and the oracle/fee/lock fields above them; `liquidity` is alone in slot 4.
+const SLOT0_SLOT: u64 = 0;
+const LIQUIDITY_SLOT: u64 = 4;
+// TickMath bounds.
+const MIN_SQRT_RATIO: u64 = 4_295_128_739;
+const MAX_SQRT_RATIO: &str = "1461446703485210103287273052203988822378723970342";
+const MAX_TICK: i32 = 887_272;
+
+pub struct SimulateSwapScenarioTool;
+
+#[async_trait::async_trait]
+impl Tool for SimulateSwapScenarioTool {
+    fn name(&self) -> &'static str {
+        "simulate_swap_scenario"
+    }
+
+    fn description(&self) -> &'static str {
+        "Stress-test a Uniswap V3 swap under hypothetical conditions: quotes it with eth_call pinned to a block, once as-is and once with state overrides for a scenario (a moved pool price, changed in-range liquidity, or raw storage/balance/code overrides), and compares the outputs. Nothing is sent."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "from_token": {
+                    "type": "string",
+                    "description": "Token to sell: address, registry symbol, or \"ETH\" for WETH"
+                },
+                "to_token": {
+                    "type": "string",
+                    "description": "Token to buy: address, registry symbol, or \"ETH\" for WETH"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Amount of from_token to sell (in base units)"
+                },
+                "fee": {
+                    "type": "integer",
+                    "description": "Pool fee tier (e.g., 500, 3000, 10000). Default 3000."
+                },
+                "block": {
+                    "type": ["integer", "string"],
+                    "description": "Block to pin both quotes to: a number or \"latest\", \"pending\", \"safe\", \"finalized\". Default latest. Past blocks need an archive node."
+                },
+                "scenario": {
+                    "type": "object",
+                    "description": "Overrides applied to the pinned block's state for the scenario quote.",
+                    "properties": {
+                        "price_change_percent": {
+                            "type": "number",
+                            "description": "Move the pool price of from_token in to_token by this percentage (e.g. -20). Exclusive with sqrt_price_x96."
+                        },
+                        "sqrt_price_x96": {
+                            "type": "string",
+                            "description": "Set the pool's sqrtPriceX96; the tick is recomputed from it."
+                        },
+                        "liquidity": {
+                            "type": "string",
+                            "description": "Set the pool's in-range liquidity (uint128)."
+                        },
+                        "state_overrides": {
+                            "type": "object",
+                            "description": "Raw eth_call state overrides by address: {\"0x...\": {\"balance\", \"nonce\", \"code\", \"state\" or \"stateDiff\"}}. Pool overrides above are applied on top."
+                        }
+                    }
+                }
+            },
+            "required": ["from_token", "to_token", "amount"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let amount = U256::from_str(
+            args["amount"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("amount"))?,
+        )
+        .map_err(|e| ToolError::invalid_argument(format!("Invalid amount: {}", e)))?;
+        let token_in = scenario_token(client, &args, "from_token")?;
+        let token_out = scenario_token(client, &args, "to_token")?;
+        if token_in == token_out {
+            return Err(ToolError::invalid_argument(
+                "from_token and to_token are the same token",
+            ));
+        }
+        let fee = fee_arg(&args)?.unwrap_or(3000);
+        let scenario = &args["scenario"];
+        let mut overrides = match scenario.get("state_overrides") {
+            None | Some(Value::Null) => StateOverride::default(),
+            Some(raw) => serde_json::from_value(raw.clone()).map_err(|e| {
+                ToolError::invalid_argument(format!("Invalid scenario.state_overrides: {}", e))
+            })?,
+        };
+
+        let uniswap = client.uniswap_v3()?;
+        let block = pinned_block(client, &args).await?;
+        let pool = factory_pool(client, token_in, token_out, fee).await?;
+        if pool == Address::ZERO {
+            return Err(ToolError::invalid_argument(format!(
+                "No Uniswap V3 pool for this pair at fee tier {}",
+                fee
+            )));
+        }
+        let slot0 = storage(client, pool, SLOT0_SLOT, block).await?;
+        let liquidity = storage(client, pool, LIQUIDITY_SLOT, block).await?;
+        let sqrt_price = slot0 & low_bits(160);
+        if sqrt_price.is_zero() {
+            return Err(anyhow::anyhow!(
+                "Pool {} is not initialized at block {}",
+                pool,
+                block_label(block)
+            ));
+        }
+        let before = pool_state(sqrt_price, slot_tick(slot0), liquidity & low_bits(128));
+
+        // The pool's own slots, rewritten for the scenario.
+        let price_change = f64_arg(scenario, "price_change_percent")?;
+        let new_sqrt_price = match (scenario.get("sqrt_price_x96"), price_change) {
+            (Some(v), Some(_)) if !v.is_null() => {
+                return Err(ToolError::invalid_argument(
+                    "Pass scenario.sqrt_price_x96 or scenario.price_change_percent, not both",
+                ))
+            }
+            (Some(Value::String(v)), _) => Some(U256::from_str(v).map_err(|e| {
+                ToolError::invalid_argument(format!("Invalid scenario.sqrt_price_x96: {}", e))
+            })?),
+            (Some(v), _) if !v.is_null() => {
+                return Err(ToolError::invalid_argument(
+                    "scenario.sqrt_price_x96 must be a decimal string",
+                ))
+            }
+            (_, Some(percent)) => {
+                Some(moved_sqrt_price(sqrt_price, percent, token_in < token_out)?)
+            }
+            _ => None,
+        };
+        let mut applied = Vec::new();
+        let mut after = before.clone();
+        if let Some(new_sqrt_price) = new_sqrt_price {
+            let max = U256::from_str(MAX_SQRT_RATIO)?;
+            if new_sqrt_price < U256::from(MIN_SQRT_RATIO) || new_sqrt_price >= max {
+                return Err(ToolError::invalid_argument(format!(
+                    "sqrtPriceX96 {} is outside Uniswap's range [{}, {})",
+                    new_sqrt_price, MIN_SQRT_RATIO, max
+                )));
+            }
+            let tick = tick_at_sqrt_price(new_sqrt_price);
+            let tick_bits = U256::from((tick as i64 & 0xFF_FFFF) as u64) << 160;
+            let word = (slot0 & !low_bits(184)) | tick_bits | new_sqrt_price;
+            add_storage_override(&mut overrides, pool, SLOT0_SLOT, word);
+            after["sqrt_price_x96"] = json!(new_sqrt_price.to_string());
+            after["tick"] = json!(tick);
+            applied.push("slot0 (sqrtPriceX96, tick)");
+        }
+        if let Some(value) = scenario.get("liquidity").filter(|v| !v.is_null()) {
+            let new_liquidity = value
+                .as_str()
+                .and_then(|v| u128::from_str(v).ok())
+                .ok_or_else(|| {
+                    ToolError::invalid_argument(
+                        "scenario.liquidity must be a decimal string within uint128",
+                    )
+                })?;
+            add_storage_override(
+                &mut overrides,
+                pool,
+                LIQUIDITY_SLOT,
+                U256::from(new_liquidity),
+            );
+            after["liquidity"] = json!(new_liquidity.to_string());
+            applied.push("liquidity");
+        }
+        if overrides.is_empty() {
+            return Err(ToolError::invalid_argument(
+                "scenario needs at least one of price_change_percent, sqrt_price_x96, liquidity or state_overrides",
+            ));
+        }
+
+        let req = quoter::request(
+            client,
+            &uniswap,
+            SwapMode::ExactIn,
+            token_in,
+            token_out,
+            fee,
+            amount,
+        );
+        let baseline = client.provider.call(&req).block(block).await;
+        let scenario_quote = client
+            .provider
+            .call(&req)
+            .block(block)
+            .overrides(&overrides)
+            .await;
+        let baseline = quoter::decode(&uniswap, SwapMode::ExactIn, amount, &baseline)?;
+        let scenario_quote =
+            quoter::decode(&uniswap, SwapMode::ExactIn, amount, &scenario_quote)
+                .map_err(|e| anyhow::anyhow!("Quote under the scenario failed: {}", e))?;
+
+        let decimals_out = client.get_token_metadata(token_out).await?.decimals;
+        let output = |out: U256| -> Result<Value> {
+            Ok(json!({
+                "amount_out": format_units(out, decimals_out)?,
+                "raw_amount_out": out.to_string()
+            }))
+        };
+        let change = if baseline.amount_out.is_zero() {
+            None
+        } else {
+            let base = Decimal::from_str(&baseline.amount_out.to_string())?;
+            let scen = Decimal::from_str(&scenario_quote.amount_out.to_string())?;
+            Some(
+                ((scen - base) / base * Decimal::ONE_HUNDRED)
+                    .round_dp(4)
+                    .normalize(),
+            )
+        };
+        let mut scenario_result = output(scenario_quote.amount_out)?;
+        scenario_result["pool"] = after;
+        let mut baseline_result = output(baseline.amount_out)?;
+        baseline_result["pool"] = before;
+
+        Ok(json!({
+            "block": block_label(block),
+            "pool": pool.to_string(),
+            "token_in": token_in.to_string(),
+            "token_out": token_out.to_string(),
+            "amount_in": amount.to_string(),
+            "baseline": baseline_result,
+            "scenario": scenario_result,
+            "output_change_percent": change,
+            "overrides": {
+                "pool_slots": applied,
+                "accounts": overrides.keys().map(|a| a.to_string()).collect::<Vec<_>>()
+            },
+            "note": "Quoted with QuoterV2 eth_call at the pinned block. A moved price keeps the pool's tick liquidity map as it is, so large moves across initialized ticks are approximate."
+        }))
+    }
+}
+
+/// `ETH` stands for WETH; the quoter only trades ERC20s.
+fn scenario_token(client: &EthereumClient, args: &Value, key: &str) -> Result<Address> {
+    let token = args[key].as_str().ok_or_else(|| ToolError::missing(key))?;
+    if token.eq_ignore_ascii_case("ETH") {
+        return Ok(client.chain()?.weth);
+    }
+    resolve_token(client, token)
+}
+
+/// The block both quotes run against: the `block` argument resolved to a number, so the
+/// baseline and the scenario see the same state even as the chain moves.
+async fn pinned_block(client: &EthereumClient, args: &Value) -> Result<BlockId> {
+    let tag = match args["block"].as_str().map(str::trim) {
+        None => match u64_arg(args, "block")? {
+            Some(number) => return Ok(BlockId::number(number)),
+            None => BlockNumberOrTag::Latest,
+        },
+        Some(tag) if BLOCK_TAGS.contains(&tag) && tag != "earliest" => {
+            BlockNumberOrTag::from_str(tag)?
+        }
+        Some(_) => match u64_arg(args, "block").ok().flatten() {
+            Some(number) => return Ok(BlockId::number(number)),
+            None => {
+                return Err(ToolError::invalid_argument(format!(
+                "block must be a block number or one of latest, pending, safe, finalized, got {}",
+                args["block"]
+            )))
+            }
+        },
+    };
+    let number = client
+        .provider
+        .get_block_by_number(tag, false)
+        .await?
+        .and_then(|b| b.header.number);
+    Ok(match number {
+        Some(number) => BlockId::number(number),
+        None => BlockId::Number(tag),
+    })
+}
+
+fn block_label(block: BlockId) -> Value {
+    match block {
+        BlockId::Number(BlockNumberOrTag::Number(number)) => json!(number),
+        other => json!(other),
+    }
+}
+
+async fn storage(
+    client: &EthereumClient,
+    pool: Address,
+    slot: u64,
+    block: BlockId,
+) -> Result<U256> {
+    Ok(client
+        .provider
+        .get_storage_at(pool, U256::from(slot))
+        .block_id(block)
+        .await?)
+}
+
+fn add_storage_override(overrides: &mut StateOverride, account: Address, slot: u64, value: U256) {
+    overrides
+        .entry(account)
+        .or_default()
+        .state_diff
+        .get_or_insert_with(HashMap::new)
+        .insert(B256::from(U256::from(slot)), B256::from(value));
+}
+
+fn low_bits(bits: usize) -> U256 {
+    (U256::from(1) << bits) - U256::from(1)
+}
+
+/// The signed 24-bit tick packed above sqrtPriceX96 in `slot0`.
+fn slot_tick(slot0: U256) -> i32 {
+    let raw: U256 = (slot0 >> 160) & low_bits(24);
+    let raw = raw.to::<u32>();
+    ((raw << 8) as i32) >> 8
+}
+
+fn pool_state(sqrt_price: U256, tick: i32, liquidity: U256) -> Value {
+    json!({
+        "sqrt_price_x96": sqrt_price.to_string(),
+        "tick": tick,
+        "liquidity": liquidity.to_string()
+    })
+}
+
+/// `sqrt_price` after the price of the input token in the output token moves by
+/// `percent`. The pool price is token1 per token0, so it moves the other way when the
+/// input is token1.
+fn moved_sqrt_price(sqrt_price: U256, percent: f64, input_is_token0: bool) -> Result<U256> {
+    if percent <= -100.0 || !percent.is_finite() {
+        return Err(ToolError::invalid_argument(
+            "scenario.price_change_percent must be greater than -100",
+        ));
+    }
+    let factor = (1.0 + percent / 100.0).sqrt();
+    let factor = if input_is_token0 {
+        factor
+    } else {
+        1.0 / factor
+    };
+    let scale = U256::from(10u64.pow(18));
+    Ok(sqrt_price * U256::from((factor * 1e18).round() as u128) / scale)
+}
+
+/// The tick whose price range holds `sqrt_price`: floor(log_1.0001(price)).
+fn tick_at_sqrt_price(sqrt_price: U256) -> i32 {
+    let ratio = sqrt_price.to_string().parse::<f64>().unwrap_or(0.0) / 2f64.powi(96);
+    let tick = (2.0 * ratio.ln() / 1.0001f64.ln()).floor();
+    (tick as i32).clamp(-MAX_TICK, MAX_TICK)
+}
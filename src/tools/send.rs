@@ -0,0 +1,132 @@
+use super::{transaction_sender, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{Address, Bytes, U256},
+    rpc::types::eth::TransactionRequest,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+pub struct SendTransactionTool;
+
+#[async_trait::async_trait]
+impl Tool for SendTransactionTool {
+    fn name(&self) -> &'static str {
+        "send_transaction"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sign a transaction with the server wallet and broadcast it. Nonce, gas limit and fees are filled from the node when omitted. With unsigned (always in read-only mode), return the fully populated EIP-1559 transaction for an external signer instead."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "to": {
+                    "type": "string",
+                    "description": "Recipient or contract address"
+                },
+                "value": {
+                    "type": "string",
+                    "description": "ETH to send, in wei (decimal or 0x-hex). Default 0."
+                },
+                "data": {
+                    "type": "string",
+                    "description": "Hex calldata (e.g. the transaction.data returned by swap_tokens). Default empty."
+                },
+                "gas_limit": {
+                    "type": "integer",
+                    "description": "Gas limit. Default: eth_estimateGas."
+                },
+                "max_fee_per_gas": {
+                    "type": "string",
+                    "description": "EIP-1559 maxFeePerGas in wei. Default: estimated from fee history."
+                },
+                "max_priority_fee_per_gas": {
+                    "type": "string",
+                    "description": "EIP-1559 maxPriorityFeePerGas in wei. Default: estimated, capped at max_fee_per_gas."
+                },
+                "private": {
+                    "type": "boolean",
+                    "description": "Send only to the configured PRIVATE_RPC_URL relay instead of the public mempool. Default false."
+                },
+                "unsigned": {
+                    "type": "boolean",
+                    "description": "Return the transaction unsigned, with nonce, gas and fees filled, instead of signing and sending it. Always on in read-only mode. Default false."
+                },
+                "from": {
+                    "type": "string",
+                    "description": "With unsigned: the account that will sign (address or ENS name). Default: the server wallet; required in read-only mode."
+                }
+            },
+            "required": ["to"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let (from, unsigned) = transaction_sender(client, &args).await?;
+        let to = Address::from_str(
+            args["to"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("to"))?,
+        )?;
+        let value = wei_arg(&args, "value")?.unwrap_or_default();
+        let data = match args.get("data").and_then(|v| v.as_str()) {
+            Some(data) => Bytes::from_str(data)
+                .map_err(|e| anyhow::anyhow!("data must be hex calldata: {}", e))?,
+            None => Bytes::new(),
+        };
+        let private = args
+            .get("private")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let mut tx = TransactionRequest::default()
+            .to(to)
+            .value(value)
+            .input(data.into());
+        if let Some(gas) = u64_arg(&args, "gas_limit")? {
+            tx = tx.with_gas_limit(gas as u128);
+        }
+        if let Some(fee) = wei_arg(&args, "max_fee_per_gas")? {
+            tx = tx.with_max_fee_per_gas(fee.saturating_to());
+        }
+        if let Some(fee) = wei_arg(&args, "max_priority_fee_per_gas")? {
+            tx = tx.with_max_priority_fee_per_gas(fee.saturating_to());
+        }
+
+        if unsigned {
+            let unsigned = client.unsigned_transaction(tx, from).await?;
+            return Ok(json!({ "unsigned_transaction": unsigned.to_json() }));
+        }
+        let submission = client.submit_transaction(tx, private).await?;
+        let mut result = submission.to_json();
+        result["from"] = json!(from.to_string());
+        result["to"] = json!(to.to_string());
+        result["value"] = json!(value.to_string());
+        Ok(result)
+    }
+}
+
+/// A wei amount given as a decimal or 0x-hex string, or a JSON integer.
+pub(crate) fn wei_arg(args: &Value, key: &str) -> Result<Option<U256>> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => U256::from_str(s.trim())
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("{} must be a wei amount, got {:?}", key, s)),
+        Some(Value::Number(n)) => n
+            .as_u64()
+            .map(|n| Some(U256::from(n)))
+            .ok_or_else(|| anyhow::anyhow!("{} must be a wei amount, got {}", key, n)),
+        Some(other) => Err(anyhow::anyhow!(
+            "{} must be a wei amount, got {}",
+            key,
+            other
+        )),
+    }
+}
@@ -0,0 +1,252 @@
+use super::price::FEE_TIERS;
+use super::swap::SwapMode;
+use super::{resolve_token_with_decision, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use crate::quoter;
+use alloy::primitives::{Address, U256};
+use anyhow::Result;
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const DEFAULT_STEPS: u64 = 10;
+const MAX_STEPS: u64 = 50;
+
+pub struct OptimizeSwapSplitTool;
+
+/// QuoterV2 result for one fee tier at one input size.
+#[derive(Clone, Copy)]
+struct Quote {
+    amount_out: U256,
+    gas_estimate: U256,
+}
+
+#[async_trait::async_trait]
+impl Tool for OptimizeSwapSplitTool {
+    fn name(&self) -> &'static str {
+        "optimize_swap_split"
+    }
+
+    fn description(&self) -> &'static str {
+        "Find how to split a large swap across Uniswap V3 fee tiers to maximize total output, and compare it with the best single-pool swap."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "from_token": {
+                    "type": "string",
+                    "description": "Address or registry symbol of the token to sell"
+                },
+                "to_token": {
+                    "type": "string",
+                    "description": "Address or registry symbol of the token to buy"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Total amount of from_token to sell (in base units)"
+                },
+                "steps": {
+                    "type": "integer",
+                    "description": "Granularity of the search: the amount is allocated in this many equal slices. Default 10, max 50."
+                },
+                "fee_tiers": {
+                    "type": "array",
+                    "items": { "type": "integer" },
+                    "description": "Fee tiers to consider. Default [100, 500, 3000, 10000]."
+                }
+            },
+            "required": ["from_token", "to_token", "amount"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let (from_token, from_resolution) = resolve_token_with_decision(
+            client,
+            args["from_token"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("from_token"))?,
+        )?;
+        let (to_token, to_resolution) = resolve_token_with_decision(
+            client,
+            args["to_token"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("to_token"))?,
+        )?;
+        let amount = U256::from_str(
+            args["amount"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("amount"))?,
+        )?;
+        if amount.is_zero() {
+            return Err(anyhow::anyhow!("amount must be greater than zero"));
+        }
+        let steps = u64_arg(&args, "steps")?
+            .unwrap_or(DEFAULT_STEPS)
+            .clamp(1, MAX_STEPS);
+        let tiers: Vec<u32> = match args.get("fee_tiers").and_then(|v| v.as_array()) {
+            Some(tiers) => tiers
+                .iter()
+                .map(|t| {
+                    t.as_u64()
+                        .map(|t| t as u32)
+                        .ok_or_else(|| anyhow::anyhow!("fee_tiers must be an array of integers"))
+                })
+                .collect::<Result<_>>()?,
+            None => FEE_TIERS.to_vec(),
+        };
+
+        // A tier whose full-size quote fails (no pool, or not enough liquidity) is left out.
+        let full_quotes = join_all(
+            tiers
+                .iter()
+                .map(|fee| quote(client, from_token, to_token, *fee, amount)),
+        )
+        .await;
+        let mut quotes: HashMap<(u32, U256), Quote> = HashMap::new();
+        let mut usable = Vec::new();
+        let mut last_error = None;
+        for (fee, q) in tiers.iter().zip(full_quotes) {
+            match q {
+                Ok(q) => {
+                    quotes.insert((*fee, amount), q);
+                    usable.push(*fee);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        let Some(best_single) = usable
+            .iter()
+            .copied()
+            .max_by_key(|fee| quotes[&(*fee, amount)].amount_out)
+        else {
+            return Err(anyhow::anyhow!(
+                "No fee tier could quote the full amount for this pair{}",
+                last_error.map(|e| format!(" ({})", e)).unwrap_or_default()
+            ));
+        };
+
+        // Greedy allocation: each slice goes to the tier with the highest marginal output.
+        // Pool outputs are concave in input, so this is optimal at the chosen granularity.
+        let slice = amount / U256::from(steps);
+        let mut allocation: HashMap<u32, U256> = usable.iter().map(|f| (*f, U256::ZERO)).collect();
+        let mut allocated = U256::ZERO;
+        for i in 0..steps {
+            // The last slice absorbs the division remainder.
+            let size = if i == steps - 1 {
+                amount - allocated
+            } else {
+                slice
+            };
+            let missing: Vec<(u32, U256)> = usable
+                .iter()
+                .map(|fee| (*fee, allocation[fee] + size))
+                .filter(|key| !quotes.contains_key(key))
+                .collect();
+            let fetched = join_all(
+                missing
+                    .iter()
+                    .map(|(fee, amt)| quote(client, from_token, to_token, *fee, *amt)),
+            )
+            .await;
+            for (key, q) in missing.into_iter().zip(fetched) {
+                if let Ok(q) = q {
+                    quotes.insert(key, q);
+                }
+            }
+
+            let current = |fee: u32| {
+                let a = allocation[&fee];
+                if a.is_zero() {
+                    U256::ZERO
+                } else {
+                    quotes[&(fee, a)].amount_out
+                }
+            };
+            let best = usable
+                .iter()
+                .filter_map(|fee| {
+                    let next = quotes.get(&(*fee, allocation[fee] + size))?;
+                    Some((*fee, next.amount_out.saturating_sub(current(*fee))))
+                })
+                .max_by_key(|(_, marginal)| *marginal);
+            let Some((fee, _)) = best else {
+                return Err(anyhow::anyhow!(
+                    "Quotes failed while allocating slice {} of {}",
+                    i + 1,
+                    steps
+                ));
+            };
+            *allocation.get_mut(&fee).unwrap() += size;
+            allocated += size;
+        }
+
+        let mut legs: Vec<(u32, U256, Quote)> = allocation
+            .into_iter()
+            .filter(|(_, a)| !a.is_zero())
+            .map(|(fee, a)| (fee, a, quotes[&(fee, a)]))
+            .collect();
+        legs.sort_by_key(|(fee, _, _)| *fee);
+        let total_out: U256 = legs.iter().map(|(_, _, q)| q.amount_out).sum();
+        let total_gas: U256 = legs.iter().map(|(_, _, q)| q.gas_estimate).sum();
+        let single = quotes[&(best_single, amount)];
+        let improvement = total_out.saturating_sub(single.amount_out);
+
+        Ok(json!({
+            "from_token": from_token.to_string(),
+            "to_token": to_token.to_string(),
+            "amount_in": amount.to_string(),
+            "split": legs.iter().map(|(fee, a, q)| json!({
+                "fee": fee,
+                "amount_in": a.to_string(),
+                "share_percent": percent(*a, amount).round_dp(2),
+                "amount_out": q.amount_out.to_string(),
+                "gas_estimate": q.gas_estimate.to_string()
+            })).collect::<Vec<_>>(),
+            "total_output": total_out.to_string(),
+            "total_gas_estimate": total_gas.to_string(),
+            "best_single_pool": {
+                "fee": best_single,
+                "amount_out": single.amount_out.to_string(),
+                "gas_estimate": single.gas_estimate.to_string()
+            },
+            "improvement": improvement.to_string(),
+            "improvement_percent": percent(improvement, single.amount_out).round_dp(4),
+            "steps": steps,
+            "quotes_used": quotes.len(),
+            "token_resolution": {
+                "from_token": from_resolution,
+                "to_token": to_resolution
+            },
+            "note": "Each extra pool is a separate swap with its own gas; compare the improvement against total_gas_estimate before splitting."
+        }))
+    }
+}
+
+async fn quote(
+    client: &EthereumClient,
+    from_token: Address,
+    to_token: Address,
+    fee: u32,
+    amount: U256,
+) -> Result<Quote> {
+    let q =
+        quoter::quote_single(client, SwapMode::ExactIn, from_token, to_token, fee, amount).await?;
+    Ok(Quote {
+        amount_out: q.amount_out,
+        gas_estimate: q.gas_estimate,
+    })
+}
+
+fn percent(part: U256, whole: U256) -> Decimal {
+    if whole.is_zero() {
+        return Decimal::ZERO;
+    }
+    let part = Decimal::from_str(&part.to_string()).unwrap_or(Decimal::ZERO);
+    let whole = Decimal::from_str(&whole.to_string()).unwrap_or(Decimal::ONE);
+    part / whole * Decimal::from(100)
+}
@@ -1,17 +1,27 @@
-use super::Tool;
-use crate::ethereum::EthereumClient;
+use super::balance::format_units;
+use super::convert::is_eth;
+use super::price::{liquid_pools, price_args_for, verify_pool, GetTokenPriceTool, FEE_TIERS};
+use super::{f64_arg, fee_arg, resolve_address, transaction_sender, u64_arg, Tool};
+use crate::chains::UniswapV3;
+use crate::error::ToolError;
+use crate::ethereum::{revert_data, revert_reason, EthereumClient};
+use crate::{approval, oracle, quoter, simulation};
 use alloy::{
-    primitives::{Address, U256},
+    eips::BlockNumberOrTag,
+    primitives::{address, Address, Bytes, U256},
     providers::Provider,
     rpc::types::eth::TransactionRequest,
     sol,
     sol_types::SolCall,
+    transports::TransportError,
 };
 use anyhow::Result;
+use futures::future::join_all;
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
 use serde_json::{json, Value};
 use std::str::FromStr;
+use std::time::Duration;
 
 // Uniswap V3 QuoterV2 Interface
 sol! {
@@ -33,6 +43,25 @@ sol! {
             uint32 initializedTicksCrossed,
             uint256 gasEstimate
         );
+
+    #[allow(missing_docs)]
+    struct QuoteExactOutputSingleParams {
+        address tokenIn;
+        address tokenOut;
+        uint256 amount;
+        uint24 fee;
+        uint160 sqrtPriceLimitX96;
+    }
+
+    #[allow(missing_docs)]
+    function quoteExactOutputSingle(QuoteExactOutputSingleParams memory params)
+        external
+        returns (
+            uint256 amountIn,
+            uint160 sqrtPriceX96After,
+            uint32 initializedTicksCrossed,
+            uint256 gasEstimate
+        );
 }
 
 // Uniswap V3 SwapRouter Interface
@@ -51,6 +80,217 @@ sol! {
 
     #[allow(missing_docs)]
     function exactInputSingle(ExactInputSingleParams calldata params) external payable returns (uint256 amountOut);
+
+    #[allow(missing_docs)]
+    struct ExactOutputSingleParams {
+        address tokenIn;
+        address tokenOut;
+        uint24 fee;
+        address recipient;
+        uint256 deadline;
+        uint256 amountOut;
+        uint256 amountInMaximum;
+        uint160 sqrtPriceLimitX96;
+    }
+
+    #[allow(missing_docs)]
+    function exactOutputSingle(ExactOutputSingleParams calldata params) external payable returns (uint256 amountIn);
+
+    #[allow(missing_docs)]
+    function multicall(bytes[] data) external payable returns (bytes[] results);
+    #[allow(missing_docs)]
+    function unwrapWETH9(uint256 amountMinimum, address recipient) external payable;
+    #[allow(missing_docs)]
+    function refundETH() external payable;
+}
+
+/// Placeholder address some APIs and wallets use for native ETH.
+pub const NATIVE_ETH: Address = address!("EeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE");
+
+// SwapRouter02 (IV3SwapRouter): the same call without the deadline field.
+mod router02 {
+    alloy::sol! {
+        #[allow(missing_docs)]
+        struct ExactInputSingleParams {
+            address tokenIn;
+            address tokenOut;
+            uint24 fee;
+            address recipient;
+            uint256 amountIn;
+            uint256 amountOutMinimum;
+            uint160 sqrtPriceLimitX96;
+        }
+
+        #[allow(missing_docs)]
+        function exactInputSingle(ExactInputSingleParams calldata params) external payable returns (uint256 amountOut);
+
+        #[allow(missing_docs)]
+        struct ExactOutputSingleParams {
+            address tokenIn;
+            address tokenOut;
+            uint24 fee;
+            address recipient;
+            uint256 amountOut;
+            uint256 amountInMaximum;
+            uint160 sqrtPriceLimitX96;
+        }
+
+        #[allow(missing_docs)]
+        function exactOutputSingle(ExactOutputSingleParams calldata params) external payable returns (uint256 amountIn);
+
+        // The deadline moved here from the swap params.
+        #[allow(missing_docs)]
+        function multicall(uint256 deadline, bytes[] data) external payable returns (bytes[] results);
+    }
+}
+
+/// Whether `amount` is what is sold (`exact_in`) or what is bought (`exact_out`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapMode {
+    ExactIn,
+    ExactOut,
+}
+
+impl SwapMode {
+    pub fn parse(value: Option<&str>) -> Result<Self> {
+        match value {
+            None | Some("exact_in") => Ok(Self::ExactIn),
+            Some("exact_out") => Ok(Self::ExactOut),
+            Some(other) => Err(anyhow::anyhow!(
+                "mode must be \"exact_in\" or \"exact_out\", got {:?}",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ExactIn => "exact_in",
+            Self::ExactOut => "exact_out",
+        }
+    }
+}
+
+/// `exactInputSingle` calldata for the chain's router. SwapRouter02 deployments drop the
+/// deadline; both return `amountOut`, so results decode with `exactInputSingleCall`.
+pub fn exact_input_single_calldata(uniswap: &UniswapV3, params: ExactInputSingleParams) -> Vec<u8> {
+    if !uniswap.router_02 {
+        return exactInputSingleCall { params }.abi_encode();
+    }
+    router02::exactInputSingleCall {
+        params: router02::ExactInputSingleParams {
+            tokenIn: params.tokenIn,
+            tokenOut: params.tokenOut,
+            fee: params.fee,
+            recipient: params.recipient,
+            amountIn: params.amountIn,
+            amountOutMinimum: params.amountOutMinimum,
+            sqrtPriceLimitX96: params.sqrtPriceLimitX96,
+        },
+    }
+    .abi_encode()
+}
+
+/// `exactOutputSingle` calldata for the chain's router; see `exact_input_single_calldata`.
+pub fn exact_output_single_calldata(
+    uniswap: &UniswapV3,
+    params: ExactOutputSingleParams,
+) -> Vec<u8> {
+    if !uniswap.router_02 {
+        return exactOutputSingleCall { params }.abi_encode();
+    }
+    router02::exactOutputSingleCall {
+        params: router02::ExactOutputSingleParams {
+            tokenIn: params.tokenIn,
+            tokenOut: params.tokenOut,
+            fee: params.fee,
+            recipient: params.recipient,
+            amountOut: params.amountOut,
+            amountInMaximum: params.amountInMaximum,
+            sqrtPriceLimitX96: params.sqrtPriceLimitX96,
+        },
+    }
+    .abi_encode()
+}
+
+/// Batches a swap whose output the router kept with `unwrapWETH9`, so `recipient` receives
+/// native ETH in the same transaction.
+pub fn with_unwrap(swap_call: Vec<u8>, amount_minimum: U256, recipient: Address) -> Vec<u8> {
+    multicallCall {
+        data: vec![
+            Bytes::from(swap_call),
+            Bytes::from(
+                unwrapWETH9Call {
+                    amountMinimum: amount_minimum,
+                    recipient,
+                }
+                .abi_encode(),
+            ),
+        ],
+    }
+    .abi_encode()
+}
+
+/// Output of the router call: `exactInputSingle`'s return value, unwrapped from `multicall`
+/// results when the swap was batched with `unwrapWETH9`.
+pub fn decode_amount_out(data: &[u8], batched: bool) -> Option<U256> {
+    first_return::<exactInputSingleCall>(data, batched).map(|r| r.amountOut)
+}
+
+/// `exactOutputSingle`'s `amountIn`, the same way as `decode_amount_out`.
+pub fn decode_amount_in(data: &[u8], batched: bool) -> Option<U256> {
+    first_return::<exactOutputSingleCall>(data, batched).map(|r| r.amountIn)
+}
+
+/// Unix time by which the swap must be mined: `seconds` after the latest block, or after
+/// the local clock if the block can't be read. Returns the deadline and where its base came
+/// from.
+pub async fn swap_deadline(client: &EthereumClient, seconds: u64) -> (u64, &'static str) {
+    match client
+        .provider
+        .get_block_by_number(BlockNumberOrTag::Latest, false)
+        .await
+    {
+        Ok(Some(block)) => (block.header.timestamp + seconds, "latest block"),
+        _ => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            (now + seconds, "local clock")
+        }
+    }
+}
+
+fn first_return<C: SolCall>(data: &[u8], batched: bool) -> Option<C::Return> {
+    let swap_return = if batched {
+        multicallCall::abi_decode_returns(data, true)
+            .ok()?
+            .results
+            .into_iter()
+            .next()?
+    } else {
+        Bytes::copy_from_slice(data)
+    };
+    C::abi_decode_returns(&swap_return, true).ok()
+}
+
+/// A swap side: `ETH` (or `NATIVE_ETH`) is the chain's WETH, flagged native; anything else
+/// must be an address or an ENS name.
+pub(crate) async fn swap_token(
+    client: &EthereumClient,
+    args: &Value,
+    key: &str,
+) -> Result<(Address, bool)> {
+    let raw = args[key].as_str().ok_or_else(|| ToolError::missing(key))?;
+    if raw.eq_ignore_ascii_case("ETH") {
+        return Ok((client.chain()?.weth, true));
+    }
+    let token = resolve_address(client, raw).await?;
+    if token == NATIVE_ETH {
+        return Ok((client.chain()?.weth, true));
+    }
+    Ok((token, false))
 }
 
 pub struct SwapTokensTool;
@@ -61,8 +301,19 @@ impl Tool for SwapTokensTool {
         "swap_tokens"
     }
 
+    fn large_fields(&self) -> &'static [&'static str] {
+        &["/transaction/data"]
+    }
+
+    /// With `execute` and `auto_approve`, an approval may be sent and confirmed first.
+    fn wait_budget(&self, args: &Value) -> Option<Duration> {
+        let flag = |key: &str| args.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+        (flag("execute") && flag("auto_approve"))
+            .then(|| Duration::from_secs(approval::APPROVAL_TIMEOUT_SECS))
+    }
+
     fn description(&self) -> &'static str {
-        "Simulate a token swap on Uniswap V3 and construct the transaction."
+        "Simulate a token swap on Uniswap V3 and construct the transaction. With execute, also sign and send it from the server wallet. With unsigned (or execute in read-only mode), return the approval (if needed) and swap as fully populated EIP-1559 transactions for an external signer instead."
     }
 
     fn schema(&self) -> Value {
@@ -71,23 +322,76 @@ impl Tool for SwapTokensTool {
             "properties": {
                 "from_token": {
                     "type": "string",
-                    "description": "Address of the token to sell"
+                    "description": "Address of the token to sell, or \"ETH\" (or 0xEeee...EEeE) to sell native ETH, sent as the transaction value"
                 },
                 "to_token": {
                     "type": "string",
-                    "description": "Address of the token to buy"
+                    "description": "Address of the token to buy, or \"ETH\" (or 0xEeee...EEeE) to receive native ETH, unwrapped in the same transaction"
                 },
                 "amount": {
                     "type": "string",
-                    "description": "Amount of from_token to sell (in base units)"
+                    "description": "In base units: the amount of from_token to sell (exact_in), or of to_token to buy (exact_out)"
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["exact_in", "exact_out"],
+                    "description": "exact_in (default): sell exactly `amount`, with slippage bounding the minimum output. exact_out: buy exactly `amount`, with slippage bounding the maximum input."
                 },
                 "fee": {
                     "type": "integer",
-                    "description": "Pool fee tier (e.g., 500, 3000, 10000). Default 3000."
+                    "description": "Pool fee tier (e.g., 500, 3000, 10000). Default: whichever of 100, 500, 3000 and 10000 quotes best."
+                },
+                "pool_address": {
+                    "type": "string",
+                    "description": "Swap through this pool; its fee tier is read from the pool. The pool must be the factory's pool for from_token/to_token."
                 },
                 "slippage_tolerance": {
                     "type": "number",
-                    "description": "Slippage tolerance in percentage (e.g., 0.5 for 0.5%). Default 0.5."
+                    "description": "Slippage tolerance in percentage (e.g., 0.5 for 0.5%), from 0 to 100. Default 0.5."
+                },
+                "deadline_seconds": {
+                    "type": "integer",
+                    "description": "Seconds after the latest block's timestamp before the router rejects the swap. Default 1200 (20 minutes)."
+                },
+                "ignore_oracle_deviation": {
+                    "type": "boolean",
+                    "description": "Build the swap even if its rate is worse than the Chainlink rate by more than the server's MAX_ORACLE_DEVIATION_PERCENT. Default false."
+                },
+                "include_net_output": {
+                    "type": "boolean",
+                    "description": "Also report output net of gas: the estimated gas cost converted into to_token and subtracted from the quote. Default false."
+                },
+                "max_gas_fraction": {
+                    "type": "number",
+                    "description": "With include_net_output, flag the swap when gas costs more than this fraction of the gross output. Default 0.05 (5%)."
+                },
+                "include_balance_changes": {
+                    "type": "boolean",
+                    "description": "Trace the router simulation and report per-account ETH/ERC20 balance deltas. Requires debug_traceCall. Default false."
+                },
+                "max_quote_discrepancy": {
+                    "type": "number",
+                    "description": "Percent the router simulation's output may differ from the Quoter's before quote_consistency warns. Default 1."
+                },
+                "execute": {
+                    "type": "boolean",
+                    "description": "Sign and broadcast the swap from the server wallet after simulating it. Default false."
+                },
+                "auto_approve": {
+                    "type": "boolean",
+                    "description": "With execute: if the router's allowance is too low, send an approval (sized by the server's APPROVAL_POLICY), wait for it to confirm, then swap. Default false, which fails with the allowance shortfall instead."
+                },
+                "private": {
+                    "type": "boolean",
+                    "description": "With execute: send through the PRIVATE_RPC_URL relay instead of the public mempool. Default false."
+                },
+                "unsigned": {
+                    "type": "boolean",
+                    "description": "Build the swap (after an approval, if auto_approve allows one) as unsigned transactions with nonce, gas and fees filled, instead of sending anything. Read-only mode does this for execute. Default false."
+                },
+                "from": {
+                    "type": "string",
+                    "description": "With unsigned: the account that will sign and receive the output (address or ENS name). Default: the server wallet; required in read-only mode."
                 }
             },
             "required": ["from_token", "to_token", "amount"]
@@ -95,117 +399,677 @@ impl Tool for SwapTokensTool {
     }
 
     async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
-        let from_token = Address::from_str(
-            args["from_token"]
-                .as_str()
-                .ok_or_else(|| anyhow::anyhow!("Missing from_token"))?,
-        )?;
-        let to_token = Address::from_str(
-            args["to_token"]
-                .as_str()
-                .ok_or_else(|| anyhow::anyhow!("Missing to_token"))?,
-        )?;
-        let amount_in = U256::from_str(
+        let amount = U256::from_str(
             args["amount"]
                 .as_str()
-                .ok_or_else(|| anyhow::anyhow!("Missing amount"))?,
+                .ok_or_else(|| ToolError::missing("amount"))?,
         )?;
-        let fee = (args.get("fee").and_then(|v| v.as_u64()).unwrap_or(3000) as u32) & 0xFFFFFF; // clamp to uint24
-        let slippage_percent = args
-            .get("slippage_tolerance")
-            .and_then(|v| v.as_f64())
-            .unwrap_or(0.5);
-
-        // Uniswap V3 QuoterV2 Address (Mainnet)
-        let quoter_address = Address::from_str("0x61fFE0149A332c47d847296F720a48855e9cb754")?;
-        // Uniswap V3 SwapRouter Address (Mainnet)
-        let router_address = Address::from_str("0xE592427A0AEce92De3Edee1F18E0157C05861564")?;
-
-        // 1. Simulate via Quoter to get estimated output
-        let quote_call_data = quoteExactInputSingleCall {
-            params: QuoteExactInputSingleParams {
-                tokenIn: from_token,
-                tokenOut: to_token,
-                amountIn: amount_in,
-                fee,
-                sqrtPriceLimitX96: U256::ZERO,
-            },
+        let (from_token, from_native) = swap_token(client, &args, "from_token").await?;
+        let (to_token, to_native) = swap_token(client, &args, "to_token").await?;
+        if from_token == to_token {
+            return Err(anyhow::anyhow!(
+                "from_token and to_token are the same asset; wrapping or unwrapping ETH is not a swap"
+            ));
+        }
+        let mode = SwapMode::parse(args.get("mode").and_then(|v| v.as_str()))?;
+        let requested_fee = fee_arg(&args)?;
+        let slippage_percent = slippage_arg(&args)?;
+        let deadline_seconds =
+            u64_arg(&args, "deadline_seconds")?.unwrap_or(DEFAULT_DEADLINE_SECONDS);
+        if deadline_seconds == 0 {
+            return Err(anyhow::anyhow!("deadline_seconds must be positive"));
         }
-        .abi_encode();
+        let include_balance_changes = args
+            .get("include_balance_changes")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let include_net_output = args
+            .get("include_net_output")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let max_gas_fraction = f64_arg(&args, "max_gas_fraction")?
+            .and_then(Decimal::from_f64)
+            .unwrap_or(Decimal::new(5, 2));
+        let ignore_oracle_deviation = args
+            .get("ignore_oracle_deviation")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let max_quote_discrepancy = f64_arg(&args, "max_quote_discrepancy")?
+            .and_then(Decimal::from_f64)
+            .unwrap_or(Decimal::from(DEFAULT_MAX_QUOTE_DISCREPANCY_PERCENT));
+        let flag = |key: &str| args.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+        let (execute, auto_approve, private) =
+            (flag("execute"), flag("auto_approve"), flag("private"));
 
-        let tx_req = TransactionRequest::default()
-            .to(quoter_address)
-            .input(quote_call_data.into());
+        // The router transaction pays out to, and is simulated from, the server wallet (or,
+        // unsigned, the account that will sign it).
+        let (signer, unsigned) = transaction_sender(client, &args).await?;
+        let build_unsigned = unsigned && (execute || flag("unsigned"));
+        let uniswap = client.uniswap_v3()?;
+        let router_address = uniswap.swap_router;
+
+        // Catch non-token addresses up front; otherwise the quoter reverts opaquely.
+        let from_decimals = ensure_erc20(client, from_token, "from_token").await?;
+        let to_decimals = ensure_erc20(client, to_token, "to_token").await?;
+
+        // The Quoter call for a fee tier: the output for exact_in, the input for exact_out.
+        let quote_request =
+            |fee: u32| quoter::request(client, &uniswap, mode, from_token, to_token, fee, amount);
+
+        // An explicit pool pins the fee tier; the router still routes by (pair, fee).
+        let mut prequoted = None;
+        let mut fee_selection = Value::Null;
+        let (fee, pool) = match args.get("pool_address").and_then(|v| v.as_str()) {
+            Some(pool) => {
+                let pool = Address::from_str(pool)?;
+                let pair = verify_pool(client, pool, from_token, to_token).await?;
+                if let Some(requested) = requested_fee.filter(|f| *f != pair.fee) {
+                    return Err(anyhow::anyhow!(
+                        "fee {} conflicts with pool {}, whose fee tier is {}",
+                        requested,
+                        pool,
+                        pair.fee
+                    ));
+                }
+                (
+                    pair.fee,
+                    json!({ "address": pool.to_string(), "fee": pair.fee }),
+                )
+            }
+            None => match requested_fee {
+                Some(fee) => (fee, Value::Null),
+                None => {
+                    // Quote every tier with liquidity and take the best price.
+                    let tiers = liquid_pools(client, &[(from_token, to_token)])
+                        .await?
+                        .remove(0);
+                    if tiers.is_empty() {
+                        return Err(anyhow::anyhow!(
+                            "No Uniswap V3 pool with liquidity for {}/{} at fee tiers {:?}",
+                            from_token,
+                            to_token,
+                            FEE_TIERS
+                        ));
+                    }
+                    let results =
+                        join_all(tiers.iter().map(|t| async move {
+                            client.call_cached(&quote_request(t.fee)).await
+                        }))
+                        .await;
+                    let quoted: Vec<Option<U256>> = results
+                        .iter()
+                        .map(|res| {
+                            let q = quoter::decode(&uniswap, mode, amount, res).ok()?;
+                            Some(match mode {
+                                SwapMode::ExactIn => q.amount_out,
+                                SwapMode::ExactOut => q.amount_in,
+                            })
+                        })
+                        .collect();
+                    // Most out for exact_in, least in for exact_out; the deepest pool if
+                    // no tier quotes, so its error is what gets reported.
+                    let best = (0..tiers.len())
+                        .filter(|i| quoted[*i].is_some())
+                        .max_by_key(|i| match mode {
+                            SwapMode::ExactIn => quoted[*i],
+                            SwapMode::ExactOut => quoted[*i].map(|a| U256::MAX - a),
+                        })
+                        .unwrap_or(0);
+                    fee_selection = json!({
+                        "chosen_fee": tiers[best].fee,
+                        "basis": match mode {
+                            SwapMode::ExactIn => "highest quoted output",
+                            SwapMode::ExactOut => "lowest quoted input",
+                        },
+                        "candidates": tiers.iter().zip(&quoted).map(|(t, q)| json!({
+                            "fee": t.fee,
+                            "pool": t.pool.to_string(),
+                            "liquidity": t.liquidity.to_string(),
+                            "quoted_amount": q.map(|a| a.to_string())
+                        })).collect::<Vec<_>>()
+                    });
+                    let fee = tiers[best].fee;
+                    prequoted = results.into_iter().nth(best);
+                    (fee, Value::Null)
+                }
+            },
+        };
 
-        let result = client.provider.call(&tx_req).await?;
+        // 1. Simulate via Quoter to get the estimated output (or, for exact_out, input)
+        let result = match prequoted {
+            Some(result) => result,
+            None => client.call_cached(&quote_request(fee)).await,
+        };
         let mut decode_error: Option<String> = None;
-        let (amount_out, gas_estimate_quote) =
-            match quoteExactInputSingleCall::abi_decode_returns(&result, true) {
-                Ok(v) => (v.amountOut, v.gasEstimate),
+        let (amount_in, amount_out, gas_estimate_quote) =
+            match quoter::decode(&uniswap, mode, amount, &result) {
+                Ok(q) => (q.amount_in, q.amount_out, q.gas_estimate),
+                // A failed call without a quote in its revert data aborts; a return the
+                // configured quoter's ABI doesn't fit is reported in quoter_decode_error.
                 Err(e) => {
+                    result?;
                     decode_error = Some(e.to_string());
-                    (U256::ZERO, U256::ZERO)
+                    match mode {
+                        SwapMode::ExactIn => (amount, U256::ZERO, U256::ZERO),
+                        SwapMode::ExactOut => (U256::ZERO, amount, U256::ZERO),
+                    }
                 }
             };
 
-        // 2. Calculate Minimum Output with Slippage
-        let amount_out_decimal = Decimal::from_str(&amount_out.to_string())?;
-        let slippage_decimal =
-            Decimal::from_f64(slippage_percent).unwrap_or(Decimal::ZERO) / Decimal::from(100);
-        let amount_out_min_decimal = amount_out_decimal * (Decimal::ONE - slippage_decimal);
-        let amount_out_min_str = amount_out_min_decimal.floor().to_string();
-        let amount_out_min = U256::from_str(&amount_out_min_str)?;
+        // Guardrail: refuse to build a swap that prices far below the oracle.
+        let oracle_check = match client.max_oracle_deviation {
+            Some(max_deviation) if !amount_out.is_zero() && !amount_in.is_zero() => {
+                match oracle::pair_rate(client, from_token, to_token).await {
+                    // A stale or unreadable feed can't vouch for the rate: refuse unless told
+                    // to go ahead without the check.
+                    Err(e) if !ignore_oracle_deviation => {
+                        let message = format!(
+                            "Oracle rate unavailable for the swap guardrail ({:#}). Pass ignore_oracle_deviation: true to build it anyway.",
+                            e
+                        );
+                        return Err(e.context(message));
+                    }
+                    Err(e) => json!({
+                        "status": "unchecked",
+                        "message": format!("{:#}", e)
+                    }),
+                    Ok(Some(oracle_rate)) => {
+                        let execution_rate =
+                            Decimal::from_str(&format_units(amount_out, to_decimals)?)?
+                                / Decimal::from_str(&format_units(amount_in, from_decimals)?)?;
+                        let deviation = oracle::deviation_percent(oracle_rate, execution_rate);
+                        let exceeded = deviation > max_deviation;
+                        if exceeded && !ignore_oracle_deviation {
+                            return Err(anyhow::anyhow!(
+                                "Execution rate {} is {}% worse than the Chainlink rate {} (limit {}%). Pass ignore_oracle_deviation: true to build it anyway.",
+                                execution_rate.round_dp(8),
+                                deviation.round_dp(2),
+                                oracle_rate.round_dp(8),
+                                max_deviation
+                            ));
+                        }
+                        json!({
+                            "status": if exceeded { "overridden" } else { "ok" },
+                            "oracle_rate": oracle_rate.round_dp(8),
+                            "execution_rate": execution_rate.round_dp(8),
+                            "deviation_percent": deviation.round_dp(4),
+                            "max_deviation_percent": max_deviation
+                        })
+                    }
+                    Ok(None) => json!({
+                        "status": "no_feed",
+                        "message": "No Chainlink USD feed for one of the tokens; rate not checked"
+                    }),
+                }
+            }
+            _ => Value::Null,
+        };
+
+        // 2. Bound the side that floats with slippage: minimum output for exact_in, maximum
+        // input for exact_out.
+        let (amount_out_min, amount_in_max) =
+            slippage_bounds(mode, amount_in, amount_out, slippage_percent)?;
 
         // 3. Construct Real Transaction for Router
-        let router_params = ExactInputSingleParams {
-            tokenIn: from_token,
-            tokenOut: to_token,
-            fee,
-            recipient: client.signer_address, // Send to self
-            deadline: U256::MAX,              // No deadline for simulation
-            amountIn: amount_in,
-            amountOutMinimum: amount_out_min,
-            sqrtPriceLimitX96: U256::ZERO,
+        let (deadline, deadline_base) = swap_deadline(client, deadline_seconds).await;
+        // To pay out native ETH the router keeps the WETH until unwrapWETH9 sends it on.
+        let recipient = if to_native { router_address } else { signer };
+        let router_call_data = match mode {
+            SwapMode::ExactIn => exact_input_single_calldata(
+                &uniswap,
+                ExactInputSingleParams {
+                    tokenIn: from_token,
+                    tokenOut: to_token,
+                    fee,
+                    recipient,
+                    deadline: U256::from(deadline),
+                    amountIn: amount_in,
+                    amountOutMinimum: amount_out_min,
+                    sqrtPriceLimitX96: U256::ZERO,
+                },
+            ),
+            SwapMode::ExactOut => exact_output_single_calldata(
+                &uniswap,
+                ExactOutputSingleParams {
+                    tokenIn: from_token,
+                    tokenOut: to_token,
+                    fee,
+                    recipient,
+                    deadline: U256::from(deadline),
+                    amountOut: amount_out,
+                    amountInMaximum: amount_in_max,
+                    sqrtPriceLimitX96: U256::ZERO,
+                },
+            ),
         };
-
-        let router_call_data = exactInputSingleCall {
-            params: router_params,
+        let mut router_calls = vec![("exactInputSingle", router_call_data)];
+        if mode == SwapMode::ExactOut {
+            router_calls[0].0 = "exactOutputSingle";
+        }
+        if to_native {
+            let unwrap = unwrapWETH9Call {
+                amountMinimum: amount_out_min,
+                recipient: signer,
+            };
+            router_calls.push(("unwrapWETH9", unwrap.abi_encode()));
+        } else if from_native && mode == SwapMode::ExactOut {
+            // Exact-output ETH swaps send the maximum; refundETH returns what wasn't spent.
+            router_calls.push(("refundETH", refundETHCall {}.abi_encode()));
         }
-        .abi_encode();
+        let functions: Vec<&str> = router_calls.iter().map(|(name, _)| *name).collect();
+        let data: Vec<Bytes> = router_calls
+            .iter()
+            .map(|(_, call)| Bytes::from(call.clone()))
+            .collect();
+        // SwapRouter02 only enforces a deadline through multicall(deadline, data).
+        let batched = uniswap.router_02 || data.len() > 1;
+        let (router_call_data, description) = if uniswap.router_02 {
+            (
+                router02::multicallCall {
+                    deadline: U256::from(deadline),
+                    data,
+                }
+                .abi_encode(),
+                format!(
+                    "Uniswap V3 SwapRouter02.multicall(deadline, [{}])",
+                    functions.join(", ")
+                ),
+            )
+        } else if batched {
+            (
+                multicallCall { data }.abi_encode(),
+                format!("Uniswap V3 SwapRouter.multicall({})", functions.join(", ")),
+            )
+        } else {
+            (
+                router_calls.remove(0).1,
+                format!("Uniswap V3 SwapRouter.{}", functions[0]),
+            )
+        };
         let router_call_hex = hex::encode(&router_call_data);
+        // The payable router wraps msg.value itself when tokenIn is WETH.
+        let value = if from_native {
+            amount_in_max
+        } else {
+            U256::ZERO
+        };
 
         // 4. Simulate the router transaction via eth_call (read-only)
         let router_sim_tx = TransactionRequest::default()
             .to(router_address)
-            .from(client.signer_address)
+            .from(signer)
+            .value(value)
             .input(router_call_data.clone().into());
+        // The floating side: amountOut for exact_in, amountIn for exact_out.
+        let (quoted_amount, simulated_key) = match mode {
+            SwapMode::ExactIn => (amount_out, "simulated_amount_out"),
+            SwapMode::ExactOut => (amount_in, "simulated_amount_in"),
+        };
+        let mut simulated_amount = None;
         let router_simulation = match client.provider.call(&router_sim_tx).await {
             Ok(data) => {
-                // If it succeeds, decode the returned amountOut.
-                match exactInputSingleCall::abi_decode_returns(&data, true) {
-                    Ok(sim_amount_out) => json!({
-                        "status": "ok",
-                        "simulated_amount_out": sim_amount_out.amountOut.to_string()
-                    }),
-                    Err(_) => json!({"status": "ok", "message": "call succeeded"}),
+                // If it succeeds, decode the returned amount.
+                let decoded = match mode {
+                    SwapMode::ExactIn => decode_amount_out(&data, batched),
+                    SwapMode::ExactOut => decode_amount_in(&data, batched),
+                };
+                match decoded {
+                    Some(amount) => {
+                        simulated_amount = Some(amount);
+                        json!({
+                            "status": "ok",
+                            simulated_key: amount.to_string()
+                        })
+                    }
+                    None => json!({"status": "ok", "message": "call succeeded"}),
                 }
             }
-            Err(e) => json!({"status": "error", "message": e.to_string()}),
+            Err(e) => json!({
+                "status": "error",
+                "message": e.to_string(),
+                "revert_reason": revert_data(&e).as_deref().and_then(revert_reason)
+            }),
+        };
+        // Both read the same pool, so a gap means the reads saw different states (e.g.
+        // load-balanced nodes at different blocks) and the quote is not stable.
+        let quote_consistency = match simulated_amount {
+            Some(simulated) if !quoted_amount.is_zero() => {
+                quote_discrepancy(quoted_amount, simulated, max_quote_discrepancy)?
+            }
+            _ => Value::Null,
+        };
+
+        // 5. Optionally trace the same call to report who gains/loses what.
+        let balance_changes = if include_balance_changes {
+            match simulation::trace_balance_changes(client, &router_sim_tx).await {
+                Ok(changes) => json!({
+                    "status": "ok",
+                    "source": "debug_traceCall (callTracer)",
+                    "changes": changes.iter().map(|c| c.to_json()).collect::<Vec<_>>()
+                }),
+                // Tracer unavailable (most public RPCs): the decoded output is all we have.
+                Err(e) => json!({
+                    "status": "unavailable",
+                    "message": e.to_string(),
+                    "decoded_output": router_simulation.clone()
+                }),
+            }
+        } else {
+            Value::Null
+        };
+
+        // 6. Optionally express the quote net of the gas it costs to execute.
+        let net_output = if include_net_output {
+            match estimate_net_output(
+                client,
+                to_token,
+                amount_out,
+                to_decimals,
+                gas_estimate_quote,
+                max_gas_fraction,
+            )
+            .await
+            {
+                Ok(net) => net,
+                Err(e) => json!({ "status": "unavailable", "message": e.to_string() }),
+            }
+        } else {
+            Value::Null
+        };
+
+        // 7. Optionally execute, or build the transactions for an external signer: approval
+        // first if needed (and allowed), then the swap.
+        let (execution, unsigned_transactions) = if execute || build_unsigned {
+            if quoted_amount.is_zero() {
+                return Err(anyhow::anyhow!(
+                    "Not executing: the quote failed ({})",
+                    decode_error.as_deref().unwrap_or("zero amount")
+                ));
+            }
+            // Native ETH travels as msg.value and needs no allowance.
+            let current = if from_native {
+                U256::MAX
+            } else {
+                approval::allowance(client, from_token, signer, router_address).await?
+            };
+            // Exact-output swaps may pull up to the slippage-bounded maximum.
+            let needed = amount_in_max;
+            let approval_amount = if current < needed {
+                if !auto_approve {
+                    return Err(anyhow::anyhow!(
+                        "Insufficient allowance: the router may spend {} of {} but the swap needs {}; approve {} for {} first, or pass auto_approve: true",
+                        current,
+                        from_token,
+                        needed,
+                        needed,
+                        router_address
+                    ));
+                }
+                Some(client.approval_policy.amount(needed))
+            } else {
+                None
+            };
+            let mut swap_tx = TransactionRequest::default()
+                .to(router_address)
+                .value(value)
+                .input(router_call_data.clone().into());
+            if build_unsigned {
+                let mut transactions = Vec::new();
+                if let Some(amount) = approval_amount {
+                    let approval = client
+                        .unsigned_transaction(
+                            approval::approve_request(from_token, router_address, amount),
+                            signer,
+                        )
+                        .await?;
+                    // The swap can't be estimated until the approval is mined, so the
+                    // Quoter's estimate stands in.
+                    if gas_estimate_quote.is_zero() {
+                        return Err(anyhow::anyhow!(
+                            "Cannot estimate the swap's gas before its approval is mined and the quoter gave no estimate; sign and send the approval first, then build the swap"
+                        ));
+                    }
+                    swap_tx = swap_tx.nonce(approval.tx.nonce + 1).gas_limit(
+                        gas_estimate_quote.saturating_to::<u128>() + u128::from(BASE_TX_GAS),
+                    );
+                    transactions.push(approval.to_json());
+                }
+                transactions.push(
+                    client
+                        .unsigned_transaction(swap_tx, signer)
+                        .await?
+                        .to_json(),
+                );
+                (Value::Null, json!(transactions))
+            } else {
+                let approval = match approval_amount {
+                    Some(amount) => Some(
+                        approval::approve_and_wait(
+                            client,
+                            from_token,
+                            router_address,
+                            amount,
+                            private,
+                        )
+                        .await?,
+                    ),
+                    None => None,
+                };
+                let swap = client.submit_transaction(swap_tx, private).await?;
+                (
+                    json!({
+                        "approval": approval,
+                        "swap": swap.to_json()
+                    }),
+                    Value::Null,
+                )
+            }
+        } else {
+            (Value::Null, Value::Null)
         };
 
         Ok(json!({
+            "mode": mode.as_str(),
+            "estimated_input": amount_in.to_string(),
+            "maximum_input": amount_in_max.to_string(),
             "estimated_output": amount_out.to_string(),
             "minimum_output": amount_out_min.to_string(),
             "gas_estimate_simulation": gas_estimate_quote.to_string(),
             "transaction": {
                 "to": router_address.to_string(),
                 "data": format!("0x{}", router_call_hex),
-                "value": "0", // Assuming ERC20 swap. If ETH, need to handle value.
-                "description": "Uniswap V3 SwapRouter.exactInputSingle"
+                "value": value.to_string(),
+                "description": description,
+                "deadline": deadline,
+                "deadline_seconds": deadline_seconds,
+                "deadline_base": deadline_base
+            },
+            "native_eth": {
+                "input": from_native,
+                "output": to_native
             },
+            "fee": fee,
+            "fee_selection": fee_selection,
+            "pool": pool,
             "router_call_simulation": router_simulation,
+            "quote_consistency": quote_consistency,
+            "balance_changes": balance_changes,
+            "oracle_check": oracle_check,
+            "net_output": net_output,
+            "execution": execution,
+            "unsigned_transactions": unsigned_transactions,
             "simulation_note": "Gas estimate is from Quoter. Router eth_call included; actual execution still depends on approvals/balance."
             , "quoter_decode_error": decode_error
         }))
     }
 }
+
+/// The caller's `slippage_tolerance` in percent, `DEFAULT_SLIPPAGE_PERCENT` when omitted.
+/// Below 0 the minimum output would exceed the quote (a certain revert) and above 100 it
+/// would be negative, so both are errors.
+pub fn slippage_arg(args: &Value) -> Result<Decimal> {
+    let percent = f64_arg(args, "slippage_tolerance")?.unwrap_or(DEFAULT_SLIPPAGE_PERCENT);
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(anyhow::anyhow!(
+            "slippage_tolerance must be a percentage from 0 to 100, got {}",
+            percent
+        ));
+    }
+    Decimal::from_f64(percent).ok_or_else(|| {
+        anyhow::anyhow!(
+            "slippage_tolerance {} is not representable as a decimal",
+            percent
+        )
+    })
+}
+
+/// The side of a swap that floats with `slippage_percent`: the minimum output for
+/// exact_in (rounded down) and the maximum input for exact_out (rounded up). The fixed
+/// side is returned unchanged. Returns `(amount_out_min, amount_in_max)`.
+pub fn slippage_bounds(
+    mode: SwapMode,
+    amount_in: U256,
+    amount_out: U256,
+    slippage_percent: Decimal,
+) -> Result<(U256, U256)> {
+    if !(Decimal::ZERO..=Decimal::ONE_HUNDRED).contains(&slippage_percent) {
+        return Err(anyhow::anyhow!(
+            "slippage must be a percentage from 0 to 100, got {}",
+            slippage_percent
+        ));
+    }
+    let slippage = slippage_percent / Decimal::ONE_HUNDRED;
+    match mode {
+        SwapMode::ExactIn => {
+            let amount_out_decimal = Decimal::from_str(&amount_out.to_string())?;
+            let amount_out_min = (amount_out_decimal * (Decimal::ONE - slippage)).floor();
+            Ok((U256::from_str(&amount_out_min.to_string())?, amount_in))
+        }
+        SwapMode::ExactOut => {
+            let amount_in_decimal = Decimal::from_str(&amount_in.to_string())?;
+            let amount_in_max = (amount_in_decimal * (Decimal::ONE + slippage)).ceil();
+            Ok((amount_out, U256::from_str(&amount_in_max.to_string())?))
+        }
+    }
+}
+
+/// Compares the Quoter's amount (`amountOut`, or `amountIn` for exact output) with the
+/// router simulation's, flagging a difference above `max_percent` of the quote.
+pub fn quote_discrepancy(quoted: U256, simulated: U256, max_percent: Decimal) -> Result<Value> {
+    let quoted_dec = Decimal::from_str(&quoted.to_string())?;
+    let simulated_dec = Decimal::from_str(&simulated.to_string())?;
+    let discrepancy = (simulated_dec - quoted_dec).abs() / quoted_dec * Decimal::from(100);
+    let exceeded = discrepancy > max_percent;
+    Ok(json!({
+        "status": if exceeded { "warning" } else { "ok" },
+        "quoter_amount": quoted.to_string(),
+        "router_amount": simulated.to_string(),
+        "discrepancy_percent": discrepancy.round_dp(4).normalize(),
+        "max_discrepancy_percent": max_percent,
+        "warning": if exceeded {
+            Some(format!(
+                "Router simulation returned {} but the Quoter quoted {} ({}% apart); the pool state differed between reads, so don't trust this quote. Re-quote before executing.",
+                simulated,
+                quoted,
+                discrepancy.round_dp(2).normalize()
+            ))
+        } else {
+            None
+        }
+    }))
+}
+
+pub(crate) async fn ensure_erc20(
+    client: &EthereumClient,
+    token: Address,
+    arg_name: &str,
+) -> Result<u8> {
+    match client.get_token_metadata(token).await {
+        Ok(metadata) => Ok(metadata.decimals),
+        // Only a revert or unusable return data is the token's answer; any other node error
+        // says nothing about it.
+        Err(e)
+            if e.downcast_ref::<TransportError>().is_some()
+                && !matches!(ToolError::classify(&e), ToolError::Revert { .. }) =>
+        {
+            Err(e)
+        }
+        Err(_) => Err(anyhow::anyhow!(
+            "{} ({}) does not appear to be an ERC20 token",
+            arg_name,
+            token
+        )),
+    }
+}
+
+// Intrinsic cost of any transaction; the Quoter's gasEstimate only covers the swap itself.
+const BASE_TX_GAS: u64 = 21_000;
+/// Quoter/router output gap, in percent, above which `quote_consistency` warns.
+pub const DEFAULT_MAX_QUOTE_DISCREPANCY_PERCENT: u64 = 1;
+/// Seconds from the latest block to the swap deadline when `deadline_seconds` is omitted.
+pub const DEFAULT_DEADLINE_SECONDS: u64 = 1200;
+/// Slippage tolerance, in percent, used when `slippage_tolerance` is omitted.
+pub const DEFAULT_SLIPPAGE_PERCENT: f64 = 0.5;
+
+async fn estimate_net_output(
+    client: &EthereumClient,
+    to_token: Address,
+    amount_out: U256,
+    to_decimals: u8,
+    quote_gas: U256,
+    max_gas_fraction: Decimal,
+) -> Result<Value> {
+    let gas_units = quote_gas + U256::from(BASE_TX_GAS);
+    let gas_price = client.provider.get_gas_price().await?;
+    let gas_cost_eth = Decimal::from_str(&format_units(gas_units * U256::from(gas_price), 18)?)?;
+
+    // Price of one output token in ETH, to express the gas cost in output units.
+    let to_token_eth = if is_eth(client, &to_token.to_string()) {
+        Decimal::ONE
+    } else {
+        let price = GetTokenPriceTool
+            .price(client, price_args_for(&to_token.to_string()))
+            .await?;
+        serde_json::from_value::<Decimal>(price["price_eth"].clone())?
+    };
+    if to_token_eth.is_zero() {
+        return Err(anyhow::anyhow!("to_token has a zero ETH price"));
+    }
+
+    let gross = Decimal::from_str(&format_units(amount_out, to_decimals)?)?;
+    let gas_in_output = gas_cost_eth / to_token_eth;
+    let (net, fraction, too_expensive) = net_of_gas(gross, gas_in_output, max_gas_fraction);
+
+    Ok(json!({
+        "status": "ok",
+        "gross_output": gross.normalize().to_string(),
+        "gas_units": gas_units.to_string(),
+        "gas_price_wei": gas_price.to_string(),
+        "gas_cost_eth": gas_cost_eth.normalize().to_string(),
+        "gas_cost_in_output": gas_in_output.round_dp(u32::from(to_decimals)).normalize().to_string(),
+        "net_output": net.round_dp(u32::from(to_decimals)).normalize().to_string(),
+        "gas_fraction": fraction.map(|f| f.round_dp(4)),
+        "gas_exceeds_threshold": too_expensive,
+        "max_gas_fraction": max_gas_fraction,
+        "message": if too_expensive {
+            Some("Gas eats more of this trade than max_gas_fraction allows; consider a larger size or skipping it.")
+        } else {
+            None
+        }
+    }))
+}
+
+/// Gross output minus gas (both in output-token units), gas as a fraction of the gross,
+/// and whether that fraction exceeds `max_fraction`. A zero gross output always flags.
+pub fn net_of_gas(
+    gross: Decimal,
+    gas_in_output: Decimal,
+    max_fraction: Decimal,
+) -> (Decimal, Option<Decimal>, bool) {
+    let net = gross - gas_in_output;
+    if gross.is_zero() {
+        return (net, None, true);
+    }
+    let fraction = gas_in_output / gross;
+    (net, Some(fraction), fraction > max_fraction)
+}
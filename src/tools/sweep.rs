@@ -0,0 +1,90 @@
+use super::balance::format_units;
+use super::{u64_arg, Tool};
+use crate::ethereum::EthereumClient;
+use crate::format::display_amount;
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+// Gas used by a plain ETH transfer to an EOA.
+const ETH_TRANSFER_GAS: u64 = 21_000;
+
+pub struct MaxSendableEthTool;
+
+#[async_trait::async_trait]
+impl Tool for MaxSendableEthTool {
+    fn name(&self) -> &'static str {
+        "get_max_sendable_eth"
+    }
+
+    fn description(&self) -> &'static str {
+        "Compute the maximum ETH an address can send (balance minus the gas the send itself costs at current fees)."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Address to sweep. Defaults to the server's signer."
+                },
+                "gas_limit": {
+                    "type": "integer",
+                    "description": "Gas limit of the send transaction. Default 21000 (plain ETH transfer)."
+                }
+            }
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let address = match args.get("address").and_then(|v| v.as_str()) {
+            Some(addr) => Address::from_str(addr)?,
+            None => client.signer_address.ok_or_else(|| {
+                anyhow::anyhow!("Pass address: the server is read-only and has no wallet address")
+            })?,
+        };
+        let gas_limit = u64_arg(&args, "gas_limit")?.unwrap_or(ETH_TRANSFER_GAS);
+
+        let balance = client.provider.get_balance(address).await?;
+
+        let (max_fee_per_gas, max_priority_fee_per_gas) = budget_fees(client).await?;
+
+        let gas_cost = U256::from(gas_limit) * U256::from(max_fee_per_gas);
+        if balance <= gas_cost {
+            return Err(anyhow::anyhow!(
+                "Balance too low to cover gas: balance {} ETH, gas cost {} ETH",
+                format_units(balance, 18)?,
+                format_units(gas_cost, 18)?
+            ));
+        }
+        let max_sendable = balance - gas_cost;
+
+        Ok(json!({
+            "address": address.to_string(),
+            "max_sendable": display_amount(&format_units(max_sendable, 18)?),
+            "raw_max_sendable": max_sendable.to_string(),
+            "balance": display_amount(&format_units(balance, 18)?),
+            "raw_balance": balance.to_string(),
+            "gas_limit": gas_limit,
+            "max_fee_per_gas": max_fee_per_gas.to_string(),
+            "max_priority_fee_per_gas": max_priority_fee_per_gas.to_string(),
+            "gas_cost": display_amount(&format_units(gas_cost, 18)?),
+            "raw_gas_cost": gas_cost.to_string()
+        }))
+    }
+}
+
+/// `(maxFeePerGas, maxPriorityFeePerGas)` to budget gas against, so a transaction is covered
+/// even if the base fee rises. Falls back to the legacy gas price (and no tip) on nodes
+/// without EIP-1559 fee history.
+pub(crate) async fn budget_fees(client: &EthereumClient) -> Result<(u128, u128)> {
+    match client.provider.estimate_eip1559_fees(None).await {
+        Ok(fees) => Ok((fees.max_fee_per_gas, fees.max_priority_fee_per_gas)),
+        Err(_) => Ok((client.provider.get_gas_price().await?, 0)),
+    }
+}
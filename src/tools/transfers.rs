@@ -0,0 +1,183 @@
+use super::balance::format_units;
+use super::events::{get_logs_chunked, DEFAULT_BLOCK_CHUNK};
+use super::{resolve_token_with_decision, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use crate::format::display_amount;
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::eth::Filter,
+    sol,
+    sol_types::SolEvent,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+sol! {
+    #[allow(missing_docs)]
+    event Transfer(address indexed from, address indexed to, uint256 value);
+}
+
+const DEFAULT_LIMIT: usize = 50;
+// Aggregates need every log in the range, so the range is capped instead of paginated.
+const DEFAULT_MAX_BLOCKS: u64 = 50_000;
+
+pub struct GetTokenTransfersTool;
+
+#[async_trait::async_trait]
+impl Tool for GetTokenTransfersTool {
+    fn name(&self) -> &'static str {
+        "get_token_transfers"
+    }
+
+    fn description(&self) -> &'static str {
+        "List the most recent ERC20 Transfer events of a token over a block range, with aggregate stats (unique senders/receivers, total volume, mints and burns)."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token_address": {
+                    "type": "string",
+                    "description": "ERC20 token contract address or registry symbol"
+                },
+                "from_block": {
+                    "type": "integer",
+                    "description": "First block of the range (inclusive). Default: 1000 blocks before to_block."
+                },
+                "to_block": {
+                    "type": ["integer", "string"],
+                    "description": "Last block of the range (inclusive) or \"latest\". Default latest."
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Number of most recent transfers to return. Default 50. Stats always cover the whole range."
+                },
+                "block_chunk": {
+                    "type": "integer",
+                    "description": "Maximum blocks per eth_getLogs request. Default 2000."
+                }
+            },
+            "required": ["token_address"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let token_arg = args["token_address"]
+            .as_str()
+            .ok_or_else(|| ToolError::missing("token_address"))?;
+        let (token, token_resolution) = resolve_token_with_decision(client, token_arg)?;
+        let to_block = match args.get("to_block") {
+            None | Some(Value::Null) => client.provider.get_block_number().await?,
+            Some(Value::String(s)) if s == "latest" => client.provider.get_block_number().await?,
+            Some(_) => u64_arg(&args, "to_block")
+                .ok()
+                .flatten()
+                .ok_or_else(|| anyhow::anyhow!("to_block must be a block number or \"latest\""))?,
+        };
+        let from_block =
+            u64_arg(&args, "from_block")?.unwrap_or_else(|| to_block.saturating_sub(999));
+        if from_block > to_block {
+            return Err(anyhow::anyhow!(
+                "from_block {} is after to_block {}",
+                from_block,
+                to_block
+            ));
+        }
+        if to_block - from_block + 1 > DEFAULT_MAX_BLOCKS {
+            return Err(anyhow::anyhow!(
+                "Block range of {} blocks exceeds the {} block limit; narrow the range",
+                to_block - from_block + 1,
+                DEFAULT_MAX_BLOCKS
+            ));
+        }
+        let limit = u64_arg(&args, "limit")?.map_or(DEFAULT_LIMIT, |n| n as usize);
+        let block_chunk = u64_arg(&args, "block_chunk")?.unwrap_or(DEFAULT_BLOCK_CHUNK);
+
+        let filter = Filter::new()
+            .address(token)
+            .event_signature(Transfer::SIGNATURE_HASH);
+        let mut logs = get_logs_chunked(client, &filter, from_block, to_block, block_chunk).await?;
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+
+        let decimals = token_decimals(client, token).await;
+        let amount = |raw: U256| -> Value {
+            decimals
+                .and_then(|d| format_units(raw, d).ok())
+                .map_or(Value::Null, |s| json!(display_amount(&s)))
+        };
+
+        let mut senders = HashSet::new();
+        let mut receivers = HashSet::new();
+        let mut volume = U256::ZERO;
+        let (mut mints, mut burns, mut skipped) = (0usize, 0usize, 0usize);
+        let mut transfers = Vec::new();
+        for log in &logs {
+            // ERC721 shares the Transfer topic but indexes the token id, so it fails to decode.
+            let Ok(event) = Transfer::decode_raw_log(log.topics(), &log.data().data, true) else {
+                skipped += 1;
+                continue;
+            };
+            if event.from == Address::ZERO {
+                mints += 1;
+            } else {
+                senders.insert(event.from);
+            }
+            if event.to == Address::ZERO {
+                burns += 1;
+            } else {
+                receivers.insert(event.to);
+            }
+            volume = volume.saturating_add(event.value);
+            transfers.push((log, event));
+        }
+
+        let total = transfers.len();
+        let recent: Vec<Value> = transfers
+            .iter()
+            .rev()
+            .take(limit)
+            .map(|(log, event)| {
+                json!({
+                    "from": event.from.to_string(),
+                    "to": event.to.to_string(),
+                    "amount": amount(event.value),
+                    "raw_amount": event.value.to_string(),
+                    "block_number": log.block_number,
+                    "transaction_hash": log.transaction_hash.map(|h| h.to_string()),
+                    "log_index": log.log_index
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "token": token.to_string(),
+            "token_resolution": token_resolution,
+            "decimals": decimals,
+            "from_block": from_block,
+            "to_block": to_block,
+            "transfers": recent,
+            "stats": {
+                "transfer_count": total,
+                "unique_senders": senders.len(),
+                "unique_receivers": receivers.len(),
+                "total_volume": amount(volume),
+                "raw_total_volume": volume.to_string(),
+                "mints": mints,
+                "burns": burns,
+                "undecodable_logs": skipped
+            }
+        }))
+    }
+}
+
+async fn token_decimals(client: &EthereumClient, token: Address) -> Option<u8> {
+    client
+        .get_token_metadata(token)
+        .await
+        .ok()
+        .map(|m| m.decimals)
+}
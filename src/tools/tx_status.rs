@@ -0,0 +1,108 @@
+use super::watch::get_transaction;
+use super::Tool;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use crate::format;
+use crate::rpc;
+use alloy::{primitives::TxHash, providers::Provider};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+pub struct GetTransactionStatusTool;
+
+#[async_trait::async_trait]
+impl Tool for GetTransactionStatusTool {
+    fn name(&self) -> &'static str {
+        "get_transaction_status"
+    }
+
+    fn description(&self) -> &'static str {
+        "Check a sent transaction once: success or reverted with block number, gas used, effective gas price and confirmations, or pending / not_found when it has no receipt yet."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tx_hash": {
+                    "type": "string",
+                    "description": "Transaction hash to check"
+                }
+            },
+            "required": ["tx_hash"]
+        })
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let tx_hash = TxHash::from_str(
+            args["tx_hash"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("tx_hash"))?,
+        )?;
+        // Raw JSON rather than a typed receipt, as in watch_transaction.
+        let receipt: Option<Value> = client
+            .provider
+            .raw_request("eth_getTransactionReceipt".into(), (tx_hash,))
+            .await?;
+        let Some(receipt) = receipt.filter(|r| !r.is_null()) else {
+            let tx = get_transaction(client, tx_hash).await?;
+            return Ok(json!({
+                "tx_hash": tx_hash.to_string(),
+                "status": if tx.is_some() { "pending" } else { "not_found" },
+                "block_number": Value::Null,
+                "block_timestamp": Value::Null,
+                "confirmations": 0,
+                "nonce": tx.as_ref().and_then(|t| rpc::quantity(t, "nonce")).map(|n| n as u64),
+                "from": tx.as_ref().and_then(|t| rpc::string(t, "from")),
+                "receipt": Value::Null,
+                "note": if tx.is_some() {
+                    "In the mempool, not yet mined."
+                } else {
+                    "Unknown to this node: not yet propagated, dropped, or replaced by another transaction with the same nonce."
+                }
+            }));
+        };
+
+        let head = client.provider.get_block_number().await?;
+        let mined_in = rpc::quantity(&receipt, "blockNumber").map(|n| n as u64);
+        let confirmations = mined_in.map_or(0, |n| head.saturating_sub(n) + 1);
+        let tx = if rpc::quantity(&receipt, "effectiveGasPrice").is_none() {
+            get_transaction(client, tx_hash).await?
+        } else {
+            None
+        };
+        let block: Option<Value> = match mined_in {
+            Some(n) => {
+                client
+                    .provider
+                    .raw_request("eth_getBlockByNumber".into(), (format!("0x{:x}", n), false))
+                    .await?
+            }
+            None => None,
+        };
+        let block_timestamp = block
+            .as_ref()
+            .and_then(|b| rpc::quantity(b, "timestamp"))
+            .map(|t| format::timestamp(t as u64));
+        let summary = rpc::receipt_summary(&receipt, tx.as_ref());
+        let status = match summary["success"].as_bool() {
+            Some(true) => "success",
+            Some(false) => "reverted",
+            // Pre-Byzantium receipts have a state root instead of a status.
+            None => "mined",
+        };
+        Ok(json!({
+            "tx_hash": tx_hash.to_string(),
+            "status": status,
+            "block_number": mined_in,
+            "block_timestamp": block_timestamp,
+            "confirmations": confirmations,
+            "latest_block": head,
+            "gas_used": summary["gas_used"],
+            "effective_gas_price": summary["effective_gas_price"],
+            "fee_paid_wei": summary["fee_paid_wei"],
+            "receipt": summary
+        }))
+    }
+}
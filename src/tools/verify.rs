@@ -0,0 +1,103 @@
+use super::hash::decode_input;
+use super::Tool;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use alloy::{
+    dyn_abi::TypedData,
+    primitives::{Address, Signature},
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+pub struct VerifySignatureTool;
+
+#[async_trait::async_trait]
+impl Tool for VerifySignatureTool {
+    fn name(&self) -> &'static str {
+        "verify_signature"
+    }
+
+    fn description(&self) -> &'static str {
+        "Recover the signer of an EIP-191 personal_sign message or an EIP-712 typed-data payload and check it against an expected address. No RPC calls."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "message": {
+                    "type": "string",
+                    "description": "Signed message (personal_sign). Not needed when typed_data is given."
+                },
+                "encoding": {
+                    "type": "string",
+                    "enum": ["auto", "hex", "utf8"],
+                    "description": "How to read `message`. auto (default) treats 0x-prefixed input as hex bytes, anything else as UTF-8."
+                },
+                "typed_data": {
+                    "type": "object",
+                    "description": "EIP-712 payload ({types, primaryType, domain, message}) as passed to eth_signTypedData_v4"
+                },
+                "signature": {
+                    "type": "string",
+                    "description": "65-byte signature as 0x-hex (r, s, v)"
+                },
+                "address": {
+                    "type": "string",
+                    "description": "Expected signer"
+                }
+            },
+            "required": ["signature", "address"]
+        })
+    }
+
+    async fn call(&self, _client: &EthereumClient, args: Value) -> Result<Value> {
+        let signature = Signature::from_str(
+            args["signature"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("signature"))?,
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid signature: {}", e))?;
+        let expected = Address::from_str(
+            args["address"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("address"))?,
+        )?;
+
+        let (scheme, digest, recovered) = match args.get("typed_data").filter(|v| !v.is_null()) {
+            Some(typed_data) => {
+                let typed: TypedData = serde_json::from_value(typed_data.clone())
+                    .map_err(|e| anyhow::anyhow!("Invalid typed_data: {}", e))?;
+                let hash = typed.eip712_signing_hash()?;
+                (
+                    "eip712",
+                    hash,
+                    signature.recover_address_from_prehash(&hash),
+                )
+            }
+            None => {
+                let message = args["message"]
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("Provide message or typed_data"))?;
+                let encoding = args
+                    .get("encoding")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("auto");
+                let (bytes, _) = decode_input(message, encoding)?;
+                let hash = alloy::primitives::eip191_hash_message(&bytes);
+                ("eip191", hash, signature.recover_address_from_msg(&bytes))
+            }
+        };
+        let recovered =
+            recovered.map_err(|e| anyhow::anyhow!("Could not recover signer: {}", e))?;
+
+        Ok(json!({
+            "valid": recovered == expected,
+            "recovered_address": recovered.to_string(),
+            "expected_address": expected.to_string(),
+            "scheme": scheme,
+            "digest": digest.to_string()
+        }))
+    }
+}
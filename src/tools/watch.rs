@@ -0,0 +1,187 @@
+use super::{report_progress, u64_arg, Tool};
+use crate::chains;
+use crate::error::ToolError;
+use crate::ethereum::EthereumClient;
+use crate::{format, rpc};
+use alloy::{primitives::TxHash, providers::Provider};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+const DEFAULT_CONFIRMATIONS: u64 = 1;
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const DEFAULT_POLL_SECS: u64 = 3;
+
+pub struct WatchTransactionTool;
+
+#[async_trait::async_trait]
+impl Tool for WatchTransactionTool {
+    fn name(&self) -> &'static str {
+        "watch_transaction"
+    }
+
+    fn description(&self) -> &'static str {
+        "Poll a transaction until it has the requested number of confirmations (or a timeout), returning the receipt and the progress updates seen along the way. Each update is also sent as a progress notification when the call carries a progressToken. A receipt that disappears is reported as reorged, and watching continues."
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tx_hash": {
+                    "type": "string",
+                    "description": "Transaction hash to watch"
+                },
+                "confirmations": {
+                    "type": "integer",
+                    "description": "Confirmations to wait for (the mining block counts as 1). Default 1."
+                },
+                "timeout_seconds": {
+                    "type": "integer",
+                    "description": "Give up after this long and return the last known state. Default 300."
+                },
+                "poll_interval_seconds": {
+                    "type": "integer",
+                    "description": "Delay between receipt polls. Defaults to the chain's block time, between 1 and 3 seconds."
+                }
+            },
+            "required": ["tx_hash"]
+        })
+    }
+
+    fn wait_budget(&self, args: &Value) -> Option<Duration> {
+        let secs = u64_arg(args, "timeout_seconds").ok().flatten();
+        Some(Duration::from_secs(secs.unwrap_or(DEFAULT_TIMEOUT_SECS)))
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        let tx_hash = TxHash::from_str(
+            args["tx_hash"]
+                .as_str()
+                .ok_or_else(|| ToolError::missing("tx_hash"))?,
+        )?;
+        let target = u64_arg(&args, "confirmations")?
+            .unwrap_or(DEFAULT_CONFIRMATIONS)
+            .max(1);
+        let timeout =
+            Duration::from_secs(u64_arg(&args, "timeout_seconds")?.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        // Confirmation ETAs and the default poll interval follow the chain's block time.
+        let block_time = chains::block_time_seconds(client.chain_id());
+        let poll = match u64_arg(&args, "poll_interval_seconds")? {
+            Some(secs) => Duration::from_secs(secs.max(1)),
+            None => Duration::from_secs_f64(block_time.clamp(1.0, DEFAULT_POLL_SECS as f64)),
+        };
+
+        // Every state change goes out as a progress event when the caller asked for them,
+        // and is returned with the final result either way.
+        let mut updates: Vec<Value> = Vec::new();
+        let mut last_message = String::new();
+        let mut record = |status: &str, message: String| {
+            if message != last_message {
+                debug!("watch {}: {}", tx_hash, message);
+                let update = json!({
+                    "status": status,
+                    "message": message,
+                    "timestamp": format::timestamp(unix_now())
+                });
+                report_progress(update.clone());
+                updates.push(update);
+                last_message = message;
+            }
+        };
+        // The block the receipt was last seen in, to notice it disappearing.
+        let mut seen_in: Option<u64> = None;
+        let mut reorgs = 0u64;
+
+        let started = Instant::now();
+        loop {
+            // Raw JSON rather than typed receipts so a provider omitting optional fields
+            // degrades to nulls instead of a deserialization error.
+            let receipt: Option<Value> = client
+                .provider
+                .raw_request("eth_getTransactionReceipt".into(), (tx_hash,))
+                .await?;
+            match receipt.filter(|r| !r.is_null()) {
+                Some(receipt) => {
+                    let head = client.provider.get_block_number().await?;
+                    let mined_in =
+                        rpc::quantity(&receipt, "blockNumber").map_or(head, |n| n as u64);
+                    seen_in = Some(mined_in);
+                    let confirmations = head.saturating_sub(mined_in) + 1;
+                    record("mined", format!("mined in block {}", mined_in));
+                    let shown = confirmations.min(target);
+                    let message = if confirmations >= target {
+                        format!("{}/{} confirmations", shown, target)
+                    } else {
+                        let eta = (target - confirmations) as f64 * block_time;
+                        format!("{}/{} confirmations (~{:.0}s to go)", shown, target, eta)
+                    };
+                    record("confirming", message);
+                    if confirmations >= target {
+                        let tx = if rpc::quantity(&receipt, "effectiveGasPrice").is_none() {
+                            get_transaction(client, tx_hash).await?
+                        } else {
+                            None
+                        };
+                        return Ok(json!({
+                            "tx_hash": tx_hash.to_string(),
+                            "status": "confirmed",
+                            "confirmations": confirmations,
+                            "receipt": rpc::receipt_summary(&receipt, tx.as_ref()),
+                            "reorgs": reorgs,
+                            "updates": updates
+                        }));
+                    }
+                }
+                None => {
+                    // A receipt that vanishes after being seen means its block was reorged
+                    // out; the transaction may be mined again, so watching goes on.
+                    if let Some(block) = seen_in.take() {
+                        reorgs += 1;
+                        record(
+                            "reorged",
+                            format!("block {} was reorged out; receipt gone", block),
+                        );
+                    }
+                    match get_transaction(client, tx_hash).await? {
+                        Some(_) => record("pending", "pending".to_string()),
+                        None => record(
+                            "not_found",
+                            "not found; not yet propagated, or dropped".to_string(),
+                        ),
+                    }
+                }
+            }
+
+            if started.elapsed() + poll > timeout {
+                return Ok(json!({
+                    "tx_hash": tx_hash.to_string(),
+                    "status": "timeout",
+                    "message": format!("Stopped watching after {}s; last state: {}", timeout.as_secs(), last_message),
+                    "receipt": Value::Null,
+                    "reorgs": reorgs,
+                    "updates": updates
+                }));
+            }
+            tokio::time::sleep(poll).await;
+        }
+    }
+}
+
+/// The raw transaction object, or `None` if the node doesn't know the hash.
+pub async fn get_transaction(client: &EthereumClient, tx_hash: TxHash) -> Result<Option<Value>> {
+    let tx: Option<Value> = client
+        .provider
+        .raw_request("eth_getTransactionByHash".into(), (tx_hash,))
+        .await?;
+    Ok(tx.filter(|t| !t.is_null()))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
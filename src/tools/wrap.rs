@@ -0,0 +1,236 @@
+use super::balance::{balanceOfCall, format_units};
+use super::send::wei_arg;
+use super::watch::WatchTransactionTool;
+use super::{transaction_sender, u64_arg, Tool};
+use crate::error::ToolError;
+use crate::ethereum::{decode_return, EthereumClient};
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+    sol,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+sol! {
+    #[allow(missing_docs)]
+    function deposit() external payable;
+    #[allow(missing_docs)]
+    function withdraw(uint256 wad) external;
+}
+
+const DEFAULT_WAIT_SECS: u64 = 120;
+
+pub struct WrapEthTool;
+pub struct UnwrapWethTool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Wrap,
+    Unwrap,
+}
+
+#[async_trait::async_trait]
+impl Tool for WrapEthTool {
+    fn name(&self) -> &'static str {
+        "wrap_eth"
+    }
+
+    fn description(&self) -> &'static str {
+        "Wrap native ETH into WETH: sends deposit() with the amount as value to the chain's WETH contract from the server wallet, waits for it to confirm and returns the transaction hash with the resulting ETH and WETH balances. With unsigned (always in read-only mode), return the transaction for an external signer instead."
+    }
+
+    fn schema(&self) -> Value {
+        schema(Direction::Wrap)
+    }
+
+    fn wait_budget(&self, args: &Value) -> Option<Duration> {
+        wait_budget(args)
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        convert(client, &args, Direction::Wrap).await
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for UnwrapWethTool {
+    fn name(&self) -> &'static str {
+        "unwrap_weth"
+    }
+
+    fn description(&self) -> &'static str {
+        "Unwrap WETH into native ETH: sends withdraw(amount) to the chain's WETH contract from the server wallet, waits for it to confirm and returns the transaction hash with the resulting ETH and WETH balances. With unsigned (always in read-only mode), return the transaction for an external signer instead."
+    }
+
+    fn schema(&self) -> Value {
+        schema(Direction::Unwrap)
+    }
+
+    fn wait_budget(&self, args: &Value) -> Option<Duration> {
+        wait_budget(args)
+    }
+
+    async fn call(&self, client: &EthereumClient, args: Value) -> Result<Value> {
+        convert(client, &args, Direction::Unwrap).await
+    }
+}
+
+fn schema(direction: Direction) -> Value {
+    let amount = match direction {
+        Direction::Wrap => "ETH to wrap, in wei (decimal or 0x-hex)",
+        Direction::Unwrap => {
+            "WETH to unwrap, in wei (decimal or 0x-hex), or \"max\" for the whole WETH balance"
+        }
+    };
+    json!({
+        "type": "object",
+        "properties": {
+            "amount": {
+                "type": "string",
+                "description": amount
+            },
+            "wait": {
+                "type": "boolean",
+                "description": "Wait for one confirmation before reading the resulting balances. Default true."
+            },
+            "timeout_seconds": {
+                "type": "integer",
+                "description": "How long to wait for the confirmation. Default 120."
+            },
+            "private": {
+                "type": "boolean",
+                "description": "Send only to the configured PRIVATE_RPC_URL relay instead of the public mempool. Default false."
+            },
+            "unsigned": {
+                "type": "boolean",
+                "description": "Return the transaction unsigned, with nonce, gas and fees filled, instead of signing and sending it. Always on in read-only mode. Default false."
+            },
+            "from": {
+                "type": "string",
+                "description": "With unsigned: the account that will sign (address or ENS name). Default: the server wallet; required in read-only mode."
+            }
+        },
+        "required": ["amount"]
+    })
+}
+
+/// The confirmation wait, unless `wait` is off.
+fn wait_budget(args: &Value) -> Option<Duration> {
+    if args.get("wait").and_then(|v| v.as_bool()) == Some(false) {
+        return None;
+    }
+    let secs = u64_arg(args, "timeout_seconds").ok().flatten();
+    Some(Duration::from_secs(secs.unwrap_or(DEFAULT_WAIT_SECS)))
+}
+
+async fn convert(client: &EthereumClient, args: &Value, direction: Direction) -> Result<Value> {
+    let (account, unsigned) = transaction_sender(client, args).await?;
+    let weth = client.chain()?.weth;
+    let flag =
+        |key: &str, default: bool| args.get(key).and_then(|v| v.as_bool()).unwrap_or(default);
+    // `None` is "max": the whole WETH balance, known once it is read.
+    let amount = match (direction, args["amount"].as_str().map(str::trim)) {
+        (Direction::Unwrap, Some("max" | "MAX")) => None,
+        _ => Some(
+            wei_arg(args, "amount")
+                .map_err(ToolError::invalid_argument)?
+                .ok_or_else(|| ToolError::missing("amount"))?,
+        ),
+    };
+    let before = balances(client, account, weth).await?;
+    let amount = amount.unwrap_or(before.1);
+    if amount.is_zero() {
+        return Err(ToolError::invalid_argument(
+            "amount must be greater than zero",
+        ));
+    }
+    // withdraw reverts on a short balance; deposit is covered by the send's own check.
+    if direction == Direction::Unwrap && amount > before.1 {
+        return Err(ToolError::invalid_argument(format!(
+            "Insufficient WETH: {} holds {} wei but the unwrap needs {}",
+            account, before.1, amount
+        )));
+    }
+
+    let (call_data, value) = match direction {
+        Direction::Wrap => (depositCall {}.abi_encode(), amount),
+        Direction::Unwrap => (withdrawCall { wad: amount }.abi_encode(), U256::ZERO),
+    };
+    let tx = TransactionRequest::default()
+        .with_to(weth)
+        .with_value(value)
+        .with_input(call_data.clone());
+    let mut result = json!({
+        "action": match direction {
+            Direction::Wrap => "wrap",
+            Direction::Unwrap => "unwrap",
+        },
+        "weth": weth.to_string(),
+        "account": account.to_string(),
+        "amount": amount.to_string(),
+        "amount_eth": format_units(amount, 18)?,
+        "data": format!("0x{}", hex::encode(&call_data))
+    });
+
+    if unsigned {
+        result["unsigned_transaction"] = client.unsigned_transaction(tx, account).await?.to_json();
+        result["balances"] = balances_json(before)?;
+        return Ok(result);
+    }
+    let submission = client
+        .submit_transaction(tx, flag("private", false))
+        .await?;
+    for (key, value) in submission.to_json().as_object().into_iter().flatten() {
+        result[key] = value.clone();
+    }
+    result["status"] = json!("submitted");
+    if flag("wait", true) {
+        let watched = WatchTransactionTool
+            .call(
+                client,
+                json!({
+                    "tx_hash": submission.tx_hash.to_string(),
+                    "confirmations": 1,
+                    "timeout_seconds": u64_arg(args, "timeout_seconds")?.unwrap_or(DEFAULT_WAIT_SECS)
+                }),
+            )
+            .await?;
+        result["status"] = match (&watched["status"], &watched["receipt"]["success"]) {
+            (_, Value::Bool(false)) => json!("reverted"),
+            (status, _) => status.clone(),
+        };
+        result["receipt"] = watched["receipt"].clone();
+    }
+    // Until it confirms, these are still the balances from before the transaction.
+    result["balances"] = balances_json(balances(client, account, weth).await?)?;
+    Ok(result)
+}
+
+/// `account`'s native ETH and WETH balances, in wei.
+async fn balances(
+    client: &EthereumClient,
+    account: Address,
+    weth: Address,
+) -> Result<(U256, U256)> {
+    let eth = client.provider.get_balance(account).await?;
+    let req = TransactionRequest::default()
+        .to(weth)
+        .input(balanceOfCall { account }.abi_encode().into());
+    let data = client.provider.call(&req).await?;
+    let weth_balance = decode_return::<balanceOfCall>(&data, weth)?._0;
+    Ok((eth, weth_balance))
+}
+
+fn balances_json((eth, weth): (U256, U256)) -> Result<Value> {
+    Ok(json!({
+        "eth": format_units(eth, 18)?,
+        "raw_eth": eth.to_string(),
+        "weth": format_units(weth, 18)?,
+        "raw_weth": weth.to_string()
+    }))
+}
@@ -1,18 +1,190 @@
+use alloy::primitives::{Address, U256};
+use alloy::rpc::types::eth::TransactionRequest;
+use base64::Engine;
 use dotenv::dotenv;
 use ethereum_trading_mcp::{
-    ethereum::EthereumClient,
-    tools::{balance::GetBalanceTool, price::GetTokenPriceTool, swap::SwapTokensTool, Tool},
+    approval::{self, ApprovalPolicy},
+    audit::AuditLog,
+    chains,
+    config::{SignerCheck, WalletSource},
+    denylist::DenyList,
+    error::{ToolError, NOT_SUPPORTED_CODE, REVERT_CODE, RPC_UNAVAILABLE_CODE, TIMEOUT_CODE},
+    ethereum::{
+        aggregate3Call, call3, decode_result, decode_return, describe_call_error, http_provider,
+        load_signer, revert_reason, Call3Result, EthereumClient, Submission,
+    },
+    format::{group_thousands, rfc3339},
+    limits::{Busy, RequestLimiter},
+    oracle::{self, latestRoundDataReturn},
+    quoter,
+    retry::{is_transient, RetryPolicy},
+    rpc::receipt_summary,
+    server::{
+        health_result, initialize_result, tool_time_limit, within_time_limit,
+        SUPPORTED_PROTOCOL_VERSIONS,
+    },
+    session::Session,
+    simulation::balance_changes_from_trace,
+    tokens::{SymbolPolicy, TokenListValidation, TokenRegistry},
+    tools::{
+        approve::ApproveTokenTool,
+        balance::{decode_symbol, format_units, GetBalanceTool},
+        balances::GetBalancesTool,
+        batch::BatchReadTool,
+        block::GetBlockTool,
+        chains::ListChainsTool,
+        convert::ConvertTool,
+        cross_rate::GetCrossRateTool,
+        crosscheck::CompareRpcEndpointsTool,
+        discover::DiscoverTokensTool,
+        encode::EncodeCalldataTool,
+        ens::{namehash, ResolveEnsTool},
+        events::GetBlockRangeEventsTool,
+        explain_tx::ExplainFailedTransactionTool,
+        f64_arg, fee_arg,
+        gas::{suggest_fees, GetGasPriceTool},
+        gas_cost::GetHistoricalGasCostTool,
+        gas_deadline::{recommend_for_deadline, GasForDeadlineTool},
+        hash::HashTool,
+        impact::EstimatePriceImpactTool,
+        observations::GetPoolObservationsTool,
+        permit::SignPermitTool,
+        plan::SimulateSwapPlanTool,
+        pool_fees::GetPoolFeesTool,
+        price::{
+            check_pool_state, oriented_price, tick_price, twap_tick, verify_pool, GetTokenPriceTool,
+        },
+        price_watch::{UnwatchPriceTool, WatchPriceTool},
+        prices::GetPricesTool,
+        profile::AccountProfileTool,
+        rebasing::{has_selector, CheckRebasingTool},
+        reference::GetReferencePricesTool,
+        required_eth::GetRequiredEthTool,
+        safety::{percent_shortfall, CheckTokenSafetyTool},
+        scenario::SimulateSwapScenarioTool,
+        select_fields,
+        send::SendTransactionTool,
+        split::OptimizeSwapSplitTool,
+        split_stream,
+        swap::{
+            exact_input_single_calldata, net_of_gas, quote_discrepancy, slippage_arg,
+            slippage_bounds, ExactInputSingleParams, SwapMode, SwapTokensTool,
+        },
+        sweep::MaxSendableEthTool,
+        tool_content,
+        transfers::GetTokenTransfersTool,
+        tx_status::GetTransactionStatusTool,
+        u64_arg,
+        verify::VerifySignatureTool,
+        watch::WatchTransactionTool,
+        with_common_properties, with_progress, with_session,
+        wrap::{UnwrapWethTool, WrapEthTool},
+        Tool,
+    },
 };
 use rust_decimal::Decimal;
-use serde_json::json;
+use serde_json::{json, Value};
 use std::env;
 use std::str::FromStr;
 
+// A mainnet client whose reads all fail (closed port). Construction still needs a chain id,
+// which a mock answers.
+async fn offline_client() -> EthereumClient {
+    let client = EthereumClient::new(
+        &mock_rpc(|method, _| panic!("unexpected {}", method)).await,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    client.on_provider(http_provider(
+        "http://127.0.0.1:1".parse().unwrap(),
+        RetryPolicy::none(),
+    ))
+}
+
+/// Latest block served by `mock_rpc`, at timestamp 1_700_000_000.
+fn mock_block() -> Value {
+    let zero = format!("0x{}", "0".repeat(64));
+    json!({
+        "hash": zero,
+        "parentHash": zero,
+        "sha3Uncles": zero,
+        "miner": "0x0000000000000000000000000000000000000000",
+        "stateRoot": zero,
+        "transactionsRoot": zero,
+        "receiptsRoot": zero,
+        "logsBloom": format!("0x{}", "0".repeat(512)),
+        "difficulty": "0x0",
+        "number": "0x1",
+        "gasLimit": "0x1c9c380",
+        "gasUsed": "0x0",
+        "timestamp": "0x6553f100",
+        "extraData": "0x",
+        "uncles": [],
+        "transactions": []
+    })
+}
+
+// Minimal JSON-RPC server on localhost; `handler(method, params)` supplies each result.
+// `eth_chainId` is always answered with mainnet, for client construction, and
+// `eth_getBlockByNumber` with `mock_block`.
+async fn mock_rpc(handler: fn(&str, &Value) -> Value) -> String {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                loop {
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        if line == "\r\n" {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                content_length = value.trim().parse().unwrap_or(0);
+                            }
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    stream.read_exact(&mut body).await.unwrap();
+                    let req: Value = serde_json::from_slice(&body).unwrap();
+                    let result = match req["method"].as_str().unwrap_or_default() {
+                        "eth_chainId" => json!("0x1"),
+                        "eth_getBlockByNumber" => mock_block(),
+                        method => handler(method, &req["params"]),
+                    };
+                    // A handler answers with a JSON-RPC error by returning {"rpc_error": {...}}.
+                    let response = match result.get("rpc_error") {
+                        Some(error) => json!({"jsonrpc": "2.0", "id": req["id"], "error": error}),
+                        None => json!({"jsonrpc": "2.0", "id": req["id"], "result": result}),
+                    }
+                    .to_string();
+                    let http = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                        response.len(),
+                        response
+                    );
+                    stream.get_mut().write_all(http.as_bytes()).await.unwrap();
+                }
+            });
+        }
+    });
+    url
+}
+
 async fn setup_client() -> EthereumClient {
     dotenv().ok();
     let rpc = env::var("ETHEREUM_RPC_URL").expect("ETHEREUM_RPC_URL must be set for tests");
     let pk = env::var("PRIVATE_KEY").expect("PRIVATE_KEY must be set for tests");
-    EthereumClient::new(&rpc, &pk)
+    EthereumClient::new(&rpc, pk.as_str())
         .await
         .expect("Failed to create Ethereum client")
 }
@@ -198,3 +370,6540 @@ async fn test_get_balance_invalid_address_errors() {
     let result = tool.call(&client, args).await;
     assert!(result.is_err(), "Expected error for invalid address");
 }
+
+#[tokio::test]
+async fn test_max_sendable_eth_leaves_gas() {
+    let client = setup_client().await;
+    let tool = MaxSendableEthTool;
+
+    let args = json!({
+        "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+    });
+
+    let result = tool.call(&client, args).await.unwrap();
+    println!("Max sendable: {}", result);
+
+    let balance = Decimal::from_str(result["raw_balance"].as_str().unwrap()).unwrap();
+    let sendable = Decimal::from_str(result["raw_max_sendable"].as_str().unwrap()).unwrap();
+    let gas_cost = Decimal::from_str(result["raw_gas_cost"].as_str().unwrap()).unwrap();
+    assert_eq!(sendable + gas_cost, balance);
+}
+
+#[test]
+fn test_balance_changes_from_call_trace() {
+    // Signer sends 1 WETH to a pool and receives 3000 USDC; a reverted inner call is ignored.
+    let signer = "0x00000000000000000000000000000000000000aa";
+    let pool = "0x00000000000000000000000000000000000000bb";
+    let weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+    let usdc = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+    let transfer_topic = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+    let topic_for = |a: &str| format!("0x000000000000000000000000{}", &a[2..]);
+
+    let trace = json!({
+        "type": "CALL",
+        "from": signer,
+        "to": pool,
+        "value": "0x0",
+        "logs": [
+            {
+                "address": weth,
+                "topics": [transfer_topic, topic_for(signer), topic_for(pool)],
+                "data": "0x0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+            }
+        ],
+        "calls": [
+            {
+                "type": "CALL",
+                "from": pool,
+                "to": signer,
+                "value": "0x0",
+                "logs": [
+                    {
+                        "address": usdc,
+                        "topics": [transfer_topic, topic_for(pool), topic_for(signer)],
+                        "data": "0x00000000000000000000000000000000000000000000000000000000b2d05e00"
+                    }
+                ]
+            },
+            {
+                "type": "CALL",
+                "from": pool,
+                "to": signer,
+                "value": "0x1",
+                "error": "execution reverted"
+            }
+        ]
+    });
+
+    let changes = balance_changes_from_trace(&trace).unwrap();
+    assert_eq!(changes.len(), 4);
+    let delta_of = |token: &str, account: &str| {
+        changes
+            .iter()
+            .find(|c| {
+                c.token.map(|t| t.to_string().to_lowercase()) == Some(token.to_lowercase())
+                    && c.account.to_string().to_lowercase() == account
+            })
+            .map(|c| c.delta.to_string())
+    };
+    assert_eq!(
+        delta_of(weth, signer).as_deref(),
+        Some("-1000000000000000000")
+    );
+    assert_eq!(delta_of(usdc, signer).as_deref(), Some("3000000000"));
+    assert_eq!(delta_of(usdc, pool).as_deref(), Some("-3000000000"));
+}
+
+#[tokio::test]
+async fn test_swap_simulation_balance_changes_or_fallback() {
+    let client = setup_client().await;
+    let tool = SwapTokensTool;
+
+    let args = json!({
+        "from_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "to_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "amount": "1000000000000000000",
+        "include_balance_changes": true
+    });
+
+    let result = tool.call(&client, args).await.unwrap();
+    let status = result["balance_changes"]["status"].as_str().unwrap_or("");
+    assert!(status == "ok" || status == "unavailable");
+}
+
+#[tokio::test]
+async fn test_get_token_prices_isolates_failures() {
+    let client = setup_client().await;
+    let tool = GetPricesTool;
+
+    let args = json!({
+        "tokens": ["ETH", "UNKNOWN", "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"],
+        "retries": 0
+    });
+
+    let result = tool.call(&client, args).await.unwrap();
+    println!("Prices: {}", result);
+
+    let prices = result["prices"].as_array().unwrap();
+    assert_eq!(prices.len(), 3);
+    assert_eq!(prices[0]["status"], "ok");
+    assert_eq!(prices[1]["token"], "UNKNOWN");
+    assert_eq!(prices[1]["status"], "error");
+    assert_eq!(result["summary"]["failed"], 1);
+}
+
+#[tokio::test]
+async fn test_check_pool_state_rejects_uninitialized_and_empty_pools() {
+    use alloy::sol_types::SolCall;
+
+    // Two 0x1111/WETH pools: 0x9999 (fee 3000) was created via the factory but never
+    // initialized, so slot0 is all zeroes; 0x8888 (fee 500) has a price but no in-range
+    // liquidity.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let answer = |target: &str, data: &str| -> String {
+            let empty = target.ends_with("8888");
+            match &data[..8] {
+                // token0, token1
+                "0dfe1681" => word(0x1111),
+                "d21220a7" => format!("{:0>64}", "c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"),
+                // fee
+                "ddca3f43" => word(if empty { 500 } else { 3000 }),
+                // getPool(tokenA, tokenB, fee)
+                "1698ee82" if data.ends_with(&word(500)) => word(0x8888),
+                "1698ee82" => word(0x9999),
+                // slot0: sqrtPriceX96, tick, ..., unlocked
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(if empty { 1 << 96 } else { 0 }),
+                    word(0),
+                    word(0),
+                    word(0),
+                    word(0),
+                    word(0),
+                    word(u128::from(empty))
+                ),
+                // liquidity
+                "1a686502" => word(0),
+                // latestRoundData: ETH/USD 2000
+                "feaf968c" => format!(
+                    "{}{}{}{}{}",
+                    word(1),
+                    word(2_000 * 100_000_000),
+                    word(1),
+                    word(1),
+                    word(1)
+                ),
+                // decimals: 18 for tokens, read one by one
+                "313ce567" => word(18),
+                _ => String::new(),
+            }
+        };
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            return json!(format!("0x{}", answer(&to, &data[2..])));
+        }
+        let calls = aggregate3Call::abi_decode(&hex::decode(&data[2..]).unwrap(), true)
+            .unwrap()
+            .calls;
+        let results: Vec<Call3Result> = calls
+            .iter()
+            .map(|c| {
+                let selector = hex::encode(&c.callData[..4]);
+                let target = c.target.to_string().to_lowercase();
+                Call3Result {
+                    success: true,
+                    // Feeds are the only contracts asked for decimals in a batch.
+                    returnData: hex::decode(if selector == "313ce567" {
+                        word(8)
+                    } else {
+                        answer(&target, &hex::encode(&c.callData))
+                    })
+                    .unwrap()
+                    .into(),
+                }
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let price = |pool: &str| {
+        GetTokenPriceTool.call(
+            &client,
+            json!({
+                "token_symbol": "TKN",
+                "token_address": "0x0000000000000000000000000000000000001111",
+                "pool_address": pool
+            }),
+        )
+    };
+
+    let err = price("0x0000000000000000000000000000000000009999")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not yet initialized"), "{}", err);
+
+    let err = price("0x0000000000000000000000000000000000008888")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no in-range liquidity"), "{}", err);
+
+    // A mid-swap read is refused too.
+    let sqrt_price = U256::from(1u64) << 96;
+    let err = check_pool_state(sqrt_price, false, 1_000).unwrap_err();
+    assert!(err.to_string().contains("locked"));
+    assert!(check_pool_state(sqrt_price, true, 1_000).is_ok());
+}
+
+#[test]
+fn test_audit_log_redacts_and_flushes() {
+    let key = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    let path = env::temp_dir().join(format!("mcp-audit-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let audit = AuditLog::open(path.to_str().unwrap(), key).unwrap();
+    let args = json!({ "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045", "note": key });
+    let approval = alloy::primitives::TxHash::repeat_byte(0xaa);
+    let swap = alloy::primitives::TxHash::repeat_byte(0xbb);
+    // Hashes come from what the call broadcast, however deeply the result nests them.
+    audit.record(
+        "swap_tokens",
+        &args,
+        &Ok(json!({ "execution": { "approval": { "tx_hash": approval }, "swap": { "tx_hash": swap } } })),
+        &[approval, swap],
+        std::time::Duration::from_millis(5),
+    );
+    audit.record(
+        "swap_tokens",
+        &json!({ "private_key": key }),
+        &Err(anyhow::anyhow!("boom")),
+        &[],
+        std::time::Duration::from_millis(1),
+    );
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(!contents.contains(&key[2..]));
+
+    let lines: Vec<serde_json::Value> = contents
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["status"], "success");
+    assert_eq!(lines[0]["tx_hash"], swap.to_string());
+    assert_eq!(
+        lines[0]["tx_hashes"],
+        json!([approval.to_string(), swap.to_string()])
+    );
+    assert_eq!(lines[1]["status"], "error");
+    assert_eq!(lines[1]["tx_hash"], Value::Null);
+    assert_eq!(lines[1]["arguments"]["private_key"], "[REDACTED]");
+    let millis = lines[0]["timestamp_ms"].as_u64().unwrap();
+    assert_eq!(lines[0]["timestamp"]["unix"], millis / 1000);
+}
+
+#[tokio::test]
+async fn test_convert_eth_to_usdc() {
+    let client = setup_client().await;
+    let tool = ConvertTool;
+
+    let args = json!({
+        "from_token": "ETH",
+        "to_token": "USDC",
+        "amount": "0.5"
+    });
+
+    let result = tool.call(&client, args).await.unwrap();
+    println!("0.5 ETH in USDC: {}", result);
+
+    let out: Decimal = serde_json::from_value(result["amount_out"].clone()).unwrap();
+    assert!(out > Decimal::ZERO);
+    assert_eq!(result["source"], "Uniswap V3 pool rate");
+}
+
+#[tokio::test]
+async fn test_swap_rejects_non_erc20_to_token() {
+    let client = setup_client().await;
+    let tool = SwapTokensTool;
+
+    // The SwapRouter is a contract, but not a token.
+    let args = json!({
+        "from_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "to_token": "0xE592427A0AEce92De3Edee1F18E0157C05861564",
+        "amount": "1000000000000000000"
+    });
+
+    let err = tool.call(&client, args).await.unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("to_token (0xE592427A0AEce92De3Edee1F18E0157C05861564) does not appear to be an ERC20 token"));
+}
+
+#[tokio::test]
+async fn test_swap_token_check_passes_node_errors_through() {
+    // ..aa is a token, ..bb hits a node error, ..cc reverts on decimals().
+    let url = mock_rpc(|method, params| {
+        let to = params[0]["to"].as_str().unwrap_or_default().to_lowercase();
+        match method {
+            "eth_call" if to.ends_with("aa") => json!(format!("0x{:064x}", 18)),
+            "eth_call" if to.ends_with("bb") => {
+                json!({ "rpc_error": { "code": -32000, "message": "header not found" } })
+            }
+            "eth_call" => json!({ "rpc_error": { "code": 3, "message": "execution reverted" } }),
+            other => panic!("unexpected {}", other),
+        }
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let token = |n: u8| format!("0x{}{:02x}", "00".repeat(19), n);
+    let swap = |to: String| {
+        json!({
+            "from_token": token(0xaa),
+            "to_token": to,
+            "amount": "1000"
+        })
+    };
+
+    let err = SwapTokensTool
+        .call(&client, swap(token(0xbb)))
+        .await
+        .unwrap_err();
+    assert!(!err.to_string().contains("ERC20"), "{}", err);
+    assert!(
+        format!("{:#}", err).contains("header not found"),
+        "{:#}",
+        err
+    );
+
+    let err = SwapTokensTool
+        .call(&client, swap(token(0xcc)))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("does not appear to be an ERC20 token"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_get_block_range_events_decodes_transfers() {
+    let client = setup_client().await;
+    let tool = GetBlockRangeEventsTool;
+
+    // USDC Transfer events over a small historical range.
+    let args = json!({
+        "event_signature": "Transfer(address,address,uint256)",
+        "contract_address": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "from_block": 19000000,
+        "to_block": 19000009,
+        "block_chunk": 5
+    });
+
+    let result = tool.call(&client, args).await.unwrap();
+    assert_eq!(result["to_block"], 19000004);
+    assert_eq!(result["next_cursor"], "19000005");
+    if let Some(first) = result["events"].as_array().and_then(|e| e.first()) {
+        assert!(first["args"].get("arg2").is_some());
+    }
+}
+
+#[test]
+fn test_token_registry_loads_list_over_builtins() {
+    let path = env::temp_dir().join(format!("mcp-tokens-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"tokens": [
+            {"symbol": "UNI", "address": "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984", "decimals": 18},
+            {"symbol": "usdc", "address": "0x0000000000000000000000000000000000000001", "decimals": 6}
+        ]}"#,
+    )
+    .unwrap();
+
+    let registry = TokenRegistry::load(path.to_str()).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(registry.resolve("uni").unwrap().decimals, 18);
+    assert_eq!(
+        registry.resolve("USDC").unwrap().address.to_string(),
+        "0x0000000000000000000000000000000000000001"
+    );
+    assert!(registry.resolve("WBTC").is_some());
+    assert_eq!(
+        TokenListValidation::parse("drop").unwrap(),
+        TokenListValidation::Drop
+    );
+    assert!(TokenListValidation::parse("maybe").is_err());
+}
+
+#[test]
+fn test_token_registry_symbol_disambiguation() {
+    let path = env::temp_dir().join(format!("mcp-tokens-dup-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"[
+            {"symbol": "PEPE", "address": "0x6982508145454Ce325dDbE47a25d4ec3d2311933", "decimals": 18, "chainId": 1},
+            {"symbol": "PEPE", "address": "0x0000000000000000000000000000000000000002", "decimals": 18, "verified": false},
+            {"symbol": "PEPE", "address": "0x0000000000000000000000000000000000000003", "decimals": 18, "chainId": 8453},
+            {"symbol": "DUP", "address": "0x0000000000000000000000000000000000000004", "decimals": 18},
+            {"symbol": "DUP", "address": "0x0000000000000000000000000000000000000005", "decimals": 18},
+            {"symbol": "SHADY", "address": "0x0000000000000000000000000000000000000006", "decimals": 18, "verified": false}
+        ]"#,
+    )
+    .unwrap();
+    let registry = TokenRegistry::load(path.to_str()).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    // The Base entry makes PEPE ambiguous until the registry is narrowed to mainnet.
+    assert!(registry.resolve_symbol("PEPE").is_err());
+    let registry = registry.for_chain(1);
+    let pepe = registry.resolve_symbol("pepe").unwrap().unwrap();
+    assert_eq!(
+        pepe.token.address.to_string(),
+        "0x6982508145454Ce325dDbE47a25d4ec3d2311933"
+    );
+    assert_eq!(pepe.candidates, 2);
+    assert_eq!(pepe.to_json()["verified"], true);
+
+    let err = registry.resolve_symbol("DUP").unwrap_err().to_string();
+    assert!(err.contains("explicitly"), "{}", err);
+    assert!(registry.resolve("DUP").is_none());
+    assert!(registry.resolve_symbol("NOPE").unwrap().is_none());
+
+    assert_eq!(
+        registry.resolve_symbol("SHADY").unwrap().unwrap().to_json()["verified"],
+        false
+    );
+    let strict = registry.with_policy(SymbolPolicy::parse("verified_only").unwrap());
+    assert!(strict.resolve_symbol("SHADY").is_err());
+    assert!(strict.resolve_symbol("USDC").unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_token_registry_validation_drops_wrong_decimals() {
+    let client = setup_client().await;
+    let path = env::temp_dir().join(format!("mcp-tokens-bad-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"[{"symbol": "UNI", "address": "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984", "decimals": 6}]"#,
+    )
+    .unwrap();
+
+    let registry = TokenRegistry::load(path.to_str())
+        .unwrap()
+        .validate(&client, TokenListValidation::Drop)
+        .await;
+    let _ = std::fs::remove_file(&path);
+
+    assert!(registry.resolve("UNI").is_none());
+    assert!(registry.resolve("USDC").is_some());
+}
+
+#[tokio::test]
+async fn test_account_profile_of_known_wallet() {
+    let client = setup_client().await;
+    let tool = AccountProfileTool;
+
+    let args = json!({
+        "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+        "tokens": ["USDC", "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"]
+    });
+
+    let result = tool.call(&client, args).await.unwrap();
+    println!("Profile: {}", result);
+
+    assert_eq!(result["is_contract"], false);
+    assert!(result["nonce"].as_u64().unwrap() > 0);
+    assert_eq!(result["holdings"][0]["symbol"], "ETH");
+    assert!(result.get("total_value_usd").is_some());
+}
+
+#[test]
+fn test_format_helpers() {
+    assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+    assert_eq!(rfc3339(1_709_251_199), "2024-02-29T23:59:59Z");
+    assert_eq!(group_thousands("1234567.891"), "1,234,567.891");
+    assert_eq!(group_thousands("-1000"), "-1,000");
+    assert_eq!(group_thousands("999"), "999");
+}
+
+#[tokio::test]
+async fn test_check_token_safety_on_established_token() {
+    let client = setup_client().await;
+    let tool = CheckTokenSafetyTool;
+
+    // UNI is a plain ERC20 with a deep 0.3% WETH pool.
+    let args = json!({
+        "token_address": "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+    });
+
+    let result = tool.call(&client, args).await.unwrap();
+    println!("UNI safety: {}", result);
+    assert_eq!(result["verdict"], "no_red_flags");
+    assert_eq!(result["sell"]["success"], true);
+}
+
+#[test]
+fn test_percent_shortfall_handles_amounts_beyond_decimal() {
+    assert_eq!(
+        percent_shortfall(U256::from(75), U256::from(100)),
+        Decimal::from(25)
+    );
+    assert_eq!(
+        percent_shortfall(U256::from(101), U256::from(100)),
+        Decimal::ZERO
+    );
+    // Past Decimal's 96-bit range: half of U256::MAX is missing.
+    assert_eq!(
+        percent_shortfall(U256::MAX / U256::from(2), U256::MAX),
+        Decimal::from(50)
+    );
+    assert_eq!(
+        percent_shortfall(U256::from(1), U256::MAX),
+        Decimal::new(9999, 2)
+    );
+}
+
+#[tokio::test]
+async fn test_private_submission_requires_relay() {
+    // No RPC is contacted: the relay check happens before any fill or signing.
+    let client = offline_client().await;
+    let err = client
+        .submit_transaction(TransactionRequest::default(), true)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("PRIVATE_RPC_URL"), "{}", err);
+
+    let client = client
+        .with_private_relay("https://rpc.flashbots.net")
+        .unwrap();
+    assert!(client.private_relay.is_some());
+
+    // A relay URL's path and query can hold an API key, so results name only its host.
+    let submission = Submission {
+        tx_hash: Default::default(),
+        nonce: 7,
+        gas_limit: 21000,
+        max_fee_per_gas: 1,
+        private_relay: Some(
+            "https://relay.example/v1/secret-key?auth=token"
+                .parse()
+                .unwrap(),
+        ),
+    };
+    let json = submission.to_json();
+    assert_eq!(json["submitted_to"], "https://relay.example");
+    assert!(!json.to_string().contains("secret-key"), "{}", json);
+}
+
+#[tokio::test]
+async fn test_compute_hash_operations() {
+    let client = offline_client().await;
+    let tool = HashTool;
+
+    let res = tool
+        .call(&client, json!({"operation": "selector", "input": "function transfer(address to, uint256 amount)"}))
+        .await
+        .unwrap();
+    assert_eq!(res["signature"], "transfer(address,uint256)");
+    assert_eq!(res["selector"], "0xa9059cbb");
+
+    let res = tool
+        .call(&client, json!({"operation": "event_topic", "input": "event Transfer(address indexed from, address indexed to, uint256 value)"}))
+        .await
+        .unwrap();
+    assert_eq!(
+        res["topic0"],
+        "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+    );
+
+    let res = tool
+        .call(&client, json!({"operation": "keccak256", "input": ""}))
+        .await
+        .unwrap();
+    assert_eq!(
+        res["hash"],
+        "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+    let hex = tool
+        .call(
+            &client,
+            json!({"operation": "keccak256", "input": "0x616263"}),
+        )
+        .await
+        .unwrap();
+    let utf8 = tool
+        .call(&client, json!({"operation": "keccak256", "input": "abc"}))
+        .await
+        .unwrap();
+    assert_eq!(hex["encoding"], "hex");
+    assert_eq!(hex["hash"], utf8["hash"]);
+}
+
+#[test]
+fn test_denylist_blocks_addresses_anywhere_in_arguments() {
+    let denylist = DenyList::parse(
+        "# sanctioned\n0x8589427373D6D84E98730D7795D8f6f8731FDA16\n\n0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48 # USDC\n",
+    )
+    .unwrap();
+    assert_eq!(denylist.len(), 2);
+    let registry = TokenRegistry::builtin();
+
+    // Case-insensitive match on a nested recipient.
+    let err = denylist
+        .check_args(
+            &json!({"tx": {"to": "0x8589427373d6d84e98730d7795d8f6f8731fda16"}}),
+            &registry,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("deny-list"), "{}", err);
+    // A symbol resolving to a denied token is refused too.
+    assert!(denylist
+        .check_args(&json!({"tokens": ["WETH", "usdc"]}), &registry)
+        .is_err());
+    assert!(denylist
+        .check_args(&json!({"token_symbol": "WETH", "amount": "100"}), &registry)
+        .is_ok());
+
+    let json_list = DenyList::parse(r#"["0x8589427373D6D84E98730D7795D8f6f8731FDA16"]"#).unwrap();
+    assert_eq!(json_list.len(), 1);
+    assert!(DenyList::parse("not-an-address").is_err());
+}
+
+#[tokio::test]
+async fn test_multicall_falls_back_to_individual_calls() {
+    // Calldata 0x12 reverts with data, anything else returns a word.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        if data == "0x12" {
+            json!({ "rpc_error": { "code": 3, "message": "execution reverted", "data": "0xdead" } })
+        } else {
+            json!(format!("0x{:064x}", 7))
+        }
+    })
+    .await;
+    let mut client = EthereumClient::read_only(&url).await.unwrap();
+    client.multicall_available = false;
+    let target = alloy::primitives::Address::ZERO;
+
+    // A revert fails only its own allowFailure call, with the revert data...
+    let results = client
+        .multicall(vec![call3(target, vec![0x12]), call3(target, vec![0x34])])
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(!results[0].success);
+    assert_eq!(results[0].returnData.to_vec(), vec![0xde, 0xad]);
+    assert!(results[1].success);
+
+    // ...while a required call fails the whole batch, as aggregate3 would.
+    let mut required = call3(target, vec![0x12]);
+    required.allowFailure = false;
+    assert!(client.multicall(vec![required]).await.is_err());
+
+    // An unreachable node is an outage, not a revert, even for allowFailure calls.
+    let mut offline = offline_client().await;
+    offline.multicall_available = false;
+    let err = offline
+        .multicall(vec![call3(target, vec![0x34])])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(ToolError::classify(&err).code(), RPC_UNAVAILABLE_CODE);
+}
+
+#[tokio::test]
+async fn test_watch_transaction_returns_confirmed_receipt() {
+    let client = setup_client().await;
+    // First ever ETH transfer (block 46147); long since confirmed, so no polling happens.
+    let res = WatchTransactionTool
+        .call(
+            &client,
+            json!({
+                "tx_hash": "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060",
+                "confirmations": 12
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "confirmed");
+    assert_eq!(res["receipt"]["block_number"], 46147);
+    // Pre-Byzantium receipts have no status field.
+    assert_eq!(res["receipt"]["success"], Value::Null);
+    assert_eq!(res["updates"][0]["message"], "mined in block 46147");
+    assert_eq!(res["updates"][1]["message"], "12/12 confirmations");
+}
+
+#[test]
+fn test_oracle_deviation_percent() {
+    let oracle_rate = Decimal::from(2000);
+    assert_eq!(
+        oracle::deviation_percent(oracle_rate, Decimal::from(1950)),
+        Decimal::from_str("2.5").unwrap()
+    );
+    // A better-than-oracle rate is a negative deviation and never trips the guard.
+    assert!(oracle::deviation_percent(oracle_rate, Decimal::from(2010)) < Decimal::ZERO);
+    let usdc =
+        alloy::primitives::Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap();
+    assert!(oracle::usd_feed(chains::MAINNET, usdc).is_some());
+    assert!(oracle::usd_feed(10, usdc).is_none());
+}
+
+#[tokio::test]
+async fn test_oracle_refuses_stale_feeds() {
+    use alloy::sol_types::SolCall;
+    use ethereum_trading_mcp::ethereum::getCurrentBlockTimestampCall;
+
+    // ETH/USD 2000 and USDC/USD 1 updated a minute before the block; DAI/USD last updated
+    // 10000s before it.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let calls = aggregate3Call::abi_decode(&hex::decode(&data[2..]).unwrap(), true)
+            .unwrap()
+            .calls;
+        let results: Vec<Call3Result> = calls
+            .iter()
+            .map(|c| {
+                let target = hex::encode(c.target);
+                let answer = match hex::encode(&c.callData[..4]).as_str() {
+                    "feaf968c" => {
+                        let (price, updated_at) = match target.as_str() {
+                            "5f4ec3df9cbd43714fe2740f5e3616155c5b8419" => {
+                                (200_000_000_000, 1_699_999_940)
+                            }
+                            "8fffffd4afb6115b954bd326cbe7b4ba576818f6" => {
+                                (100_000_000, 1_699_999_940)
+                            }
+                            _ => (100_000_000, 1_699_990_000),
+                        };
+                        format!(
+                            "{}{}{}{}{}",
+                            word(1),
+                            word(price),
+                            word(updated_at),
+                            word(updated_at),
+                            word(1)
+                        )
+                    }
+                    "313ce567" => word(8),
+                    selector if selector == hex::encode(getCurrentBlockTimestampCall::SELECTOR) => {
+                        word(1_700_000_000)
+                    }
+                    other => panic!("unexpected call {}", other),
+                };
+                Call3Result {
+                    success: true,
+                    returnData: hex::decode(answer).unwrap().into(),
+                }
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let token = |a: &str| alloy::primitives::Address::from_str(a).unwrap();
+    let weth = token("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
+
+    let rate = oracle::pair_rate(
+        &client,
+        weth,
+        token("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(rate, Some(Decimal::from(2000)));
+
+    let err = oracle::pair_rate(
+        &client,
+        weth,
+        token("0x6B175474E89094C44Da98b954EedeAC495271d0F"),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("10000s ago"), "{}", err);
+
+    // Stablecoin pegs get the same check.
+    let usdc = oracle::stablecoin_peg(&client, token("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(usdc.price_usd, Decimal::ONE);
+    let err = oracle::stablecoin_peg(&client, token("0x6B175474E89094C44Da98b954EedeAC495271d0F"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("10000s ago"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_swap_refuses_rate_far_below_oracle() {
+    let mut client = setup_client().await;
+    // A negative limit means even a perfect oracle-rate fill is "too far" off.
+    client.max_oracle_deviation = Some(Decimal::from(-50));
+    let args = json!({
+        "from_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "to_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "amount": "1000000000000000000"
+    });
+    let err = SwapTokensTool
+        .call(&client, args.clone())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Chainlink rate"), "{}", err);
+
+    let mut overridden = args;
+    overridden["ignore_oracle_deviation"] = json!(true);
+    let res = SwapTokensTool.call(&client, overridden).await.unwrap();
+    assert_eq!(res["oracle_check"]["status"], "overridden");
+}
+
+#[tokio::test]
+async fn test_list_chains_marks_active_chain() {
+    let client = setup_client().await;
+    let res = ListChainsTool.call(&client, json!({})).await.unwrap();
+    let chains = res["chains"].as_array().unwrap();
+    assert!(chains.len() >= 4);
+    let active: Vec<_> = chains.iter().filter(|c| c["active"] == true).collect();
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0]["chain_id"], res["active_chain_id"]);
+    assert!(active[0]["uniswap_v3"]["factory"].is_string());
+}
+
+#[tokio::test]
+async fn test_chainlink_non_positive_price_is_rejected() {
+    // Mock aggregator: latestRoundData() answers 0 in an otherwise valid round, decimals() = 8.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let word = |n: u64| format!("{:064x}", n);
+        if data.starts_with("0xfeaf968c") {
+            json!(format!(
+                "0x{}{}{}{}{}",
+                word(1),
+                word(0),
+                word(1),
+                word(1),
+                word(1)
+            ))
+        } else {
+            json!(format!("0x{}", word(8)))
+        }
+    })
+    .await;
+    let mut client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    client.multicall_available = false;
+    let err = oracle::latest_price(&client, oracle::eth_usd_feed(&client).unwrap())
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Chainlink feed returned non-positive price"),
+        "{}",
+        err
+    );
+
+    let round = |answer: i64, updated_at: u64, answered_in: u128| latestRoundDataReturn {
+        roundId: 10,
+        answer: alloy::primitives::I256::try_from(answer).unwrap(),
+        startedAt: U256::from(1),
+        updatedAt: U256::from(updated_at),
+        answeredInRound: answered_in,
+    };
+    assert!(oracle::price_from_round(&round(-5, 1, 10), 8).is_err());
+    assert!(oracle::price_from_round(&round(100, 0, 10), 8).is_err());
+    assert!(oracle::price_from_round(&round(100, 1, 9), 8)
+        .unwrap_err()
+        .to_string()
+        .contains("stale"));
+    assert_eq!(
+        oracle::price_from_round(&round(250_000_000_000, 1, 10), 8).unwrap(),
+        Decimal::from(2500)
+    );
+}
+
+#[tokio::test]
+async fn test_revert_reasons_are_decoded() {
+    use alloy::providers::Provider;
+
+    let error_string = hex::decode(
+        "08c379a0\
+         0000000000000000000000000000000000000000000000000000000000000020\
+         0000000000000000000000000000000000000000000000000000000000000003\
+         5354460000000000000000000000000000000000000000000000000000000000",
+    )
+    .unwrap();
+    assert_eq!(revert_reason(&error_string).as_deref(), Some("STF"));
+    let panic = hex::decode(format!("4e487b71{:064x}", 0x11)).unwrap();
+    assert_eq!(
+        revert_reason(&panic).as_deref(),
+        Some("Panic(0x11): arithmetic underflow or overflow")
+    );
+    assert_eq!(revert_reason(&[]), None);
+    assert_eq!(revert_reason(&hex::decode("e450d38c").unwrap()), None);
+
+    // A failed batch entry names its reason.
+    let failed = Call3Result {
+        success: false,
+        returnData: error_string.into(),
+    };
+    let err = decode_result::<aggregate3Call>(&failed, Address::ZERO)
+        .err()
+        .unwrap();
+    assert!(err.to_string().ends_with("reverted: STF"), "{}", err);
+
+    // Nodes that only return the data get the reason appended to their message.
+    let url = mock_rpc(|method, _| match method {
+        "eth_call" => json!({ "rpc_error": {
+            "code": 3,
+            "message": "execution reverted",
+            "data": format!("0x4e487b71{:064x}", 0x11)
+        }}),
+        _ => json!("0x"),
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let err = client
+        .provider
+        .call(&TransactionRequest::default())
+        .await
+        .unwrap_err();
+    assert!(
+        describe_call_error(&err)
+            .ends_with("(revert reason: Panic(0x11): arithmetic underflow or overflow)"),
+        "{}",
+        describe_call_error(&err)
+    );
+}
+
+#[tokio::test]
+async fn test_tool_errors_are_classified() {
+    use alloy::providers::Provider;
+
+    // A bad argument keeps its kind through anyhow.
+    let client = offline_client().await;
+    let err = GetBalanceTool.call(&client, json!({})).await.unwrap_err();
+    let kind = ToolError::classify(&err);
+    assert_eq!(kind, ToolError::InvalidArgument("Missing address".into()));
+    assert_eq!(kind.code(), -32602);
+    assert_eq!(
+        kind.data(),
+        json!({"kind": "invalid_argument", "retryable": false})
+    );
+
+    // A node that refuses connections is an outage worth retrying.
+    let err = GetBlockTool.call(&client, json!({})).await.unwrap_err();
+    let kind = ToolError::classify(&err);
+    assert_eq!(kind.code(), RPC_UNAVAILABLE_CODE);
+    assert_eq!(kind.data()["retryable"], true);
+
+    // A revert carries its decoded reason.
+    let url = mock_rpc(|method, _| match method {
+        "eth_call" => json!({ "rpc_error": {
+            "code": 3,
+            "message": "execution reverted: STF",
+            "data": "0x08c379a0\
+                     0000000000000000000000000000000000000000000000000000000000000020\
+                     0000000000000000000000000000000000000000000000000000000000000003\
+                     5354460000000000000000000000000000000000000000000000000000000000"
+        }}),
+        _ => json!("0x"),
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let err = client
+        .provider
+        .call(&TransactionRequest::default())
+        .await
+        .unwrap_err();
+    let kind = ToolError::classify(&err.into());
+    assert_eq!(kind.code(), REVERT_CODE);
+    let data = kind.data();
+    assert_eq!(data["kind"], "revert");
+    assert_eq!(data["revert"]["kind"], "error_string");
+    assert_eq!(data["revert"]["reason"], "STF");
+
+    // Signing in read-only mode is not supported, and not retryable.
+    let kind = ToolError::classify(&client.require_signer().unwrap_err());
+    assert_eq!(kind.code(), NOT_SUPPORTED_CODE);
+    assert_eq!(kind.kind(), "not_supported");
+}
+
+#[tokio::test]
+async fn test_large_fields_are_attached_as_resources() {
+    let client = offline_client().await;
+    let payload = format!("0x{}", "ab".repeat(1000));
+    let res = EncodeCalldataTool
+        .call(
+            &client,
+            json!({"function_signature": "f(bytes data)", "args": [payload]}),
+        )
+        .await
+        .unwrap();
+    let calldata = res["calldata"].as_str().unwrap().to_string();
+
+    let content = tool_content("encode_calldata", &res, EncodeCalldataTool.large_fields());
+    assert_eq!(content.len(), 2);
+    let text: Value = serde_json::from_str(content[0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(text["selector"], res["selector"]);
+    assert!(text["calldata"]
+        .as_str()
+        .unwrap()
+        .contains("attached as resource tool://encode_calldata/calldata"));
+    assert_eq!(content[1]["type"], "resource");
+    let resource = &content[1]["resource"];
+    assert_eq!(resource["uri"], "tool://encode_calldata/calldata");
+    assert_eq!(resource["mimeType"], "application/octet-stream");
+    let blob = base64::engine::general_purpose::STANDARD
+        .decode(resource["blob"].as_str().unwrap())
+        .unwrap();
+    assert_eq!(format!("0x{}", hex::encode(blob)), calldata);
+
+    // Short fields, and `*` over arrays: only the long entry moves out.
+    let res = json!({"transactions": [{"data": "0x1234"}, {"data": calldata}]});
+    let content = tool_content("simulate_swap_plan", &res, &["/transactions/*/data"]);
+    assert_eq!(content.len(), 2);
+    assert_eq!(
+        content[1]["resource"]["uri"],
+        "tool://simulate_swap_plan/transactions/1/data"
+    );
+    assert!(content[0]["text"].as_str().unwrap().contains("0x1234"));
+    assert_eq!(tool_content("get_block", &res, &[]).len(), 1);
+}
+
+#[tokio::test]
+async fn test_encode_calldata() {
+    let client = offline_client().await;
+    let res = EncodeCalldataTool
+        .call(
+            &client,
+            json!({
+                "function_signature": "transfer(address to, uint256 amount)",
+                "args": ["0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045", "1000000"]
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["selector"], "0xa9059cbb");
+    assert_eq!(
+        res["calldata"],
+        "0xa9059cbb000000000000000000000000d8da6bf26964af9d7eed9e03e53415d37aa9604500000000000000000000000000000000000000000000000000000000000f4240"
+    );
+
+    // Nested arrays and tuples are JSON arrays.
+    let res = EncodeCalldataTool
+        .call(
+            &client,
+            json!({
+                "function_signature": "f((uint24,bool)[] pairs)",
+                "args": [[[3000, true], ["500", false]]]
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["signature"], "f((uint24,bool)[])");
+
+    let err = EncodeCalldataTool
+        .call(
+            &client,
+            json!({
+                "function_signature": "transfer(address to, uint256 amount)",
+                "args": ["0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045", "-1"]
+            }),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("argument 1 (uint256 amount)"),
+        "{}",
+        err
+    );
+    assert!(EncodeCalldataTool
+        .call(
+            &client,
+            json!({"function_signature": "approve(address,uint256)", "args": ["0x01"]})
+        )
+        .await
+        .is_err());
+}
+
+#[test]
+fn test_chain_block_times() {
+    assert_eq!(chains::block_time_seconds(1), 12.0);
+    assert_eq!(chains::block_time_seconds(42161), 0.25);
+    assert_eq!(chains::block_time_seconds(8453), 2.0);
+    // Unknown chains fall back to Ethereum's block time.
+    assert_eq!(chains::block_time_seconds(999_999), 12.0);
+    assert!(chains::supported()
+        .iter()
+        .all(|c| c.block_time_seconds > 0.0));
+}
+
+#[tokio::test]
+async fn test_verify_signature_personal_sign_and_typed_data() {
+    use alloy::signers::{local::PrivateKeySigner, SignerSync};
+
+    let client = offline_client().await;
+    let signer = PrivateKeySigner::random();
+    let address = signer.address().to_string();
+    let hex_sig = |sig: alloy::primitives::Signature| format!("0x{}", hex::encode(sig.as_bytes()));
+
+    let sig = hex_sig(signer.sign_message_sync(b"login nonce 42").unwrap());
+    let res = VerifySignatureTool
+        .call(
+            &client,
+            json!({"message": "login nonce 42", "signature": sig, "address": address}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["valid"], true);
+    assert_eq!(res["scheme"], "eip191");
+
+    // Same signature over a different message recovers someone else.
+    let res = VerifySignatureTool
+        .call(
+            &client,
+            json!({"message": "login nonce 43", "signature": sig, "address": address}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["valid"], false);
+
+    let typed_data = json!({
+        "types": {
+            "EIP712Domain": [{"name": "name", "type": "string"}, {"name": "chainId", "type": "uint256"}],
+            "Login": [{"name": "user", "type": "address"}, {"name": "nonce", "type": "uint256"}]
+        },
+        "primaryType": "Login",
+        "domain": {"name": "Example", "chainId": 1},
+        "message": {"user": address, "nonce": 7}
+    });
+    let typed: alloy::dyn_abi::TypedData = serde_json::from_value(typed_data.clone()).unwrap();
+    let sig = hex_sig(
+        signer
+            .sign_hash_sync(&typed.eip712_signing_hash().unwrap())
+            .unwrap(),
+    );
+    let res = VerifySignatureTool
+        .call(
+            &client,
+            json!({"typed_data": typed_data, "signature": sig, "address": address}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["valid"], true);
+    assert_eq!(res["scheme"], "eip712");
+}
+
+#[test]
+fn test_select_fields_trims_results() {
+    let result = json!({"estimated_output": "1", "transaction": {"data": "0x..."}, "note": "x"});
+    assert_eq!(select_fields(result.clone(), None).unwrap(), result);
+    assert_eq!(
+        select_fields(result.clone(), Some(&json!(["estimated_output"]))).unwrap(),
+        json!({"estimated_output": "1"})
+    );
+    let err = select_fields(result.clone(), Some(&json!(["estimated_outptu"]))).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("available: estimated_output, note, transaction"));
+    assert!(select_fields(result, Some(&json!("note"))).is_err());
+
+    let schema = with_common_properties(HashTool.schema());
+    assert_eq!(schema["properties"]["fields"]["type"], "array");
+    assert_eq!(schema["properties"]["stream"]["type"], "string");
+}
+
+#[test]
+fn test_recommend_gas_for_deadline() {
+    let history = alloy::rpc::types::eth::FeeHistory {
+        base_fee_per_gas: vec![9, 10, 10, 100],
+        gas_used_ratio: vec![0.5, 0.0, 0.9],
+        oldest_block: 100,
+        reward: Some(vec![
+            vec![1, 2, 3, 4, 5],
+            vec![0, 0, 0, 0, 0],
+            vec![3, 4, 5, 6, 7],
+        ]),
+        ..Default::default()
+    };
+
+    let next = recommend_for_deadline(&history, 1).unwrap();
+    // 90th percentile of the two non-empty blocks; the empty block is skipped.
+    assert_eq!(next.blocks_sampled, 2);
+    assert_eq!(next.max_priority_fee_per_gas, 7);
+    assert_eq!(next.max_base_fee, 100);
+    assert_eq!(next.max_fee_per_gas, 107);
+
+    let relaxed = recommend_for_deadline(&history, 3).unwrap();
+    assert_eq!(relaxed.percentile, 50.0);
+    assert_eq!(relaxed.max_priority_fee_per_gas, 5);
+    // 100 -> 112 -> 126 across the two extra blocks.
+    assert_eq!(relaxed.max_base_fee, 126);
+}
+
+#[test]
+fn test_receipt_summary_tolerates_missing_fields() {
+    // Pre-Byzantium contract creation: no status, null `to`, no effectiveGasPrice or logs.
+    let receipt = json!({
+        "blockNumber": "0xb443",
+        "gasUsed": "0x5208",
+        "to": null,
+        "contractAddress": "0x6ba6d9e5a4a5ab87a5bb4e1e2c6a4c0a0d3b2d11",
+        "root": "0x01"
+    });
+    let summary = receipt_summary(&receipt, Some(&json!({"gasPrice": "0x2"})));
+    assert_eq!(summary["success"], Value::Null);
+    assert_eq!(summary["block_number"], 46147);
+    assert_eq!(summary["to"], Value::Null);
+    assert_eq!(summary["from"], Value::Null);
+    assert_eq!(summary["logs"], 0);
+    assert_eq!(summary["effective_gas_price"], "2");
+    assert_eq!(
+        summary["effective_gas_price_source"],
+        "transaction.gasPrice"
+    );
+    assert_eq!(summary["fee_paid_wei"], "42000");
+
+    // Nothing at all: every field degrades to null rather than panicking.
+    let empty = receipt_summary(&json!({}), None);
+    assert_eq!(empty["gas_used"], Value::Null);
+    assert_eq!(empty["fee_paid_wei"], Value::Null);
+    assert_eq!(
+        receipt_summary(&json!({"status": "0x0"}), None)["success"],
+        false
+    );
+}
+
+#[tokio::test]
+async fn test_tools_handle_sparse_rpc_responses() {
+    let url = mock_rpc(|method, _params| match method {
+        "eth_chainId" => json!("0x1"),
+        "eth_blockNumber" => json!("0x10"),
+        // Minimal receipt: no status, effectiveGasPrice, logs, from or to.
+        "eth_getTransactionReceipt" => json!({"blockNumber": "0x10", "gasUsed": "0x5208"}),
+        "eth_getTransactionByHash" => json!({"gasPrice": "0x3b9aca00"}),
+        // No reward percentiles, no base fees.
+        "eth_feeHistory" => json!({"oldestBlock": "0x1", "gasUsedRatio": [0.5]}),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let res = WatchTransactionTool
+        .call(
+            &client,
+            json!({"tx_hash": "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "confirmed");
+    assert_eq!(res["receipt"]["success"], Value::Null);
+    assert_eq!(res["receipt"]["effective_gas_price"], "1000000000");
+
+    let err = GasForDeadlineTool
+        .call(&client, json!({"target_blocks": 2}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no base fees"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_watch_transaction_reports_progress_and_reorgs() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static POLLS: AtomicU64 = AtomicU64::new(0);
+
+    // Mined in block 0x10, reorged out on the next poll, then mined again in 0x11.
+    let url = mock_rpc(|method, _| match method {
+        "eth_getTransactionReceipt" => match POLLS.fetch_add(1, Ordering::SeqCst) {
+            0 => json!({"blockNumber": "0x10", "gasUsed": "0x5208", "effectiveGasPrice": "0x1"}),
+            1 => Value::Null,
+            _ => json!({"blockNumber": "0x11", "gasUsed": "0x5208", "effectiveGasPrice": "0x1"}),
+        },
+        "eth_getTransactionByHash" => json!({"gasPrice": "0x1"}),
+        "eth_blockNumber" => match POLLS.load(Ordering::SeqCst) {
+            1 => json!("0x10"),
+            _ => json!("0x13"),
+        },
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+
+    let (sink, mut events) = tokio::sync::mpsc::unbounded_channel();
+    let res = with_progress(
+        sink,
+        WatchTransactionTool.call(
+            &client,
+            json!({"tx_hash": format!("0x{}", "ab".repeat(32)), "confirmations": 3, "poll_interval_seconds": 1}),
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(res["status"], "confirmed");
+    assert_eq!(res["reorgs"], 1);
+    let statuses: Vec<String> = res["updates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|u| u["status"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            "mined",
+            "confirming",
+            "reorged",
+            "pending",
+            "mined",
+            "confirming"
+        ]
+    );
+
+    // Each update also went out as a progress event, as it happened.
+    let mut sent = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        sent.push(event);
+    }
+    assert_eq!(&sent, res["updates"].as_array().unwrap());
+}
+
+#[test]
+fn test_net_of_gas() {
+    let d = |s: &str| Decimal::from_str(s).unwrap();
+    let (net, fraction, flagged) = net_of_gas(d("100"), d("2"), d("0.05"));
+    assert_eq!(net, d("98"));
+    assert_eq!(fraction, Some(d("0.02")));
+    assert!(!flagged);
+
+    // Gas larger than the trade: negative net, flagged.
+    let (net, _, flagged) = net_of_gas(d("1"), d("3"), d("0.05"));
+    assert_eq!(net, d("-2"));
+    assert!(flagged);
+    assert!(net_of_gas(Decimal::ZERO, d("1"), d("0.05")).2);
+}
+
+#[tokio::test]
+async fn test_token_transfers_scans_in_chunks() {
+    // Each eth_getLogs chunk yields one mint to A and one A -> B transfer, at the chunk's
+    // first block; a 4-topic ERC721 transfer is mixed into the first chunk.
+    let url = mock_rpc(|method, params| match method {
+        "eth_call" => json!(format!("0x{:064x}", 6)),
+        "eth_getLogs" => {
+            let from = params[0]["fromBlock"].as_str().unwrap().to_string();
+            let topic0 = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+            let word = |n: u64| format!("0x{:064x}", n);
+            let log = |from_addr: u64, to_addr: u64, index: u64| {
+                json!({
+                    "address": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                    "topics": [topic0, word(from_addr), word(to_addr)],
+                    "data": word(1_500_000),
+                    "blockNumber": from,
+                    "blockHash": word(1),
+                    "transactionHash": format!("0x{:064x}", index + 1),
+                    "transactionIndex": "0x0",
+                    "logIndex": format!("0x{:x}", index),
+                    "removed": false
+                })
+            };
+            let mut logs = vec![log(0, 0xa, 0), log(0xa, 0xb, 1)];
+            if from == "0x1" {
+                logs.push(json!({
+                    "address": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                    "topics": [topic0, word(0xa), word(0xb), word(7)],
+                    "data": "0x",
+                    "blockNumber": from,
+                    "blockHash": word(1),
+                    "transactionHash": word(9),
+                    "transactionIndex": "0x0",
+                    "logIndex": "0x2",
+                    "removed": false
+                }));
+            }
+            json!(logs)
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let res = GetTokenTransfersTool
+        .call(
+            &client,
+            json!({
+                "token_address": "USDC",
+                "from_block": 1,
+                "to_block": 4500,
+                "limit": 2
+            }),
+        )
+        .await
+        .unwrap();
+    let stats = &res["stats"];
+    assert_eq!(stats["transfer_count"], 6);
+    assert_eq!(stats["mints"], 3);
+    assert_eq!(stats["burns"], 0);
+    assert_eq!(stats["unique_senders"], 1);
+    assert_eq!(stats["unique_receivers"], 2);
+    assert_eq!(stats["undecodable_logs"], 1);
+    assert_eq!(stats["total_volume"], "9");
+    assert_eq!(res["decimals"], 6);
+    // Most recent first: the last chunk starts at block 4001.
+    let transfers = res["transfers"].as_array().unwrap();
+    assert_eq!(transfers.len(), 2);
+    assert_eq!(transfers[0]["block_number"], 4001);
+    assert_eq!(transfers[0]["amount"], "1.5");
+
+    let err = GetTokenTransfersTool
+        .call(
+            &client,
+            json!({"token_address": "USDC", "from_block": 1, "to_block": 100_000}),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("narrow the range"));
+}
+
+#[tokio::test]
+async fn test_numeric_args_accept_strings() {
+    let args = json!({
+        "fee": "3000",
+        "whole_float": 500.0,
+        "slippage_tolerance": " 0.5 ",
+        "number": 1.25,
+        "bad": "three thousand",
+        "negative": -1,
+        "null": null
+    });
+    assert_eq!(u64_arg(&args, "fee").unwrap(), Some(3000));
+    assert_eq!(u64_arg(&args, "whole_float").unwrap(), Some(500));
+    assert_eq!(u64_arg(&args, "missing").unwrap(), None);
+    assert_eq!(u64_arg(&args, "null").unwrap(), None);
+    assert_eq!(f64_arg(&args, "slippage_tolerance").unwrap(), Some(0.5));
+    assert_eq!(f64_arg(&args, "number").unwrap(), Some(1.25));
+    assert!(u64_arg(&args, "number").is_err());
+    assert!(u64_arg(&args, "negative").is_err());
+    let err = u64_arg(&args, "bad").unwrap_err().to_string();
+    assert!(
+        err.contains("bad must be a non-negative integer"),
+        "{}",
+        err
+    );
+    assert!(f64_arg(&args, "bad").is_err());
+    assert_eq!(fee_arg(&args).unwrap(), Some(3000));
+    // Out-of-range and unknown tiers are refused, not truncated to a uint24.
+    for fee in [json!(4_294_967_796u64), json!(2500), json!(16_777_716)] {
+        let err = fee_arg(&json!({ "fee": fee })).unwrap_err();
+        assert_eq!(
+            ToolError::classify(&err),
+            ToolError::InvalidArgument(format!(
+                "fee must be one of [100, 500, 3000, 10000], got {}",
+                fee
+            ))
+        );
+    }
+
+    // A typo'd fee tier is an error rather than a silent fallback to 3000.
+    let client = offline_client().await;
+    let err = SwapTokensTool
+        .call(
+            &client,
+            json!({
+                "from_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+                "to_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                "amount": "1000",
+                "fee": "0.3%"
+            }),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("fee must be"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_stablecoin_priced_at_live_peg() {
+    // USDC/USD reads 0.998, ETH/USD 2000; both feeds use 8 decimals.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let word = |n: u64| format!("{:064x}", n);
+        if !data.starts_with("0xfeaf968c") {
+            return json!(format!("0x{}", word(8)));
+        }
+        let answer = if to == "0x8fffffd4afb6115b954bd326cbe7b4ba576818f6" {
+            99_800_000
+        } else {
+            200_000_000_000
+        };
+        json!(format!(
+            "0x{}{}{}{}{}",
+            word(1),
+            word(answer),
+            word(1),
+            word(1),
+            word(1)
+        ))
+    })
+    .await;
+    let mut client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    client.multicall_available = false;
+
+    let price = GetTokenPriceTool
+        .call(&client, json!({"token_symbol": "USDC"}))
+        .await
+        .unwrap();
+    assert_eq!(price["price_usd"], json!("0.99800000"));
+    assert_eq!(price["stablecoin_peg"]["source"], "Chainlink USD feed");
+
+    let converted = ConvertTool
+        .call(
+            &client,
+            json!({"from_token": "USDC", "to_token": "ETH", "amount": "2000"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(converted["amount_out"], json!("0.998"));
+    assert_eq!(converted["source"], "stablecoin peg and Chainlink ETH/USD");
+    assert!(converted["stablecoin_pegs"]["USDC"].is_object());
+
+    client.assume_stablecoin_peg = true;
+    let price = GetTokenPriceTool
+        .call(&client, json!({"token_symbol": "USDC"}))
+        .await
+        .unwrap();
+    assert_eq!(price["price_usd"], json!("1"));
+    assert_eq!(price["stablecoin_peg"]["source"], "assumed $1 peg");
+    assert!(price["stablecoin_peg"]["feed"].is_null());
+}
+
+#[tokio::test]
+async fn test_swap_plan_overrides_missing_approval() {
+    // USDC -> ETH with no allowance: the router only succeeds when the simulation carries a
+    // storage override, i.e. when the approval was simulated in.
+    let url = mock_rpc(|method, params| {
+        use alloy::primitives::Bytes;
+        use alloy::sol_types::SolValue;
+        assert_eq!(method, "eth_call");
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let overridden = params.get(2).is_some_and(|o| !o.is_null());
+        let word = |n: u128| format!("0x{:064x}", n);
+        match &data[..10] {
+            "0x313ce567" => json!(word(6)),
+            "0x70a08231" => json!(word(5_000_000_000)),
+            "0xdd62ed3e" if overridden => {
+                let diff = params[2].as_object().unwrap().values().next().unwrap();
+                json!(diff["stateDiff"]
+                    .as_object()
+                    .unwrap()
+                    .values()
+                    .next()
+                    .unwrap())
+            }
+            "0xdd62ed3e" => json!(word(0)),
+            "0x095ea7b3" => json!(word(1)),
+            // quoteExactInputSingle: amountOut 0.5 ETH, gasEstimate 100k
+            "0xc6a5026a" => json!(format!(
+                "0x{:064x}{:064x}{:064x}{:064x}",
+                500_000_000_000_000_000u128, 1, 1, 100_000
+            )),
+            // multicall(exactInputSingle, unwrapWETH9)
+            "0xac9650d8" if overridden => {
+                let results = vec![
+                    Bytes::from(U256::from(500_000_000_000_000_000u128).abi_encode()),
+                    Bytes::new(),
+                ];
+                json!(format!("0x{}", hex::encode(results.abi_encode())))
+            }
+            other => panic!("unexpected call {} (overridden: {})", other, overridden),
+        }
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let res = SimulateSwapPlanTool
+        .call(
+            &client,
+            json!({"from_token": "USDC", "to_token": "ETH", "amount": "1000000000"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["would_succeed"], true, "{}", res);
+    let steps = res["steps"].as_array().unwrap();
+    let status = |name: &str| {
+        steps
+            .iter()
+            .find(|s| s["step"] == name)
+            .map(|s| s["status"].clone())
+    };
+    assert_eq!(status("balance"), Some(json!("ok")));
+    assert_eq!(status("approve"), Some(json!("action_required")));
+    assert_eq!(status("swap"), Some(json!("ok")));
+    assert_eq!(status("unwrap"), Some(json!("ok")));
+    assert_eq!(res["transactions"].as_array().unwrap().len(), 2);
+    assert_eq!(res["net_result"]["received"], "0.5");
+    assert_eq!(res["net_result"]["spent"], "1000");
+}
+
+#[test]
+fn test_sessions_are_distinct_and_count_requests() {
+    let a = Session::new();
+    let b = Session::new();
+    assert_ne!(a.id, b.id);
+    assert_eq!(a.id.len(), 32);
+    assert_eq!(a.next_request(), 1);
+    assert_eq!(a.next_request(), 2);
+    assert_eq!(a.requests(), 2);
+    assert_eq!(b.requests(), 0);
+}
+
+#[tokio::test]
+async fn test_swap_split_beats_single_pool() {
+    // Two constant-product-like pools at price 1: the 0.3% tier is three times as deep as
+    // the 0.05% tier; the other tiers have no pool (empty return data).
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let word = |i: usize| U256::from_str_radix(&data[10 + 64 * i..74 + 64 * i], 16).unwrap();
+        let (amount_in, fee) = (word(2), word(3));
+        let depth = U256::from(10u64).pow(U256::from(21));
+        let liquidity = match fee.to::<u64>() {
+            500 => depth,
+            3000 => depth * U256::from(3),
+            _ => return json!("0x"),
+        };
+        let out = amount_in * liquidity / (liquidity + amount_in);
+        json!(format!(
+            "0x{:064x}{:064x}{:064x}{:064x}",
+            out, 1, 1, 100_000
+        ))
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let res = OptimizeSwapSplitTool
+        .call(
+            &client,
+            json!({
+                "from_token": "USDC",
+                "to_token": "WETH",
+                "amount": "2000000000000000000000"
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["best_single_pool"]["fee"], 3000);
+    let split = res["split"].as_array().unwrap();
+    assert_eq!(split.len(), 2, "{}", res);
+    let total = U256::from_str_radix(res["total_output"].as_str().unwrap(), 10).unwrap();
+    let single =
+        U256::from_str_radix(res["best_single_pool"]["amount_out"].as_str().unwrap(), 10).unwrap();
+    assert!(total > single);
+    // Depth-proportional is optimal: roughly a quarter in the shallow pool.
+    let shallow = split.iter().find(|l| l["fee"] == 500).unwrap();
+    let share: f64 = shallow["share_percent"].as_str().unwrap().parse().unwrap();
+    assert!((20.0..=30.0).contains(&share), "{}", share);
+    let legs_in: U256 = split
+        .iter()
+        .map(|l| U256::from_str_radix(l["amount_in"].as_str().unwrap(), 10).unwrap())
+        .sum();
+    assert_eq!(legs_in.to_string(), "2000000000000000000000");
+}
+
+#[test]
+fn test_request_limiter_per_client_and_global() {
+    let limiter = RequestLimiter::new(3, 2);
+    let a1 = limiter.try_acquire("a").unwrap();
+    let _a2 = limiter.try_acquire("a").unwrap();
+    assert_eq!(
+        limiter.try_acquire("a").err(),
+        Some(Busy::Client { limit: 2 })
+    );
+    let _b1 = limiter.try_acquire("b").unwrap();
+    // Client b is under its own limit, but the server is full.
+    assert_eq!(
+        limiter.try_acquire("b").err(),
+        Some(Busy::Server { limit: 3 })
+    );
+    assert_eq!(limiter.in_flight(), 3);
+
+    drop(a1);
+    assert_eq!(limiter.in_flight(), 2);
+    let _a3 = limiter.try_acquire("a").unwrap();
+    assert!(Busy::Server { limit: 3 }
+        .to_string()
+        .starts_with("Server busy"));
+}
+
+#[test]
+fn test_tick_price() {
+    use rust_decimal::prelude::ToPrimitive;
+    assert_eq!(tick_price(0), Some(Decimal::ONE));
+    assert_eq!(tick_price(1), Some(Decimal::new(10001, 4)));
+    assert_eq!(
+        (tick_price(-1).unwrap() * Decimal::new(10001, 4)).round_dp(20),
+        Decimal::ONE
+    );
+    for tick in [200_000, -200_000, 76_012, -276_324] {
+        let expected = 1.0001f64.powi(tick);
+        let got = tick_price(tick).unwrap().to_f64().unwrap();
+        assert!(((got - expected) / expected).abs() < 1e-9, "tick {}", tick);
+    }
+    // Beyond Decimal's range.
+    assert_eq!(tick_price(887_272), None);
+}
+
+#[tokio::test]
+async fn test_pool_observations_window() {
+    use alloy::sol_types::SolCall;
+    use ethereum_trading_mcp::ethereum::getCurrentBlockTimestampCall;
+    use ethereum_trading_mcp::tools::{observations::observationsCall, price::slot0Call};
+
+    // Buffer of 4 slots that has not wrapped yet: index 1 is newest, slot 2 is empty, so
+    // the oldest observation is slot 0, written 600 seconds ago.
+    let url = mock_rpc(|_, params| {
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let selector = hex::decode(&data[2..10]).unwrap();
+        let words = |w: &[u64]| {
+            json!(format!(
+                "0x{}",
+                w.iter().map(|v| format!("{:064x}", v)).collect::<String>()
+            ))
+        };
+        if selector == slot0Call::SELECTOR {
+            words(&[1 << 40, 100, 1, 4, 4, 0, 1])
+        } else if selector == getCurrentBlockTimestampCall::SELECTOR {
+            words(&[1_000_600])
+        } else if selector == observationsCall::SELECTOR {
+            match u64::from_str_radix(&data[10..], 16).unwrap() {
+                0 => words(&[1_000_000, 5, 0, 1]),
+                _ => words(&[0, 0, 0, 0]),
+            }
+        } else {
+            panic!("unexpected call {}", data)
+        }
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let pool = "0x8ad599c3A0ff1De082011EFDDc58f1908eb6e6D8";
+    let res = GetPoolObservationsTool
+        .call(
+            &client,
+            json!({ "pool_address": pool, "twap_window_seconds": 300 }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["slot0"]["observation_cardinality"], 4);
+    assert_eq!(res["oldest_observation"]["index"], 0);
+    assert_eq!(res["max_twap_window_seconds"], 600);
+    assert_eq!(res["recommendation"]["supported"], true);
+
+    let res = GetPoolObservationsTool
+        .call(
+            &client,
+            json!({ "pool_address": pool, "twap_window_seconds": 1800 }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["recommendation"]["supported"], false);
+    assert_eq!(res["recommendation"]["suggested_cardinality"], 150);
+    assert!(res["recommendation"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("increaseObservationCardinalityNext(150)"));
+}
+
+#[test]
+fn test_initialize_negotiates_protocol_version() {
+    let res = initialize_result(Some(&json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {},
+        "clientInfo": { "name": "test", "version": "0" }
+    })));
+    assert_eq!(res["protocolVersion"], "2024-11-05");
+    assert!(res["capabilities"]["tools"].is_object());
+    assert_eq!(res["serverInfo"]["name"], "ethereum-trading-mcp");
+    assert_eq!(res["serverInfo"]["version"], env!("CARGO_PKG_VERSION"));
+
+    let res = initialize_result(Some(&json!({ "protocolVersion": "1999-01-01" })));
+    assert_eq!(res["protocolVersion"], SUPPORTED_PROTOCOL_VERSIONS[0]);
+    assert_eq!(
+        initialize_result(None)["protocolVersion"],
+        SUPPORTED_PROTOCOL_VERSIONS[0]
+    );
+}
+
+#[tokio::test]
+async fn test_verify_pool_checks_pair_and_factory() {
+    use alloy::sol_types::SolCall;
+    use ethereum_trading_mcp::tools::price::{feeCall, getPoolCall, token0Call, token1Call};
+
+    // Every "pool" reports USDC/WETH at 0.05%; the factory only knows the real one.
+    let url = mock_rpc(|_, params| {
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let selector = hex::decode(&data[2..10]).unwrap();
+        let word = |hex: &str| json!(format!("0x{:0>64}", hex));
+        if selector == token0Call::SELECTOR {
+            word("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48")
+        } else if selector == token1Call::SELECTOR {
+            word("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2")
+        } else if selector == feeCall::SELECTOR {
+            word("1f4")
+        } else if selector == getPoolCall::SELECTOR {
+            word("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640")
+        } else {
+            panic!("unexpected call {}", data)
+        }
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let usdc = client.tokens.resolve("USDC").unwrap().address;
+    let weth = client.tokens.resolve("WETH").unwrap().address;
+    let wbtc = client.tokens.resolve("WBTC").unwrap().address;
+    let real = Address::from_str("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap();
+
+    let pair = verify_pool(&client, real, weth, usdc).await.unwrap();
+    assert_eq!((pair.token0, pair.token1, pair.fee), (usdc, weth, 500));
+
+    let err = verify_pool(&client, real, usdc, wbtc).await.unwrap_err();
+    assert!(err.to_string().contains("trades"), "{}", err);
+
+    let fake = Address::from_str("0x0000000000000000000000000000000000000bad").unwrap();
+    let err = verify_pool(&client, fake, usdc, weth).await.unwrap_err();
+    assert!(err.to_string().contains("factory pool"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_server_answers_batches() {
+    use tokio::io::AsyncWriteExt;
+
+    let url = mock_rpc(|method, _| match method {
+        "eth_chainId" => json!("0x1"),
+        "eth_getCode" => json!("0x00"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let mut server = tokio::process::Command::new(env!("CARGO_BIN_EXE_ethereum-trading-mcp"))
+        .env("ETHEREUM_RPC_URL", &url)
+        .env(
+            "PRIVATE_KEY",
+            "0x0123456789012345678901234567890123456789012345678901234567890123",
+        )
+        .env("RUST_LOG", "error")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let input = [
+        json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2024-11-05"}},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {
+                "name": "compute_hash",
+                "arguments": {"operation": "selector", "input": "transfer(address,uint256)"}
+            }},
+            {"jsonrpc": "2.0", "method": "tools/list"},
+            {"jsonrpc": "2.0", "id": 3, "method": "admin/reinitialize"}
+        ]),
+        json!([{"jsonrpc": "2.0", "method": "notifications/initialized"}]),
+        json!([]),
+    ]
+    .iter()
+    .map(|v| format!("{}\n", v))
+    .collect::<String>();
+    let mut stdin = server.stdin.take().unwrap();
+    stdin.write_all(input.as_bytes()).await.unwrap();
+    drop(stdin);
+    let output = server.wait_with_output().await.unwrap();
+    let lines: Vec<Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+
+    // The notification-only batch is silent. Lines are answered as they finish, so the
+    // two replies can come in either order.
+    assert_eq!(lines.len(), 2, "{:?}", lines);
+    let (batch, empty) = match lines[0].as_array() {
+        Some(batch) => (batch, &lines[1]),
+        None => (lines[1].as_array().unwrap(), &lines[0]),
+    };
+    assert_eq!(batch.len(), 3);
+    assert_eq!(batch[0]["id"], 1);
+    assert_eq!(batch[0]["result"]["protocolVersion"], "2024-11-05");
+    assert_eq!(batch[1]["id"], 2);
+    assert_eq!(batch[1]["result"]["data"]["selector"], "0xa9059cbb");
+    assert_eq!(batch[2]["id"], 3);
+    assert_eq!(batch[2]["error"]["code"], -32600);
+
+    assert_eq!(empty["error"]["code"], -32600);
+    assert!(empty["id"].is_null());
+}
+
+#[tokio::test]
+async fn test_stdio_lines_run_concurrently_under_the_client_limit() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    // The watched transaction never shows up, so the watch runs until its timeout.
+    let url = mock_rpc(|method, _| match method {
+        "eth_getCode" => json!("0x00"),
+        "eth_getTransactionReceipt" | "eth_getTransactionByHash" => Value::Null,
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let mut server = tokio::process::Command::new(env!("CARGO_BIN_EXE_ethereum-trading-mcp"))
+        .env("ETHEREUM_RPC_URL", &url)
+        .env("RUST_LOG", "error")
+        .env("MAX_CONCURRENT_REQUESTS_PER_CLIENT", "1")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let watch = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {
+        "name": "watch_transaction",
+        "arguments": {"tx_hash": format!("0x{}", "ab".repeat(32)), "timeout_seconds": 2, "poll_interval_seconds": 1}
+    }});
+    let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+    let mut stdin = server.stdin.take().unwrap();
+    let mut stdout = BufReader::new(server.stdout.take().unwrap()).lines();
+    stdin
+        .write_all(format!("{}\n", watch).as_bytes())
+        .await
+        .unwrap();
+    // Give the watch time to start before the second line arrives.
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    stdin
+        .write_all(format!("{}\n", list).as_bytes())
+        .await
+        .unwrap();
+
+    // The second line is answered while the watch still runs: refused, since the one
+    // stdio client already has a request in flight.
+    let first: Value = serde_json::from_str(&stdout.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(first["id"], 2);
+    assert_eq!(first["error"]["code"], -32000, "{}", first);
+    let second: Value = serde_json::from_str(&stdout.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(second["id"], 1);
+    assert_eq!(second["result"]["data"]["status"], "timeout");
+
+    // With the watch done, the limit has room again.
+    stdin
+        .write_all(format!("{}\n", list).as_bytes())
+        .await
+        .unwrap();
+    let third: Value = serde_json::from_str(&stdout.next_line().await.unwrap().unwrap()).unwrap();
+    assert!(third["result"]["tools"].is_array(), "{}", third);
+    drop(stdin);
+    assert!(server.wait().await.unwrap().success());
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_server_drains_the_request_in_flight_on_sigterm() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio::io::AsyncWriteExt;
+    static BALANCE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+    let url = mock_rpc(|method, _| match method {
+        "eth_getBalance" => {
+            BALANCE_REQUESTED.store(true, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            json!("0xde0b6b3a7640000")
+        }
+        "eth_getCode" => json!("0x00"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let spawn = || {
+        tokio::process::Command::new(env!("CARGO_BIN_EXE_ethereum-trading-mcp"))
+            .env("ETHEREUM_RPC_URL", &url)
+            .env("RUST_LOG", "info")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap()
+    };
+
+    let mut server = spawn();
+    let mut stdin = server.stdin.take().unwrap();
+    let call = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {
+        "name": "get_balance",
+        "arguments": {"address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"}
+    }});
+    stdin
+        .write_all(format!("{}\n", call).as_bytes())
+        .await
+        .unwrap();
+    // The mock blocks a runtime worker while it sleeps, so signal from a thread of its own.
+    let pid = server.id().unwrap().to_string();
+    let killer = std::thread::spawn(move || {
+        while !BALANCE_REQUESTED.load(Ordering::SeqCst) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        std::process::Command::new("kill")
+            .args(["-TERM", &pid])
+            .status()
+            .unwrap()
+    });
+    // stdin stays open: the signal alone stops the server.
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        server.wait_with_output(),
+    )
+    .await
+    .expect("server did not stop")
+    .unwrap();
+    drop(stdin);
+    assert!(killer.join().unwrap().success());
+    assert!(output.status.success(), "{:?}", output.status);
+    let response: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["id"], 1);
+    assert!(response["result"].is_object(), "{}", response);
+    let log = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        log.contains("Received SIGTERM; finishing the requests in flight"),
+        "{}",
+        log
+    );
+    assert!(log.contains("Shut down cleanly"), "{}", log);
+
+    // Closing stdin is a clean shutdown too, and says so.
+    let mut server = spawn();
+    drop(server.stdin.take());
+    let output = server.wait_with_output().await.unwrap();
+    assert!(output.status.success());
+    let log = String::from_utf8(output.stderr).unwrap();
+    assert!(log.contains("stdin reached EOF"), "{}", log);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_sigterm_drops_requests_after_the_grace_period() {
+    use tokio::io::AsyncWriteExt;
+
+    // The watched transaction never shows up, so only the shutdown ends the watch.
+    let url = mock_rpc(|method, _| match method {
+        "eth_getCode" => json!("0x00"),
+        "eth_getTransactionReceipt" | "eth_getTransactionByHash" => Value::Null,
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let mut server = tokio::process::Command::new(env!("CARGO_BIN_EXE_ethereum-trading-mcp"))
+        .env("ETHEREUM_RPC_URL", &url)
+        .env("RUST_LOG", "info")
+        .env("SHUTDOWN_GRACE_SECONDS", "1")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let mut stdin = server.stdin.take().unwrap();
+    let watch = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {
+        "name": "watch_transaction",
+        "arguments": {"tx_hash": format!("0x{}", "ab".repeat(32)), "timeout_seconds": 600}
+    }});
+    stdin
+        .write_all(format!("{}\n", watch).as_bytes())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    let pid = server.id().unwrap().to_string();
+    assert!(std::process::Command::new("kill")
+        .args(["-TERM", &pid])
+        .status()
+        .unwrap()
+        .success());
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        server.wait_with_output(),
+    )
+    .await
+    .expect("server did not stop within the grace period")
+    .unwrap();
+    drop(stdin);
+    assert!(output.status.success(), "{:?}", output.status);
+    assert!(output.stdout.is_empty());
+    let log = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        log.contains("1 requests still running after the 1s grace period"),
+        "{}",
+        log
+    );
+}
+
+#[tokio::test]
+async fn test_send_transaction_fills_nonce_and_checks_balance() {
+    fn node(method: &str, balance: &str) -> Value {
+        match method {
+            "eth_getTransactionCount" => json!("0x7"),
+            "eth_chainId" => json!("0x1"),
+            "eth_getBalance" => json!(balance),
+            "eth_sendRawTransaction" => json!(format!("0x{}", "ab".repeat(32))),
+            other => panic!("unexpected {}", other),
+        }
+    }
+    let args = json!({
+        "to": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+        "value": "1000",
+        "gas_limit": 21000,
+        "max_fee_per_gas": "1000000000",
+        "max_priority_fee_per_gas": 1000000
+    });
+    let key = "0x0123456789012345678901234567890123456789012345678901234567890123";
+
+    let funded = EthereumClient::new(&mock_rpc(|m, _| node(m, "0xde0b6b3a7640000")).await, key)
+        .await
+        .unwrap();
+    let res = SendTransactionTool
+        .call(&funded, args.clone())
+        .await
+        .unwrap();
+    assert_eq!(res["tx_hash"], format!("0x{}", "ab".repeat(32)));
+    assert_eq!(res["nonce"], 7);
+    assert_eq!(res["gas_limit"], "21000");
+    assert_eq!(res["private"], false);
+
+    // 21000 gas at 1 gwei plus 1000 wei is more than this balance.
+    let broke = EthereumClient::new(&mock_rpc(|m, _| node(m, "0x1000")).await, key)
+        .await
+        .unwrap();
+    let err = SendTransactionTool.call(&broke, args).await.unwrap_err();
+    assert!(
+        err.to_string().starts_with("Insufficient balance"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_wrap_and_unwrap_weth() {
+    let url = mock_rpc(|method, params| match method {
+        "eth_getTransactionCount" => json!("0x7"),
+        "eth_estimateGas" => json!("0xb411"),
+        "eth_feeHistory" => json!({
+            "oldestBlock": "0x1",
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00"],
+            "gasUsedRatio": [0.5],
+            "reward": [["0x3b9aca00"]]
+        }),
+        "eth_getBalance" => json!("0xde0b6b3a7640000"),
+        "eth_call" => {
+            // WETH from the chain config, balanceOf: 0.5 WETH.
+            assert_eq!(
+                params[0]["to"].as_str().unwrap().to_lowercase(),
+                "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+            );
+            json!(format!("0x{:064x}", 500_000_000_000_000_000u64))
+        }
+        "eth_sendRawTransaction" => json!(format!("0x{}", "ab".repeat(32))),
+        "eth_getTransactionReceipt" => json!({
+            "transactionHash": format!("0x{}", "ab".repeat(32)),
+            "blockNumber": "0x1",
+            "status": "0x1",
+            "gasUsed": "0xb411",
+            "effectiveGasPrice": "0x3b9aca00"
+        }),
+        "eth_blockNumber" => json!("0x1"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let res = WrapEthTool
+        .call(&client, json!({ "amount": "100000000000000000" }))
+        .await
+        .unwrap();
+    assert_eq!(res["action"], "wrap");
+    assert_eq!(res["weth"], "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
+    assert_eq!(res["data"], "0xd0e30db0");
+    assert_eq!(res["amount_eth"], "0.1");
+    assert_eq!(res["tx_hash"], format!("0x{}", "ab".repeat(32)));
+    assert_eq!(res["status"], "confirmed");
+    assert_eq!(res["balances"]["eth"], "1");
+    assert_eq!(res["balances"]["weth"], "0.5");
+
+    // "max" unwraps the whole WETH balance; unsigned builds withdraw(amount) with no value.
+    let res = UnwrapWethTool
+        .call(&client, json!({ "amount": "max", "unsigned": true }))
+        .await
+        .unwrap();
+    assert_eq!(res["amount"], "500000000000000000");
+    let tx = &res["unsigned_transaction"];
+    assert_eq!(tx["value"], "0");
+    assert!(tx["data"].as_str().unwrap().starts_with("0x2e1a7d4d"));
+
+    let err = UnwrapWethTool
+        .call(&client, json!({ "amount": "1000000000000000000" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("Insufficient WETH"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_empty_call_result_is_explained() {
+    use alloy::providers::Provider;
+    use alloy::sol_types::SolCall;
+    use ethereum_trading_mcp::tools::balance::decimalsCall;
+
+    // An EOA: every eth_call succeeds with no return data.
+    let url = mock_rpc(|method, _| {
+        assert_eq!(method, "eth_call");
+        json!("0x")
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let eoa = Address::from_str("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045").unwrap();
+
+    let req = TransactionRequest::default()
+        .to(eoa)
+        .input(decimalsCall {}.abi_encode().into());
+    let res = client.provider.call(&req).await.unwrap();
+    let err = match decode_return::<decimalsCall>(&res, eoa) {
+        Ok(_) => panic!("empty return data decoded"),
+        Err(e) => e.to_string(),
+    };
+    assert!(err.contains("decimals()"), "{}", err);
+    assert!(err.contains("returned no data"), "{}", err);
+
+    let err = GetBalanceTool
+        .call(
+            &client,
+            json!({ "address": eoa.to_string(), "token_address": eoa.to_string() }),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("is the address a contract"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_compare_rpc_endpoints_flags_lagging_endpoint() {
+    fn node(method: &str, block: &str, balance: &str) -> Value {
+        match method {
+            "eth_blockNumber" => json!(block),
+            "eth_getBalance" => json!(balance),
+            other => panic!("unexpected {}", other),
+        }
+    }
+    let primary = mock_rpc(|m, _| node(m, "0x64", "0x10")).await;
+    let healthy = mock_rpc(|m, _| node(m, "0x65", "0x10")).await;
+    let lagging = mock_rpc(|m, _| node(m, "0x50", "0x20")).await;
+    let client = EthereumClient::new(
+        &primary,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let args = json!({
+        "query": "get_balance",
+        "arguments": { "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045" }
+    });
+    let err = CompareRpcEndpointsTool
+        .call(&client, args.clone())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("ETHEREUM_RPC_URLS"));
+
+    let client = client
+        .with_extra_endpoint(&format!("{}/v2/secret-key", healthy))
+        .unwrap()
+        .with_extra_endpoint(&lagging)
+        .unwrap();
+    let res = CompareRpcEndpointsTool.call(&client, args).await.unwrap();
+    assert_eq!(res["reference_value"], "16");
+    assert_eq!(res["highest_block"], 101);
+    let endpoints = res["endpoints"].as_array().unwrap();
+    assert_eq!(endpoints.len(), 3);
+    assert_eq!(endpoints[1]["agrees"], true);
+    assert!(!endpoints[1]["endpoint"]
+        .as_str()
+        .unwrap()
+        .contains("secret-key"));
+    assert_eq!(endpoints[2]["lag_blocks"], 21);
+    assert_eq!(res["stale_endpoints"], json!([lagging]));
+    assert_eq!(res["disagreeing_endpoints"], json!([lagging]));
+    assert_eq!(res["consistent"], false);
+}
+
+#[tokio::test]
+async fn test_client_rejects_unsupported_chain() {
+    let url = mock_rpc(|method, _| panic!("unexpected {}", method)).await;
+    let key = "0x0123456789012345678901234567890123456789012345678901234567890123";
+
+    let client = EthereumClient::new(&url, key).await.unwrap();
+    assert_eq!(client.chain_id(), 1);
+
+    let err = EthereumClient::connect(&url, Some(&key.into()), &[10, 8453], RetryPolicy::none())
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("chain 1"), "{}", err);
+    assert!(err.contains("SUPPORTED_CHAIN_IDS"), "{}", err);
+
+    let err = EthereumClient::new("http://127.0.0.1:1", key)
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("Could not read the chain id"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_approval_policy_and_allowance() {
+    let needed = U256::from(1_000u64);
+    assert_eq!(ApprovalPolicy::parse("").unwrap(), ApprovalPolicy::Exact);
+    assert_eq!(
+        ApprovalPolicy::parse("Unlimited").unwrap(),
+        ApprovalPolicy::Unlimited
+    );
+    assert!(ApprovalPolicy::parse("sometimes").is_err());
+    assert_eq!(ApprovalPolicy::Exact.amount(needed), needed);
+    assert_eq!(ApprovalPolicy::Unlimited.amount(needed), U256::MAX);
+
+    let url = mock_rpc(|method, params| match method {
+        "eth_call" => {
+            let data = params[0]["input"]
+                .as_str()
+                .or(params[0]["data"].as_str())
+                .unwrap();
+            assert_eq!(&data[2..10], "dd62ed3e");
+            json!(format!("0x{:064x}", 500))
+        }
+        _ => panic!("unexpected {}", method),
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let current = approval::allowance(
+        &client,
+        Address::ZERO,
+        client.signer_address.unwrap(),
+        Address::ZERO,
+    )
+    .await
+    .unwrap();
+    assert_eq!(current, U256::from(500u64));
+    assert!(current < needed);
+}
+
+#[tokio::test]
+async fn test_approve_token_simulation() {
+    let url = mock_rpc(|method, params| match method {
+        "eth_call" => {
+            let data = params[0]["input"]
+                .as_str()
+                .or(params[0]["data"].as_str())
+                .unwrap();
+            assert_eq!(&data[2..10], "095ea7b3");
+            assert!(params[0]["from"].is_string());
+            json!(format!("0x{:064x}", 1))
+        }
+        _ => panic!("unexpected {}", method),
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let spender = "0xE592427A0AEce92De3Edee1F18E0157C05861564";
+
+    let res = ApproveTokenTool
+        .call(
+            &client,
+            json!({"token_address": "USDC", "spender": spender, "amount": "max", "simulate_only": true}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["amount"], U256::MAX.to_string());
+    assert_eq!(res["unlimited"], true);
+    assert_eq!(res["simulation"]["status"], "ok");
+    assert!(res["data"].as_str().unwrap().starts_with("0x095ea7b3"));
+
+    let err = ApproveTokenTool
+        .call(
+            &client,
+            json!({"token_address": "USDC", "spender": spender, "amount": "lots", "simulate_only": true}),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("\"max\""), "{}", err);
+}
+
+#[tokio::test]
+async fn test_admin_config_is_redacted() {
+    use tokio::io::AsyncWriteExt;
+
+    let url = mock_rpc(|method, _| match method {
+        "eth_getCode" => json!("0x00"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let key = "0x0123456789012345678901234567890123456789012345678901234567890123";
+    let run = |admin: &str| {
+        tokio::process::Command::new(env!("CARGO_BIN_EXE_ethereum-trading-mcp"))
+            .env("ETHEREUM_RPC_URL", format!("{}/v3/secret-api-key", url))
+            .env("PRIVATE_KEY", key)
+            .env("ENABLE_ADMIN_METHODS", admin)
+            .env("RUST_LOG", "error")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap()
+    };
+    let request = format!(
+        "{}\n",
+        json!({"jsonrpc": "2.0", "id": 1, "method": "admin/config"})
+    );
+
+    let mut responses = Vec::new();
+    for admin in ["true", "false"] {
+        let mut server = run(admin);
+        let mut stdin = server.stdin.take().unwrap();
+        stdin.write_all(request.as_bytes()).await.unwrap();
+        drop(stdin);
+        let output = server.wait_with_output().await.unwrap();
+        responses.push(String::from_utf8(output.stdout).unwrap());
+    }
+
+    assert!(!responses[0].contains("secret-api-key"), "{}", responses[0]);
+    assert!(!responses[0].contains(&key[2..]), "{}", responses[0]);
+    let res: Value = serde_json::from_str(responses[0].trim()).unwrap();
+    let result = &res["result"];
+    assert_eq!(result["chain_id"], 1);
+    assert_eq!(result["config"]["rpc"]["endpoint_count"], 1);
+    assert_eq!(result["config"]["private_key"], "<redacted>");
+    assert_eq!(result["config"]["approval_policy"], "exact");
+    assert_eq!(
+        result["swap_defaults"]["fee_tiers"],
+        json!([100, 500, 3000, 10000])
+    );
+    assert!(result["tools"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("swap_tokens")));
+
+    let res: Value = serde_json::from_str(responses[1].trim()).unwrap();
+    assert_eq!(res["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn test_stream_sends_array_elements_as_progress() {
+    use tokio::io::AsyncWriteExt;
+
+    let err = split_stream(json!({"chains": [], "note": "x"}), Some(&json!("note"))).unwrap_err();
+    assert!(err.to_string().contains("arrays: chains"), "{}", err);
+
+    let url = mock_rpc(|method, _| match method {
+        "eth_getCode" => json!("0x00"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let mut server = tokio::process::Command::new(env!("CARGO_BIN_EXE_ethereum-trading-mcp"))
+        .env("ETHEREUM_RPC_URL", &url)
+        .env(
+            "PRIVATE_KEY",
+            "0x0123456789012345678901234567890123456789012345678901234567890123",
+        )
+        .env("RUST_LOG", "error")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let call = |id: u64, meta: Value| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {"name": "list_chains", "arguments": {"stream": "chains"}, "_meta": meta}
+        })
+    };
+    let input = format!(
+        "{}\n{}\n",
+        call(1, json!({"progressToken": "t1"})),
+        call(2, json!({}))
+    );
+    let mut stdin = server.stdin.take().unwrap();
+    stdin.write_all(input.as_bytes()).await.unwrap();
+    drop(stdin);
+    let output = server.wait_with_output().await.unwrap();
+    let lines: Vec<Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+
+    // The second call fails at once and may be answered first; the first call's progress
+    // still comes ahead of its own response.
+    let (failed, lines): (Vec<Value>, Vec<Value>) = lines.into_iter().partition(|l| l["id"] == 2);
+    assert_eq!(failed[0]["error"]["code"], -32602);
+    let total = lines.len() - 1;
+    assert!(total > 0);
+    for (i, line) in lines[..total].iter().enumerate() {
+        assert_eq!(line["method"], "notifications/progress");
+        assert_eq!(line["params"]["progressToken"], "t1");
+        assert_eq!(line["params"]["progress"], i + 1);
+        assert_eq!(line["params"]["total"], total);
+        assert!(line["params"]["item"]["chain_id"].is_u64());
+    }
+    assert_eq!(lines[total]["id"], 1);
+    assert_eq!(
+        lines[total]["result"]["data"]["chains"]["streamed_items"],
+        total
+    );
+}
+
+#[tokio::test]
+async fn test_contract_addresses_follow_chain() {
+    let client = offline_client().await;
+    let mainnet = chains::get(chains::MAINNET).unwrap();
+    assert_eq!(client.chain().unwrap().weth, mainnet.weth);
+    assert_eq!(
+        client.uniswap_v3().unwrap().swap_router,
+        mainnet.uniswap_v3.unwrap().swap_router
+    );
+
+    let params = ExactInputSingleParams {
+        tokenIn: Address::ZERO,
+        tokenOut: Address::ZERO,
+        fee: 500,
+        recipient: Address::ZERO,
+        deadline: U256::MAX,
+        amountIn: U256::from(1u64),
+        amountOutMinimum: U256::ZERO,
+        sqrtPriceLimitX96: U256::ZERO,
+    };
+    // SwapRouter takes a deadline; Base's SwapRouter02 does not.
+    let v1 = exact_input_single_calldata(&mainnet.uniswap_v3.unwrap(), params.clone());
+    assert_eq!(hex::encode(&v1[..4]), "414bf389");
+    assert_eq!(v1.len(), 4 + 8 * 32);
+    let base = chains::get(8453).unwrap().uniswap_v3.unwrap();
+    let v2 = exact_input_single_calldata(&base, params);
+    assert_eq!(hex::encode(&v2[..4]), "04e45aaf");
+    assert_eq!(v2.len(), 4 + 7 * 32);
+}
+
+#[test]
+fn test_format_units_is_exact_for_large_values() {
+    assert_eq!(format_units(U256::ZERO, 18).unwrap(), "0");
+    assert_eq!(
+        format_units(U256::from(1u64), 18).unwrap(),
+        "0.000000000000000001"
+    );
+    assert_eq!(format_units(U256::from(1_500_000u64), 6).unwrap(), "1.5");
+    assert_eq!(format_units(U256::from(42u64), 0).unwrap(), "42");
+    // 589 trillion SHIB at 18 decimals: far beyond Decimal's ~7.9e28 range.
+    let shib = U256::from_str("589735030408323000000000000000000").unwrap();
+    assert_eq!(format_units(shib, 18).unwrap(), "589735030408323");
+    let shib_dust = U256::from_str("123456789012345678901234567890123456789").unwrap();
+    assert_eq!(
+        format_units(shib_dust, 18).unwrap(),
+        "123456789012345678901.234567890123456789"
+    );
+    assert_eq!(
+        format_units(U256::MAX, 18).unwrap(),
+        "115792089237316195423570985008687907853269984665640564039457.584007913129639935"
+    );
+    assert_eq!(
+        format_units(U256::MAX, 77).unwrap(),
+        "1.15792089237316195423570985008687907853269984665640564039457584007913129639935"
+    );
+    assert_eq!(
+        format_units(U256::MAX, 80).unwrap(),
+        "0.00115792089237316195423570985008687907853269984665640564039457584007913129639935"
+    );
+}
+
+#[test]
+fn test_decode_symbol_accepts_bytes32() {
+    // MKR's symbol() return data: "MKR" as a right-padded bytes32.
+    let mkr =
+        hex::decode("4d4b520000000000000000000000000000000000000000000000000000000000").unwrap();
+    assert_eq!(decode_symbol(&mkr).as_deref(), Some("MKR"));
+    // SAI, same shape.
+    let sai =
+        hex::decode("5341490000000000000000000000000000000000000000000000000000000000").unwrap();
+    assert_eq!(decode_symbol(&sai).as_deref(), Some("SAI"));
+
+    // A regular ABI string still decodes as one.
+    let usdc = hex::decode(concat!(
+        "0000000000000000000000000000000000000000000000000000000000000020",
+        "0000000000000000000000000000000000000000000000000000000000000004",
+        "5553444300000000000000000000000000000000000000000000000000000000"
+    ))
+    .unwrap();
+    assert_eq!(decode_symbol(&usdc).as_deref(), Some("USDC"));
+
+    assert_eq!(decode_symbol(&[]), None);
+    assert_eq!(decode_symbol(&[0xff; 32]), None);
+}
+
+#[tokio::test]
+async fn test_pool_fees_interpretation() {
+    use alloy::sol_types::SolCall;
+    use ethereum_trading_mcp::tools::pool_fees::{
+        decimalsCall, feeGrowthGlobal0X128Call, feeGrowthGlobal1X128Call, protocolFeesCall,
+    };
+    use ethereum_trading_mcp::tools::price::{
+        feeCall, liquidityCall, slot0Call, token0Call, token1Call,
+    };
+
+    let url = mock_rpc(|_, params| {
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let selector = hex::decode(&data[2..10]).unwrap();
+        let word = |v: U256| format!("{:064x}", v);
+        let one = U256::from(1u64);
+        let result = if selector == slot0Call::SELECTOR {
+            [one << 96, one, one, one, one, U256::from(0x45u64), one]
+                .map(word)
+                .concat()
+        } else if selector == feeCall::SELECTOR {
+            word(U256::from(500u64))
+        } else if selector == liquidityCall::SELECTOR {
+            word(U256::from(10u64).pow(U256::from(18)))
+        } else if selector == token0Call::SELECTOR {
+            word(U256::from(0xaau64))
+        } else if selector == token1Call::SELECTOR {
+            word(U256::from(0xbbu64))
+        } else if selector == feeGrowthGlobal0X128Call::SELECTOR {
+            // 1.5 in Q128.128
+            word(U256::from(3u64) << 127)
+        } else if selector == feeGrowthGlobal1X128Call::SELECTOR {
+            word(U256::from(7u64) << 128)
+        } else if selector == protocolFeesCall::SELECTOR {
+            word(U256::from(1_500_000u64))
+                + &word(U256::from(2u64) * U256::from(10u64).pow(U256::from(18)))
+        } else if selector == decimalsCall::SELECTOR {
+            word(U256::from(if to.ends_with("aa") { 6u64 } else { 18 }))
+        } else {
+            panic!("unexpected call {}", data)
+        };
+        json!(format!("0x{}", result))
+    })
+    .await;
+    let mut client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    client.multicall_available = false;
+
+    let res = GetPoolFeesTool
+        .call(
+            &client,
+            json!({ "pool_address": "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640" }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["fee_tier"], 500);
+    assert_eq!(res["fee_percent"], "0.05");
+    assert_eq!(res["protocol_fee"]["enabled"], true);
+    assert_eq!(res["protocol_fee"]["token0_share"], "1/5");
+    assert_eq!(res["protocol_fee"]["token1_share"], "1/4");
+    assert_eq!(
+        res["uncollected_protocol_fees"]["token0"]["formatted"],
+        "1.5"
+    );
+    assert_eq!(res["uncollected_protocol_fees"]["token1"]["formatted"], "2");
+    assert_eq!(res["fee_growth_global"]["token0_per_unit_liquidity"], "1.5");
+    assert_eq!(res["fee_growth_global"]["token1_per_unit_liquidity"], "7");
+}
+
+#[tokio::test]
+async fn test_token_metadata_is_cached() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        match &data[2..10] {
+            // decimals()
+            "313ce567" => json!(format!("0x{:064x}", 8)),
+            // symbol(), as MKR-style bytes32
+            "95d89b41" => json!(format!("0x{:0<64}", "57425443")),
+            other => panic!("unexpected selector {}", other),
+        }
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let token = Address::from_str("0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599").unwrap();
+
+    let first = client.get_token_metadata(token).await.unwrap();
+    assert_eq!(first.decimals, 8);
+    assert_eq!(first.symbol.as_deref(), Some("WBTC"));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+
+    // Clones share the cache, so neither read goes to the node.
+    let clone = client.clone();
+    assert_eq!(clone.get_token_metadata(token).await.unwrap(), first);
+    assert_eq!(client.get_token_metadata(token).await.unwrap(), first);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_get_gas_price_suggests_eip1559_fees() {
+    let history = alloy::rpc::types::eth::FeeHistory {
+        base_fee_per_gas: vec![9, 10, 10, 20],
+        gas_used_ratio: vec![0.5, 0.0, 0.9],
+        oldest_block: 100,
+        reward: Some(vec![vec![1, 2, 3], vec![0, 0, 0], vec![3, 4, 5]]),
+        ..Default::default()
+    };
+    let fees = suggest_fees(&history).unwrap();
+    // The empty middle block is skipped; the median of two samples is the upper one.
+    assert_eq!(fees.blocks_sampled, 2);
+    assert_eq!(fees.priority_fees, [3, 4, 5]);
+    assert_eq!(fees.max_fee_per_gas, 2 * 20 + 4);
+    let legacy = alloy::rpc::types::eth::FeeHistory {
+        base_fee_per_gas: vec![0, 0],
+        ..Default::default()
+    };
+    assert!(suggest_fees(&legacy).is_none());
+
+    let url = mock_rpc(|method, params| match method {
+        "eth_gasPrice" => json!("0x3b9aca00"),
+        "eth_feeHistory" => {
+            assert_eq!(params[0], "0x5");
+            json!({
+                "oldestBlock": "0x64",
+                "baseFeePerGas": ["0x77359400", "0x77359400"],
+                "gasUsedRatio": [0.5],
+                "reward": [["0x3b9aca00", "0x77359400", "0xb2d05e00"]]
+            })
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let res = GetGasPriceTool
+        .call(&client, json!({ "blocks": 5 }))
+        .await
+        .unwrap();
+    assert_eq!(res["gas_price"]["gwei"], "1");
+    assert_eq!(res["eip1559_supported"], true);
+    assert_eq!(res["eip1559"]["next_base_fee"]["gwei"], "2");
+    assert_eq!(res["eip1559"]["max_priority_fee_per_gas"]["gwei"], "2");
+    assert_eq!(res["eip1559"]["max_fee_per_gas"]["wei"], "6000000000");
+    assert!(GetGasPriceTool
+        .call(&client, json!({ "blocks": 0 }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_check_rebasing_flags_share_accounting() {
+    // PUSH4 balanceOf(address), PUSH4 sharesOf(address)
+    let code = [0x63, 0x70, 0xa0, 0x82, 0x31, 0x63, 0xf5, 0xeb, 0x42, 0xdc];
+    assert!(has_selector(&code, "balanceOf(address)"));
+    assert!(has_selector(&code, "sharesOf(address)"));
+    assert!(!has_selector(&code, "rebase()"));
+
+    let url = mock_rpc(|method, params| match method {
+        "eth_getCode" => json!("0x6370a082316388f5eb42dc"),
+        "eth_getStorageAt" => json!(format!("0x{:064x}", 0)),
+        "eth_call" => {
+            let data = params[0]["input"]
+                .as_str()
+                .or(params[0]["data"].as_str())
+                .unwrap();
+            match &data[2..10] {
+                // totalSupply()
+                "18160ddd" => json!(format!("0x{:064x}", 100)),
+                // getTotalShares()
+                "d5002f2e" => json!(format!("0x{:064x}", 90)),
+                _ => json!("0x"),
+            }
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let mut client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    client.multicall_available = false;
+
+    let res = CheckRebasingTool
+        .call(
+            &client,
+            json!({"token_address": "0xae7ab96520DE3A18E5e111B5EaAb095312D7fE84"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["rebasing"], true);
+    assert_eq!(res["share_accounting"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        res["share_accounting"][0]["check"],
+        "totalSupply vs getTotalShares"
+    );
+    assert_eq!(res["share_accounting"][0]["mismatch"], true);
+    // The injected 0x88 byte breaks the sharesOf push, so only the call-based check fires.
+    assert_eq!(res["selectors_found"], json!([]));
+    assert_eq!(res["implementation"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_cross_rate_prefers_a_direct_pool() {
+    use alloy::sol_types::SolCall;
+
+    // A 0x1111/0x2222 pool at 0.25 token1 per token0 (sqrtPriceX96 = 2^95); no pools with
+    // USDC, so ETH/USDC goes through USD: ETH/USD 2000, USDC/USD 1.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let answer = |target: &str, data: &str| -> String {
+            match &data[..8] {
+                // getPool
+                "1698ee82" if data.contains("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48") => word(0),
+                "1698ee82" => word(0x9999),
+                // slot0: sqrtPriceX96, tick, ..., unlocked
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(1 << 95),
+                    word(0),
+                    word(0),
+                    word(1),
+                    word(1),
+                    word(0),
+                    word(1)
+                ),
+                // liquidity
+                "1a686502" => word(1),
+                // latestRoundData
+                "feaf968c" => {
+                    let usd = if target == "0x5f4ec3df9cbd43714fe2740f5e3616155c5b8419" {
+                        2_000
+                    } else {
+                        1
+                    };
+                    // Updated a minute before `mock_block`.
+                    format!(
+                        "{}{}{}{}{}",
+                        word(1),
+                        word(usd * 100_000_000),
+                        word(1_699_999_940),
+                        word(1_699_999_940),
+                        word(1)
+                    )
+                }
+                // symbol: "TKN"
+                "95d89b41" => format!("{}{}{:0<64}", word(0x20), word(3), "544b4e"),
+                // decimals: 18 for tokens, read one by one
+                "313ce567" => word(18),
+                _ => String::new(),
+            }
+        };
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            return json!(format!("0x{}", answer(&to, &data[2..])));
+        }
+        let calls = aggregate3Call::abi_decode(&hex::decode(&data[2..]).unwrap(), true)
+            .unwrap()
+            .calls;
+        let results: Vec<Call3Result> = calls
+            .iter()
+            .map(|c| {
+                let selector = hex::encode(&c.callData[..4]);
+                let target = c.target.to_string().to_lowercase();
+                Call3Result {
+                    success: true,
+                    // Feeds are the only contracts asked for decimals in a batch.
+                    returnData: hex::decode(if selector == "313ce567" {
+                        word(8)
+                    } else {
+                        answer(&target, &hex::encode(&c.callData))
+                    })
+                    .unwrap()
+                    .into(),
+                }
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+
+    let direct = GetCrossRateTool
+        .call(
+            &client,
+            json!({
+                "base_token": "0x0000000000000000000000000000000000001111",
+                "quote_token": "0x0000000000000000000000000000000000002222"
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(direct["method"], "direct_pool");
+    assert_eq!(direct["rate"], json!(Decimal::new(25, 2)));
+    assert_eq!(direct["inverse_rate"], json!(Decimal::from(4)));
+    assert_eq!(
+        direct["pool"]["address"],
+        "0x0000000000000000000000000000000000009999"
+    );
+    assert_eq!(direct["base_token"]["symbol"], "TKN");
+    assert_eq!(direct["quote_token"]["symbol"], "TKN");
+
+    // Priced the other way round, the same pool gives the inverse.
+    let inverted = GetCrossRateTool
+        .call(
+            &client,
+            json!({
+                "base_token": "0x0000000000000000000000000000000000002222",
+                "quote_token": "0x0000000000000000000000000000000000001111"
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(inverted["rate"], json!(Decimal::from(4)));
+
+    let via_usd = GetCrossRateTool
+        .call(
+            &client,
+            json!({ "base_token": "ETH", "quote_token": "USDC" }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(via_usd["method"], "usd_cross");
+    assert_eq!(via_usd["rate"], json!(Decimal::from(2000)));
+    assert_eq!(via_usd["inverse_rate"], json!(Decimal::new(5, 4)));
+    assert_eq!(via_usd["usd_prices"]["quote"]["source"], "Stablecoin peg");
+    assert!(via_usd["pool"].is_null());
+}
+
+#[tokio::test]
+async fn test_token_price_batches_pool_and_oracle_reads() {
+    use alloy::sol_types::SolCall;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static BATCHED: AtomicUsize = AtomicUsize::new(0);
+
+    // Token/WETH pool at 0.25 WETH per token (sqrtPriceX96 = 2^95), ETH/USD 2000.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let answer = |data: &str| -> String {
+            match &data[..8] {
+                // getPool
+                "1698ee82" => word(0x9999),
+                // slot0: sqrtPriceX96, tick, ..., unlocked
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(1 << 95),
+                    word(0),
+                    word(0),
+                    word(1),
+                    word(1),
+                    word(0),
+                    word(1)
+                ),
+                // liquidity
+                "1a686502" => word(1),
+                // token0
+                "0dfe1681" => word(0x1111),
+                // latestRoundData
+                "feaf968c" => format!(
+                    "{}{}{}{}{}",
+                    word(1),
+                    word(200_000_000_000),
+                    word(1),
+                    word(1),
+                    word(1)
+                ),
+                // decimals: 8 for the feed, 18 for the tokens
+                "313ce567" => word(18),
+                _ => String::new(),
+            }
+        };
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            return json!(format!("0x{}", answer(&data[2..])));
+        }
+        BATCHED.fetch_add(1, Ordering::SeqCst);
+        let calls = aggregate3Call::abi_decode(&hex::decode(&data[2..]).unwrap(), true)
+            .unwrap()
+            .calls;
+        let results: Vec<Call3Result> = calls
+            .iter()
+            .map(|c| {
+                let selector = hex::encode(&c.callData[..4]);
+                let feed_decimals = selector == "313ce567";
+                Call3Result {
+                    success: true,
+                    returnData: hex::decode(if feed_decimals {
+                        word(8)
+                    } else {
+                        answer(&selector)
+                    })
+                    .unwrap()
+                    .into(),
+                }
+            })
+            .collect();
+        if calls
+            .iter()
+            .any(|c| hex::encode(&c.callData[..4]) == "0dfe1681")
+        {
+            assert_eq!(results.len(), 5, "slot0, liquidity, token0 and the feed");
+        }
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let token = "0x0000000000000000000000000000000000001111";
+    let price = GetTokenPriceTool
+        .call(
+            &client,
+            json!({"token_symbol": "TKN", "token_address": token}),
+        )
+        .await
+        .unwrap();
+    // Pool lookups, their state, the routes' token balances (every quote token has a pool
+    // here), then the chosen pool's state with the feed.
+    assert_eq!(BATCHED.load(Ordering::SeqCst), 4);
+    assert_eq!(price["price_eth"], json!("0.2500"));
+    assert_eq!(price["price_usd"], json!("500.000000000000"));
+    assert_eq!(price["routing"]["quote_token"], "WETH");
+}
+
+#[tokio::test]
+async fn test_token_price_prefers_a_fresh_chainlink_feed() {
+    use alloy::sol_types::SolCall;
+    use ethereum_trading_mcp::ethereum::getCurrentBlockTimestampCall;
+
+    // LINK/USD 15 updated a minute before the block, ETH/USD 2000. LINK also has a WETH
+    // pool at 0.25 WETH per LINK, for when the feed is refused.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let link_feed = "2c1d072e956affc0d435cb7ac38ef18d24d9127c";
+        let answer = |target: &str, call: &[u8]| -> String {
+            match hex::encode(&call[..4]).as_str() {
+                "1698ee82" => word(0x9999),
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(1 << 95),
+                    word(0),
+                    word(0),
+                    word(1),
+                    word(1),
+                    word(0),
+                    word(1)
+                ),
+                "1a686502" => word(1),
+                "0dfe1681" => {
+                    "000000000000000000000000514910771af9ca656af840dff83e8264ecf986ca".into()
+                }
+                "feaf968c" => {
+                    let price = if target.ends_with(link_feed) {
+                        1_500_000_000
+                    } else {
+                        200_000_000_000
+                    };
+                    format!(
+                        "{}{}{}{}{}",
+                        word(1),
+                        word(price),
+                        word(1_699_999_940),
+                        word(1_699_999_940),
+                        word(1)
+                    )
+                }
+                "313ce567" if target.starts_with("0x") => word(18),
+                "313ce567" => word(8),
+                selector if selector == hex::encode(getCurrentBlockTimestampCall::SELECTOR) => {
+                    word(1_700_000_000)
+                }
+                _ => String::new(),
+            }
+        };
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        let call = hex::decode(&data[2..]).unwrap();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            // Token decimals are read one by one; feeds only appear in batches.
+            return json!(format!("0x{}", answer(&to, &call)));
+        }
+        let calls = aggregate3Call::abi_decode(&call, true).unwrap().calls;
+        let results: Vec<Call3Result> = calls
+            .iter()
+            .map(|c| Call3Result {
+                success: true,
+                returnData: hex::decode(answer(&hex::encode(c.target), &c.callData))
+                    .unwrap()
+                    .into(),
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let link = "0x514910771AF9Ca656af840dff83E8264EcF986CA";
+
+    let price = GetTokenPriceTool
+        .call(
+            &client,
+            json!({"token_symbol": "LINK", "token_address": link}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(price["source"], "Chainlink Oracle");
+    assert_eq!(price["price_usd"], json!("15.00000000"));
+    assert_eq!(price["oracle"]["age_seconds"], 60);
+    assert_eq!(
+        price["oracle"]["feed"],
+        "0x2c1d072e956AFFC0D435Cb7AC38EF18d24d9127c"
+    );
+
+    // Too old for the caller: priced from the pool instead, saying why.
+    let price = GetTokenPriceTool
+        .call(
+            &client,
+            json!({"token_symbol": "LINK", "token_address": link, "max_price_age_seconds": 30}),
+        )
+        .await
+        .unwrap();
+    assert!(price["source"].as_str().unwrap().starts_with("Uniswap V3"));
+    assert_eq!(price["price_eth"], json!("0.2500"));
+    assert!(price["oracle_error"]
+        .as_str()
+        .unwrap()
+        .contains("last updated 60s ago, more than the 30s allowed"));
+}
+
+#[tokio::test]
+async fn test_token_price_routes_through_stablecoin_without_weth_pool() {
+    use alloy::sol_types::SolCall;
+
+    // Only a token/USDC pool exists, at 0.25 USDC per token; ETH/USD 2000. Every token
+    // and the feed report 8 decimals.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let answer = |call: &[u8]| -> String {
+            let args = hex::encode(&call[4..]);
+            match hex::encode(&call[..4]).as_str() {
+                // getPool(token, quote, fee)
+                "1698ee82"
+                    if args[64..128].ends_with("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48") =>
+                {
+                    word(0x9999)
+                }
+                "1698ee82" => word(0),
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(1 << 95),
+                    word(0),
+                    word(0),
+                    word(1),
+                    word(1),
+                    word(0),
+                    word(1)
+                ),
+                "1a686502" => word(1),
+                "0dfe1681" => word(0x1111),
+                "feaf968c" => format!(
+                    "{}{}{}{}{}",
+                    word(1),
+                    word(200_000_000_000),
+                    word(1),
+                    word(1),
+                    word(1)
+                ),
+                "313ce567" => word(8),
+                _ => String::new(),
+            }
+        };
+        let data = hex::decode(
+            &params[0]["input"]
+                .as_str()
+                .or(params[0]["data"].as_str())
+                .unwrap()[2..],
+        )
+        .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            return json!(format!("0x{}", answer(&data)));
+        }
+        let results: Vec<Call3Result> = aggregate3Call::abi_decode(&data, true)
+            .unwrap()
+            .calls
+            .iter()
+            .map(|c| Call3Result {
+                success: true,
+                returnData: hex::decode(answer(&c.callData)).unwrap().into(),
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let mut client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    client.assume_stablecoin_peg = true;
+
+    let price = GetTokenPriceTool
+        .call(
+            &client,
+            json!({"token_symbol": "TKN", "token_address": "0x0000000000000000000000000000000000001111"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(price["routing"]["quote_token"], "USDC");
+    assert_eq!(price["routing"]["bridged"], false);
+    assert_eq!(price["source"], "Uniswap V3 (Derived from USDC pair)");
+    let usd = Decimal::from_str(price["price_usd"].as_str().unwrap()).unwrap();
+    assert_eq!(usd.normalize().to_string(), "0.25");
+}
+
+#[test]
+fn test_chain_stablecoins_include_bridged_variants() {
+    let optimism = chains::get(10).unwrap();
+    let usdc_e = optimism
+        .stablecoins
+        .iter()
+        .find(|s| s.symbol == "USDC.e")
+        .unwrap();
+    assert!(usdc_e.bridged);
+    assert!(optimism.stablecoins[0].symbol == "USDC" && !optimism.stablecoins[0].bridged);
+    assert_eq!(
+        oracle::default_stablecoins(8453),
+        chains::get(8453)
+            .unwrap()
+            .stablecoins
+            .iter()
+            .map(|s| s.address)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        oracle::default_stablecoins(31337),
+        oracle::DEFAULT_STABLECOINS.to_vec()
+    );
+}
+
+#[test]
+fn test_quote_discrepancy_warns_above_threshold() {
+    let max = rust_decimal::Decimal::ONE;
+    let ok = quote_discrepancy(U256::from(1_000_000u64), U256::from(995_000u64), max).unwrap();
+    assert_eq!(ok["status"], "ok");
+    assert_eq!(ok["discrepancy_percent"], json!("0.5"));
+    assert!(ok["warning"].is_null());
+
+    let off = quote_discrepancy(U256::from(1_000_000u64), U256::from(1_030_000u64), max).unwrap();
+    assert_eq!(off["status"], "warning");
+    assert_eq!(off["discrepancy_percent"], json!("3"));
+    assert!(off["warning"].as_str().unwrap().contains("3% apart"));
+}
+
+#[tokio::test]
+async fn test_slippage_tolerance_is_bounded() {
+    use rust_decimal::Decimal;
+    let (quoted_in, quoted_out) = (U256::from(1_000u64), U256::from(2_001u64));
+
+    // The bounds themselves: 0% keeps the quote, 100% accepts any output or pays double.
+    let bounds = |mode, percent: i64| {
+        slippage_bounds(mode, quoted_in, quoted_out, Decimal::from(percent)).unwrap()
+    };
+    assert_eq!(bounds(SwapMode::ExactIn, 0), (quoted_out, quoted_in));
+    assert_eq!(bounds(SwapMode::ExactIn, 100), (U256::ZERO, quoted_in));
+    assert_eq!(bounds(SwapMode::ExactOut, 0), (quoted_out, quoted_in));
+    assert_eq!(
+        bounds(SwapMode::ExactOut, 100),
+        (quoted_out, U256::from(2_000u64))
+    );
+    // Minimum output rounds down, maximum input up.
+    let half = Decimal::new(5, 1);
+    assert_eq!(
+        slippage_bounds(SwapMode::ExactIn, quoted_in, quoted_out, half).unwrap(),
+        (U256::from(1_990u64), quoted_in)
+    );
+    assert_eq!(
+        slippage_bounds(SwapMode::ExactOut, quoted_in, quoted_out, half).unwrap(),
+        (quoted_out, U256::from(1_005u64))
+    );
+    assert!(slippage_bounds(SwapMode::ExactIn, quoted_in, quoted_out, Decimal::from(-1)).is_err());
+
+    assert_eq!(slippage_arg(&json!({})).unwrap(), half);
+    assert_eq!(
+        slippage_arg(&json!({ "slippage_tolerance": 100 })).unwrap(),
+        Decimal::ONE_HUNDRED
+    );
+    assert_eq!(
+        slippage_arg(&json!({ "slippage_tolerance": "0" })).unwrap(),
+        Decimal::ZERO
+    );
+
+    // Out-of-range tolerances are refused before anything is quoted.
+    let client = offline_client().await;
+    for (bad, expected) in [
+        (
+            json!(-0.1),
+            "slippage_tolerance must be a percentage from 0 to 100",
+        ),
+        (
+            json!(100.5),
+            "slippage_tolerance must be a percentage from 0 to 100",
+        ),
+        (json!("NaN"), "slippage_tolerance must be a number"),
+        (json!("inf"), "slippage_tolerance must be a number"),
+    ] {
+        let err = SwapTokensTool
+            .call(
+                &client,
+                json!({
+                    "from_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+                    "to_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                    "amount": "1000",
+                    "slippage_tolerance": bad
+                }),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with(expected), "{}: {}", bad, err);
+    }
+}
+
+#[tokio::test]
+async fn test_swap_native_eth_sets_value_and_unwraps() {
+    // WETH/USDC quote of 2000 USDC per ETH; the router echoes the quote.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        match &data[2..10] {
+            "313ce567" if to == "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" => {
+                json!(format!("0x{}", word(6)))
+            }
+            "313ce567" => json!(format!("0x{}", word(18))),
+            "95d89b41" => json!("0x"),
+            // quoteExactInputSingle: amountOut, sqrtPriceX96After, ticks, gasEstimate
+            "c6a5026a" => json!(format!(
+                "0x{}{}{}{}",
+                word(2_000_000_000),
+                word(1),
+                word(1),
+                word(100_000)
+            )),
+            // exactInputSingle, only reachable with the ETH attached
+            "414bf389" => {
+                assert_eq!(params[0]["value"], "0xde0b6b3a7640000");
+                json!(format!("0x{}", word(2_000_000_000)))
+            }
+            // multicall(exactInputSingle, unwrapWETH9): not decoded by this mock
+            "ac9650d8" => json!("0x"),
+            other => panic!("unexpected selector {}", other),
+        }
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let usdc = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    let res = SwapTokensTool
+        .call(
+            &client,
+            json!({"from_token": "ETH", "to_token": usdc, "amount": "1000000000000000000", "fee": 3000}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["transaction"]["value"], "1000000000000000000");
+    assert!(res["transaction"]["data"]
+        .as_str()
+        .unwrap()
+        .starts_with("0x414bf389"));
+    assert_eq!(res["native_eth"]["input"], true);
+    assert_eq!(
+        res["router_call_simulation"]["simulated_amount_out"],
+        "2000000000"
+    );
+
+    let res = SwapTokensTool
+        .call(
+            &client,
+            json!({
+                "from_token": usdc,
+                "to_token": "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE",
+                "amount": "2000000000",
+                "fee": 3000
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["transaction"]["value"], "0");
+    assert!(res["transaction"]["data"]
+        .as_str()
+        .unwrap()
+        .starts_with("0xac9650d8"));
+    assert_eq!(res["native_eth"]["output"], true);
+
+    let err = SwapTokensTool
+        .call(
+            &client,
+            json!({"from_token": "ETH", "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", "amount": "1"}),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("same asset"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_swap_scenario_quotes_with_pool_overrides() {
+    const POOL: &str = "0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640";
+    let url = mock_rpc(|method, params| {
+        let word = |n: u128| format!("{:064x}", n);
+        match method {
+            "eth_getCode" => json!("0x"),
+            "eth_getStorageAt" => {
+                assert_eq!(params[0].as_str().unwrap().to_lowercase(), POOL);
+                assert_eq!(params[2], "0x1", "reads are pinned to the block");
+                if U256::from_str(params[1].as_str().unwrap()).unwrap() == U256::ZERO {
+                    // unlocked, tick -13864, sqrtPriceX96 2^95
+                    let slot0 = (U256::from(1) << 240)
+                        | (U256::from(0xFFC9D8u64) << 160)
+                        | (U256::from(1) << 95);
+                    json!(format!("0x{:064x}", slot0))
+                } else {
+                    json!(format!("0x{}", word(5_000_000)))
+                }
+            }
+            "eth_call" => {
+                let to = params[0]["to"].as_str().unwrap().to_lowercase();
+                let data = params[0]["input"]
+                    .as_str()
+                    .or(params[0]["data"].as_str())
+                    .unwrap();
+                if to == "0x1f98431c8ad98523631ae4a59f267346ea31f984" {
+                    return json!(format!("0x{:0>64}", &POOL[2..]));
+                }
+                if to == "0x61ffe0149a332c47d847296f720a48855e9cb754" {
+                    assert_eq!(params[1], "0x1", "quotes are pinned to the block");
+                    let amount_out = match params.get(2).and_then(|o| o.get(POOL)) {
+                        None => 1_000_000_000,
+                        Some(pool) => {
+                            let diff = &pool["stateDiff"];
+                            let slot0 = diff[format!("0x{:064x}", 0)].as_str();
+                            let liquidity = diff[format!("0x{:064x}", 4)].as_str();
+                            match (slot0, liquidity) {
+                                (Some(slot0), None) => {
+                                    let slot0 = U256::from_str(slot0).unwrap();
+                                    assert!(slot0.bit(240), "lock bit kept");
+                                    800_000_000
+                                }
+                                (None, Some(_)) => 990_000_000,
+                                other => panic!("unexpected overrides {:?}", other),
+                            }
+                        }
+                    };
+                    return json!(format!(
+                        "0x{}{}{}{}",
+                        word(amount_out),
+                        word(0),
+                        word(0),
+                        word(90_000)
+                    ));
+                }
+                match &data[..10] {
+                    "0x313ce567" => json!(format!("0x{}", word(6))),
+                    _ => json!({ "rpc_error": { "code": 3, "message": "execution reverted" } }),
+                }
+            }
+            other => panic!("unexpected {}", other),
+        }
+    })
+    .await;
+    let mut client = EthereumClient::read_only(&url).await.unwrap();
+    client.multicall_available = false;
+    let args = |scenario: Value| {
+        json!({
+            "from_token": "ETH",
+            "to_token": "USDC",
+            "amount": "1000000000000000000",
+            "scenario": scenario
+        })
+    };
+
+    // WETH is token1: a 20% lower WETH price is a higher sqrtPriceX96.
+    let res = SimulateSwapScenarioTool
+        .call(&client, args(json!({"price_change_percent": -20})))
+        .await
+        .unwrap();
+    assert_eq!(res["block"], 1);
+    assert_eq!(res["pool"].as_str().unwrap().to_lowercase(), POOL);
+    assert_eq!(res["baseline"]["amount_out"], "1000");
+    assert_eq!(res["baseline"]["pool"]["tick"], -13864);
+    assert_eq!(res["scenario"]["amount_out"], "800");
+    assert_eq!(res["scenario"]["pool"]["tick"], -11633);
+    assert_eq!(res["output_change_percent"], json!("-20"));
+    assert_eq!(
+        res["overrides"]["pool_slots"],
+        json!(["slot0 (sqrtPriceX96, tick)"])
+    );
+
+    let res = SimulateSwapScenarioTool
+        .call(&client, args(json!({"liquidity": "1000"})))
+        .await
+        .unwrap();
+    assert_eq!(res["scenario"]["amount_out"], "990");
+    assert_eq!(res["scenario"]["pool"]["liquidity"], "1000");
+    assert_eq!(res["baseline"]["pool"]["liquidity"], "5000000");
+
+    let err = SimulateSwapScenarioTool
+        .call(&client, args(json!({})))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("scenario needs"), "{}", err);
+    let err = SimulateSwapScenarioTool
+        .call(
+            &client,
+            args(json!({"price_change_percent": 5, "sqrt_price_x96": "1"})),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not both"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_get_balances_in_one_batch() {
+    use alloy::sol_types::SolCall;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static BATCHES: AtomicUsize = AtomicUsize::new(0);
+
+    // 1.5 ETH, 2.5 of an 18-decimal "TKN" at 0x1111; 0x2222 is not a token.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        assert_eq!(
+            params[0]["to"].as_str().unwrap().to_lowercase(),
+            "0xca11bde05977b3631167028862be2a173976ca11"
+        );
+        BATCHES.fetch_add(1, Ordering::SeqCst);
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let word = |n: u128| format!("{:064x}", n);
+        let calls = aggregate3Call::abi_decode(&hex::decode(&data[2..]).unwrap(), true)
+            .unwrap()
+            .calls;
+        let results: Vec<Call3Result> = calls
+            .iter()
+            .map(|c| {
+                let answer = match (
+                    c.target.to_string().as_str(),
+                    hex::encode(&c.callData[..4]).as_str(),
+                ) {
+                    (_, "4d2301cc") => word(1_500_000_000_000_000_000),
+                    ("0x0000000000000000000000000000000000001111", "70a08231") => {
+                        word(2_500_000_000_000_000_000)
+                    }
+                    ("0x0000000000000000000000000000000000001111", "313ce567") => word(18),
+                    ("0x0000000000000000000000000000000000001111", "95d89b41") => {
+                        format!("{}{}{:0<64}", word(0x20), word(3), "544b4e")
+                    }
+                    _ => String::new(),
+                };
+                Call3Result {
+                    success: !answer.is_empty(),
+                    returnData: hex::decode(answer).unwrap().into(),
+                }
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+
+    let res = GetBalancesTool
+        .call(
+            &client,
+            json!({
+                "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+                "token_addresses": [
+                    "ETH",
+                    "0x0000000000000000000000000000000000001111",
+                    "0x0000000000000000000000000000000000002222",
+                    "NOT_A_TOKEN",
+                    "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE"
+                ]
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(BATCHES.load(Ordering::SeqCst), 1);
+    let balances = res["balances"].as_array().unwrap();
+    assert_eq!(balances.len(), 5);
+    for native in [&balances[0], &balances[4]] {
+        assert_eq!(native["status"], "ok");
+        assert_eq!(native["symbol"], "ETH");
+        assert_eq!(native["balance"], "1.5");
+        assert_eq!(native["decimals"], 18);
+    }
+    assert_eq!(balances[1]["symbol"], "TKN");
+    assert_eq!(balances[1]["balance"], "2.5");
+    assert_eq!(balances[1]["raw_balance"], "2500000000000000000");
+    assert_eq!(balances[2]["status"], "error");
+    assert!(balances[2]["error"].as_str().unwrap().contains("balanceOf"));
+    assert_eq!(balances[3]["status"], "error");
+    assert!(balances[3]["error"]
+        .as_str()
+        .unwrap()
+        .contains("Unknown token symbol"));
+    assert_eq!(res["summary"]["succeeded"], 3);
+    assert_eq!(res["summary"]["failed"], 2);
+}
+
+#[tokio::test]
+async fn test_batch_tools_refuse_oversized_batches() {
+    // Refused before any RPC call: the offline client has no reachable endpoint.
+    let mut client = offline_client().await;
+    client.max_batch_size = 2;
+    let expected = "batch size 3 exceeds maximum 2; split the request";
+
+    let call =
+        json!({"name": "compute_hash", "arguments": {"operation": "selector", "input": "f()"}});
+    let err = BatchReadTool
+        .call(&client, json!({ "calls": [call, call, call] }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), expected);
+    let err = GetBalancesTool
+        .call(
+            &client,
+            json!({ "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045", "token_addresses": ["ETH", "USDC", "DAI"] }),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), expected);
+    let err = GetPricesTool
+        .call(&client, json!({ "tokens": ["ETH", "USDC", "DAI"] }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), expected);
+    let err = AccountProfileTool
+        .call(
+            &client,
+            json!({ "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045", "tokens": ["USDC", "DAI", "WETH"] }),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), expected);
+
+    // At the cap is fine.
+    let res = BatchReadTool
+        .call(&client, json!({ "calls": [call, call] }))
+        .await
+        .unwrap();
+    assert_eq!(res["results"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_batch_read_aggregates_balances_and_calls() {
+    use alloy::sol_types::SolCall;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static BATCHED: AtomicUsize = AtomicUsize::new(0);
+
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            return match &data[2..10] {
+                "313ce567" => json!(format!("0x{}", word(6))),
+                "95d89b41" => json!(format!("0x{:0<64}", "55534443")),
+                other => panic!("unexpected selector {}", other),
+            };
+        }
+        BATCHED.fetch_add(1, Ordering::SeqCst);
+        let calls = aggregate3Call::abi_decode(&hex::decode(&data[2..]).unwrap(), true)
+            .unwrap()
+            .calls;
+        let results: Vec<Call3Result> = calls
+            .iter()
+            .map(|c| {
+                let value = match hex::encode(&c.callData[..4]).as_str() {
+                    // getEthBalance
+                    "4d2301cc" => word(2_000_000_000_000_000_000),
+                    // balanceOf
+                    "70a08231" => word(1_500_000),
+                    // totalSupply
+                    "18160ddd" => word(42),
+                    other => panic!("unexpected batched selector {}", other),
+                };
+                Call3Result {
+                    success: true,
+                    returnData: hex::decode(value).unwrap().into(),
+                }
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let holder = "0x0000000000000000000000000000000000000001";
+    let usdc = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    let res = BatchReadTool
+        .call(
+            &client,
+            json!({"calls": [
+                {"name": "get_balance", "arguments": {"address": holder}},
+                {"name": "get_balance", "arguments": {"address": holder, "token_address": usdc}},
+                {"name": "eth_call", "arguments": {"to": usdc, "data": "0x18160ddd"}},
+                {"name": "compute_hash", "arguments": {"operation": "selector", "input": "totalSupply()"}},
+                {"name": "send_transaction", "arguments": {}},
+                {"name": "get_balance", "arguments": {}}
+            ]}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(BATCHED.load(Ordering::SeqCst), 1);
+    assert_eq!(res["aggregated_calls"], 3);
+    assert_eq!(res["rpc_round_trips_saved"], 2);
+    let results = &res["results"];
+    assert_eq!(results[0]["result"]["balance"], "2");
+    assert_eq!(results[0]["result"]["symbol"], "ETH");
+    assert_eq!(results[1]["result"]["balance"], "1.5");
+    assert_eq!(results[1]["result"]["symbol"], "USDC");
+    assert_eq!(
+        results[2]["result"]["return_data"],
+        format!("0x{:064x}", 42)
+    );
+    assert_eq!(results[3]["result"]["selector"], "0x18160ddd");
+    assert!(results[4]["error"]
+        .as_str()
+        .unwrap()
+        .contains("not a read-only tool"));
+    assert_eq!(results[5]["error"], "Missing address");
+}
+
+#[tokio::test]
+async fn test_swap_exact_out_bounds_input() {
+    // Buying exactly 2000 USDC costs 1 WETH per the quoter; the router agrees.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        match &data[2..10] {
+            "313ce567" if to == "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" => {
+                json!(format!("0x{}", word(6)))
+            }
+            "313ce567" => json!(format!("0x{}", word(18))),
+            "95d89b41" => json!("0x"),
+            // quoteExactOutputSingle: amountIn, sqrtPriceX96After, ticks, gasEstimate
+            "bd21704a" => json!(format!(
+                "0x{}{}{}{}",
+                word(1_000_000_000_000_000_000),
+                word(1),
+                word(1),
+                word(100_000)
+            )),
+            // exactOutputSingle returns amountIn
+            "db3e2198" => json!(format!("0x{}", word(1_000_000_000_000_000_000))),
+            // multicall(exactOutputSingle, refundETH): not decoded by this mock
+            "ac9650d8" => json!("0x"),
+            other => panic!("unexpected selector {}", other),
+        }
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let usdc = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+    let weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+    let res = SwapTokensTool
+        .call(
+            &client,
+            json!({"from_token": weth, "to_token": usdc, "amount": "2000000000", "mode": "exact_out", "fee": 3000}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["mode"], "exact_out");
+    assert_eq!(res["estimated_input"], "1000000000000000000");
+    assert_eq!(res["maximum_input"], "1005000000000000000");
+    assert_eq!(res["estimated_output"], "2000000000");
+    assert_eq!(res["minimum_output"], "2000000000");
+    assert_eq!(res["transaction"]["value"], "0");
+    assert!(res["transaction"]["data"]
+        .as_str()
+        .unwrap()
+        .starts_with("0xdb3e2198"));
+    assert_eq!(
+        res["router_call_simulation"]["simulated_amount_in"],
+        "1000000000000000000"
+    );
+    assert_eq!(res["quote_consistency"]["status"], "ok");
+    // 20 minutes after the latest block, encoded in the router params.
+    assert_eq!(res["transaction"]["deadline"], 1_700_001_200u64);
+    assert_eq!(res["transaction"]["deadline_base"], "latest block");
+    assert!(res["transaction"]["data"]
+        .as_str()
+        .unwrap()
+        .contains(&format!("{:064x}", 1_700_001_200u64)));
+
+    let res = SwapTokensTool
+        .call(
+            &client,
+            json!({"from_token": "ETH", "to_token": usdc, "amount": "2000000000", "mode": "exact_out", "fee": 3000, "deadline_seconds": 60}),
+        )
+        .await
+        .unwrap();
+    // The maximum goes out as value; refundETH returns the unspent part.
+    assert_eq!(res["transaction"]["value"], "1005000000000000000");
+    assert!(res["transaction"]["data"]
+        .as_str()
+        .unwrap()
+        .starts_with("0xac9650d8"));
+    assert_eq!(
+        res["transaction"]["description"],
+        "Uniswap V3 SwapRouter.multicall(exactOutputSingle, refundETH)"
+    );
+    assert_eq!(res["transaction"]["deadline"], 1_700_000_060u64);
+
+    let err = SwapTokensTool
+        .call(
+            &client,
+            json!({"from_token": weth, "to_token": usdc, "amount": "1", "mode": "exact"}),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("exact_out"), "{}", err);
+}
+
+#[test]
+fn test_oriented_price_inverts_with_each_tokens_decimals() {
+    // Token (6 decimals) is token1 against WETH (18) at 2 WETH per token: the raw ratio is
+    // 0.5 * 10^6 / 10^18 token base units per WETH base unit.
+    let raw = Decimal::from_str("0.0000000000005").unwrap();
+    assert_eq!(
+        oriented_price(raw, false, 6, 18).unwrap().normalize(),
+        Decimal::from(2)
+    );
+    // As token0 the same raw ratio is WETH per token directly, scaled by 10^(6 - 18).
+    let raw = Decimal::from(2_000_000_000_000u64);
+    assert_eq!(
+        oriented_price(raw, true, 6, 18).unwrap().normalize(),
+        Decimal::from(2)
+    );
+}
+
+#[tokio::test]
+async fn test_price_impact_reports_best_pool() {
+    use alloy::sol_types::SolCall;
+
+    // Token/WETH pools at 0.3% and 1%, both at 0.25 WETH per token (ETH/USD 2000). Selling
+    // 1 token returns 0.2 WETH through the 0.3% pool and 0.1 WETH through the 1% pool.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let answer = |call: &[u8]| -> String {
+            let args = hex::encode(&call[4..]);
+            match hex::encode(&call[..4]).as_str() {
+                // getPool(token, quote, fee): WETH only, fees 3000 and 10000
+                "1698ee82"
+                    if args[64..128].ends_with("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2")
+                        && (args[128..] == word(3000) || args[128..] == word(10000)) =>
+                {
+                    word(0x9000 + u128::from_str_radix(&args[124..128], 16).unwrap())
+                }
+                "1698ee82" => word(0),
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(1 << 95),
+                    word(0),
+                    word(0),
+                    word(1),
+                    word(1),
+                    word(0),
+                    word(1)
+                ),
+                "1a686502" => word(1),
+                "0dfe1681" => word(0x1111),
+                "feaf968c" => format!(
+                    "{}{}{}{}{}",
+                    word(1),
+                    word(200_000_000_000),
+                    word(1),
+                    word(1),
+                    word(1)
+                ),
+                // quoteExactInputSingle: amountOut by fee tier
+                "c6a5026a" => {
+                    let out = if args[192..256] == word(3000) {
+                        200_000_000_000_000_000
+                    } else {
+                        100_000_000_000_000_000
+                    };
+                    format!("{}{}{}{}", word(out), word(1), word(1), word(100_000))
+                }
+                _ => String::new(),
+            }
+        };
+        let data = hex::decode(
+            &params[0]["input"]
+                .as_str()
+                .or(params[0]["data"].as_str())
+                .unwrap()[2..],
+        )
+        .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            // Token decimals, read directly
+            if data[..4] == [0x31, 0x3c, 0xe5, 0x67] {
+                return json!(format!("0x{}", word(18)));
+            }
+            return json!(format!("0x{}", answer(&data)));
+        }
+        let results: Vec<Call3Result> = aggregate3Call::abi_decode(&data, true)
+            .unwrap()
+            .calls
+            .iter()
+            .map(|c| {
+                // Only the feed's decimals are batched.
+                let returned = if c.callData[..4] == [0x31, 0x3c, 0xe5, 0x67] {
+                    word(8)
+                } else {
+                    answer(&c.callData)
+                };
+                Call3Result {
+                    success: true,
+                    returnData: hex::decode(returned).unwrap().into(),
+                }
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let token = "0x0000000000000000000000000000000000001111";
+
+    let res = EstimatePriceImpactTool
+        .call(
+            &client,
+            json!({"token_address": token, "size": "500", "size_unit": "usd"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["size_tokens"], "1");
+    assert_eq!(res["spot_price_usd"], json!("500"));
+    assert_eq!(res["best"]["fee"], 3000);
+    assert_eq!(res["best"]["quote_token"], "WETH");
+    assert_eq!(res["best"]["execution_price"], json!("0.2"));
+    assert_eq!(res["best"]["price_impact_percent"], json!("20"));
+    assert_eq!(res["best"]["amount_out_usd"], json!("400"));
+    assert_eq!(res["pools"].as_array().unwrap().len(), 2);
+    assert_eq!(res["pools"][1]["price_impact_percent"], json!("60"));
+
+    let err = EstimatePriceImpactTool
+        .call(
+            &client,
+            json!({"token_address": token, "size": "1", "size_unit": "eth"}),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("size_unit"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_swap_picks_best_quoting_fee_tier() {
+    use alloy::sol_types::SolCall;
+
+    // WETH/USDC pools exist at 0.05% and 0.3%; the 0.05% pool quotes more USDC for 1 WETH.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let answer = |call: &[u8]| -> String {
+            let args = hex::encode(&call[4..]);
+            match hex::encode(&call[..4]).as_str() {
+                "1698ee82" if args[128..] == word(500) || args[128..] == word(3000) => {
+                    word(0x9000 + u128::from_str_radix(&args[124..128], 16).unwrap())
+                }
+                "1698ee82" => word(0),
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(1 << 95),
+                    word(0),
+                    word(0),
+                    word(1),
+                    word(1),
+                    word(0),
+                    word(1)
+                ),
+                "1a686502" => word(1),
+                // quoteExactInputSingle by fee tier: amountOut, sqrtPriceX96After, ticks, gas
+                "c6a5026a" => {
+                    let out = if args[192..256] == word(500) {
+                        2_000_000_000
+                    } else {
+                        1_990_000_000
+                    };
+                    format!("{}{}{}{}", word(out), word(1), word(1), word(100_000))
+                }
+                "414bf389" => word(2_000_000_000),
+                "313ce567" => word(18),
+                "95d89b41" => String::new(),
+                other => panic!("unexpected selector {}", other),
+            }
+        };
+        let data = hex::decode(
+            &params[0]["input"]
+                .as_str()
+                .or(params[0]["data"].as_str())
+                .unwrap()[2..],
+        )
+        .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            return json!(format!("0x{}", answer(&data)));
+        }
+        let results: Vec<Call3Result> = aggregate3Call::abi_decode(&data, true)
+            .unwrap()
+            .calls
+            .iter()
+            .map(|c| Call3Result {
+                success: true,
+                returnData: hex::decode(answer(&c.callData)).unwrap().into(),
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let res = SwapTokensTool
+        .call(
+            &client,
+            json!({
+                "from_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+                "to_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                "amount": "1000000000000000000"
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["fee"], 500);
+    assert_eq!(res["fee_selection"]["chosen_fee"], 500);
+    assert_eq!(
+        res["fee_selection"]["candidates"].as_array().unwrap().len(),
+        2
+    );
+    assert_eq!(res["estimated_output"], "2000000000");
+}
+
+/// Starts the server binary on the HTTP transport against `rpc_url`, on a free local port.
+/// Returns the process and the endpoint to POST to.
+fn spawn_http_server(rpc_url: &str, env: &[(&str, &str)]) -> (tokio::process::Child, String) {
+    let bind = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let server = tokio::process::Command::new(env!("CARGO_BIN_EXE_ethereum-trading-mcp"))
+        .env("ETHEREUM_RPC_URL", rpc_url)
+        .env(
+            "PRIVATE_KEY",
+            "0x0123456789012345678901234567890123456789012345678901234567890123",
+        )
+        .env("RUST_LOG", "error")
+        .env("TRANSPORT", "http")
+        .env("HTTP_BIND", bind.to_string())
+        .envs(env.iter().copied())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    (server, format!("http://{}/", bind))
+}
+
+/// The JSON-RPC messages of a `text/event-stream` body.
+fn sse_messages(body: &str) -> Vec<Value> {
+    body.lines()
+        .filter_map(|l| l.strip_prefix("data: "))
+        .map(|d| serde_json::from_str(d).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_http_transport_answers_over_sse() {
+    let url = mock_rpc(|method, _| match method {
+        "eth_getCode" => json!("0x00"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let (mut server, endpoint) = spawn_http_server(&url, &[]);
+    let http = reqwest::Client::new();
+    let post = |body: Value, session: Option<String>| {
+        let mut req = http.post(&endpoint).json(&body);
+        if let Some(session) = session {
+            req = req.header("Mcp-Session-Id", session);
+        }
+        req.send()
+    };
+    let events = sse_messages;
+
+    let mut init = None;
+    for _ in 0..100 {
+        match post(
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+            None,
+        )
+        .await
+        {
+            Ok(res) => {
+                init = Some(res);
+                break;
+            }
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+        }
+    }
+    let init = init.expect("HTTP transport never came up");
+    assert_eq!(init.headers()["content-type"], "text/event-stream");
+    let session = init.headers()["mcp-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let reply = events(&init.text().await.unwrap());
+    assert_eq!(reply.len(), 1);
+    assert_eq!(reply[0]["id"], 1);
+    assert!(reply[0]["result"]["serverInfo"].is_object());
+
+    let notified = post(
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        Some(session.clone()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(notified.status(), 202);
+
+    let streamed = post(
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "list_chains", "arguments": {"stream": "chains"}, "_meta": {"progressToken": "t"}}
+        }),
+        Some(session.clone()),
+    )
+    .await
+    .unwrap();
+    let messages = events(&streamed.text().await.unwrap());
+    let (last, progress) = messages.split_last().unwrap();
+    assert!(!progress.is_empty());
+    assert!(progress
+        .iter()
+        .all(|m| m["method"] == "notifications/progress"));
+    assert_eq!(last["id"], 2);
+
+    let unknown = post(
+        json!({"jsonrpc": "2.0", "id": 3, "method": "tools/list"}),
+        Some("nope".into()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(unknown.status(), 404);
+    let closed = http
+        .delete(&endpoint)
+        .header("Mcp-Session-Id", &session)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(closed.status(), 204);
+    let parse_error = http
+        .post(&endpoint)
+        .header("content-type", "application/json")
+        .body("{")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(parse_error.status(), 400);
+
+    // What a web page can send without a preflight is refused.
+    let list = json!({"jsonrpc": "2.0", "id": 4, "method": "tools/list"});
+    let plain = http
+        .post(&endpoint)
+        .header("content-type", "text/plain")
+        .body(list.to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(plain.status(), 415);
+    let foreign = http
+        .post(&endpoint)
+        .header("origin", "http://attacker.example")
+        .json(&list)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(foreign.status(), 403);
+    let local = http
+        .post(&endpoint)
+        .header("origin", "http://localhost:6274")
+        .json(&list)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(local.status(), 200);
+    server.kill().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_http_sessions_are_capped_and_expire() {
+    let url = mock_rpc(|method, _| match method {
+        "eth_getCode" => json!("0x00"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let (mut server, endpoint) = spawn_http_server(
+        &url,
+        &[
+            ("HTTP_MAX_SESSIONS", "1"),
+            ("HTTP_SESSION_IDLE_SECONDS", "1"),
+        ],
+    );
+    let http = reqwest::Client::new();
+    let initialize = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}});
+
+    let mut first = None;
+    for _ in 0..100 {
+        match http.post(&endpoint).json(&initialize).send().await {
+            Ok(res) => {
+                first = Some(res);
+                break;
+            }
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+        }
+    }
+    let first = first.expect("HTTP transport never came up");
+    let session = first.headers()["mcp-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let full = http.post(&endpoint).json(&initialize).send().await.unwrap();
+    assert_eq!(full.status(), 503);
+
+    // Once idle past the TTL, the session is gone and its slot is free again.
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    let second = http.post(&endpoint).json(&initialize).send().await.unwrap();
+    assert_eq!(second.status(), 200);
+    let expired = http
+        .post(&endpoint)
+        .header("Mcp-Session-Id", &session)
+        .json(&json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(expired.status(), 404);
+    server.kill().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_http_auth_token_is_required_when_set() {
+    let url = mock_rpc(|method, _| match method {
+        "eth_getCode" => json!("0x00"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let (mut server, endpoint) = spawn_http_server(&url, &[("HTTP_AUTH_TOKEN", "s3cret")]);
+    let http = reqwest::Client::new();
+    let list = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"});
+
+    let mut anonymous = None;
+    for _ in 0..100 {
+        match http.post(&endpoint).json(&list).send().await {
+            Ok(res) => {
+                anonymous = Some(res);
+                break;
+            }
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+        }
+    }
+    assert_eq!(
+        anonymous.expect("HTTP transport never came up").status(),
+        401
+    );
+    let wrong = http
+        .post(&endpoint)
+        .bearer_auth("s3cre7")
+        .json(&list)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(wrong.status(), 401);
+    let authorized = http
+        .post(&endpoint)
+        .bearer_auth("s3cret")
+        .json(&list)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(authorized.status(), 200);
+    let reply = sse_messages(&authorized.text().await.unwrap());
+    assert!(reply[0]["result"]["tools"].is_array());
+    server.kill().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_http_per_client_limit_ignores_session_ids() {
+    // The watched transaction never shows up, so the watch runs until its timeout.
+    let url = mock_rpc(|method, _| match method {
+        "eth_getCode" => json!("0x00"),
+        "eth_getTransactionReceipt" | "eth_getTransactionByHash" => Value::Null,
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let (mut server, endpoint) =
+        spawn_http_server(&url, &[("MAX_CONCURRENT_REQUESTS_PER_CLIENT", "1")]);
+    let http = reqwest::Client::new();
+    let watch = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": "watch_transaction", "arguments": {
+            "tx_hash": format!("0x{}", "ab".repeat(32)),
+            "timeout_seconds": 3,
+            "poll_interval_seconds": 1
+        }}
+    });
+    let mut slow = None;
+    for _ in 0..100 {
+        match http.post(&endpoint).json(&watch).send().await {
+            Ok(res) => {
+                slow = Some(res);
+                break;
+            }
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+        }
+    }
+    let slow = slow.expect("HTTP transport never came up");
+
+    // No Mcp-Session-Id on either request: each gets a fresh session, but the same peer.
+    let second = http
+        .post(&endpoint)
+        .json(&json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}))
+        .send()
+        .await
+        .unwrap();
+    let reply = sse_messages(&second.text().await.unwrap());
+    assert_eq!(reply[0]["error"]["code"], -32000, "{}", reply[0]);
+    assert!(reply[0]["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("this client"));
+
+    let reply = sse_messages(&slow.text().await.unwrap());
+    assert_eq!(reply.last().unwrap()["result"]["data"]["status"], "timeout");
+    server.kill().await.unwrap();
+}
+
+/// Answers a permit token's reads through Multicall3 (and its `permit` eth_call), as an
+/// EIP-2612 token named "Test" at version "2", or as a DAI-style token when `dai`.
+fn permit_token_rpc(method: &str, params: &Value, dai: bool) -> Value {
+    use alloy::sol_types::{Eip712Domain, SolCall, SolValue};
+
+    assert_eq!(method, "eth_call");
+    let token = Address::from_str("0x1111111111111111111111111111111111111111").unwrap();
+    let data = hex::decode(
+        &params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap()[2..],
+    )
+    .unwrap();
+    if params[0]["to"].as_str().unwrap().to_lowercase()
+        != "0xca11bde05977b3631167028862be2a173976ca11"
+    {
+        // permit(): accepted.
+        assert_eq!(hex::encode(&data[..4]), "d505accf");
+        return json!("0x");
+    }
+    let domain = Eip712Domain::new(
+        Some("Test".into()),
+        Some("2".into()),
+        Some(U256::from(1)),
+        Some(token),
+        None,
+    );
+    let results: Vec<Call3Result> = aggregate3Call::abi_decode(&data, true)
+        .unwrap()
+        .calls
+        .iter()
+        .map(|c| {
+            let answer = match hex::encode(&c.callData[..4]).as_str() {
+                "7ecebe00" => Some(U256::from(4).abi_encode()),
+                "3644e515" => Some(domain.separator().abi_encode()),
+                "30adf81f" if dai => Some(
+                    alloy::primitives::keccak256(
+                        "Permit(address holder,address spender,uint256 nonce,uint256 expiry,bool allowed)",
+                    )
+                    .abi_encode(),
+                ),
+                "06fdde03" => Some("Test".to_string().abi_encode()),
+                "54fd4d50" => Some("2".to_string().abi_encode()),
+                _ => None,
+            };
+            Call3Result {
+                success: answer.is_some(),
+                returnData: answer.unwrap_or_default().into(),
+            }
+        })
+        .collect();
+    json!(format!(
+        "0x{}",
+        hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+    ))
+}
+
+#[tokio::test]
+async fn test_sign_permit_signs_the_tokens_domain() {
+    let url = mock_rpc(|method, params| permit_token_rpc(method, params, false)).await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let args = json!({
+        "token_address": "0x1111111111111111111111111111111111111111",
+        "spender": "0x2222222222222222222222222222222222222222",
+        "value": "max"
+    });
+
+    let res = SignPermitTool.call(&client, args.clone()).await.unwrap();
+    assert_eq!(res["nonce"], "4");
+    assert_eq!(res["deadline"], "1700003600");
+    assert_eq!(res["domain"]["name"], "Test");
+    assert_eq!(res["domain"]["version"], "2");
+    assert_eq!(res["domain_source"], "DOMAIN_SEPARATOR()");
+    assert_eq!(res["domain_matches_token"], true);
+    let digest = alloy::primitives::B256::from_str(res["digest"].as_str().unwrap()).unwrap();
+    let signature =
+        alloy::primitives::Signature::from_str(res["signature"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        signature.recover_address_from_prehash(&digest).unwrap(),
+        client.signer_address.unwrap()
+    );
+    assert!(res["v"] == 27 || res["v"] == 28);
+    assert!(res["permit_calldata"]["data"]
+        .as_str()
+        .unwrap()
+        .starts_with("0xd505accf"));
+
+    let url = mock_rpc(|method, params| permit_token_rpc(method, params, true)).await;
+    let dai = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let err = SignPermitTool.call(&dai, args).await.unwrap_err();
+    assert!(err.to_string().contains("non-standard permit"), "{}", err);
+}
+
+/// A mainnet ENS registry where vitalik.eth points at 0xd8dA... (with a matching reverse
+/// record), noresolver.eth is owned but has no resolver, and nothing else is registered.
+fn ens_rpc(method: &str, params: &Value) -> Value {
+    use alloy::sol_types::{SolCall, SolValue};
+
+    let resolver = Address::from_str("0x4976fb03C32e5B8cfe2b6cCB31c09Ba78EBaBa41").unwrap();
+    let vitalik = Address::from_str("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045").unwrap();
+    let answer = |data: &[u8]| -> Vec<u8> {
+        let node = alloy::primitives::B256::from_slice(&data[4..36]);
+        let registered = node == namehash("vitalik.eth");
+        let reverse = node == namehash(&format!("{}.addr.reverse", hex::encode(vitalik)));
+        match hex::encode(&data[..4]).as_str() {
+            // owner(node)
+            "02571be3" if registered || node == namehash("noresolver.eth") => vitalik.abi_encode(),
+            "02571be3" => Address::ZERO.abi_encode(),
+            // resolver(node)
+            "0178b8bf" if registered || reverse => resolver.abi_encode(),
+            "0178b8bf" => Address::ZERO.abi_encode(),
+            // addr(node)
+            "3b3b57de" => vitalik.abi_encode(),
+            // name(node)
+            "691f3431" => "vitalik.eth".to_string().abi_encode(),
+            other => panic!("unexpected selector {}", other),
+        }
+    };
+    match method {
+        "eth_getBalance" => {
+            assert_eq!(
+                params[0].as_str().unwrap().to_lowercase(),
+                hex_addr(vitalik)
+            );
+            json!("0xde0b6b3a7640000")
+        }
+        "eth_call" => {
+            let data = hex::decode(
+                &params[0]["input"]
+                    .as_str()
+                    .or(params[0]["data"].as_str())
+                    .unwrap()[2..],
+            )
+            .unwrap();
+            if params[0]["to"].as_str().unwrap().to_lowercase()
+                != hex_addr(ethereum_trading_mcp::ethereum::MULTICALL3_ADDRESS)
+            {
+                return json!(format!("0x{}", hex::encode(answer(&data))));
+            }
+            let results: Vec<Call3Result> = aggregate3Call::abi_decode(&data, true)
+                .unwrap()
+                .calls
+                .iter()
+                .map(|c| Call3Result {
+                    success: true,
+                    returnData: answer(&c.callData).into(),
+                })
+                .collect();
+            json!(format!(
+                "0x{}",
+                hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+            ))
+        }
+        other => panic!("unexpected {}", other),
+    }
+}
+
+fn hex_addr(address: Address) -> String {
+    format!("0x{}", hex::encode(address))
+}
+
+#[tokio::test]
+async fn test_resolve_ens_forward_reverse_and_missing_records() {
+    assert_eq!(
+        namehash("eth").to_string(),
+        "0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+    );
+    assert_eq!(
+        namehash("foo.eth").to_string(),
+        "0xde9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+    );
+
+    let url = mock_rpc(ens_rpc).await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let res = ResolveEnsTool
+        .call(&client, json!({"name": "Vitalik.eth"}))
+        .await
+        .unwrap();
+    assert_eq!(res["name"], "vitalik.eth");
+    assert_eq!(res["address"], "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045");
+
+    let res = ResolveEnsTool
+        .call(
+            &client,
+            json!({"address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["name"], "vitalik.eth");
+    assert_eq!(res["verified"], true);
+
+    let err = ResolveEnsTool
+        .call(&client, json!({"name": "nobody.eth"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("is not registered"), "{}", err);
+    let err = ResolveEnsTool
+        .call(&client, json!({"name": "noresolver.eth"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("has no resolver set"), "{}", err);
+
+    // Address arguments resolve names transparently.
+    let res = GetBalanceTool
+        .call(&client, json!({"address": "vitalik.eth"}))
+        .await
+        .unwrap();
+    assert_eq!(res["raw_balance"], "1000000000000000000");
+    let err = GetBalanceTool
+        .call(&client, json!({"address": "not-an-address"}))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("not an address or an ENS name"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_denylist_checks_resolved_ens_names() {
+    let url = mock_rpc(ens_rpc).await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let denylist = DenyList::parse("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045").unwrap();
+
+    // The raw argument is not an address, so only resolving it catches the match.
+    let args = json!({"to": "Vitalik.eth", "amount": "1"});
+    assert!(denylist.check_args(&args, &client.tokens).is_ok());
+    let err = denylist.check_ens_names(&args, &client).await.unwrap_err();
+    assert!(err.to_string().contains("deny-list"), "{}", err);
+    assert!(err.to_string().contains("vitalik.eth"), "{}", err);
+
+    // Unregistered names are left for the tool to report.
+    assert!(denylist
+        .check_ens_names(&json!({"to": "nobody.eth"}), &client)
+        .await
+        .is_ok());
+    assert!(DenyList::default()
+        .check_ens_names(&args, &client)
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_watch_price_alerts_on_crossing_and_unwatches() {
+    use alloy::sol_types::SolCall;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static BLOCK: AtomicU64 = AtomicU64::new(100);
+
+    // ETH/USD is 2000 at block 100 and 2100 from block 101 on.
+    let url = mock_rpc(|method, params| {
+        if method == "eth_blockNumber" {
+            return json!(format!("0x{:x}", BLOCK.fetch_add(1, Ordering::SeqCst)));
+        }
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let data = hex::decode(
+            &params[0]["input"]
+                .as_str()
+                .or(params[0]["data"].as_str())
+                .unwrap()[2..],
+        )
+        .unwrap();
+        let price = if BLOCK.load(Ordering::SeqCst) <= 101 {
+            200_000_000_000
+        } else {
+            210_000_000_000
+        };
+        let results: Vec<Call3Result> = aggregate3Call::abi_decode(&data, true)
+            .unwrap()
+            .calls
+            .iter()
+            .map(|c| {
+                let answer = match hex::encode(&c.callData[..4]).as_str() {
+                    "feaf968c" => format!(
+                        "{}{}{}{}{}",
+                        word(1),
+                        word(price),
+                        word(1),
+                        word(1),
+                        word(1)
+                    ),
+                    "313ce567" => word(8),
+                    other => panic!("unexpected selector {}", other),
+                };
+                Call3Result {
+                    success: true,
+                    returnData: hex::decode(answer).unwrap().into(),
+                }
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+
+    let res = WatchPriceTool
+        .call(
+            &client,
+            json!({"token": "ETH", "above": "2050", "poll_interval_seconds": 1, "timeout_seconds": 30}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "triggered");
+    let alerts = res["alerts"].as_array().unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0]["condition"], "above");
+    assert_eq!(alerts[0]["price_usd"], "2100");
+
+    let err = WatchPriceTool
+        .call(&client, json!({"token": "ETH", "above": "1", "below": "2"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("must be less than"), "{}", err);
+
+    // A repeating watch runs until its own session stops it by id.
+    let watch = with_session(
+        "s1".into(),
+        WatchPriceTool.call(
+            &client,
+            json!({"token": "ETH", "below": "1000", "repeat": true, "watch_id": "w1", "poll_interval_seconds": 1}),
+        ),
+    );
+    let stop = async {
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        let unwatch = |session: &str| {
+            with_session(
+                session.into(),
+                UnwatchPriceTool.call(&client, json!({"watch_id": "w1"})),
+            )
+        };
+        let other = unwatch("s2").await.unwrap_err();
+        assert!(other.to_string().contains("No running watch"), "{}", other);
+        unwatch("s1").await.unwrap()
+    };
+    let (res, stopped) = tokio::join!(watch, stop);
+    assert_eq!(stopped["stopped"], true);
+    let res = res.unwrap();
+    assert_eq!(res["status"], "unwatched");
+    assert!(res["alerts"].as_array().unwrap().is_empty());
+    let err = UnwatchPriceTool
+        .call(&client, json!({"watch_id": "w1"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("No running watch"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_transaction_status_mined_pending_and_unknown() {
+    // Hash ..01 was mined in block 0x10 and reverted, ..02 is in the mempool, ..03 is unknown.
+    let url = mock_rpc(|method, params| {
+        let hash = params[0].as_str().unwrap_or_default().to_string();
+        match method {
+            "eth_blockNumber" => json!("0x12"),
+            "eth_getTransactionReceipt" if hash.ends_with("01") => json!({
+                "transactionHash": hash,
+                "status": "0x0",
+                "blockNumber": "0x10",
+                "blockHash": format!("0x{}", "ab".repeat(32)),
+                "gasUsed": "0x5208",
+                "effectiveGasPrice": "0x3b9aca00",
+                "logs": []
+            }),
+            "eth_getTransactionReceipt" => Value::Null,
+            "eth_getTransactionByHash" if hash.ends_with("02") => json!({
+                "hash": hash,
+                "nonce": "0x7",
+                "from": "0x14791697260e4c9a71f18484c9f997b308e59325"
+            }),
+            "eth_getTransactionByHash" => Value::Null,
+            other => panic!("unexpected {}", other),
+        }
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let hash = |n: u8| format!("0x{}{:02x}", "00".repeat(31), n);
+
+    let res = GetTransactionStatusTool
+        .call(&client, json!({"tx_hash": hash(1)}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "reverted");
+    assert_eq!(res["block_number"], 16);
+    assert_eq!(res["block_timestamp"]["iso"], "2023-11-14T22:13:20Z");
+    assert_eq!(res["confirmations"], 3);
+    assert_eq!(res["gas_used"], "21000");
+    assert_eq!(res["effective_gas_price"], "1000000000");
+
+    let res = GetTransactionStatusTool
+        .call(&client, json!({"tx_hash": hash(2)}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "pending");
+    assert_eq!(res["nonce"], 7);
+    assert_eq!(res["confirmations"], 0);
+
+    let res = GetTransactionStatusTool
+        .call(&client, json!({"tx_hash": hash(3)}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "not_found");
+}
+
+#[tokio::test]
+async fn test_quoter_calls_carry_a_sender_and_decode_per_version() {
+    use alloy::sol_types::SolValue;
+    use alloy::transports::TransportError;
+
+    let client = offline_client().await;
+    let v2 = client.uniswap_v3().unwrap();
+    assert_eq!(v2.quoter_version, chains::QuoterVersion::V2);
+    let v1 = chains::UniswapV3 {
+        quoter_version: chains::QuoterVersion::V1,
+        ..v2
+    };
+    let (weth, usdc) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    let amount = U256::from(10).pow(U256::from(18));
+
+    // The quoter isn't view, so the call is sent as the signer would send it.
+    let req = quoter::request(&client, &v1, SwapMode::ExactIn, weth, usdc, 500, amount);
+    assert_eq!(req.from, client.signer_address);
+    let data = req.input.input().unwrap();
+    assert_eq!(&data[..4], &hex::decode("f7729d43").unwrap()[..]);
+
+    // V1 returns only the amount; V2 adds the price after, ticks crossed and gas.
+    let v1_return = Ok(U256::from(2_000_000_000u64).abi_encode().into());
+    let q = quoter::decode(&v1, SwapMode::ExactIn, amount, &v1_return).unwrap();
+    assert_eq!(q.amount_out, U256::from(2_000_000_000u64));
+    assert_eq!(q.gas_estimate, U256::ZERO);
+    let v2_return = Ok((
+        U256::from(1_990_000_000u64),
+        U256::from(1),
+        1u32,
+        U256::from(90_000),
+    )
+        .abi_encode_params()
+        .into());
+    let q = quoter::decode(&v2, SwapMode::ExactIn, amount, &v2_return).unwrap();
+    assert_eq!(q.amount_out, U256::from(1_990_000_000u64));
+    assert_eq!(q.gas_estimate, U256::from(90_000));
+    // A V1 return read as V2 is a named mismatch, not a zero quote.
+    let err = quoter::decode(&v2, SwapMode::ExactIn, amount, &v1_return).unwrap_err();
+    assert!(
+        err.to_string().contains("not a QuoterV2 exact_in quote"),
+        "{}",
+        err
+    );
+
+    // A revert carrying the swap result is a quote; exact_out reads it as the input.
+    let revert = |data: &str| -> Result<alloy::primitives::Bytes, TransportError> {
+        // The payload type isn't re-exported; the variant names it.
+        Err(TransportError::ErrorResp(
+            serde_json::from_str(
+                &json!({ "code": 3, "message": "execution reverted", "data": data }).to_string(),
+            )
+            .unwrap(),
+        ))
+    };
+    let quoted = format!(
+        "0x{}",
+        hex::encode((U256::from(501), U256::from(1), U256::from(2)).abi_encode_params())
+    );
+    let q = quoter::decode(&v2, SwapMode::ExactOut, amount, &revert(&quoted)).unwrap();
+    assert_eq!((q.amount_in, q.amount_out), (U256::from(501), amount));
+    let err = quoter::decode(
+        &v1,
+        SwapMode::ExactIn,
+        amount,
+        &revert("0x08c379a000000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000003535044000000000000000000000000000000000000000000000000000000000"),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("quote failed"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_required_eth_adds_value_and_gas_and_reports_shortfall() {
+    let url = mock_rpc(|method, _| match method {
+        // 0.01 ETH
+        "eth_getBalance" => json!("0x2386f26fc10000"),
+        "eth_estimateGas" => json!("0x5208"),
+        // No fee history, so gas is budgeted at the legacy 1 gwei gas price.
+        "eth_feeHistory" => Value::Null,
+        "eth_gasPrice" => json!("0x3b9aca00"),
+        // Chainlink unreadable: the USD figures are left out.
+        "eth_call" => json!("0x"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let to = "0x000000000000000000000000000000000000dEaD";
+
+    let res = GetRequiredEthTool
+        .call(
+            &client,
+            json!({ "transaction": { "to": to, "value": "20000000000000000" } }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["transactions"][0]["gas"], 21_000);
+    assert_eq!(res["transactions"][0]["gas_source"], "eth_estimateGas");
+    assert_eq!(res["raw_required"], "20021000000000000");
+    assert_eq!(res["raw_shortfall"], "10021000000000000");
+    assert_eq!(res["sufficient"], false);
+    assert!(res["shortfall_usd"].is_null());
+
+    // A planned send_transaction with its own gas limit, covered by the balance.
+    let res = GetRequiredEthTool
+        .call(
+            &client,
+            json!({
+                "tool": "send_transaction",
+                "arguments": { "to": to, "value": "1000", "gas_limit": 50_000 }
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["transactions"][0]["gas_source"], "gas_limit");
+    assert_eq!(res["raw_required"], "50000000001000");
+    assert_eq!(res["raw_shortfall"], "0");
+    assert_eq!(res["sufficient"], true);
+
+    for args in [
+        json!({ "tool": "get_balance", "arguments": {} }),
+        json!({ "tool": "send_transaction", "transaction": { "to": to } }),
+        json!({}),
+    ] {
+        assert!(GetRequiredEthTool.call(&client, args).await.is_err());
+    }
+}
+
+#[test]
+fn test_wallet_loads_from_mnemonic_and_keystore() {
+    use alloy::signers::k256::elliptic_curve::rand_core::OsRng;
+    use alloy::signers::local::PrivateKeySigner;
+
+    let phrase = "test test test test test test test test test test test junk";
+    let mnemonic = |derivation_path: Option<&str>, index| WalletSource::Mnemonic {
+        phrase: phrase.into(),
+        derivation_path: derivation_path.map(String::from),
+        index,
+    };
+    let address = |wallet: &WalletSource| load_signer(wallet).unwrap().address().to_string();
+    assert_eq!(
+        address(&mnemonic(None, 0)),
+        "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+    );
+    assert_eq!(
+        address(&mnemonic(None, 1)),
+        "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+    );
+    assert_eq!(
+        address(&mnemonic(Some("m/44'/60'/0'/0/1"), 0)),
+        "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+    );
+    let bad_checksum = WalletSource::Mnemonic {
+        phrase: "test test test test test test test test test test test test".into(),
+        derivation_path: None,
+        index: 0,
+    };
+    assert!(load_signer(&bad_checksum).is_err());
+
+    let dir = env::temp_dir();
+    let name = format!("mcp-keystore-{}.json", std::process::id());
+    let key = [7u8; 32];
+    let (expected, _) =
+        PrivateKeySigner::encrypt_keystore(&dir, &mut OsRng, key, "hunter2", Some(&name)).unwrap();
+    let path = dir.join(&name).to_string_lossy().into_owned();
+    let keystore = |password: &str| WalletSource::Keystore {
+        path: path.clone(),
+        password: password.into(),
+    };
+    assert_eq!(
+        address(&keystore("hunter2")),
+        expected.address().to_string()
+    );
+    let err = load_signer(&keystore("wrong")).unwrap_err();
+    assert!(
+        err.to_string().contains("Could not decrypt keystore"),
+        "{}",
+        err
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_call_cache_answers_repeats_within_a_block() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static HEAD: AtomicU64 = AtomicU64::new(100);
+    static CALLS: AtomicU64 = AtomicU64::new(0);
+
+    let url = mock_rpc(|method, params| match method {
+        "eth_blockNumber" => json!(format!("0x{:x}", HEAD.load(Ordering::SeqCst))),
+        "eth_call" => {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            // Reads are pinned to the cached head, not "latest".
+            assert_eq!(
+                params[1],
+                json!(format!("0x{:x}", HEAD.load(Ordering::SeqCst)))
+            );
+            json!(format!("0x{:064x}", HEAD.load(Ordering::SeqCst)))
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap()
+    .with_call_cache(std::time::Duration::from_millis(200));
+    let read = |data: &str| {
+        TransactionRequest::default()
+            .to(Address::repeat_byte(0x11))
+            .input(alloy::primitives::Bytes::from_str(data).unwrap().into())
+    };
+
+    let first = client.call_cached(&read("0x01")).await.unwrap();
+    assert_eq!(client.call_cached(&read("0x01")).await.unwrap(), first);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    client.call_cached(&read("0x02")).await.unwrap();
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    let stats = client.call_cache.as_ref().unwrap().stats();
+    assert_eq!(
+        (stats["hits"].as_u64(), stats["entries"].as_u64()),
+        (Some(1), Some(2))
+    );
+
+    // Once the head is re-read and has moved, the old block's answers are gone.
+    HEAD.store(101, Ordering::SeqCst);
+    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    let next = client.call_cached(&read("0x01")).await.unwrap();
+    assert_ne!(next, first);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+    let stats = client.call_cache.as_ref().unwrap().stats();
+    assert_eq!(
+        (stats["head_block"].as_u64(), stats["entries"].as_u64()),
+        (Some(101), Some(1))
+    );
+
+    // A client on another endpoint never answers from this one's cache.
+    assert!(client
+        .on_provider(http_provider(url.parse().unwrap(), RetryPolicy::none()))
+        .call_cache
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_read_only_client_reads_but_refuses_to_sign() {
+    let url = mock_rpc(|method, _| match method {
+        "eth_getBalance" => json!("0xde0b6b3a7640000"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    assert!(client.signer_address.is_none());
+
+    let res = GetBalanceTool
+        .call(
+            &client,
+            json!({ "address": "0x000000000000000000000000000000000000dEaD" }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["balance"], "1");
+
+    let to = "0x000000000000000000000000000000000000dEaD";
+    let refusals = [
+        SendTransactionTool
+            .call(&client, json!({ "to": to, "value": "1" }))
+            .await,
+        ApproveTokenTool
+            .call(
+                &client,
+                json!({ "token_address": to, "spender": to, "amount": "1" }),
+            )
+            .await,
+        SwapTokensTool
+            .call(
+                &client,
+                json!({ "from_token": "ETH", "to_token": to, "amount": "1" }),
+            )
+            .await,
+    ];
+    for res in refusals {
+        let err = res.unwrap_err().to_string();
+        assert!(err.contains("read-only mode"), "{}", err);
+        assert!(err.contains("PRIVATE_KEY"), "{}", err);
+    }
+    assert!(client.sign_hash(&Default::default()).is_err());
+    let err = MaxSendableEthTool
+        .call(&client, json!({}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Pass address"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_unsigned_transactions_are_built_for_an_external_signer() {
+    let url = mock_rpc(|method, params| match method {
+        "eth_getTransactionCount" => {
+            assert_eq!(
+                params[0].as_str().unwrap().to_lowercase(),
+                "0x000000000000000000000000000000000000beef"
+            );
+            json!("0x7")
+        }
+        "eth_estimateGas" => json!("0xb411"),
+        "eth_feeHistory" => json!({
+            "oldestBlock": "0x1",
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00"],
+            "gasUsedRatio": [0.5],
+            "reward": [["0x3b9aca00"]]
+        }),
+        "eth_getBalance" => json!("0xde0b6b3a7640000"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let from = "0x000000000000000000000000000000000000bEEF";
+    let token = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    // Read-only mode builds unsigned transactions, for the caller's account.
+    let res = ApproveTokenTool
+        .call(
+            &client,
+            json!({ "token_address": token, "spender": from, "amount": "5", "from": from }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["owner"], from);
+    let tx = &res["unsigned_transaction"];
+    assert_eq!(tx["type"], "0x2");
+    assert_eq!(tx["chain_id"], 1);
+    assert_eq!(tx["from"], from);
+    assert_eq!(tx["to"], token);
+    assert_eq!(tx["nonce"], 7);
+    assert_eq!(tx["gas_limit"], "46097");
+    assert_eq!(tx["max_priority_fee_per_gas"], "1000000000");
+    assert_eq!(tx["max_fee_per_gas"], "3000000000");
+    assert_eq!(tx["value"], "0");
+    assert_eq!(tx["data"], res["data"]);
+    // The hash to sign is the keccak of the EIP-2718 signing payload.
+    let payload = hex::decode(&tx["signing_payload"].as_str().unwrap()[2..]).unwrap();
+    assert_eq!(payload[0], 2);
+    assert_eq!(
+        tx["signing_hash"],
+        alloy::primitives::keccak256(&payload).to_string()
+    );
+
+    let err = SendTransactionTool
+        .call(&client, json!({ "to": token }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("Missing from"), "{}", err);
+
+    // With a key, from must be the wallet unless the transaction is left unsigned.
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let err = SendTransactionTool
+        .call(&client, json!({ "to": token, "from": from }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("unsigned: true"), "{}", err);
+    let res = SendTransactionTool
+        .call(
+            &client,
+            json!({ "to": token, "value": "1", "from": from, "unsigned": true }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["unsigned_transaction"]["value"], "1");
+    assert_eq!(res["unsigned_transaction"]["from"], from);
+}
+
+#[tokio::test]
+async fn test_get_block_by_tag_number_and_hash() {
+    let url = mock_rpc(|method, params| match method {
+        "eth_getBlockByHash" => {
+            assert_eq!(params[1], false);
+            Value::Null
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+
+    for block in [Value::Null, json!("latest"), json!(1), json!("1")] {
+        let res = GetBlockTool
+            .call(&client, json!({ "block": block }))
+            .await
+            .unwrap();
+        assert_eq!(res["number"], 1);
+        assert_eq!(res["hash"], format!("0x{}", "0".repeat(64)));
+        assert_eq!(res["timestamp"]["unix"], 0x6553f100);
+        assert_eq!(res["gas_limit"], 30_000_000);
+        assert_eq!(res["gas_used"], 0);
+        assert_eq!(res["transaction_count"], 0);
+        // The mock block predates London.
+        assert!(res["base_fee_per_gas"].is_null());
+    }
+
+    let hash = format!("0x{}", "ab".repeat(32));
+    let err = GetBlockTool
+        .call(&client, json!({ "block": hash }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), format!("Block {} not found", hash));
+    let err = GetBlockTool
+        .call(&client, json!({ "block": "soon" }))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().starts_with("block must be a block number"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_historical_gas_cost_prices_at_the_mined_block() {
+    let url = mock_rpc(|method, params| {
+        match method {
+            // The second transaction's block is beyond what the node keeps state for.
+            "eth_getTransactionReceipt" if params[0].as_str().unwrap().ends_with("02") => json!({
+                "status": "0x1",
+                "blockNumber": "0x65",
+                "gasUsed": "0x5208",
+                "effectiveGasPrice": "0x4a817c800"
+            }),
+            "eth_getTransactionReceipt" => json!({
+                "status": "0x1",
+                "blockNumber": "0x64",
+                "gasUsed": "0x5208",
+                "effectiveGasPrice": "0x4a817c800",
+                "logs": []
+            }),
+            "eth_call" if params[1] == "0x65" => {
+                json!({ "rpc_error": { "code": -32000, "message": "missing trie node" } })
+            }
+            "eth_call" if params[1] != "0x64" => {
+                panic!("read at {}, not the mined block", params[1])
+            }
+            "eth_call" => {
+                let data = params[0]["input"]
+                    .as_str()
+                    .or(params[0]["data"].as_str())
+                    .unwrap();
+                let word = |n: u64| format!("{:064x}", n);
+                if data.starts_with("0xfeaf968c") {
+                    // $2,000.00 with 8 decimals, updated at 0x6553f000.
+                    json!(format!(
+                        "0x{}{}{}{}{}",
+                        word(7),
+                        word(200_000_000_000),
+                        word(0x6553f000),
+                        word(0x6553f000),
+                        word(7)
+                    ))
+                } else {
+                    json!(format!("0x{}", word(8)))
+                }
+            }
+            other => panic!("unexpected {}", other),
+        }
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let hash = |n: u8| format!("0x{}{:02x}", "00".repeat(31), n);
+
+    let res = GetHistoricalGasCostTool
+        .call(&client, json!({ "tx_hash": hash(1) }))
+        .await
+        .unwrap();
+    assert_eq!(res["block_number"], 100);
+    assert_eq!(res["block_timestamp"]["unix"], 0x6553f100);
+    assert_eq!(res["effective_gas_price_gwei"], "20");
+    assert_eq!(res["fee_wei"], "420000000000000");
+    assert_eq!(res["fee_eth"], "0.00042");
+    assert_eq!(res["eth_price_usd"], json!(Decimal::from(2000)));
+    assert_eq!(res["fee_usd"], json!(Decimal::new(84, 2)));
+    assert_eq!(res["price_source"]["updated_at"]["unix"], 0x6553f000);
+    assert!(res["blob_fee_wei"].is_null());
+
+    // A pruned node can't read the feed at that block.
+    let err = GetHistoricalGasCostTool
+        .call(&client, json!({ "tx_hash": hash(2) }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("archive node"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_explain_failed_transaction_decodes_the_revert() {
+    // Hash ending 01 has a trace; 02 needs the eth_call replay.
+    let url = mock_rpc(|method, params| {
+        let hash = params[0].as_str().unwrap_or_default().to_string();
+        match method {
+            "eth_getTransactionByHash" => json!({
+                "hash": hash,
+                "from": "0x000000000000000000000000000000000000dEaD",
+                "to": "0xE592427A0AEce92De3Edee1F18E0157C05861564",
+                // exactInputSingle with a deadline (SwapRouter)
+                "input": format!("0x414bf389{}", "00".repeat(32 * 8)),
+                "value": "0x0",
+                "gas": "0x30d40",
+                "gasPrice": "0x3b9aca00",
+                "nonce": "0x1",
+                "blockNumber": "0x64"
+            }),
+            "eth_getTransactionReceipt" => json!({
+                "status": "0x0",
+                "blockNumber": "0x64",
+                "gasUsed": "0xc350",
+                "effectiveGasPrice": "0x3b9aca00",
+                "logs": []
+            }),
+            "debug_traceTransaction" if hash.ends_with("01") => json!({
+                "type": "CALL",
+                "to": "0xe592427a0aece92de3edee1f18e0157c05861564",
+                "error": "execution reverted",
+                // Error(string) "STF"
+                "output": "0x08c379a0000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000000035354460000000000000000000000000000000000000000000000000000000000",
+                "calls": [{
+                    "type": "CALL",
+                    "to": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                    "input": "0x23b872dd",
+                    "error": "execution reverted"
+                }]
+            }),
+            "debug_traceTransaction" => {
+                json!({ "rpc_error": { "code": -32601, "message": "method not found" } })
+            }
+            "eth_call" => {
+                assert_eq!(params[1], "0x63", "replayed at the parent block");
+                // Panic(0x11)
+                json!({ "rpc_error": {
+                    "code": 3,
+                    "message": "execution reverted",
+                    "data": "0x4e487b710000000000000000000000000000000000000000000000000000000000000011"
+                }})
+            }
+            other => panic!("unexpected {}", other),
+        }
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let hash = |n: u8| format!("0x{}{:02x}", "00".repeat(31), n);
+
+    let traced = ExplainFailedTransactionTool
+        .call(&client, json!({ "tx_hash": hash(1) }))
+        .await
+        .unwrap();
+    assert_eq!(traced["status"], "reverted");
+    assert_eq!(traced["source"], "debug_traceTransaction");
+    assert_eq!(traced["revert"]["kind"], "error_string");
+    assert_eq!(traced["revert"]["reason"], "STF");
+    assert!(traced["explanation"]
+        .as_str()
+        .unwrap()
+        .contains("allowance for the router"));
+    assert!(traced["function"]["signature"]
+        .as_str()
+        .unwrap()
+        .starts_with("exactInputSingle("));
+    assert_eq!(traced["reverted_in"]["depth"], 1);
+    assert_eq!(
+        traced["reverted_in"]["function"],
+        "transferFrom(address,address,uint256)"
+    );
+    assert_eq!(traced["gas_used"], 50000);
+    assert_eq!(traced["out_of_gas"], false);
+
+    let replayed = ExplainFailedTransactionTool
+        .call(&client, json!({ "tx_hash": hash(2) }))
+        .await
+        .unwrap();
+    assert_eq!(replayed["source"], "eth_call");
+    assert_eq!(replayed["revert"]["kind"], "panic");
+    assert_eq!(replayed["revert"]["code"], "0x11");
+    assert!(replayed["explanation"]
+        .as_str()
+        .unwrap()
+        .contains("arithmetic underflow or overflow"));
+}
+
+#[tokio::test]
+async fn test_rpc_retries_transient_failures_but_not_reverts() {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    static BALANCE_CALLS: AtomicU32 = AtomicU32::new(0);
+    static ETH_CALLS: AtomicU32 = AtomicU32::new(0);
+    let url = mock_rpc(|method, _| match method {
+        "eth_getBalance" if BALANCE_CALLS.fetch_add(1, Ordering::SeqCst) < 2 => {
+            json!({ "rpc_error": { "code": 429, "message": "Too many requests" } })
+        }
+        "eth_getBalance" => json!("0x1"),
+        "eth_call" => {
+            ETH_CALLS.fetch_add(1, Ordering::SeqCst);
+            json!({ "rpc_error": { "code": 3, "message": "execution reverted", "data": "0x" } })
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let retry = RetryPolicy {
+        max_retries: 2,
+        base_delay: std::time::Duration::from_millis(1),
+    };
+    let client = EthereumClient::connect(&url, None, &[1], retry)
+        .await
+        .unwrap();
+
+    // Two rate-limited answers, then the balance on the last allowed retry.
+    use alloy::providers::Provider;
+    let balance = client.provider.get_balance(Address::ZERO).await.unwrap();
+    assert_eq!(balance, U256::from(1));
+    assert_eq!(BALANCE_CALLS.load(Ordering::SeqCst), 3);
+
+    // A revert is an answer, not a failure: sent once.
+    let err = client
+        .provider
+        .call(&TransactionRequest::default().to(Address::ZERO))
+        .await
+        .unwrap_err();
+    assert!(!is_transient(&err));
+    assert_eq!(ETH_CALLS.load(Ordering::SeqCst), 1);
+
+    // Out of retries, the rate limit surfaces.
+    BALANCE_CALLS.store(0, Ordering::SeqCst);
+    let stingy = client.on_provider(http_provider(
+        url.parse().unwrap(),
+        RetryPolicy {
+            max_retries: 1,
+            base_delay: std::time::Duration::from_millis(1),
+        },
+    ));
+    let err = stingy
+        .provider
+        .get_balance(Address::ZERO)
+        .await
+        .unwrap_err();
+    assert!(is_transient(&err), "{}", err);
+    assert_eq!(BALANCE_CALLS.load(Ordering::SeqCst), 2);
+
+    // Backoff doubles per attempt, with jitter in the upper half, and is capped.
+    let policy = RetryPolicy::default();
+    for attempt in 0..3 {
+        let ceiling = policy.base_delay * 2u32.pow(attempt);
+        let delay = policy.delay(attempt);
+        assert!(delay >= ceiling / 2 && delay <= ceiling, "{:?}", delay);
+    }
+    assert!(policy.delay(30) <= std::time::Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn test_rpc_fails_over_to_the_next_endpoint() {
+    use alloy::providers::Provider;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    static LIMITED_CALLS: AtomicU32 = AtomicU32::new(0);
+    let limited = mock_rpc(|method, _| match method {
+        "eth_getBalance" => {
+            LIMITED_CALLS.fetch_add(1, Ordering::SeqCst);
+            json!({ "rpc_error": { "code": 429, "message": "Too many requests" } })
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let healthy = mock_rpc(|method, _| match method {
+        "eth_getBalance" => json!("0x2"),
+        "eth_call" => {
+            json!({ "rpc_error": { "code": 3, "message": "execution reverted", "data": "0x" } })
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+
+    // The primary is down at startup: kept as a fallback, with the chain read elsewhere.
+    let down = "http://127.0.0.1:1";
+    let client = EthereumClient::connect(
+        &format!("{}, {}", down, healthy),
+        None,
+        &[1],
+        RetryPolicy::none(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(client.rpc_endpoints().urls().len(), 2);
+    assert_eq!(client.active_endpoint(), "http://127.0.0.1:1");
+    let balance = client.provider.get_balance(Address::ZERO).await.unwrap();
+    assert_eq!(balance, U256::from(2));
+    assert_eq!(client.active_endpoint(), healthy);
+
+    // A rate-limited endpoint is left for the next one, which stays active.
+    let client = EthereumClient::connect(
+        &format!("{},{}", limited, healthy),
+        None,
+        &[1],
+        RetryPolicy::none(),
+    )
+    .await
+    .unwrap();
+    let balance = client.provider.get_balance(Address::ZERO).await.unwrap();
+    assert_eq!(balance, U256::from(2));
+    assert_eq!(client.active_endpoint(), healthy);
+    client.provider.get_balance(Address::ZERO).await.unwrap();
+    assert_eq!(LIMITED_CALLS.load(Ordering::SeqCst), 1);
+
+    // A revert is the chain's answer; no other endpoint is asked.
+    let err = client
+        .provider
+        .call(&TransactionRequest::default().to(Address::ZERO))
+        .await
+        .unwrap_err();
+    assert!(!is_transient(&err));
+    assert_eq!(client.active_endpoint(), healthy);
+
+    // When every endpoint fails, the last error surfaces.
+    let err = EthereumClient::connect(
+        &format!("{},http://127.0.0.1:2", down),
+        None,
+        &[1],
+        RetryPolicy::none(),
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(
+        err.to_string()
+            .starts_with("Could not read the chain id from http://127.0.0.1:1"),
+        "{}",
+        err
+    );
+}
+
+// Sample values for schema-valid tool arguments, by property name.
+fn sample_argument(field: &str, schema: &Value) -> Value {
+    if let Some(first) = schema.get("enum").and_then(|e| e.get(0)) {
+        return first.clone();
+    }
+    let usdc = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+    match field {
+        "token_address" | "spender" | "address" | "to" | "pool_address" | "holder"
+        | "contract_address" | "to_token" | "quote_token" => json!(usdc),
+        "from_token" | "base_token" => json!("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+        "token_symbol" | "token" => json!("ETH"),
+        "tokens" => json!(["ETH"]),
+        "amount" | "value" | "size" | "above" => json!("1"),
+        "tx_hash" => json!(format!("0x{}", "11".repeat(32))),
+        "signature" => {
+            use alloy::signers::SignerSync;
+            let signer = alloy::signers::local::PrivateKeySigner::random();
+            let sig = signer.sign_message_sync(b"hello").unwrap();
+            json!(format!("0x{}", hex::encode(sig.as_bytes())))
+        }
+        "message" => json!("hello"),
+        "name" => json!("vitalik.eth"),
+        "calls" => json!([{ "name": "get_balance", "arguments": { "address": usdc } }]),
+        "arguments" => json!({ "address": usdc }),
+        "transaction" => json!({ "to": usdc }),
+        "input" => json!("0x00"),
+        "function_signature" => json!("totalSupply()"),
+        "event_signature" => {
+            json!("Transfer(address indexed from, address indexed to, uint256 value)")
+        }
+        "watch_id" => json!("schema-check"),
+        _ => match schema["type"].as_str() {
+            Some("integer") => json!(1),
+            Some("number") => json!(1.0),
+            Some("boolean") => json!(false),
+            Some("array") => json!([]),
+            Some("object") => json!({}),
+            _ => json!("1"),
+        },
+    }
+}
+
+// Either/or inputs the schemas leave to the descriptions, since several MCP clients
+// reject a top-level anyOf/oneOf in an inputSchema: the first alternative is passed.
+const FIRST_ALTERNATIVE: [(&str, &str); 4] = [
+    ("get_required_eth", "transaction"),
+    ("resolve_ens", "name"),
+    ("verify_signature", "message"),
+    ("watch_price", "above"),
+];
+
+// Errors about server state rather than the arguments.
+const STATE_ERRORS: [(&str, &str); 2] = [
+    ("compare_rpc_endpoints", "No secondary endpoints configured"),
+    ("unwatch_price", "No running watch"),
+];
+
+// Every registered tool is called with its required fields (plus any either/or choice)
+// filled with sample values, against an unreachable node. Each must get past argument
+// validation: succeed, or fail on the RPC. Dropping any one required field must fail with
+// "Missing <field>" before any RPC is made.
+#[tokio::test]
+async fn test_every_tool_accepts_schema_valid_input() {
+    let client = offline_client().await;
+    let tools = ethereum_trading_mcp::server::registered_tools();
+    let mut names: Vec<_> = tools.keys().cloned().collect();
+    names.sort();
+    let call = |name: &str, args: Value| {
+        let tool = &tools[name];
+        let client = &client;
+        async move {
+            match tokio::time::timeout(std::time::Duration::from_secs(5), tool.call(client, args))
+                .await
+            {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("no answer within 5s".to_string()),
+            }
+        }
+    };
+
+    let mut drift = Vec::new();
+    for name in &names {
+        let schema = tools[name].schema();
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .map(|r| r.iter().filter_map(|f| f.as_str()).collect())
+            .unwrap_or_default();
+        let mut args = json!({});
+        let chosen = FIRST_ALTERNATIVE
+            .iter()
+            .filter(|(tool, _)| tool == name)
+            .map(|(_, field)| *field);
+        for field in required.iter().copied().chain(chosen) {
+            assert!(
+                schema["properties"].get(field).is_some(),
+                "{}: {} is not a schema property",
+                name,
+                field
+            );
+            args[field] = sample_argument(field, &schema["properties"][field]);
+        }
+
+        if let Err(e) = call(name, args.clone()).await {
+            let state_error = STATE_ERRORS
+                .iter()
+                .any(|(tool, msg)| tool == name && e.contains(msg));
+            if !e.contains("error sending request") && !state_error {
+                drift.push(format!("{} rejected {}: {}", name, args, e));
+            }
+        }
+        for field in &required {
+            let mut partial = args.clone();
+            partial.as_object_mut().unwrap().remove(*field);
+            let expected = format!("Missing {}", field);
+            match call(name, partial).await {
+                Err(e) if e.starts_with(&expected) => {}
+                other => drift.push(format!(
+                    "{} without {}: expected \"{}\", got {:?}",
+                    name, field, expected, other
+                )),
+            }
+        }
+    }
+    assert!(drift.is_empty(), "{}", drift.join("\n"));
+}
+
+#[tokio::test]
+async fn test_signer_check_self_call() {
+    assert_eq!(SignerCheck::parse("").unwrap(), SignerCheck::Warn);
+    assert_eq!(SignerCheck::parse("Strict").unwrap(), SignerCheck::Strict);
+    assert_eq!(SignerCheck::parse("off").unwrap(), SignerCheck::Off);
+    assert!(SignerCheck::parse("maybe").is_err());
+
+    let key = "0x0123456789012345678901234567890123456789012345678901234567890123";
+    let url = mock_rpc(|method, params| match method {
+        "eth_call" => {
+            // A zero-value self-call from the signer, carrying the chain id.
+            assert_eq!(params[0]["from"], params[0]["to"]);
+            assert_eq!(params[0]["chainId"], "0x1");
+            json!({ "rpc_error": { "code": -32000, "message": "invalid sender" } })
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::new(&url, key).await.unwrap();
+    client.check_signer(SignerCheck::Off).await.unwrap();
+    client.check_signer(SignerCheck::Warn).await.unwrap();
+    let err = client
+        .check_signer(SignerCheck::Strict)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("invalid sender"), "{}", err);
+    assert!(err.contains("SIGNER_CHECK=strict"), "{}", err);
+
+    // Read-only clients have no signer to check.
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    client.check_signer(SignerCheck::Strict).await.unwrap();
+
+    let url = mock_rpc(|method, _| match method {
+        "eth_call" => json!("0x"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::new(&url, key).await.unwrap();
+    client.check_signer(SignerCheck::Strict).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_discover_tokens_from_transfer_logs() {
+    use alloy::sol_types::SolCall;
+
+    let url = mock_rpc(|method, params| match method {
+        "eth_blockNumber" => json!("0x64"),
+        "eth_getLogs" => {
+            let filter = &params[0];
+            assert_eq!(filter["fromBlock"], "0x0");
+            assert_eq!(filter["toBlock"], "0x3b");
+            let wallet = format!("0x{:0>64}", "d8da6bf26964af9d7eed9e03e53415d37aa96045");
+            let log = |token: &str, index: u64, topics: Vec<String>| {
+                json!({
+                    "address": token,
+                    "topics": topics,
+                    "data": format!("0x{:064x}", 1),
+                    "blockNumber": "0x10",
+                    "blockHash": format!("0x{}", "11".repeat(32)),
+                    "transactionHash": format!("0x{}", "22".repeat(32)),
+                    "transactionIndex": "0x0",
+                    "logIndex": format!("0x{:x}", index),
+                    "removed": false
+                })
+            };
+            let topic0 = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+            let other = format!("0x{:064x}", 0xbeef);
+            let self_transfer = log(
+                "0x0000000000000000000000000000000000001111",
+                0,
+                vec![topic0.into(), wallet.clone(), wallet.clone()],
+            );
+            if filter["topics"][1].is_null() {
+                // Received: 0x2222 and 0x4444, plus the self-transfer again.
+                json!([
+                    self_transfer,
+                    log(
+                        "0x0000000000000000000000000000000000002222",
+                        1,
+                        vec![topic0.into(), other.clone(), wallet.clone()]
+                    ),
+                    log(
+                        "0x0000000000000000000000000000000000004444",
+                        2,
+                        vec![topic0.into(), other.clone(), wallet.clone()]
+                    )
+                ])
+            } else {
+                // Sent: the self-transfer and an ERC721 transfer, which is skipped.
+                json!([
+                    self_transfer,
+                    log(
+                        "0x0000000000000000000000000000000000003333",
+                        3,
+                        vec![
+                            topic0.into(),
+                            wallet.clone(),
+                            other,
+                            format!("0x{:064x}", 7)
+                        ]
+                    )
+                ])
+            }
+        }
+        "eth_call" => {
+            let data = params[0]["input"]
+                .as_str()
+                .or(params[0]["data"].as_str())
+                .unwrap();
+            let word = |n: u128| format!("{:064x}", n);
+            let calls = aggregate3Call::abi_decode(&hex::decode(&data[2..]).unwrap(), true)
+                .unwrap()
+                .calls;
+            let results: Vec<Call3Result> = calls
+                .iter()
+                .map(|c| {
+                    let answer = match (
+                        c.target.to_string().as_str(),
+                        hex::encode(&c.callData[..4]).as_str(),
+                    ) {
+                        ("0x0000000000000000000000000000000000001111", "70a08231") => {
+                            word(2_500_000_000_000_000_000)
+                        }
+                        ("0x0000000000000000000000000000000000002222", "70a08231") => word(0),
+                        (
+                            "0x0000000000000000000000000000000000001111"
+                            | "0x0000000000000000000000000000000000002222",
+                            "313ce567",
+                        ) => word(18),
+                        (_, "95d89b41") => format!("{}{}{:0<64}", word(0x20), word(3), "544b4e"),
+                        _ => String::new(),
+                    };
+                    Call3Result {
+                        success: !answer.is_empty(),
+                        returnData: hex::decode(answer).unwrap().into(),
+                    }
+                })
+                .collect();
+            json!(format!(
+                "0x{}",
+                hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+            ))
+        }
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+
+    let res = DiscoverTokensTool
+        .call(
+            &client,
+            json!({
+                "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+                "from_block": 0,
+                "page_blocks": 60
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["to_block"], 59);
+    assert_eq!(res["next_cursor"], "60");
+    assert_eq!(res["transfer_count"], 3);
+    assert_eq!(res["tokens_found"], 3);
+    let holdings = res["holdings"].as_array().unwrap();
+    assert_eq!(holdings.len(), 1);
+    assert_eq!(
+        holdings[0]["token_address"],
+        "0x0000000000000000000000000000000000001111"
+    );
+    assert_eq!(holdings[0]["balance"], "2.5");
+    assert_eq!(holdings[0]["symbol"], "TKN");
+    let unreadable = res["unreadable_tokens"].as_array().unwrap();
+    assert_eq!(unreadable.len(), 1);
+    assert_eq!(
+        unreadable[0]["token_address"],
+        "0x0000000000000000000000000000000000004444"
+    );
+
+    let err = DiscoverTokensTool
+        .call(
+            &client,
+            json!({
+                "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+                "from_block": 0,
+                "page_blocks": 100_000
+            }),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("page_blocks"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_health_reports_chain_and_rpc_reachability() {
+    let url = mock_rpc(|method, _| match method {
+        "eth_blockNumber" => json!("0x2a"),
+        other => panic!("unexpected {}", other),
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let health = health_result(&client).await;
+    assert_eq!(health["status"], "ok");
+    assert_eq!(health["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(health["chain_id"], 1);
+    assert_eq!(health["block_number"], 42);
+    assert_eq!(health["rpc"]["reachable"], true);
+    assert!(health["rpc"]["error"].is_null());
+
+    let health = health_result(&offline_client().await).await;
+    assert_eq!(health["status"], "degraded");
+    assert!(health["block_number"].is_null());
+    assert_eq!(health["rpc"]["reachable"], false);
+    assert!(health["rpc"]["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_token_price_from_pool_twap() {
+    use alloy::sol_types::SolCall;
+
+    // Token/WETH pool at a spot of 0.25 WETH per token; its oracle averaged tick -13864
+    // over the last 1800s (the cumulative delta rounds down). ETH/USD 2000.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let int = |n: i64| format!("{:064x}", alloy::primitives::I256::try_from(n).unwrap());
+        let answer = |data: &str| -> String {
+            match &data[..8] {
+                "1698ee82" => word(0x9999),
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(1 << 95),
+                    word(0),
+                    word(0),
+                    word(1),
+                    word(1),
+                    word(0),
+                    word(1)
+                ),
+                "1a686502" => word(1),
+                "0dfe1681" => word(0x1111),
+                "feaf968c" => format!(
+                    "{}{}{}{}{}",
+                    word(1),
+                    word(200_000_000_000),
+                    word(1),
+                    word(1),
+                    word(1)
+                ),
+                "313ce567" => word(18),
+                // observe([1800, 0])
+                "883bdbfd" => {
+                    assert_eq!(&data[8 + 64 * 2..8 + 64 * 3], word(1800));
+                    format!(
+                        "{}{}{}{}{}{}{}{}",
+                        word(0x40),
+                        word(0xa0),
+                        word(2),
+                        int(1_000_000),
+                        int(1_000_000 - 13_863 * 1800 - 5),
+                        word(2),
+                        word(0),
+                        word(0)
+                    )
+                }
+                _ => String::new(),
+            }
+        };
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            return json!(format!("0x{}", answer(&data[2..])));
+        }
+        let calls = aggregate3Call::abi_decode(&hex::decode(&data[2..]).unwrap(), true)
+            .unwrap()
+            .calls;
+        let results: Vec<Call3Result> = calls
+            .iter()
+            .map(|c| {
+                let data = hex::encode(&c.callData);
+                Call3Result {
+                    success: true,
+                    returnData: hex::decode(if &data[..8] == "313ce567" {
+                        word(8)
+                    } else {
+                        answer(&data)
+                    })
+                    .unwrap()
+                    .into(),
+                }
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+    let token = "0x0000000000000000000000000000000000001111";
+
+    let price = GetTokenPriceTool
+        .call(
+            &client,
+            json!({"token_symbol": "TKN", "token_address": token, "price_source": "twap"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(price["price_source"], "twap");
+    assert_eq!(price["twap"]["window_seconds"], 1800);
+    assert_eq!(price["twap"]["average_tick"], -13864);
+    assert_eq!(price["twap"]["spot_price_eth"], json!("0.2500"));
+    let twap: Decimal = serde_json::from_value(price["price_eth"].clone()).unwrap();
+    assert!(
+        twap < Decimal::new(25, 2) && twap > Decimal::new(2499, 4),
+        "{}",
+        twap
+    );
+
+    let spot = GetTokenPriceTool
+        .call(
+            &client,
+            json!({"token_symbol": "TKN", "token_address": token}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(spot["price_source"], "spot");
+    assert!(spot["twap"].is_null());
+
+    for bad in [
+        json!({"token_symbol": "TKN", "price_source": "median"}),
+        json!({"token_symbol": "TKN", "twap_window": 600}),
+        json!({"token_symbol": "TKN", "price_source": "twap", "twap_window": 0}),
+    ] {
+        let err = GetTokenPriceTool.call(&client, bad).await.unwrap_err();
+        assert_eq!(ToolError::classify(&err).kind(), "invalid_argument");
+    }
+
+    assert_eq!(twap_tick(&[0i64, 3601], 3600).unwrap(), 1);
+    assert_eq!(twap_tick(&[0i64, -3601], 3600).unwrap(), -2);
+    assert_eq!(twap_tick(&[0i64, -7200], 3600).unwrap(), -2);
+    assert!(twap_tick(&[0i64], 3600).is_err());
+}
+
+#[tokio::test]
+async fn test_reference_prices_compare_chainlink_with_weth_usdc_pool() {
+    use alloy::sol_types::SolCall;
+    use ethereum_trading_mcp::ethereum::getCurrentBlockTimestampCall;
+
+    // USDC is token0 of the WETH/USDC pool at 2500 USDC per WETH (raw ratio 4e8, so
+    // sqrtPriceX96 = 20000 * 2^96); Chainlink ETH/USD answers 2525, 100s before the block.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let answer = |target: &str, call: &[u8]| -> String {
+            match hex::encode(&call[..4]).as_str() {
+                "1698ee82" => word(0x9999),
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(20_000u128 << 96),
+                    word(0),
+                    word(0),
+                    word(1),
+                    word(1),
+                    word(0),
+                    word(1)
+                ),
+                "1a686502" => word(1_000_000),
+                "0dfe1681" => {
+                    "000000000000000000000000a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".into()
+                }
+                "feaf968c" => format!(
+                    "{}{}{}{}{}",
+                    word(0x1234),
+                    word(252_500_000_000),
+                    word(1_699_999_900),
+                    word(1_699_999_900),
+                    word(0x1234)
+                ),
+                "313ce567" if target.ends_with("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48") => {
+                    word(6)
+                }
+                "313ce567" if target.ends_with("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2") => {
+                    word(18)
+                }
+                "313ce567" => word(8),
+                selector if selector == hex::encode(getCurrentBlockTimestampCall::SELECTOR) => {
+                    word(1_700_000_000)
+                }
+                _ => String::new(),
+            }
+        };
+        let data = params[0]["input"]
+            .as_str()
+            .or(params[0]["data"].as_str())
+            .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        let call = hex::decode(&data[2..]).unwrap();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            return json!(format!("0x{}", answer(&to, &call)));
+        }
+        let calls = aggregate3Call::abi_decode(&call, true).unwrap().calls;
+        let results: Vec<Call3Result> = calls
+            .iter()
+            .map(|c| {
+                let answer = answer(&hex::encode(c.target), &c.callData);
+                Call3Result {
+                    success: !answer.is_empty(),
+                    returnData: hex::decode(answer).unwrap().into(),
+                }
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let client = EthereumClient::read_only(&url).await.unwrap();
+
+    let res = GetReferencePricesTool
+        .call(&client, json!({}))
+        .await
+        .unwrap();
+    let chainlink = &res["chainlink_eth_usd"];
+    assert_eq!(chainlink["price_usd"], json!("2525.00000000"));
+    assert_eq!(chainlink["round_id"], "4660");
+    assert_eq!(chainlink["updated_at"], 1_699_999_900u64);
+    assert_eq!(chainlink["age_seconds"], 100);
+    let pool = &res["uniswap_weth_usdc"];
+    assert_eq!(pool["quote_token"], "USDC");
+    assert_eq!(pool["pool"], "0x0000000000000000000000000000000000009999");
+    let price: Decimal = serde_json::from_value(pool["price"].clone()).unwrap();
+    assert_eq!(price, Decimal::from(2500));
+    assert_eq!(res["deviation_percent"], json!("0.9901"));
+}
+
+#[tokio::test]
+async fn test_token_price_picks_the_deeper_of_weth_and_stablecoin_routes() {
+    use alloy::sol_types::SolCall;
+
+    // Token/WETH (0x8888) and token/USDC (0x9999) pools, both at 0.25 quote per token, but
+    // the USDC pool holds five times as much of the token. Every token and the feed report
+    // 8 decimals; ETH/USD 2000.
+    let url = mock_rpc(|method, params| {
+        assert_eq!(method, "eth_call");
+        let word = |n: u128| format!("{:064x}", n);
+        let answer = |call: &[u8]| -> String {
+            let args = hex::encode(&call[4..]);
+            match hex::encode(&call[..4]).as_str() {
+                "1698ee82"
+                    if args[64..128].ends_with("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48") =>
+                {
+                    word(0x9999)
+                }
+                "1698ee82"
+                    if args[64..128].ends_with("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2") =>
+                {
+                    word(0x8888)
+                }
+                "1698ee82" => word(0),
+                "3850c7bd" => format!(
+                    "{}{}{}{}{}{}{}",
+                    word(1 << 95),
+                    word(0),
+                    word(0),
+                    word(1),
+                    word(1),
+                    word(0),
+                    word(1)
+                ),
+                "1a686502" => word(1),
+                "0dfe1681" => word(0x1111),
+                // balanceOf(pool)
+                "70a08231" if args.ends_with("9999") => word(5_000),
+                "70a08231" => word(1_000),
+                "feaf968c" => format!(
+                    "{}{}{}{}{}",
+                    word(1),
+                    word(200_000_000_000),
+                    word(1),
+                    word(1),
+                    word(1)
+                ),
+                "313ce567" => word(8),
+                _ => String::new(),
+            }
+        };
+        let data = hex::decode(
+            &params[0]["input"]
+                .as_str()
+                .or(params[0]["data"].as_str())
+                .unwrap()[2..],
+        )
+        .unwrap();
+        let to = params[0]["to"].as_str().unwrap().to_lowercase();
+        if to != "0xca11bde05977b3631167028862be2a173976ca11" {
+            return json!(format!("0x{}", answer(&data)));
+        }
+        let results: Vec<Call3Result> = aggregate3Call::abi_decode(&data, true)
+            .unwrap()
+            .calls
+            .iter()
+            .map(|c| Call3Result {
+                success: true,
+                returnData: hex::decode(answer(&c.callData)).unwrap().into(),
+            })
+            .collect();
+        json!(format!(
+            "0x{}",
+            hex::encode(aggregate3Call::abi_encode_returns(&(results,)))
+        ))
+    })
+    .await;
+    let mut client = EthereumClient::read_only(&url).await.unwrap();
+    client.assume_stablecoin_peg = true;
+
+    let price = GetTokenPriceTool
+        .call(
+            &client,
+            json!({"token_symbol": "TKN", "token_address": "0x0000000000000000000000000000000000001111"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(price["routing"]["quote_token"], "USDC");
+    assert_eq!(price["pool"], "0x0000000000000000000000000000000000009999");
+    let routes = price["routing"]["routes"].as_array().unwrap();
+    assert_eq!(routes.len(), 2);
+    assert_eq!(routes[0]["quote_token"], "WETH");
+    assert_eq!(routes[0]["token_balance"], "1000");
+    assert_eq!(routes[1]["quote_token"], "USDC");
+    assert_eq!(routes[1]["token_balance"], "5000");
+    let usd: Decimal = serde_json::from_value(price["price_usd"].clone()).unwrap();
+    assert_eq!(usd.normalize().to_string(), "0.25");
+}
+
+#[tokio::test]
+async fn test_tool_calls_time_out() {
+    use std::time::Duration;
+
+    let mut client = offline_client().await;
+    assert_eq!(client.tool_timeout, Some(Duration::from_secs(30)));
+
+    // Waiting tools get their own wait on top of the tool timeout.
+    let watch = json!({ "tx_hash": format!("0x{}", "ab".repeat(32)), "timeout_seconds": 600 });
+    assert_eq!(
+        tool_time_limit(&client, &WatchTransactionTool, &watch),
+        Some(Duration::from_secs(630))
+    );
+    assert_eq!(
+        tool_time_limit(
+            &client,
+            &WrapEthTool,
+            &json!({ "amount": "1", "wait": false })
+        ),
+        Some(Duration::from_secs(30))
+    );
+    assert_eq!(
+        tool_time_limit(&client, &WrapEthTool, &json!({ "amount": "1" })),
+        Some(Duration::from_secs(150))
+    );
+    assert_eq!(
+        tool_time_limit(
+            &client,
+            &SwapTokensTool,
+            &json!({ "execute": true, "auto_approve": true })
+        ),
+        Some(Duration::from_secs(210))
+    );
+    client.tool_timeout = None;
+    assert_eq!(
+        tool_time_limit(&client, &WatchTransactionTool, &watch),
+        None
+    );
+
+    let hung = async {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        (Ok(json!({})), 0)
+    };
+    let (outcome, _) = within_time_limit(
+        "slow_tool",
+        Some(Duration::from_millis(50)),
+        Default::default(),
+        hung,
+    )
+    .await;
+    let err = outcome.unwrap_err();
+    let kind = ToolError::classify(&err);
+    assert_eq!(kind.code(), TIMEOUT_CODE);
+    assert_eq!(kind.data()["kind"], "timeout");
+    assert_eq!(kind.data()["retryable"], true);
+    assert!(err.to_string().contains("slow_tool timed out"), "{}", err);
+
+    let quick = async { (Ok(json!({ "ok": true })), 2) };
+    let (outcome, sent) = within_time_limit(
+        "quick_tool",
+        Some(Duration::from_secs(5)),
+        Default::default(),
+        quick,
+    )
+    .await;
+    assert_eq!(outcome.unwrap()["ok"], true);
+    assert_eq!(sent, 2);
+}
+
+#[tokio::test]
+async fn test_timeout_after_broadcast_is_not_retryable() {
+    use std::time::Duration;
+
+    // The node takes longer to accept the broadcast than the call may run. It blocks while
+    // it sleeps, so it gets a runtime of its own.
+    let (url_tx, url_rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let url = mock_rpc(|method, _| match method {
+                "eth_getTransactionCount" => json!("0x7"),
+                "eth_getBalance" => json!("0xde0b6b3a7640000"),
+                "eth_sendRawTransaction" => {
+                    std::thread::sleep(Duration::from_secs(1));
+                    json!(format!("0x{}", "ab".repeat(32)))
+                }
+                other => panic!("unexpected {}", other),
+            })
+            .await;
+            url_tx.send(url).unwrap();
+            std::future::pending::<()>().await
+        })
+    });
+    let url = url_rx.recv().unwrap();
+    let client = EthereumClient::new(
+        &url,
+        "0x0123456789012345678901234567890123456789012345678901234567890123",
+    )
+    .await
+    .unwrap();
+    let args = json!({
+        "to": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+        "value": "1000",
+        "gas_limit": 21000,
+        "max_fee_per_gas": "1000000000",
+        "max_priority_fee_per_gas": 1000000
+    });
+    let send = async { (SendTransactionTool.call(&client, args).await, 0) };
+    let broadcasts = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let (outcome, _) = within_time_limit(
+        "send_transaction",
+        Some(Duration::from_millis(300)),
+        broadcasts.clone(),
+        send,
+    )
+    .await;
+    // The caller's sink sees the hash too, for the audit log.
+    assert_eq!(broadcasts.lock().unwrap().len(), 1);
+    let err = outcome.unwrap_err();
+    let kind = ToolError::classify(&err);
+    assert_eq!(kind.code(), TIMEOUT_CODE);
+    let data = kind.data();
+    assert_eq!(data["retryable"], false);
+    let hashes = data["tx_hashes"].as_array().unwrap();
+    assert_eq!(hashes.len(), 1);
+    assert!(
+        err.to_string().contains(&format!(
+            "after broadcasting {}",
+            hashes[0].as_str().unwrap()
+        )),
+        "{}",
+        err
+    );
+}